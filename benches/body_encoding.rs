@@ -0,0 +1,55 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use lettre::message::{
+    header::{ContentTransferEncoding, ContentType},
+    Attachment, Body, Message, MultiPart, SinglePart,
+};
+
+fn attachment_content() -> Vec<u8> {
+    (0..1024 * 1024).map(|i| (i * 7 % 256) as u8).collect()
+}
+
+fn text_content() -> Vec<u8> {
+    "Questo messaggio è lungo, molto più lungo di una riga.\r\n"
+        .repeat(20_000)
+        .into_bytes()
+}
+
+fn criterion_benchmark(c: &mut Criterion) {
+    let content = attachment_content();
+    let text = text_content();
+
+    c.bench_function("encode 1 MiB body as base64", |b| {
+        b.iter(|| Body::new(black_box(content.clone())))
+    });
+
+    c.bench_function("encode 1 MiB text body as quoted-printable", |b| {
+        b.iter(|| {
+            Body::new_with_encoding(
+                black_box(text.clone()),
+                ContentTransferEncoding::QuotedPrintable,
+            )
+        })
+    });
+
+    c.bench_function("format message with 1 MiB attachment", |b| {
+        b.iter(|| {
+            let email = Message::builder()
+                .from("NoBody <nobody@domain.tld>".parse().unwrap())
+                .to("Hei <hei@domain.tld>".parse().unwrap())
+                .subject("Happy new year")
+                .multipart(
+                    MultiPart::mixed()
+                        .singlepart(SinglePart::plain(String::from("Be happy!")))
+                        .singlepart(Attachment::new(String::from("data.bin")).body(
+                            black_box(content.clone()),
+                            ContentType::parse("application/octet-stream").unwrap(),
+                        )),
+                )
+                .unwrap();
+            email.formatted()
+        })
+    });
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
@@ -0,0 +1,59 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use lettre::message::{
+    header::{self, HeaderName, HeaderValue, Headers},
+    Mailboxes,
+};
+
+/// Headers of a typical newsletter
+fn headers() -> Headers {
+    let mailbox: Mailboxes = "NoBody <nobody@domain.tld>".parse().unwrap();
+    let mut headers = Headers::new();
+    headers.set(header::From::from(mailbox.clone()));
+    headers.set(header::ReplyTo::from(mailbox.clone()));
+    headers.set(header::To::from(mailbox));
+    headers.set(header::Subject::from(String::from("Happy new year")));
+    headers.set(header::Date::now());
+    headers.set(header::MessageId::from(String::from("<id@domain.tld>")));
+    headers.set(header::MIME_VERSION_1_0);
+    headers.set(header::UserAgent::from(String::from("lettre")));
+    headers.set(header::ListUnsubscribe::from(String::from(
+        "<https://domain.tld/unsubscribe>",
+    )));
+    for name in [
+        "X-Campaign-Id",
+        "X-Mailer-Queue",
+        "X-Priority",
+        "X-Entity-Ref-ID",
+        "X-Feedback-ID",
+    ] {
+        headers.insert_raw(HeaderValue::new(
+            HeaderName::new_from_ascii(name.to_owned()).unwrap(),
+            String::from("1234"),
+        ));
+    }
+    headers.set(header::ContentType::TEXT_PLAIN);
+    headers
+}
+
+fn criterion_benchmark(c: &mut Criterion) {
+    let headers = headers();
+
+    c.bench_function("get last header", |b| {
+        b.iter(|| black_box(&headers).get::<header::ContentType>())
+    });
+
+    c.bench_function("get missing header", |b| {
+        b.iter(|| black_box(&headers).get_raw(black_box("Content-Transfer-Encoding")))
+    });
+
+    c.bench_function("set existing header", |b| {
+        b.iter_batched_ref(
+            || headers.clone(),
+            |headers| headers.set(header::Subject::from(String::from("Happy new year"))),
+            criterion::BatchSize::SmallInput,
+        )
+    });
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
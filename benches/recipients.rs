@@ -0,0 +1,58 @@
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use lettre::message::{
+    header::{self, Headers},
+    Mailbox, Mailboxes, Message,
+};
+
+/// A thousand distinct recipients
+fn mailboxes() -> Mailboxes {
+    (0..1000)
+        .map(|i| {
+            format!("Recipient {i} <recipient{i}@domain.tld>")
+                .parse::<Mailbox>()
+                .unwrap()
+        })
+        .collect()
+}
+
+fn criterion_benchmark(c: &mut Criterion) {
+    let mailboxes = mailboxes();
+
+    c.bench_function("fold To header with 1k mailboxes", |b| {
+        let mut headers = Headers::new();
+        headers.set(header::To::from(mailboxes.clone()));
+        b.iter(|| black_box(&headers).to_string())
+    });
+
+    c.bench_function("build message with 1k recipients", |b| {
+        b.iter_batched(
+            || mailboxes.clone(),
+            |mailboxes| {
+                Message::builder()
+                    .from("NoBody <nobody@domain.tld>".parse().unwrap())
+                    .mailbox(header::To::from(mailboxes))
+                    .body(String::from("Be happy!"))
+                    .unwrap()
+            },
+            BatchSize::SmallInput,
+        )
+    });
+
+    c.bench_function("build message with 1k recipients capped to 10", |b| {
+        b.iter_batched(
+            || mailboxes.clone(),
+            |mailboxes| {
+                Message::builder()
+                    .from("NoBody <nobody@domain.tld>".parse().unwrap())
+                    .mailbox(header::To::from(mailboxes))
+                    .max_header_recipients(10)
+                    .body(String::from("Be happy!"))
+                    .unwrap()
+            },
+            BatchSize::SmallInput,
+        )
+    });
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
@@ -0,0 +1,24 @@
+//! Encodes untrusted bytes as a body with each `Content-Transfer-Encoding`
+//! able to represent them, and checks that they decode back unchanged.
+//!
+//! Run with `cargo +nightly fuzz run body_encoding` from the `fuzz` directory.
+
+#![no_main]
+
+use lettre::message::{header::ContentTransferEncoding, Body};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    for encoding in [
+        ContentTransferEncoding::QuotedPrintable,
+        ContentTransferEncoding::Base64,
+    ] {
+        let body = Body::new_with_encoding(data.to_vec(), encoding).unwrap();
+        for line in body.as_ref().split(|&b| b == b'\n') {
+            let line = line.strip_suffix(b"\r").unwrap_or(line);
+            assert!(line.len() <= 76);
+            assert!(!line.ends_with(b" ") && !line.ends_with(b"\t"));
+        }
+        assert_eq!(body.decode().as_deref(), Some(data));
+    }
+});
@@ -0,0 +1,15 @@
+//! Parses untrusted bytes as an SMTP reply, as read from the server,
+//! then as an `EHLO` reply.
+//!
+//! Run with `cargo +nightly fuzz run smtp_response` from the `fuzz` directory.
+
+#![no_main]
+
+use lettre::transport::smtp::{extension::ServerInfo, response::Response};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(response) = Response::from_bytes(data) {
+        let _ = ServerInfo::from_response(&response);
+    }
+});
@@ -1,4 +1,4 @@
-use super::Address;
+use super::{Address, AddressError};
 #[cfg(feature = "builder")]
 use crate::message::header::{self, Headers};
 #[cfg(feature = "builder")]
@@ -109,6 +109,27 @@ impl Envelope {
         })
     }
 
+    /// Creates a builder for an envelope, taking the addresses as strings,
+    /// [`Address`]es or [`Mailbox`](crate::message::Mailbox)es
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use lettre::address::Envelope;
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// let envelope = Envelope::builder()
+    ///     .from("sender@email.com")?
+    ///     .to(["to@email.com", "cc@email.com"])?
+    ///     .build()?;
+    /// assert_eq!(envelope.to().len(), 2);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn builder() -> EnvelopeBuilder {
+        EnvelopeBuilder::default()
+    }
+
     /// Gets the destination addresses of the envelope.
     ///
     /// # Examples
@@ -162,6 +183,95 @@ impl Envelope {
     }
 }
 
+/// Builder for an [`Envelope`], created with [`Envelope::builder`]
+#[derive(Debug, Clone, Default)]
+pub struct EnvelopeBuilder {
+    from: Option<Address>,
+    to: Vec<Address>,
+}
+
+impl EnvelopeBuilder {
+    /// Sets the sender of the envelope
+    ///
+    /// Fails if `address` isn't a valid address.
+    pub fn from<A: IntoAddress>(mut self, address: A) -> Result<Self, AddressError> {
+        self.from = Some(address.into_address()?);
+        Ok(self)
+    }
+
+    /// Adds recipients to the envelope
+    ///
+    /// Fails if one of the `addresses` isn't a valid address.
+    pub fn to<I>(mut self, addresses: I) -> Result<Self, AddressError>
+    where
+        I: IntoIterator,
+        I::Item: IntoAddress,
+    {
+        for address in addresses {
+            self.to.push(address.into_address()?);
+        }
+        Ok(self)
+    }
+
+    /// Builds the envelope, which may fail if there are no recipients
+    pub fn build(self) -> Result<Envelope, Error> {
+        Envelope::new(self.from, self.to)
+    }
+}
+
+/// A value which can be used as an envelope address
+///
+/// Strings are parsed as addresses, and the address of a
+/// [`Mailbox`](crate::message::Mailbox) is used.
+pub trait IntoAddress {
+    /// Converts the value into an address
+    fn into_address(self) -> Result<Address, AddressError>;
+}
+
+impl IntoAddress for Address {
+    fn into_address(self) -> Result<Address, AddressError> {
+        Ok(self)
+    }
+}
+
+impl IntoAddress for &Address {
+    fn into_address(self) -> Result<Address, AddressError> {
+        Ok(self.clone())
+    }
+}
+
+impl IntoAddress for &str {
+    fn into_address(self) -> Result<Address, AddressError> {
+        self.parse()
+    }
+}
+
+impl IntoAddress for String {
+    fn into_address(self) -> Result<Address, AddressError> {
+        self.try_into()
+    }
+}
+
+impl IntoAddress for &String {
+    fn into_address(self) -> Result<Address, AddressError> {
+        self.parse()
+    }
+}
+
+#[cfg(feature = "builder")]
+impl IntoAddress for Mailbox {
+    fn into_address(self) -> Result<Address, AddressError> {
+        Ok(self.email)
+    }
+}
+
+#[cfg(feature = "builder")]
+impl IntoAddress for &Mailbox {
+    fn into_address(self) -> Result<Address, AddressError> {
+        Ok(self.email.clone())
+    }
+}
+
 #[cfg(feature = "builder")]
 impl TryFrom<&Headers> for Envelope {
     type Error = Error;
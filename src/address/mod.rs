@@ -5,8 +5,10 @@ mod serde;
 
 mod envelope;
 mod types;
+mod verp;
 
 pub use self::{
-    envelope::Envelope,
+    envelope::{Envelope, EnvelopeBuilder, IntoAddress},
     types::{Address, AddressError},
+    verp::Verp,
 };
@@ -110,6 +110,113 @@ impl Address {
         &self.serialized[self.at_start + 1..]
     }
 
+    /// Gets the sub-address tag of the user, like `tag` in `user+tag@example.com`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lettre::Address;
+    ///
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// let address = "user+newsletter@email.com".parse::<Address>()?;
+    /// assert_eq!(address.tag(), Some("newsletter"));
+    /// assert_eq!(address.without_tag().to_string(), "user@email.com");
+    /// assert_eq!(
+    ///     address.with_tag("billing")?.to_string(),
+    ///     "user+billing@email.com"
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn tag(&self) -> Option<&str> {
+        self.split_tag().1
+    }
+
+    /// Creates the address with `tag` as sub-address tag, replacing the current one
+    pub fn with_tag(&self, tag: &str) -> Result<Self, AddressError> {
+        Address::new(format!("{}+{tag}", self.split_tag().0), self.domain())
+    }
+
+    /// Creates the address without its sub-address tag
+    pub fn without_tag(&self) -> Self {
+        match self.split_tag() {
+            (user, Some(_)) => Address::new(user, self.domain()).unwrap_or_else(|_| self.clone()),
+            (_, None) => self.clone(),
+        }
+    }
+
+    /// Normalizes the address to identify the mailbox it delivers to
+    ///
+    /// The sub-address tag is removed and the address is lowercased.
+    /// For Gmail addresses, which ignore dots in the user, the dots are
+    /// removed and `googlemail.com` is replaced by `gmail.com`.
+    ///
+    /// The result is meant for comparing and deduplicating addresses,
+    /// sending to it may not work with servers handling the user as
+    /// case-sensitive.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lettre::Address;
+    ///
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// let address = "First.Last+promo@GoogleMail.com".parse::<Address>()?;
+    /// assert_eq!(address.normalized().to_string(), "firstlast@gmail.com");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn normalized(&self) -> Self {
+        let domain = self.domain().to_lowercase();
+        if self.user().starts_with('"') {
+            // Quoted users are left as is
+            return Address::new(self.user(), domain).unwrap_or_else(|_| self.clone());
+        }
+
+        let mut user = self.split_tag().0.to_lowercase();
+        let domain = match domain.as_str() {
+            "gmail.com" | "googlemail.com" => {
+                user.retain(|c| c != '.');
+                "gmail.com".to_owned()
+            }
+            _ => domain,
+        };
+        Address::new(user, domain).unwrap_or_else(|_| self.clone())
+    }
+
+    /// Whether both addresses deliver to the same mailbox,
+    /// comparing their [normalized](Self::normalized) forms
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lettre::Address;
+    ///
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// let address = "first.last@gmail.com".parse::<Address>()?;
+    /// assert!(address.is_same_mailbox(&"FirstLast+lettre@gmail.com".parse()?));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn is_same_mailbox(&self, other: &Address) -> bool {
+        self.normalized() == other.normalized()
+    }
+
+    /// Splits the user and its sub-address tag
+    fn split_tag(&self) -> (&str, Option<&str>) {
+        let user = self.user();
+        if user.starts_with('"') {
+            return (user, None);
+        }
+        match user.split_once('+') {
+            Some((user, tag)) if !user.is_empty() => (user, Some(tag)),
+            _ => (user, None),
+        }
+    }
+
     pub(super) fn check_user(user: &str) -> Result<(), AddressError> {
         if EmailAddress::is_valid_local_part(user) {
             Ok(())
@@ -296,6 +403,39 @@ mod tests {
         assert_eq!(addr2.domain(), "[2606:4700:4700::1111]");
     }
 
+    #[test]
+    fn sub_addressing() {
+        let addr = Address::from_str("user+tag+more@example.com").unwrap();
+        assert_eq!(addr.tag(), Some("tag+more"));
+        assert_eq!(addr.without_tag().to_string(), "user@example.com");
+        assert_eq!(
+            addr.with_tag("other").unwrap().to_string(),
+            "user+other@example.com"
+        );
+
+        for untagged in [
+            "user@example.com",
+            "+tag@example.com",
+            "\"a+b\"@example.com",
+        ] {
+            let addr = Address::from_str(untagged).unwrap();
+            assert_eq!(addr.tag(), None, "{untagged}");
+            assert_eq!(addr.without_tag(), addr);
+        }
+    }
+
+    #[test]
+    fn same_mailbox() {
+        let addr = Address::from_str("First.Last@Example.com").unwrap();
+        assert_eq!(addr.normalized().to_string(), "first.last@example.com");
+        assert!(addr.is_same_mailbox(&Address::from_str("first.last+tag@example.com").unwrap()));
+        assert!(!addr.is_same_mailbox(&Address::from_str("firstlast@example.com").unwrap()));
+
+        let gmail = Address::from_str("f.irst.last+tag@googlemail.com").unwrap();
+        assert_eq!(gmail.normalized().to_string(), "firstlast@gmail.com");
+        assert!(gmail.is_same_mailbox(&Address::from_str("FirstLast@gmail.com").unwrap()));
+    }
+
     #[test]
     fn check_parts() {
         assert!(Address::check_user("aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa").is_err());
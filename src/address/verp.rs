@@ -0,0 +1,120 @@
+//! Variable envelope return path (VERP) addresses
+
+use super::{Address, AddressError, Envelope};
+
+/// Generates and parses [VERP](https://en.wikipedia.org/wiki/Variable_envelope_return_path)
+/// envelope sender addresses
+///
+/// The recipient address is encoded in the envelope sender, like
+/// `bounces+user=example.com@mydomain`, so bounces can be attributed
+/// to the recipient they were sent to.
+/// Use it with [`Transport::send_with_envelope`](crate::Transport::send_with_envelope)
+/// to send a message to each recipient with its own envelope.
+///
+/// ```rust
+/// use lettre::address::{Address, Verp};
+///
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// let verp = Verp::new("bounces@mydomain".parse()?);
+/// let recipient = "user@example.com".parse::<Address>()?;
+///
+/// let sender = verp.address(&recipient)?;
+/// assert_eq!(sender.to_string(), "bounces+user=example.com@mydomain");
+/// assert_eq!(verp.recipient(&sender), Some(recipient));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Verp {
+    bounce: Address,
+}
+
+impl Verp {
+    /// Create VERP addresses based on the `bounce` address
+    pub fn new(bounce: Address) -> Self {
+        Self { bounce }
+    }
+
+    /// Encode `recipient` in an envelope sender address
+    ///
+    /// Fails if the resulting local part is invalid, for example
+    /// because it's too long.
+    pub fn address(&self, recipient: &Address) -> Result<Address, AddressError> {
+        Address::new(
+            format!(
+                "{}+{}={}",
+                self.bounce.user(),
+                recipient.user(),
+                recipient.domain()
+            ),
+            self.bounce.domain(),
+        )
+    }
+
+    /// Build an envelope sending to `recipient`, with a VERP envelope sender
+    pub fn envelope(&self, recipient: &Address) -> Result<Envelope, AddressError> {
+        let from = self.address(recipient)?;
+        Ok(Envelope::new(Some(from), vec![recipient.clone()]).expect("envelope has a recipient"))
+    }
+
+    /// Decode the recipient from an address generated by [`Verp::address`],
+    /// for example the recipient of a bounce
+    pub fn recipient(&self, address: &Address) -> Option<Address> {
+        if !address.domain().eq_ignore_ascii_case(self.bounce.domain()) {
+            return None;
+        }
+
+        let encoded = address
+            .user()
+            .strip_prefix(self.bounce.user())?
+            .strip_prefix('+')?;
+        let (user, domain) = encoded.rsplit_once('=')?;
+        Address::new(user, domain).ok()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Verp;
+    use crate::Address;
+
+    #[test]
+    fn verp_roundtrip() {
+        let verp = Verp::new("bounces@mydomain.com".parse().unwrap());
+        let recipient = "first.last+tag@example.com".parse::<Address>().unwrap();
+
+        let address = verp.address(&recipient).unwrap();
+        assert_eq!(
+            address.to_string(),
+            "bounces+first.last+tag=example.com@mydomain.com"
+        );
+        assert_eq!(verp.recipient(&address), Some(recipient.clone()));
+
+        let envelope = verp.envelope(&recipient).unwrap();
+        assert_eq!(envelope.from(), Some(&address));
+        assert_eq!(envelope.to(), [recipient]);
+    }
+
+    #[test]
+    fn verp_recipient_mismatch() {
+        let verp = Verp::new("bounces@mydomain.com".parse().unwrap());
+
+        for address in [
+            "bounces+user=example.com@otherdomain.com",
+            "other+user=example.com@mydomain.com",
+            "bounces@mydomain.com",
+            "bounces+user@mydomain.com",
+            "bouncesx+user=example.com@mydomain.com",
+        ] {
+            assert_eq!(verp.recipient(&address.parse().unwrap()), None, "{address}");
+        }
+    }
+
+    #[test]
+    fn verp_address_too_long() {
+        let verp = Verp::new("bounces@mydomain.com".parse().unwrap());
+        let recipient = Address::new("a".repeat(60), "example.com").unwrap();
+        assert!(verp.address(&recipient).is_err());
+    }
+}
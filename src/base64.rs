@@ -3,10 +3,68 @@ use ::base64::{
     DecodeError,
 };
 
+/// Maximum length of the lines of base64 encoded bodies
+#[cfg(feature = "builder")]
+const LINE_LEN: usize = 76;
+
+#[cfg(any(
+    feature = "smtp-transport",
+    feature = "dkim",
+    all(feature = "builder", feature = "serde")
+))]
 pub(crate) fn encode<T: AsRef<[u8]>>(input: T) -> String {
     STANDARD.encode(input)
 }
 
+/// Encodes `input` at the end of `out`, wrapping lines at 76 characters
+///
+/// The lines are separated by CRLF, the last one doesn't end with it.
+/// Writes straight into `out`, reserving all the space needed at once.
+#[cfg(feature = "builder")]
+pub(crate) fn encode_wrapped(input: &[u8], out: &mut Vec<u8>) {
+    let mut pos = out.len();
+    out.resize(pos + wrapped_len(input.len()), 0);
+
+    for (i, chunk) in input.chunks(LINE_LEN / 4 * 3).enumerate() {
+        if i > 0 {
+            out[pos..pos + 2].copy_from_slice(b"\r\n");
+            pos += 2;
+        }
+        pos += STANDARD
+            .encode_slice(chunk, &mut out[pos..])
+            .expect("output has room for the encoded line");
+    }
+    debug_assert_eq!(pos, out.len());
+}
+
+/// Length of `input_len` bytes encoded by [`encode_wrapped`]
+#[cfg(feature = "builder")]
+pub(crate) fn wrapped_len(input_len: usize) -> usize {
+    let encoded_len = (input_len + 2) / 3 * 4;
+    let line_breaks = encoded_len.saturating_sub(1) / LINE_LEN;
+    encoded_len + line_breaks * 2
+}
+
 pub(crate) fn decode<T: AsRef<[u8]>>(input: T) -> Result<Vec<u8>, DecodeError> {
     STANDARD.decode(input)
 }
+
+#[cfg(all(test, feature = "builder"))]
+mod test {
+    use super::{encode_wrapped, wrapped_len};
+
+    #[test]
+    fn encode_wrapped_lines() {
+        for len in [0, 1, 2, 3, 56, 57, 58, 113, 114, 115, 1000] {
+            let input = (0..len).map(|i| i as u8).collect::<Vec<u8>>();
+
+            let mut expected = String::new();
+            email_encoding::body::base64::encode(&input, &mut expected).unwrap();
+            let mut out = b"prefix".to_vec();
+            encode_wrapped(&input, &mut out);
+
+            assert_eq!(out[6..], *expected.as_bytes(), "{len} bytes");
+            assert_eq!(wrapped_len(len), expected.len(), "{len} bytes");
+        }
+    }
+}
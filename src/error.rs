@@ -27,6 +27,12 @@ pub enum Error {
     Io(std::io::Error),
     /// Non-ASCII chars
     NonAsciiChars,
+    /// The message doesn't comply with the [`AttachmentPolicy`] set on the builder
+    ///
+    /// [`AttachmentPolicy`]: crate::message::AttachmentPolicy
+    #[cfg(feature = "builder")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "builder")))]
+    Policy(crate::message::PolicyError),
 }
 
 impl Display for Error {
@@ -41,6 +47,8 @@ impl Display for Error {
             Error::CannotParseFilename => f.write_str("could not parse attachment filename"),
             Error::NonAsciiChars => f.write_str("contains non-ASCII chars"),
             Error::Io(e) => e.fmt(f),
+            #[cfg(feature = "builder")]
+            Error::Policy(e) => e.fmt(f),
         }
     }
 }
@@ -51,4 +59,11 @@ impl From<std::io::Error> for Error {
     }
 }
 
+#[cfg(feature = "builder")]
+impl From<crate::message::PolicyError> for Error {
+    fn from(err: crate::message::PolicyError) -> Error {
+        Error::Policy(err)
+    }
+}
+
 impl StdError for Error {}
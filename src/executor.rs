@@ -3,10 +3,12 @@ use std::fmt::Debug;
 use std::future::Future;
 #[cfg(feature = "file-transport")]
 use std::io::Result as IoResult;
+#[cfg(feature = "tokio1")]
+use std::panic;
 #[cfg(feature = "file-transport")]
 use std::path::Path;
 #[cfg(feature = "smtp-transport")]
-use std::time::Duration;
+use std::{net::SocketAddr, time::Duration};
 
 use async_trait::async_trait;
 #[cfg(all(feature = "smtp-transport", feature = "async-std1"))]
@@ -60,16 +62,25 @@ pub trait Executor: Debug + Send + Sync + 'static + private::Sealed {
     #[cfg(feature = "smtp-transport")]
     fn sleep(duration: Duration) -> Self::Sleep;
 
+    /// Connects to the `resolved` addresses of `hostname` if there are some
     #[doc(hidden)]
     #[cfg(feature = "smtp-transport")]
     async fn connect(
         hostname: &str,
         port: u16,
+        resolved: Option<&[SocketAddr]>,
         timeout: Option<Duration>,
         hello_name: &ClientId,
         tls: &Tls,
+        helo_fallback: bool,
     ) -> Result<AsyncSmtpConnection, Error>;
 
+    #[doc(hidden)]
+    async fn spawn_blocking<F, T>(f: F) -> T
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static;
+
     #[doc(hidden)]
     #[cfg(feature = "file-transport-envelope")]
     async fn fs_read(path: &Path) -> IoResult<Vec<u8>>;
@@ -77,6 +88,10 @@ pub trait Executor: Debug + Send + Sync + 'static + private::Sealed {
     #[doc(hidden)]
     #[cfg(feature = "file-transport")]
     async fn fs_write(path: &Path, contents: &[u8]) -> IoResult<()>;
+
+    #[doc(hidden)]
+    #[cfg(feature = "file-transport")]
+    async fn fs_create_dir_all(path: &Path) -> IoResult<()>;
 }
 
 #[doc(hidden)]
@@ -127,9 +142,11 @@ impl Executor for Tokio1Executor {
     async fn connect(
         hostname: &str,
         port: u16,
+        resolved: Option<&[SocketAddr]>,
         timeout: Option<Duration>,
         hello_name: &ClientId,
         tls: &Tls,
+        helo_fallback: bool,
     ) -> Result<AsyncSmtpConnection, Error> {
         #[allow(clippy::match_single_binding)]
         let tls_parameters = match tls {
@@ -138,21 +155,35 @@ impl Executor for Tokio1Executor {
             _ => None,
         };
         #[allow(unused_mut)]
-        let mut conn = AsyncSmtpConnection::connect_tokio1(
-            (hostname, port),
-            timeout,
-            hello_name,
-            tls_parameters,
-            None,
-        )
-        .await?;
+        let mut conn = match resolved {
+            Some(addrs) => {
+                AsyncSmtpConnection::connect_tokio1_with_fallback(
+                    addrs,
+                    timeout,
+                    hello_name,
+                    tls_parameters,
+                    None,
+                    helo_fallback,
+                )
+                .await?
+            }
+            None => {
+                AsyncSmtpConnection::connect_tokio1_with_fallback(
+                    (hostname, port),
+                    timeout,
+                    hello_name,
+                    tls_parameters,
+                    None,
+                    helo_fallback,
+                )
+                .await?
+            }
+        };
 
         #[cfg(any(feature = "tokio1-native-tls", feature = "tokio1-rustls-tls"))]
         match tls {
-            Tls::Opportunistic(tls_parameters) => {
-                if conn.can_starttls() {
-                    conn.starttls(tls_parameters.clone(), hello_name).await?;
-                }
+            Tls::Opportunistic(tls_parameters) if conn.can_starttls() => {
+                conn.starttls(tls_parameters.clone(), hello_name).await?;
             }
             Tls::Required(tls_parameters) => {
                 conn.starttls(tls_parameters.clone(), hello_name).await?;
@@ -163,6 +194,20 @@ impl Executor for Tokio1Executor {
         Ok(conn)
     }
 
+    async fn spawn_blocking<F, T>(f: F) -> T
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        match tokio1_crate::task::spawn_blocking(f).await {
+            Ok(output) => output,
+            Err(err) => match err.try_into_panic() {
+                Ok(payload) => panic::resume_unwind(payload),
+                Err(err) => panic!("blocking task failed: {err}"),
+            },
+        }
+    }
+
     #[cfg(feature = "file-transport-envelope")]
     async fn fs_read(path: &Path) -> IoResult<Vec<u8>> {
         tokio1_crate::fs::read(path).await
@@ -172,6 +217,11 @@ impl Executor for Tokio1Executor {
     async fn fs_write(path: &Path, contents: &[u8]) -> IoResult<()> {
         tokio1_crate::fs::write(path, contents).await
     }
+
+    #[cfg(feature = "file-transport")]
+    async fn fs_create_dir_all(path: &Path) -> IoResult<()> {
+        tokio1_crate::fs::create_dir_all(path).await
+    }
 }
 
 #[cfg(all(feature = "smtp-transport", feature = "tokio1"))]
@@ -224,9 +274,11 @@ impl Executor for AsyncStd1Executor {
     async fn connect(
         hostname: &str,
         port: u16,
+        resolved: Option<&[SocketAddr]>,
         timeout: Option<Duration>,
         hello_name: &ClientId,
         tls: &Tls,
+        helo_fallback: bool,
     ) -> Result<AsyncSmtpConnection, Error> {
         #[allow(clippy::match_single_binding)]
         let tls_parameters = match tls {
@@ -235,20 +287,33 @@ impl Executor for AsyncStd1Executor {
             _ => None,
         };
         #[allow(unused_mut)]
-        let mut conn = AsyncSmtpConnection::connect_asyncstd1(
-            (hostname, port),
-            timeout,
-            hello_name,
-            tls_parameters,
-        )
-        .await?;
+        let mut conn = match resolved {
+            Some(addrs) => {
+                AsyncSmtpConnection::connect_asyncstd1_with_fallback(
+                    addrs,
+                    timeout,
+                    hello_name,
+                    tls_parameters,
+                    helo_fallback,
+                )
+                .await?
+            }
+            None => {
+                AsyncSmtpConnection::connect_asyncstd1_with_fallback(
+                    (hostname, port),
+                    timeout,
+                    hello_name,
+                    tls_parameters,
+                    helo_fallback,
+                )
+                .await?
+            }
+        };
 
         #[cfg(feature = "async-std1-rustls-tls")]
         match tls {
-            Tls::Opportunistic(tls_parameters) => {
-                if conn.can_starttls() {
-                    conn.starttls(tls_parameters.clone(), hello_name).await?;
-                }
+            Tls::Opportunistic(tls_parameters) if conn.can_starttls() => {
+                conn.starttls(tls_parameters.clone(), hello_name).await?;
             }
             Tls::Required(tls_parameters) => {
                 conn.starttls(tls_parameters.clone(), hello_name).await?;
@@ -259,6 +324,14 @@ impl Executor for AsyncStd1Executor {
         Ok(conn)
     }
 
+    async fn spawn_blocking<F, T>(f: F) -> T
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        async_std::task::spawn_blocking(f).await
+    }
+
     #[cfg(feature = "file-transport-envelope")]
     async fn fs_read(path: &Path) -> IoResult<Vec<u8>> {
         async_std::fs::read(path).await
@@ -268,6 +341,11 @@ impl Executor for AsyncStd1Executor {
     async fn fs_write(path: &Path, contents: &[u8]) -> IoResult<()> {
         async_std::fs::write(path, contents).await
     }
+
+    #[cfg(feature = "file-transport")]
+    async fn fs_create_dir_all(path: &Path) -> IoResult<()> {
+        async_std::fs::create_dir_all(path).await
+    }
 }
 
 #[cfg(all(feature = "smtp-transport", feature = "async-std1"))]
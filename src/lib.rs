@@ -29,6 +29,7 @@
 //! * **smtp-transport** 📫: Enable the SMTP transport
 //! * **pool** 📫: Connection pool for SMTP transport
 //! * **hostname** 📫: Try to use the actual system hostname for the SMTP `CLIENTID`
+//! * **oauth2-helpers**: Authenticate with refreshed OAuth 2.0 tokens using `XOAUTH2`
 //!
 //! #### SMTP over TLS via the native-tls crate
 //!
@@ -95,11 +96,15 @@
 //! * **tracing**: Logging using the `tracing` crate
 //! * **mime03**: Allow creating a [`ContentType`] from an existing [mime 0.3] `Mime` struct
 //! * **dkim**: Add support for signing email with DKIM
+//! * **infer**: Detect the content type of attachments from their content with the `infer` crate
+//! * **axum**: Return the [`HealthCheck`] of an SMTP transport from an [axum] 0.7 handler
 //!
 //! [`SMTP`]: crate::transport::smtp
 //! [`sendmail`]: crate::transport::sendmail
 //! [`file`]: crate::transport::file
 //! [`ContentType`]: crate::message::header::ContentType
+//! [`HealthCheck`]: crate::transport::smtp::health::HealthCheck
+//! [axum]: https://docs.rs/axum/0.7
 //! [tokio]: https://docs.rs/tokio/1
 //! [async-std]: https://docs.rs/async-std/1
 //! [ring]: https://github.com/briansmith/ring#ring
@@ -125,7 +130,6 @@
     clippy::clone_on_ref_ptr,
     clippy::verbose_file_reads,
     clippy::unnecessary_self_imports,
-    clippy::string_to_string,
     clippy::mem_forget,
     clippy::cast_lossless,
     clippy::inefficient_to_string,
@@ -191,14 +195,20 @@ Make sure to apply the same to any of your crate dependencies that use the `lett
 }
 
 pub mod address;
-#[cfg(any(feature = "smtp-transport", feature = "dkim"))]
+#[cfg(any(feature = "builder", feature = "smtp-transport", feature = "dkim"))]
 mod base64;
 pub mod error;
 #[cfg(any(feature = "tokio1", feature = "async-std1"))]
 mod executor;
+pub mod lint;
 #[cfg(feature = "builder")]
 #[cfg_attr(docsrs, doc(cfg(feature = "builder")))]
 pub mod message;
+#[cfg(feature = "builder")]
+mod quoted_printable;
+#[cfg(feature = "builder")]
+#[cfg_attr(docsrs, doc(cfg(feature = "builder")))]
+pub mod testing;
 pub mod transport;
 
 use std::error::Error as StdError;
@@ -304,4 +314,37 @@ mod test {
 
         assert!(Envelope::try_from(&headers).is_err(),);
     }
+
+    #[test]
+    fn envelope_builder() {
+        let mailbox: Mailbox = "Amousset <amousset@example.com>".parse().unwrap();
+        let envelope = Envelope::builder()
+            .from("kayo@example.com")
+            .unwrap()
+            .to([Address::new("kayo2", "example.com").unwrap()])
+            .unwrap()
+            .to([mailbox])
+            .unwrap()
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            envelope,
+            Envelope::new(
+                Some(Address::new("kayo", "example.com").unwrap()),
+                vec![
+                    Address::new("kayo2", "example.com").unwrap(),
+                    Address::new("amousset", "example.com").unwrap()
+                ]
+            )
+            .unwrap()
+        );
+
+        assert!(Envelope::builder().to(["kayo"]).is_err());
+        assert!(Envelope::builder()
+            .from("kayo@example.com")
+            .unwrap()
+            .build()
+            .is_err());
+    }
 }
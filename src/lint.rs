@@ -0,0 +1,214 @@
+//! Checks of raw messages before relaying them
+//!
+//! Messages coming from other systems, sent with
+//! [`Transport::send_raw`](crate::Transport::send_raw), don't go through the
+//! message builder. [`check_raw`] finds what could get them rejected or
+//! mangled on the way, for CI pipelines and gateways to report it.
+//!
+//! ```rust
+//! use lettre::lint::{check_raw, FindingKind};
+//!
+//! let findings = check_raw(b"Subject: Hi\n\n.\n");
+//! let kinds = findings.iter().map(|f| f.kind()).collect::<Vec<_>>();
+//! assert_eq!(
+//!     kinds,
+//!     [
+//!         FindingKind::BareLf,
+//!         FindingKind::LeadingDot,
+//!         FindingKind::MissingHeader("From"),
+//!         FindingKind::MissingHeader("Date"),
+//!     ]
+//! );
+//! assert_eq!(
+//!     findings[0].to_string(),
+//!     "line 1: bare LF line ending (3 lines)"
+//! );
+//! ```
+
+use std::fmt::{self, Display};
+
+/// Headers required by [RFC5322](https://tools.ietf.org/html/rfc5322#section-3.6)
+const REQUIRED_HEADERS: [&str; 2] = ["From", "Date"];
+
+/// A kind of problem found by [`check_raw`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum FindingKind {
+    /// A line starting with a dot, which ends the message early if
+    /// it isn't dot-stuffed when sent with `DATA`
+    LeadingDot,
+    /// A line ending with LF instead of CRLF
+    BareLf,
+    /// A CR not followed by LF
+    BareCr,
+    /// A byte outside of ASCII, which needs the `8BITMIME` extension
+    EightBit,
+    /// A header required in all messages is missing
+    MissingHeader(&'static str),
+}
+
+impl Display for FindingKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FindingKind::LeadingDot => f.write_str("line starting with a dot"),
+            FindingKind::BareLf => f.write_str("bare LF line ending"),
+            FindingKind::BareCr => f.write_str("bare CR"),
+            FindingKind::EightBit => f.write_str("8-bit data without 8BITMIME"),
+            FindingKind::MissingHeader(name) => write!(f, "missing {name} header"),
+        }
+    }
+}
+
+/// A problem found by [`check_raw`], with the lines it was found on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Finding {
+    kind: FindingKind,
+    line: Option<usize>,
+    lines: usize,
+}
+
+impl Finding {
+    /// What was found
+    pub fn kind(&self) -> FindingKind {
+        self.kind
+    }
+
+    /// The first line it was found on, starting from 1
+    pub fn line(&self) -> Option<usize> {
+        self.line
+    }
+
+    /// The number of lines it was found on
+    pub fn lines(&self) -> usize {
+        self.lines
+    }
+}
+
+impl Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(line) = self.line {
+            write!(f, "line {line}: ")?;
+        }
+        Display::fmt(&self.kind, f)?;
+        if self.lines > 1 {
+            write!(f, " ({} lines)", self.lines)?;
+        }
+        Ok(())
+    }
+}
+
+/// Checks the raw message `raw`, returning a finding for each kind of problem
+///
+/// Findings on lines come first, in the order of the line they were
+/// first found on.
+pub fn check_raw(raw: &[u8]) -> Vec<Finding> {
+    let mut findings = Vec::<Finding>::new();
+    let mut report = |kind, line| match findings.iter_mut().find(|f| f.kind == kind) {
+        Some(finding) => finding.lines += 1,
+        None => findings.push(Finding {
+            kind,
+            line: Some(line),
+            lines: 1,
+        }),
+    };
+
+    let mut in_headers = true;
+    let mut header_names = Vec::new();
+    for (i, line) in raw.split_inclusive(|&b| b == b'\n').enumerate() {
+        let line_number = i + 1;
+        let content = match line.strip_suffix(b"\n") {
+            Some(content) => content.strip_suffix(b"\r").unwrap_or_else(|| {
+                report(FindingKind::BareLf, line_number);
+                content
+            }),
+            None => line,
+        };
+
+        if content.starts_with(b".") {
+            report(FindingKind::LeadingDot, line_number);
+        }
+        if content.contains(&b'\r') {
+            report(FindingKind::BareCr, line_number);
+        }
+        if !content.is_ascii() {
+            report(FindingKind::EightBit, line_number);
+        }
+
+        if in_headers {
+            if content.is_empty() {
+                in_headers = false;
+            } else if let Some(colon) = content.iter().position(|&b| b == b':') {
+                header_names.push(&content[..colon]);
+            }
+        }
+    }
+
+    for name in REQUIRED_HEADERS {
+        if !header_names
+            .iter()
+            .any(|header| header.eq_ignore_ascii_case(name.as_bytes()))
+        {
+            findings.push(Finding {
+                kind: FindingKind::MissingHeader(name),
+                line: None,
+                lines: 0,
+            });
+        }
+    }
+
+    findings
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use super::{check_raw, FindingKind};
+
+    #[test]
+    fn clean_message() {
+        let raw = concat!(
+            "From: NoBody <nobody@domain.tld>\r\n",
+            "date: Tue, 15 Nov 1994 08:12:31 +0000\r\n",
+            "Subject: Happy new year\r\n",
+            "\r\n",
+            "Be happy!\r\n",
+            "From: not a header\r\n",
+        );
+        assert_eq!(check_raw(raw.as_bytes()), []);
+    }
+
+    #[test]
+    fn findings() {
+        let raw = concat!(
+            "From: NoBody <nobody@domain.tld>\r\n",
+            "Subject: Happy new year\r\n",
+            "\r\n",
+            "Be happy!\n",
+            ".\r\n",
+            "Ünïcödé\r\n",
+            "..and\rmore\n",
+            "Date: in the body\r\n",
+        );
+        let findings = check_raw(raw.as_bytes());
+        let found = findings
+            .iter()
+            .map(|f| (f.kind(), f.line(), f.lines()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            found,
+            [
+                (FindingKind::BareLf, Some(4), 2),
+                (FindingKind::LeadingDot, Some(5), 2),
+                (FindingKind::EightBit, Some(6), 1),
+                (FindingKind::BareCr, Some(7), 1),
+                (FindingKind::MissingHeader("Date"), None, 0),
+            ]
+        );
+        assert_eq!(
+            findings[2].to_string(),
+            "line 6: 8-bit data without 8BITMIME"
+        );
+        assert_eq!(findings[4].to_string(), "missing Date header");
+    }
+}
@@ -0,0 +1,304 @@
+//! Abuse Reporting Format (ARF) feedback reports
+//!
+//! Builds `multipart/report; report-type=feedback-report` bodies, as defined in
+//! [RFC5965](https://tools.ietf.org/html/rfc5965), made of a human readable
+//! description, a machine readable `message/feedback-report` part and the
+//! original message.
+//!
+//! ```rust
+//! # use std::error::Error;
+//! use lettre::{
+//!     message::arf::{FeedbackReport, FeedbackType},
+//!     Message,
+//! };
+//!
+//! # fn main() -> Result<(), Box<dyn Error>> {
+//! let original = Message::builder()
+//!     .from("Spammer <spammer@example.com>".parse()?)
+//!     .to("Victim <victim@example.org>".parse()?)
+//!     .subject("Cheap watches")
+//!     .body(String::from("Buy now!"))?;
+//!
+//! let report = FeedbackReport::new(FeedbackType::Abuse, String::from("SomeGenerator/1.0"))
+//!     .original_mail_from("spammer@example.com".parse()?)
+//!     .original_rcpt_to("victim@example.org".parse()?)
+//!     .source_ip([192, 0, 2, 1].into());
+//!
+//! let email = Message::builder()
+//!     .from("Abuse desk <abuse@example.org>".parse()?)
+//!     .to("Abuse <abuse@example.com>".parse()?)
+//!     .subject("FW: Cheap watches")
+//!     .multipart(report.multipart(
+//!         String::from(
+//!             "This is an email abuse report for an email message received from IP 192.0.2.1",
+//!         ),
+//!         &original,
+//!     ))?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{
+    fmt::{self, Display, Write},
+    net::IpAddr,
+    time::SystemTime,
+};
+
+use crate::{
+    message::{
+        header::{ContentType, Date, Header},
+        MultiPart, SinglePart,
+    },
+    Address, Message,
+};
+
+/// Type of feedback being reported
+///
+/// Registered in the [IANA Feedback Report Type Values](https://www.iana.org/assignments/marf-parameters/marf-parameters.xhtml) registry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum FeedbackType {
+    /// Unsolicited email or some other kind of email abuse
+    Abuse,
+    /// Report of an authentication failure, defined in [RFC6591](https://tools.ietf.org/html/rfc6591)
+    AuthFailure,
+    /// Indicates some kind of fraud or phishing activity
+    Fraud,
+    /// Indicates that the message isn't spam, defined in [RFC6430](https://tools.ietf.org/html/rfc6430)
+    NotSpam,
+    /// Any other feedback that doesn't fit into other registered types
+    Other,
+    /// Report of a virus found in the originating message
+    Virus,
+}
+
+impl Display for FeedbackType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Abuse => "abuse",
+            Self::AuthFailure => "auth-failure",
+            Self::Fraud => "fraud",
+            Self::NotSpam => "not-spam",
+            Self::Other => "other",
+            Self::Virus => "virus",
+        })
+    }
+}
+
+/// Machine readable part of a feedback report
+///
+/// Only the `Feedback-Type`, `User-Agent` and `Version` fields are required,
+/// the others are added when set.
+#[derive(Debug, Clone)]
+pub struct FeedbackReport {
+    feedback_type: FeedbackType,
+    user_agent: String,
+    original_mail_from: Option<Address>,
+    original_rcpt_to: Vec<Address>,
+    arrival_date: Option<SystemTime>,
+    reporting_mta: Option<String>,
+    source_ip: Option<IpAddr>,
+    incidents: Option<u32>,
+    authentication_results: Vec<String>,
+    reported_domain: Vec<String>,
+    reported_uri: Vec<String>,
+}
+
+impl FeedbackReport {
+    /// Create a new report, `user_agent` being the name and version
+    /// of the software generating it
+    pub fn new(feedback_type: FeedbackType, user_agent: String) -> Self {
+        Self {
+            feedback_type,
+            user_agent,
+            original_mail_from: None,
+            original_rcpt_to: Vec::new(),
+            arrival_date: None,
+            reporting_mta: None,
+            source_ip: None,
+            incidents: None,
+            authentication_results: Vec::new(),
+            reported_domain: Vec::new(),
+            reported_uri: Vec::new(),
+        }
+    }
+
+    /// Set the envelope sender of the original message
+    pub fn original_mail_from(mut self, address: Address) -> Self {
+        self.original_mail_from = Some(address);
+        self
+    }
+
+    /// Add an envelope recipient of the original message
+    pub fn original_rcpt_to(mut self, address: Address) -> Self {
+        self.original_rcpt_to.push(address);
+        self
+    }
+
+    /// Set the date the original message was received
+    pub fn arrival_date(mut self, date: SystemTime) -> Self {
+        self.arrival_date = Some(date);
+        self
+    }
+
+    /// Set the MTA generating the report, as a host name
+    pub fn reporting_mta(mut self, host: String) -> Self {
+        self.reporting_mta = Some(host);
+        self
+    }
+
+    /// Set the IP address from which the original message was received
+    pub fn source_ip(mut self, ip: IpAddr) -> Self {
+        self.source_ip = Some(ip);
+        self
+    }
+
+    /// Set the number of incidents this report represents
+    pub fn incidents(mut self, incidents: u32) -> Self {
+        self.incidents = Some(incidents);
+        self
+    }
+
+    /// Add the result of an authentication check, as found in an
+    /// `Authentication-Results` header
+    pub fn authentication_results(mut self, results: String) -> Self {
+        self.authentication_results.push(results);
+        self
+    }
+
+    /// Add a domain name the report generator believes to be relevant
+    pub fn reported_domain(mut self, domain: String) -> Self {
+        self.reported_domain.push(domain);
+        self
+    }
+
+    /// Add a URI the report generator believes to be relevant
+    pub fn reported_uri(mut self, uri: String) -> Self {
+        self.reported_uri.push(uri);
+        self
+    }
+
+    /// Build the `message/feedback-report` part
+    pub fn part(&self) -> SinglePart {
+        SinglePart::builder()
+            .header(
+                ContentType::parse("message/feedback-report")
+                    .expect("message/feedback-report is a valid content type"),
+            )
+            .body(self.to_string())
+    }
+
+    /// Build the whole `multipart/report` body, containing a human readable
+    /// `description`, this report and the `original` message
+    pub fn multipart(&self, description: String, original: &Message) -> MultiPart {
+        MultiPart::report(String::from("feedback-report"))
+            .singlepart(SinglePart::plain(description))
+            .singlepart(self.part())
+            .singlepart(SinglePart::rfc822(original))
+    }
+}
+
+impl Display for FeedbackReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Feedback-Type: {}", self.feedback_type)?;
+        writeln!(f, "User-Agent: {}", self.user_agent)?;
+        writeln!(f, "Version: 1")?;
+        if let Some(address) = &self.original_mail_from {
+            writeln!(f, "Original-Mail-From: <{address}>")?;
+        }
+        for address in &self.original_rcpt_to {
+            writeln!(f, "Original-Rcpt-To: <{address}>")?;
+        }
+        if let Some(date) = self.arrival_date {
+            let date = Date::new(date).display();
+            f.write_str("Arrival-Date: ")?;
+            f.write_str(date.get_raw())?;
+            f.write_char('\n')?;
+        }
+        if let Some(host) = &self.reporting_mta {
+            writeln!(f, "Reporting-MTA: dns; {host}")?;
+        }
+        if let Some(ip) = &self.source_ip {
+            writeln!(f, "Source-IP: {ip}")?;
+        }
+        if let Some(incidents) = self.incidents {
+            writeln!(f, "Incidents: {incidents}")?;
+        }
+        for results in &self.authentication_results {
+            writeln!(f, "Authentication-Results: {results}")?;
+        }
+        for domain in &self.reported_domain {
+            writeln!(f, "Reported-Domain: {domain}")?;
+        }
+        for uri in &self.reported_uri {
+            writeln!(f, "Reported-Uri: {uri}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::SystemTime;
+
+    use pretty_assertions::assert_eq;
+
+    use super::{FeedbackReport, FeedbackType};
+    use crate::message::{header::ContentType, Message};
+
+    #[test]
+    fn format_feedback_report() {
+        let report = FeedbackReport::new(FeedbackType::Abuse, String::from("SomeGenerator/1.0"))
+            .original_mail_from("spammer@example.com".parse().unwrap())
+            .original_rcpt_to("victim@example.org".parse().unwrap())
+            .arrival_date(SystemTime::UNIX_EPOCH)
+            .reporting_mta(String::from("mail.example.org"))
+            .source_ip([192, 0, 2, 1].into())
+            .incidents(2)
+            .reported_domain(String::from("example.com"));
+
+        assert_eq!(
+            String::from_utf8(report.part().formatted()).unwrap(),
+            concat!(
+                "Content-Type: message/feedback-report\r\n",
+                "Content-Transfer-Encoding: 7bit\r\n",
+                "\r\n",
+                "Feedback-Type: abuse\r\n",
+                "User-Agent: SomeGenerator/1.0\r\n",
+                "Version: 1\r\n",
+                "Original-Mail-From: <spammer@example.com>\r\n",
+                "Original-Rcpt-To: <victim@example.org>\r\n",
+                "Arrival-Date: Thu, 01 Jan 1970 00:00:00 +0000\r\n",
+                "Reporting-MTA: dns; mail.example.org\r\n",
+                "Source-IP: 192.0.2.1\r\n",
+                "Incidents: 2\r\n",
+                "Reported-Domain: example.com\r\n",
+                "\r\n"
+            )
+        );
+    }
+
+    #[test]
+    fn feedback_report_multipart() {
+        let original = Message::builder()
+            .from("Spammer <spammer@example.com>".parse().unwrap())
+            .to("Victim <victim@example.org>".parse().unwrap())
+            .body(String::from("Buy now!"))
+            .unwrap();
+
+        let multipart = FeedbackReport::new(FeedbackType::Fraud, String::from("Test/1.0"))
+            .multipart(String::from("A phishing attempt"), &original);
+
+        let content_type = multipart.headers().get::<ContentType>().unwrap();
+        assert_eq!(content_type.as_ref().subtype(), "report");
+        assert_eq!(
+            content_type.as_ref().get_param("report-type").unwrap(),
+            "feedback-report"
+        );
+
+        let formatted = String::from_utf8(multipart.formatted()).unwrap();
+        assert!(formatted.contains("Content-Type: text/plain; charset=utf-8\r\n"));
+        assert!(formatted.contains("Feedback-Type: fraud\r\n"));
+        assert!(formatted.contains(&String::from_utf8(original.formatted()).unwrap()));
+    }
+}
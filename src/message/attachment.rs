@@ -1,6 +1,7 @@
 use crate::message::{
     header::{self, ContentType},
-    IntoBody, SinglePart,
+    mimebody::{rfc822_body, rfc822_content_type},
+    IntoBody, Message, SinglePart,
 };
 
 /// `SinglePart` builder for attachments
@@ -85,6 +86,32 @@ impl Attachment {
         }
     }
 
+    /// Create a new inline attachment with a random content id
+    ///
+    /// Returns the attachment along with its content id, generated by
+    /// [`ContentId::random`](header::ContentId::random), to be referenced
+    /// from the message body:
+    ///
+    /// ```rust
+    /// # use std::error::Error;
+    /// use std::fs;
+    ///
+    /// use lettre::message::{header::ContentType, Attachment, MultiPart, SinglePart};
+    ///
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// let filebody = fs::read("docs/lettre.png")?;
+    /// let (attachment, content_id) = Attachment::new_inline_random();
+    /// let part = MultiPart::related()
+    ///     .singlepart(SinglePart::html(format!(r#"<img src="cid:{content_id}">"#)))
+    ///     .singlepart(attachment.body(filebody, ContentType::parse("image/png")?));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn new_inline_random() -> (Self, String) {
+        let content_id = header::ContentId::random().id().to_owned();
+        (Self::new_inline(content_id.clone()), content_id)
+    }
+
     /// Build the attachment into a [`SinglePart`] which can then be used to build the rest of the email
     ///
     /// Look at the [Complex MIME body example](crate::message#complex-mime-body)
@@ -102,6 +129,87 @@ impl Attachment {
         builder = builder.header(content_type);
         builder.body(content)
     }
+
+    /// Build the attachment into a [`SinglePart`], detecting the content type of `content`
+    ///
+    /// The content type is detected from the magic bytes of `content`, then from the
+    /// extension of the file name for common textual formats, falling back to
+    /// `application/octet-stream`. Prefer [`Attachment::body`] when the content
+    /// type is known.
+    ///
+    /// ```rust
+    /// # use std::error::Error;
+    /// use std::fs;
+    ///
+    /// use lettre::message::{header::ContentType, Attachment};
+    ///
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// let filebody = fs::read("docs/lettre.png")?;
+    /// let attachment = Attachment::new(String::from("lettre.png")).body_auto(filebody);
+    ///
+    /// assert_eq!(
+    ///     attachment.headers().get::<ContentType>(),
+    ///     Some(ContentType::parse("image/png")?)
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "infer")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "infer")))]
+    pub fn body_auto(self, content: Vec<u8>) -> SinglePart {
+        let filename = match &self.disposition {
+            Disposition::Attached(filename) => Some(filename.as_str()),
+            Disposition::Inline(_) => None,
+        };
+        let content_type = sniff_content_type(filename, &content);
+        self.body(content, content_type)
+    }
+
+    /// Build the attachment into a `message/rfc822` [`SinglePart`] embedding `message`
+    ///
+    /// The message is included as formatted, without being re-encoded,
+    /// allowing to forward it as an attachment.
+    ///
+    /// Shortcut for building [`SinglePart::rfc822`] with this attachment's disposition.
+    pub fn rfc822(self, message: &Message) -> SinglePart {
+        self.body(rfc822_body(message), rfc822_content_type())
+    }
+}
+
+/// Detect the content type of an attachment, see [`Attachment::body_auto`]
+#[cfg(feature = "infer")]
+fn sniff_content_type(filename: Option<&str>, content: &[u8]) -> ContentType {
+    const TEXTUAL: &[(&str, &str)] = &[
+        ("txt", "text/plain"),
+        ("csv", "text/csv"),
+        ("htm", "text/html"),
+        ("html", "text/html"),
+        ("ics", "text/calendar"),
+        ("json", "application/json"),
+        ("xml", "application/xml"),
+    ];
+
+    if let Some(content_type) =
+        infer::get(content).and_then(|kind| ContentType::parse(kind.mime_type()).ok())
+    {
+        return content_type;
+    }
+
+    let extension = filename
+        .and_then(|filename| filename.rsplit_once('.'))
+        .map(|(_, extension)| extension);
+    let textual = TEXTUAL
+        .iter()
+        .find(|(ext, _)| extension.is_some_and(|extension| extension.eq_ignore_ascii_case(ext)));
+    match textual {
+        Some((_, mime)) if mime.starts_with("text/") && std::str::from_utf8(content).is_ok() => {
+            ContentType::parse(&format!("{mime}; charset=utf-8"))
+                .expect("valid textual content type")
+        }
+        Some((_, mime)) => ContentType::parse(mime).expect("valid textual content type"),
+        None => ContentType::parse("application/octet-stream")
+            .expect("application/octet-stream is a valid content type"),
+    }
 }
 
 #[cfg(test)]
@@ -142,4 +250,57 @@ mod tests {
             )
         );
     }
+
+    #[test]
+    fn attachment_rfc822() {
+        let message = crate::Message::builder()
+            .from("NoBody <nobody@domain.tld>".parse().unwrap())
+            .to("Hei <hei@domain.tld>".parse().unwrap())
+            .date(std::time::SystemTime::UNIX_EPOCH)
+            .body(String::from("Hello world!"))
+            .unwrap();
+
+        let part = super::Attachment::new(String::from("original.eml")).rfc822(&message);
+        assert_eq!(
+            &String::from_utf8_lossy(&part.formatted()),
+            concat!(
+                "Content-Disposition: attachment; filename=\"original.eml\"\r\n",
+                "Content-Type: message/rfc822\r\n",
+                "Content-Transfer-Encoding: 7bit\r\n\r\n",
+                "From: NoBody <nobody@domain.tld>\r\n",
+                "To: Hei <hei@domain.tld>\r\n",
+                "Date: Thu, 01 Jan 1970 00:00:00 +0000\r\n",
+                "Content-Transfer-Encoding: 7bit\r\n\r\n",
+                "Hello world!\r\n"
+            )
+        );
+    }
+
+    #[cfg(feature = "infer")]
+    #[test]
+    fn attachment_body_auto() {
+        for (filename, content, content_type) in [
+            (
+                "image",
+                &b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR"[..],
+                "image/png",
+            ),
+            ("report.PDF", b"%PDF-1.7\n", "application/pdf"),
+            (
+                "notes.txt",
+                "Ciao, è tutto".as_bytes(),
+                "text/plain; charset=utf-8",
+            ),
+            ("data.json", b"{}", "application/json"),
+            ("data.bin", b"\0\x01\x02", "application/octet-stream"),
+            ("notes", b"Hello", "application/octet-stream"),
+        ] {
+            let part = super::Attachment::new(String::from(filename)).body_auto(content.to_vec());
+            assert_eq!(
+                part.headers().get::<ContentType>(),
+                Some(ContentType::parse(content_type).unwrap()),
+                "{filename}"
+            );
+        }
+    }
 }
@@ -9,6 +9,68 @@ pub struct Body {
     encoding: ContentTransferEncoding,
 }
 
+/// Tunes the automatic choice of the `Content-Transfer-Encoding` of a [`Body`]
+///
+/// The default policy makes the same choice as [`Body::new`]. Use it with
+/// [`Body::new_with_policy`] to trade size for compatibility, depending on
+/// the receivers of the messages.
+///
+/// ```rust
+/// use lettre::message::{header::ContentTransferEncoding, Body, EncodingPolicy};
+///
+/// // Mostly text with a few accents, quoted-printable keeps it readable
+/// let policy = EncodingPolicy::new().quoted_printable_max_escaped(50);
+/// let body = Body::new_with_policy(String::from("Ça été très très bien"), policy).unwrap();
+/// assert_eq!(body.encoding(), ContentTransferEncoding::QuotedPrintable);
+///
+/// // Receivers only accepting 7bit
+/// let policy = EncodingPolicy::new().seven_bit_only(true);
+/// assert!(Body::new_with_policy(String::from("Ünïcödé"), policy).is_err());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct EncodingPolicy {
+    quoted_printable_max_escaped: Option<u8>,
+    base64_threshold: Option<usize>,
+    seven_bit_only: bool,
+}
+
+impl EncodingPolicy {
+    /// Creates the default policy
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the maximum percentage of bytes needing to be escaped for a text
+    /// to be encoded as `quoted-printable` rather than `base64`
+    ///
+    /// By default, the choice is the same as with [`Body::new`], which
+    /// prefers `quoted-printable` when about a third of the bytes at most
+    /// need to be escaped. Set it to 100 to always prefer `quoted-printable`
+    /// for texts, and to 0 to always prefer `base64`.
+    pub fn quoted_printable_max_escaped(mut self, percent: u8) -> Self {
+        self.quoted_printable_max_escaped = Some(percent.min(100));
+        self
+    }
+
+    /// Encodes binary data of at least `size` bytes as `base64`, even if
+    /// it could be sent as it is
+    ///
+    /// Only applies to [`MaybeString::Binary`]. Disabled by default.
+    pub fn base64_threshold(mut self, size: usize) -> Self {
+        self.base64_threshold = Some(size);
+        self
+    }
+
+    /// Only accepts bodies which can be sent as `7bit`, failing on 8-bit
+    /// data and too long lines instead of encoding them
+    ///
+    /// Disabled by default.
+    pub fn seven_bit_only(mut self, enabled: bool) -> Self {
+        self.seven_bit_only = enabled;
+        self
+    }
+}
+
 /// Either a `Vec<u8>` or a `String`.
 ///
 /// If the content is valid utf-8 a `String` should be passed, as it
@@ -80,6 +142,27 @@ impl Body {
         Ok(Self::new_impl(buf.into(), encoding))
     }
 
+    /// Encode the supplied `buf`, choosing the encoding according to `policy`.
+    ///
+    /// If `String` is passed, line endings are converted to `CRLF`.
+    ///
+    /// Returns an [`Err`] giving back the supplied `buf` if the policy only
+    /// accepts `7bit` and `buf` can't be sent as it is.
+    pub fn new_with_policy<B: Into<MaybeString>>(
+        buf: B,
+        policy: EncodingPolicy,
+    ) -> Result<Self, Vec<u8>> {
+        let mut buf: MaybeString = buf.into();
+
+        let encoding = buf.encoding_with_policy(&policy);
+        if policy.seven_bit_only && encoding != ContentTransferEncoding::SevenBit {
+            return Err(buf.into());
+        }
+
+        buf.encode_crlf();
+        Ok(Self::new_impl(buf.into(), encoding))
+    }
+
     /// Builds a new `Body` using a pre-encoded buffer.
     ///
     /// **Generally not you want.**
@@ -97,18 +180,16 @@ impl Body {
             | ContentTransferEncoding::EightBit
             | ContentTransferEncoding::Binary => Self { buf, encoding },
             ContentTransferEncoding::QuotedPrintable => {
-                let encoded = quoted_printable::encode(buf);
+                let mut out = Vec::new();
+                crate::quoted_printable::encode(&buf, &mut out);
 
-                Self::dangerous_pre_encoded(encoded, ContentTransferEncoding::QuotedPrintable)
+                Self::dangerous_pre_encoded(out, ContentTransferEncoding::QuotedPrintable)
             }
             ContentTransferEncoding::Base64 => {
-                let len = email_encoding::body::base64::encoded_len(buf.len());
+                let mut out = Vec::new();
+                crate::base64::encode_wrapped(&buf, &mut out);
 
-                let mut out = String::with_capacity(len);
-                email_encoding::body::base64::encode(&buf, &mut out)
-                    .expect("encode body as base64");
-
-                Self::dangerous_pre_encoded(out.into_bytes(), ContentTransferEncoding::Base64)
+                Self::dangerous_pre_encoded(out, ContentTransferEncoding::Base64)
             }
         }
     }
@@ -136,6 +217,109 @@ impl Body {
     pub fn into_vec(self) -> Vec<u8> {
         self.buf
     }
+
+    /// Decodes this `Body`, reversing its `Content-Transfer-Encoding`
+    ///
+    /// Line endings of `String` bodies are left as `CRLF`.
+    ///
+    /// Returns `None` if the body isn't valid for its encoding, which can
+    /// only happen with [`Body::dangerous_pre_encoded`].
+    ///
+    /// ```rust
+    /// use lettre::message::Body;
+    ///
+    /// let body = Body::new(vec![0xde, 0xad, 0xbe, 0xef]);
+    /// assert_eq!(body.decode(), Some(vec![0xde, 0xad, 0xbe, 0xef]));
+    /// ```
+    pub fn decode(&self) -> Option<Vec<u8>> {
+        decode(&self.buf, self.encoding)
+    }
+}
+
+/// Serializes encoded bodies as base64 strings in human-readable
+/// formats, like JSON, and as bytes in the others
+#[cfg(feature = "serde")]
+pub(super) mod serde_bytes {
+    use std::fmt::{Formatter, Result as FmtResult};
+
+    use serde::{
+        de::{Deserializer, Error as DeError, SeqAccess, Visitor},
+        ser::Serializer,
+    };
+
+    pub(crate) fn serialize<S>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        if serializer.is_human_readable() {
+            serializer.serialize_str(&crate::base64::encode(bytes))
+        } else {
+            serializer.serialize_bytes(bytes)
+        }
+    }
+
+    pub(crate) fn deserialize<'de, D>(deserializer: D) -> Result<Vec<u8>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct BytesVisitor;
+
+        impl<'de> Visitor<'de> for BytesVisitor {
+            type Value = Vec<u8>;
+
+            fn expecting(&self, formatter: &mut Formatter<'_>) -> FmtResult {
+                formatter.write_str("bytes or a base64 string")
+            }
+
+            fn visit_str<E: DeError>(self, v: &str) -> Result<Self::Value, E> {
+                crate::base64::decode(v).map_err(E::custom)
+            }
+
+            fn visit_bytes<E: DeError>(self, v: &[u8]) -> Result<Self::Value, E> {
+                Ok(v.to_vec())
+            }
+
+            fn visit_byte_buf<E: DeError>(self, v: Vec<u8>) -> Result<Self::Value, E> {
+                Ok(v)
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+                let mut bytes = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+                while let Some(byte) = seq.next_element()? {
+                    bytes.push(byte);
+                }
+                Ok(bytes)
+            }
+        }
+
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_str(BytesVisitor)
+        } else {
+            deserializer.deserialize_byte_buf(BytesVisitor)
+        }
+    }
+}
+
+/// Decode a body encoded with the supplied `Content-Transfer-Encoding`
+///
+/// Returns `None` if `buf` isn't valid for `encoding`.
+pub(super) fn decode(buf: &[u8], encoding: ContentTransferEncoding) -> Option<Vec<u8>> {
+    match encoding {
+        ContentTransferEncoding::SevenBit
+        | ContentTransferEncoding::EightBit
+        | ContentTransferEncoding::Binary => Some(buf.to_vec()),
+        ContentTransferEncoding::QuotedPrintable => {
+            quoted_printable::decode(buf, quoted_printable::ParseMode::Robust).ok()
+        }
+        ContentTransferEncoding::Base64 => {
+            let buf = buf
+                .iter()
+                .copied()
+                .filter(|b| !b.is_ascii_whitespace())
+                .collect::<Vec<u8>>();
+            crate::base64::decode(buf).ok()
+        }
+    }
 }
 
 impl MaybeString {
@@ -158,6 +342,38 @@ impl MaybeString {
         }
     }
 
+    /// Suggests a `Content-Transfer-Encoding` according to `policy`
+    ///
+    /// The `8bit` and `binary` encodings are never returned
+    fn encoding_with_policy(&self, policy: &EncodingPolicy) -> ContentTransferEncoding {
+        if let (Self::Binary(b), Some(threshold)) = (self, policy.base64_threshold) {
+            if b.len() >= threshold {
+                return ContentTransferEncoding::Base64;
+            }
+        }
+
+        let encoding = self.encoding(false);
+        let Some(max_escaped) = policy.quoted_printable_max_escaped else {
+            return encoding;
+        };
+        match encoding {
+            ContentTransferEncoding::QuotedPrintable | ContentTransferEncoding::Base64
+                if self.is_ascii() || matches!(self, Self::String(_)) =>
+            {
+                let escaped = self
+                    .iter()
+                    .filter(|&b| !matches!(b, b'\t' | b' '..=b'~'))
+                    .count();
+                if escaped * 100 <= self.len() * usize::from(max_escaped) {
+                    ContentTransferEncoding::QuotedPrintable
+                } else {
+                    ContentTransferEncoding::Base64
+                }
+            }
+            encoding => encoding,
+        }
+    }
+
     /// Encode line endings to CRLF if the variant is `String`
     fn encode_crlf(&mut self) {
         match self {
@@ -176,6 +392,7 @@ impl MaybeString {
 ///
 /// If `encoding` is `None` the best encoding between `7bit`, `quoted-printable`
 /// and `base64` is chosen based on the input body. **Best option.**
+/// Pass a [`Body`] built with [`Body::new_with_policy`] to tune the choice.
 ///
 /// If `encoding` is `Some` the supplied encoding is used.
 /// **NOTE:** if using the specified `encoding` would result into a malformed
@@ -287,7 +504,9 @@ fn find_all_lf_char_indices(s: &str) -> Vec<usize> {
 mod test {
     use pretty_assertions::assert_eq;
 
-    use super::{in_place_crlf_line_endings, Body, ContentTransferEncoding};
+    use super::{
+        in_place_crlf_line_endings, Body, ContentTransferEncoding, EncodingPolicy, MaybeString,
+    };
 
     #[test]
     fn seven_bit_detect() {
@@ -546,6 +765,93 @@ mod test {
         );
     }
 
+    #[test]
+    fn policy_encoding() {
+        let text = String::from("Ça été très très bien");
+        let default = Body::new_with_policy(text.clone(), EncodingPolicy::new()).unwrap();
+        assert_eq!(default.encoding(), Body::new(text.clone()).encoding());
+        assert_eq!(default.encoding(), ContentTransferEncoding::Base64);
+
+        // The default policy makes the same choice as `Body::new`, also for binary data
+        for content in [
+            &b"Questo messaggio \xc3\xa8 corto"[..],
+            &b"\x00\x01\x02 binary"[..],
+            &[0xde, 0xad, 0xbe, 0xef][..],
+        ] {
+            for buf in [
+                MaybeString::Binary(content.to_vec()),
+                MaybeString::String(String::from_utf8_lossy(content).into_owned()),
+            ] {
+                assert_eq!(
+                    buf.encoding_with_policy(&EncodingPolicy::new()),
+                    buf.encoding(false)
+                );
+            }
+        }
+
+        let policy = EncodingPolicy::new().quoted_printable_max_escaped(50);
+        let encoded = Body::new_with_policy(text.clone(), policy).unwrap();
+        assert_eq!(encoded.encoding(), ContentTransferEncoding::QuotedPrintable);
+        assert_eq!(
+            encoded.as_ref(),
+            &b"=C3=87a =C3=A9t=C3=A9 tr=C3=A8s tr=C3=A8s bien"[..]
+        );
+
+        let policy = EncodingPolicy::new().quoted_printable_max_escaped(0);
+        let encoded = Body::new_with_policy(String::from("Ünïcödé text"), policy).unwrap();
+        assert_eq!(encoded.encoding(), ContentTransferEncoding::Base64);
+        let encoded = Body::new_with_policy(String::from("plain text"), policy).unwrap();
+        assert_eq!(encoded.encoding(), ContentTransferEncoding::SevenBit);
+    }
+
+    #[test]
+    fn policy_base64_threshold() {
+        let policy = EncodingPolicy::new().base64_threshold(8);
+        let encoded = Body::new_with_policy(b"plain text".to_vec(), policy).unwrap();
+        assert_eq!(encoded.encoding(), ContentTransferEncoding::Base64);
+        let encoded = Body::new_with_policy(b"short".to_vec(), policy).unwrap();
+        assert_eq!(encoded.encoding(), ContentTransferEncoding::SevenBit);
+        let encoded = Body::new_with_policy(String::from("plain text"), policy).unwrap();
+        assert_eq!(encoded.encoding(), ContentTransferEncoding::SevenBit);
+    }
+
+    #[test]
+    fn policy_seven_bit_only() {
+        let policy = EncodingPolicy::new().seven_bit_only(true);
+        let encoded = Body::new_with_policy(String::from("Hello\nworld"), policy).unwrap();
+        assert_eq!(encoded.encoding(), ContentTransferEncoding::SevenBit);
+        assert_eq!(encoded.as_ref(), b"Hello\r\nworld");
+
+        assert_eq!(
+            Body::new_with_policy(String::from("Ünïcödé"), policy).unwrap_err(),
+            "Ünïcödé".as_bytes()
+        );
+        assert!(Body::new_with_policy("a".repeat(100), policy).is_err());
+    }
+
+    #[test]
+    fn decode_roundtrip() {
+        for (content, encoding) in [
+            ("Hello, world!", ContentTransferEncoding::SevenBit),
+            ("Привет, мир!", ContentTransferEncoding::Base64),
+            (
+                "Questo messaggio è corto",
+                ContentTransferEncoding::QuotedPrintable,
+            ),
+        ] {
+            let body = Body::new(String::from(content));
+            assert_eq!(body.encoding(), encoding);
+            assert_eq!(body.decode().unwrap(), content.as_bytes());
+        }
+    }
+
+    #[test]
+    fn decode_invalid() {
+        let body =
+            Body::dangerous_pre_encoded(b"not base64!".to_vec(), ContentTransferEncoding::Base64);
+        assert_eq!(body.decode(), None);
+    }
+
     #[test]
     fn crlf() {
         let mut string = String::from("Send me a ✉️\nwith\nlettre!\n😀");
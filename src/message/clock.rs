@@ -0,0 +1,64 @@
+//! Source of the current time of the messages
+
+use std::{fmt::Debug, time::SystemTime};
+
+/// Tells the current time when building messages
+///
+/// Used for the default `Date` and `Resent-Date` headers, and as signing
+/// time by the DKIM signature of a [`MessageTemplate`](super::MessageTemplate).
+/// Set it with [`MessageBuilder::clock`](super::MessageBuilder::clock) or
+/// [`MessageTemplate::clock`](super::MessageTemplate::clock) to control the
+/// dates in tests or in simulated-time environments.
+///
+/// ```rust
+/// use std::time::{Duration, SystemTime};
+///
+/// use lettre::message::{header, FixedClock, Message};
+///
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// let now = SystemTime::UNIX_EPOCH + Duration::from_secs(784887151);
+/// let email = Message::builder()
+///     .clock(FixedClock::new(now))
+///     .from("NoBody <nobody@domain.tld>".parse()?)
+///     .to("Hei <hei@domain.tld>".parse()?)
+///     .body(String::from("Be happy!"))?;
+///
+/// assert_eq!(
+///     email.headers().get::<header::Date>(),
+///     Some(header::Date::new(now))
+/// );
+/// # Ok(())
+/// # }
+/// ```
+pub trait Clock: Debug + Send + Sync {
+    /// Returns the current time
+    fn now(&self) -> SystemTime;
+}
+
+/// Clock returning the system time, used by default
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+/// Clock always returning the same time
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FixedClock(SystemTime);
+
+impl FixedClock {
+    /// Creates a clock stopped at `now`
+    pub fn new(now: SystemTime) -> Self {
+        Self(now)
+    }
+}
+
+impl Clock for FixedClock {
+    fn now(&self) -> SystemTime {
+        self.0
+    }
+}
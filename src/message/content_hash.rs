@@ -0,0 +1,177 @@
+//! Stable hashes of the content of messages, for deduplication
+
+use super::{header::Headers, Message, MessageBody, Part};
+
+/// Headers which change each time a message is built
+const EXCLUDED_HEADERS: [&str; 3] = ["Date", "Message-ID", "DKIM-Signature"];
+
+/// Hash the content of `message`, see [`Message::content_hash`]
+pub(super) fn content_hash(message: &Message) -> u64 {
+    let mut hasher = Fnv1a::new();
+    hash_headers(&mut hasher, &message.headers, None);
+    match &message.body {
+        MessageBody::Raw(raw) => {
+            hasher.write_bytes(b"raw");
+            hasher.write_bytes(raw);
+        }
+        MessageBody::Mime(part) => hash_part(&mut hasher, part),
+    }
+    hasher.finish()
+}
+
+fn hash_part(hasher: &mut Fnv1a, part: &Part) {
+    match part {
+        Part::Single(part) => {
+            hasher.write_bytes(b"single");
+            hash_headers(hasher, part.headers(), None);
+            hasher.write_bytes(part.raw_body());
+        }
+        Part::Multi(part) => {
+            hasher.write_bytes(b"multi");
+            let boundary = part.boundary();
+            hash_headers(hasher, part.headers(), Some(&boundary));
+            hasher.write_len(part.parts().len());
+            for part in part.parts() {
+                hash_part(hasher, part);
+            }
+        }
+    }
+}
+
+/// Hashes the headers sorted by name, with the names lowercased and the
+/// whitespace of the values collapsed, leaving out the random `boundary`
+fn hash_headers(hasher: &mut Fnv1a, headers: &Headers, boundary: Option<&str>) {
+    let mut values = headers
+        .iter_raw()
+        .filter(|(name, _)| {
+            !EXCLUDED_HEADERS
+                .iter()
+                .any(|excluded| name.eq_ignore_ascii_case(excluded))
+        })
+        .map(|(name, value)| {
+            let value = match boundary {
+                Some(boundary) => value.replace(boundary, ""),
+                None => value.to_owned(),
+            };
+            let value = value.split_whitespace().collect::<Vec<_>>().join(" ");
+            (name.to_ascii_lowercase(), value)
+        })
+        .collect::<Vec<_>>();
+    values.sort_by(|a, b| a.0.cmp(&b.0));
+
+    hasher.write_len(values.len());
+    for (name, value) in values {
+        hasher.write_bytes(name.as_bytes());
+        hasher.write_bytes(value.as_bytes());
+    }
+}
+
+/// 64 bits [FNV-1a](http://www.isthe.com/chongo/tech/comp/fnv/index.html)
+/// hasher, which unlike the hashers of the standard library is guaranteed
+/// to give the same results across versions and platforms
+struct Fnv1a(u64);
+
+impl Fnv1a {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    fn new() -> Self {
+        Self(Self::OFFSET_BASIS)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 ^= u64::from(byte);
+            self.0 = self.0.wrapping_mul(Self::PRIME);
+        }
+    }
+
+    fn write_len(&mut self, len: usize) {
+        self.write(&(len as u64).to_le_bytes());
+    }
+
+    /// Writes `bytes` prefixed with their length, so that consecutive
+    /// writes can't be confused
+    fn write_bytes(&mut self, bytes: &[u8]) {
+        self.write_len(bytes.len());
+        self.write(bytes);
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Fnv1a;
+    use crate::message::{header::ContentType, Message, MultiPart, SinglePart};
+
+    #[test]
+    fn fnv1a() {
+        let hash = |bytes: &[u8]| {
+            let mut hasher = Fnv1a::new();
+            hasher.write(bytes);
+            hasher.finish()
+        };
+
+        assert_eq!(hash(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(hash(b"a"), 0xaf63_dc4c_8601_ec8c);
+        assert_eq!(hash(b"foobar"), 0x8594_4171_f739_67e8);
+    }
+
+    fn message(subject: &str) -> Message {
+        Message::builder()
+            .from("NoBody <nobody@domain.tld>".parse().unwrap())
+            .to("Hei <hei@domain.tld>".parse().unwrap())
+            .subject(subject)
+            .multipart(
+                MultiPart::mixed()
+                    .multipart(MultiPart::alternative_plain_html(
+                        String::from("Hello"),
+                        String::from("<p>Hello</p>"),
+                    ))
+                    .singlepart(
+                        SinglePart::builder()
+                            .header(ContentType::TEXT_PLAIN)
+                            .body(String::from("Attached")),
+                    ),
+            )
+            .unwrap()
+    }
+
+    #[test]
+    fn regenerated_messages() {
+        let first = message("Happy new year");
+        let second = message("Happy new year");
+        assert_ne!(first.formatted(), second.formatted());
+        assert_eq!(first.content_hash(), second.content_hash());
+
+        assert_ne!(
+            first.content_hash(),
+            message("Happy new year!").content_hash()
+        );
+    }
+
+    #[test]
+    fn canonicalized_headers() {
+        let build = |subject: &str, date: u64| {
+            Message::builder()
+                .from("nobody@domain.tld".parse().unwrap())
+                .to("hei@domain.tld".parse().unwrap())
+                .subject(subject)
+                .date(std::time::UNIX_EPOCH + std::time::Duration::from_secs(date))
+                .body(String::from("Be happy!"))
+                .unwrap()
+        };
+
+        assert_eq!(
+            build("Happy  new year", 784887151).content_hash(),
+            build("Happy new year", 784890000).content_hash()
+        );
+        assert_ne!(
+            build("Happy new year", 784887151).content_hash(),
+            build("Happy new year ?", 784887151).content_hash()
+        );
+    }
+}
@@ -0,0 +1,156 @@
+//! JSON outline of messages, for admin interfaces and debugging tools
+
+use std::fmt::Write;
+
+use super::{header::Headers, Message, MessageBody, Part};
+
+/// Build the JSON outline of `message`, see [`Message::to_debug_json`]
+pub(super) fn to_debug_json(message: &Message) -> String {
+    let mut out = String::new();
+    out.push_str("{\"headers\":");
+    write_headers(&mut out, &message.headers);
+    write!(out, ",\"size\":{},\"body\":", message.formatted().len()).unwrap();
+    match &message.body {
+        MessageBody::Raw(raw) => write_leaf(&mut out, &message.headers, raw.len()),
+        MessageBody::Mime(part) => write_part(&mut out, part),
+    }
+    out.push('}');
+    out
+}
+
+fn write_part(out: &mut String, part: &Part) {
+    match part {
+        Part::Single(part) => {
+            out.push_str("{\"headers\":");
+            write_headers(out, part.headers());
+            out.push(',');
+            write_leaf_fields(out, part.headers(), part.raw_body().len());
+            out.push('}');
+        }
+        Part::Multi(part) => {
+            out.push_str("{\"headers\":");
+            write_headers(out, part.headers());
+            out.push_str(",\"content_type\":");
+            write_optional_string(out, part.headers().get_raw("Content-Type"));
+            out.push_str(",\"parts\":[");
+            for (i, part) in part.parts().iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_part(out, part);
+            }
+            out.push_str("]}");
+        }
+    }
+}
+
+/// Writes the body of a message without MIME parts
+fn write_leaf(out: &mut String, headers: &Headers, size: usize) {
+    out.push('{');
+    write_leaf_fields(out, headers, size);
+    out.push('}');
+}
+
+fn write_leaf_fields(out: &mut String, headers: &Headers, size: usize) {
+    out.push_str("\"content_type\":");
+    write_optional_string(out, headers.get_raw("Content-Type"));
+    out.push_str(",\"encoding\":");
+    write_optional_string(out, headers.get_raw("Content-Transfer-Encoding"));
+    write!(out, ",\"size\":{size}").unwrap();
+}
+
+fn write_headers(out: &mut String, headers: &Headers) {
+    out.push('[');
+    for (i, (name, value)) in headers.iter_raw().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        out.push_str("{\"name\":");
+        write_string(out, name);
+        out.push_str(",\"value\":");
+        write_string(out, value);
+        out.push('}');
+    }
+    out.push(']');
+}
+
+fn write_optional_string(out: &mut String, value: Option<&str>) {
+    match value {
+        Some(value) => write_string(out, value),
+        None => out.push_str("null"),
+    }
+}
+
+fn write_string(out: &mut String, value: &str) {
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => write!(out, "\\u{:04x}", c as u32).unwrap(),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::{Duration, SystemTime};
+
+    use pretty_assertions::assert_eq;
+
+    use super::write_string;
+    use crate::message::{header::ContentType, Attachment, Message, MultiPart, SinglePart};
+
+    #[test]
+    fn escaped_strings() {
+        let mut out = String::new();
+        write_string(&mut out, "a \"quoted\"\\path\r\n\u{1}é");
+        assert_eq!(out, r#""a \"quoted\"\\path\r\n\u0001é""#);
+    }
+
+    #[test]
+    fn outline() {
+        let date = SystemTime::UNIX_EPOCH + Duration::from_secs(784887151);
+        let email = Message::builder()
+            .from("nobody@domain.tld".parse().unwrap())
+            .to("hei@domain.tld".parse().unwrap())
+            .date(date)
+            .message_id(Some(String::from("<id@domain.tld>")))
+            .multipart(
+                MultiPart::mixed()
+                    .boundary("boundary")
+                    .singlepart(SinglePart::plain(String::from("Be happy!")))
+                    .singlepart(Attachment::new(String::from("secret.bin")).body(
+                        vec![0, 159, 146, 150],
+                        ContentType::parse("application/octet-stream").unwrap(),
+                    )),
+            )
+            .unwrap();
+
+        let json = email.to_debug_json();
+        assert!(!json.contains("AJ+Slg"));
+        assert!(!json.contains("Be happy"));
+        assert_eq!(
+            json,
+            concat!(
+                r#"{"headers":[{"name":"From","value":"nobody@domain.tld"},"#,
+                r#"{"name":"To","value":"hei@domain.tld"},"#,
+                r#"{"name":"Date","value":"Tue, 15 Nov 1994 08:12:31 +0000"},"#,
+                r#"{"name":"Message-ID","value":"<id@domain.tld>"},"#,
+                r#"{"name":"MIME-Version","value":"1.0"}],"size":454,"#,
+                r#""body":{"headers":[{"name":"Content-Type","value":"multipart/mixed; boundary=\"boundary\""}],"#,
+                r#""content_type":"multipart/mixed; boundary=\"boundary\"","parts":["#,
+                r#"{"headers":[{"name":"Content-Type","value":"text/plain; charset=utf-8"},{"name":"Content-Transfer-Encoding","value":"7bit"}],"#,
+                r#""content_type":"text/plain; charset=utf-8","encoding":"7bit","size":9},"#,
+                r#"{"headers":[{"name":"Content-Disposition","value":"attachment; filename=\"secret.bin\""},"#,
+                r#"{"name":"Content-Type","value":"application/octet-stream"},{"name":"Content-Transfer-Encoding","value":"base64"}],"#,
+                r#""content_type":"application/octet-stream","encoding":"base64","size":8}]}}"#,
+            )
+        );
+    }
+}
@@ -1,8 +1,9 @@
 use std::{
     borrow::Cow,
     error::Error as StdError,
-    fmt::{self, Display},
-    time::SystemTime,
+    fmt::{self, Debug, Display},
+    sync::Arc,
+    time::{Duration, SystemTime},
 };
 
 use ed25519_dalek::Signer;
@@ -11,7 +12,7 @@ use sha2::{Digest, Sha256};
 
 use crate::message::{
     header::{HeaderName, HeaderValue},
-    Headers, Message,
+    Clock, Headers, Message,
 };
 
 /// Describe Dkim Canonicalization to apply to either body or headers
@@ -53,6 +54,37 @@ impl Display for DkimCanonicalization {
     }
 }
 
+/// Describe how much of the body is covered by the signature
+///
+/// Signing a prefix of the body with the `l=` tag lets mailing lists append
+/// footers without invalidating the signature, but also lets anyone append
+/// content to the message. Prefer [`DkimBodyLength::Omitted`] unless the
+/// messages go through such lists.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum DkimBodyLength {
+    /// Sign the whole body, without `l=` tag
+    #[default]
+    Omitted,
+    /// Sign the whole body and declare its length with the `l=` tag
+    Whole,
+    /// Sign the given number of bytes of the canonicalized body, declared
+    /// with the `l=` tag
+    ///
+    /// Shorter bodies are signed whole, with their actual length declared.
+    Prefix(usize),
+}
+
+impl DkimBodyLength {
+    /// The length to declare for a canonicalized body of `len` bytes
+    fn declared(self, len: usize) -> Option<usize> {
+        match self {
+            DkimBodyLength::Omitted => None,
+            DkimBodyLength::Whole => Some(len),
+            DkimBodyLength::Prefix(prefix) => Some(prefix.min(len)),
+        }
+    }
+}
+
 /// Describe the algorithm used for signing the message
 #[derive(Copy, Clone, Debug)]
 pub enum DkimSigningAlgorithm {
@@ -101,10 +133,8 @@ impl StdError for DkimSigningKeyError {
 }
 
 /// Describe a signing key to be carried by DkimConfig struct
-#[derive(Debug)]
 pub struct DkimSigningKey(InnerDkimSigningKey);
 
-#[derive(Debug)]
 enum InnerDkimSigningKey {
     Rsa(RsaPrivateKey),
     Ed25519(ed25519_dalek::SigningKey),
@@ -142,6 +172,14 @@ impl DkimSigningKey {
     }
 }
 
+impl Debug for DkimSigningKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DkimSigningKey")
+            .field("algorithm", &self.get_signing_algorithm())
+            .finish_non_exhaustive()
+    }
+}
+
 /// A struct to describe Dkim configuration applied when signing a message
 #[derive(Debug)]
 pub struct DkimConfig {
@@ -156,6 +194,14 @@ pub struct DkimConfig {
     headers: Vec<HeaderName>,
     /// The signing algorithm to be used when signing
     canonicalization: DkimCanonicalization,
+    /// How much of the body is signed
+    body_length: DkimBodyLength,
+    /// Whether to add the `t=` tag
+    timestamp: bool,
+    /// Validity of the signature, declared with the `x=` tag
+    expiration: Option<Duration>,
+    /// Source of the signing time, `SystemTime::now` if not set
+    clock: Option<Arc<dyn Clock>>,
 }
 
 impl DkimConfig {
@@ -171,15 +217,19 @@ impl DkimConfig {
             domain,
             private_key,
             headers: vec![
-                HeaderName::new_from_ascii_str("From"),
-                HeaderName::new_from_ascii_str("Subject"),
-                HeaderName::new_from_ascii_str("To"),
-                HeaderName::new_from_ascii_str("Date"),
+                HeaderName::FROM,
+                HeaderName::SUBJECT,
+                HeaderName::TO,
+                HeaderName::DATE,
             ],
             canonicalization: DkimCanonicalization {
                 header: DkimCanonicalizationType::Simple,
                 body: DkimCanonicalizationType::Relaxed,
             },
+            body_length: DkimBodyLength::Omitted,
+            timestamp: true,
+            expiration: None,
+            clock: None,
         }
     }
 
@@ -197,14 +247,52 @@ impl DkimConfig {
             private_key,
             headers,
             canonicalization,
+            body_length: DkimBodyLength::Omitted,
+            timestamp: true,
+            expiration: None,
+            clock: None,
         }
     }
+
+    /// Set how much of the body is signed, see [`DkimBodyLength`]
+    pub fn body_length(mut self, body_length: DkimBodyLength) -> Self {
+        self.body_length = body_length;
+        self
+    }
+
+    /// Set whether to declare the signing time with the `t=` tag
+    ///
+    /// Enabled by default.
+    pub fn timestamp(mut self, timestamp: bool) -> Self {
+        self.timestamp = timestamp;
+        self
+    }
+
+    /// Set how long the signature stays valid, declared with the `x=` tag
+    ///
+    /// Receivers consider the signature invalid once the signing time plus
+    /// `expiration`, in whole seconds and at least one, has passed. Should leave
+    /// time for delays in delivery, like a few days. Disabled by default.
+    pub fn expiration(mut self, expiration: Option<Duration>) -> Self {
+        self.expiration = expiration;
+        self
+    }
+
+    /// Use `clock` to get the signing time in [`dkim_sign`] and [`Message::sign`]
+    ///
+    /// Messages signed by a [`MessageTemplate`](super::MessageTemplate) use the
+    /// clock of the message instead. See [`Clock`].
+    pub fn clock<C: Clock + 'static>(mut self, clock: C) -> Self {
+        self.clock = Some(Arc::new(clock));
+        self
+    }
 }
 
 /// Create a Headers struct with a Dkim-Signature Header created from given parameters
 fn dkim_header_format(
     config: &DkimConfig,
     timestamp: u64,
+    body_length: Option<usize>,
     headers_list: &str,
     body_hash: &str,
     signature: &str,
@@ -213,10 +301,24 @@ fn dkim_header_format(
     let header_name =
         dkim_canonicalize_header_tag("DKIM-Signature", config.canonicalization.header);
     let header_name = HeaderName::new_from_ascii(header_name.into()).unwrap();
-    headers.insert_raw(HeaderValue::new(header_name, format!("v=1; a={signing_algorithm}-sha256; d={domain}; s={selector}; c={canon}; q=dns/txt; t={timestamp}; h={headers_list}; bh={body_hash}; b={signature}",domain=config.domain, selector=config.selector,canon=config.canonicalization,timestamp=timestamp,headers_list=headers_list,body_hash=body_hash,signature=signature,signing_algorithm=config.private_key.get_signing_algorithm())));
+    headers.insert_raw(HeaderValue::new(header_name, format!("v=1; a={signing_algorithm}-sha256; d={domain}; s={selector}; c={canon}; q=dns/txt;{times}{body_length} h={headers_list}; bh={body_hash}; b={signature}",domain=config.domain, selector=config.selector,canon=config.canonicalization,times=dkim_times(config, timestamp),body_length=body_length.map(|len| format!(" l={len};")).unwrap_or_default(),headers_list=headers_list,body_hash=body_hash,signature=signature,signing_algorithm=config.private_key.get_signing_algorithm())));
     headers
 }
 
+/// Format the `t=` and `x=` tags for a signature made at `timestamp`
+fn dkim_times(config: &DkimConfig, timestamp: u64) -> String {
+    let mut times = String::new();
+    if config.timestamp {
+        times += &format!(" t={timestamp};");
+    }
+    if let Some(expiration) = config.expiration {
+        // The expiration must be after the signing time
+        let expiration = timestamp + expiration.as_secs().max(1);
+        times += &format!(" x={expiration};");
+    }
+    times
+}
+
 /// Canonicalize the body of an email
 fn dkim_canonicalize_body(
     mut body: &[u8],
@@ -346,19 +448,27 @@ fn dkim_canonicalize_headers<'a>(
 /// Sign with Dkim a message by adding Dkim-Signature header created with configuration expressed by
 /// dkim_config
 pub fn dkim_sign(message: &mut Message, dkim_config: &DkimConfig) {
-    dkim_sign_fixed_time(message, dkim_config, SystemTime::now())
+    let now = match &dkim_config.clock {
+        Some(clock) => clock.now(),
+        None => SystemTime::now(),
+    };
+    dkim_sign_fixed_time(message, dkim_config, now)
 }
 
-fn dkim_sign_fixed_time(message: &mut Message, dkim_config: &DkimConfig, timestamp: SystemTime) {
+pub(super) fn dkim_sign_fixed_time(
+    message: &mut Message,
+    dkim_config: &DkimConfig,
+    timestamp: SystemTime,
+) {
     let timestamp = timestamp
         .duration_since(SystemTime::UNIX_EPOCH)
         .unwrap()
         .as_secs();
     let headers = message.headers();
-    let body_hash = Sha256::digest(dkim_canonicalize_body(
-        &message.body_raw(),
-        dkim_config.canonicalization.body,
-    ));
+    let body = message.body_raw();
+    let body = dkim_canonicalize_body(&body, dkim_config.canonicalization.body);
+    let body_length = dkim_config.body_length.declared(body.len());
+    let body_hash = Sha256::digest(&body[..body_length.unwrap_or(body.len())]);
     let bh = crate::base64::encode(body_hash);
     let mut signed_headers_list =
         dkim_config
@@ -375,7 +485,14 @@ fn dkim_sign_fixed_time(message: &mut Message, dkim_config: &DkimConfig, timesta
     if let DkimCanonicalizationType::Relaxed = dkim_config.canonicalization.header {
         signed_headers_list.make_ascii_lowercase();
     }
-    let dkim_header = dkim_header_format(dkim_config, timestamp, &signed_headers_list, &bh, "");
+    let dkim_header = dkim_header_format(
+        dkim_config,
+        timestamp,
+        body_length,
+        &signed_headers_list,
+        &bh,
+        "",
+    );
     let signed_headers = dkim_canonicalize_headers(
         dkim_config.headers.iter().map(|h| h.as_ref()),
         headers,
@@ -403,28 +520,35 @@ fn dkim_sign_fixed_time(message: &mut Message, dkim_config: &DkimConfig, timesta
     let dkim_header = dkim_header_format(
         dkim_config,
         timestamp,
+        body_length,
         &signed_headers_list,
         &bh,
         &signature,
     );
     message.headers.insert_raw(HeaderValue::new(
-        HeaderName::new_from_ascii_str("DKIM-Signature"),
-        dkim_header.get_raw("DKIM-Signature").unwrap().to_owned(),
+        HeaderName::DKIM_SIGNATURE,
+        dkim_header
+            .get_raw(&HeaderName::DKIM_SIGNATURE)
+            .unwrap()
+            .to_owned(),
     ));
 }
 
 #[cfg(test)]
 mod test {
+    use std::time::Duration;
+
     use pretty_assertions::assert_eq;
+    use sha2::{Digest, Sha256};
 
     use super::{
         super::{
             header::{HeaderName, HeaderValue},
-            Header, Message,
+            FixedClock, Header, Message,
         },
-        dkim_canonicalize_body, dkim_canonicalize_headers, dkim_sign_fixed_time,
-        DkimCanonicalization, DkimCanonicalizationType, DkimConfig, DkimSigningAlgorithm,
-        DkimSigningKey,
+        dkim_canonicalize_body, dkim_canonicalize_headers, dkim_sign, dkim_sign_fixed_time,
+        DkimBodyLength, DkimCanonicalization, DkimCanonicalizationType, DkimConfig,
+        DkimSigningAlgorithm, DkimSigningKey,
     };
     use crate::StdError;
 
@@ -515,6 +639,15 @@ cJ5Ku0OTwRtSMaseRPX+T4EfG1Caa/eunPPN4rh+CSup2BVVarOT
         );
     }
 
+    #[test]
+    fn test_signing_key_debug() {
+        let signing_key = DkimSigningKey::new(KEY_RSA, DkimSigningAlgorithm::Rsa).unwrap();
+        assert_eq!(
+            format!("{signing_key:?}"),
+            "DkimSigningKey { algorithm: Rsa, .. }"
+        );
+    }
+
     #[test]
     fn test_signature_rsa_simple() {
         let mut message = test_message();
@@ -612,4 +745,78 @@ cJ5Ku0OTwRtSMaseRPX+T4EfG1Caa/eunPPN4rh+CSup2BVVarOT
             )
         );
     }
+
+    #[test]
+    fn test_signature_body_length() {
+        let sign = |body_length| {
+            let mut message = test_message();
+            let signing_key = DkimSigningKey::new(KEY_RSA, DkimSigningAlgorithm::Rsa).unwrap();
+            let config = DkimConfig::default_config(
+                "dkimtest".to_owned(),
+                "example.org".to_owned(),
+                signing_key,
+            )
+            .body_length(body_length);
+            dkim_sign_fixed_time(&mut message, &config, std::time::UNIX_EPOCH);
+            let header = message.headers.get_raw("DKIM-Signature").unwrap();
+            let tag = |name: &str| {
+                header
+                    .split(';')
+                    .map(str::trim)
+                    .find_map(|tag| tag.strip_prefix(name)?.strip_prefix('='))
+                    .map(str::to_owned)
+            };
+            (tag("l"), tag("bh").unwrap())
+        };
+
+        // The relaxed canonicalized body is "test\r\n\r\ntest test\r\n"
+        let (length, whole_hash) = sign(DkimBodyLength::Omitted);
+        assert_eq!(length, None);
+        assert_eq!(
+            sign(DkimBodyLength::Whole),
+            (Some("19".to_owned()), whole_hash.clone())
+        );
+        assert_eq!(
+            sign(DkimBodyLength::Prefix(100)),
+            (Some("19".to_owned()), whole_hash)
+        );
+        assert_eq!(
+            sign(DkimBodyLength::Prefix(6)),
+            (
+                Some("6".to_owned()),
+                crate::base64::encode(Sha256::digest(b"test\r\n"))
+            )
+        );
+    }
+
+    #[test]
+    fn test_signature_times() {
+        let now = std::time::UNIX_EPOCH + Duration::from_secs(784887151);
+        let sign = |config: DkimConfig| {
+            let mut message = test_message();
+            dkim_sign(&mut message, &config.clock(FixedClock::new(now)));
+            let header = message.headers.get_raw("DKIM-Signature").unwrap();
+            header
+                .split(';')
+                .map(str::trim)
+                .filter(|tag| tag.starts_with("t=") || tag.starts_with("x="))
+                .collect::<Vec<_>>()
+                .join("; ")
+        };
+        let config = || {
+            let signing_key = DkimSigningKey::new(KEY_RSA, DkimSigningAlgorithm::Rsa).unwrap();
+            DkimConfig::default_config("dkimtest".to_owned(), "example.org".to_owned(), signing_key)
+        };
+
+        assert_eq!(sign(config()), "t=784887151");
+        assert_eq!(
+            sign(config().expiration(Some(Duration::from_secs(3 * 24 * 60 * 60)))),
+            "t=784887151; x=785146351"
+        );
+        assert_eq!(
+            sign(config().timestamp(false).expiration(Some(Duration::ZERO))),
+            "x=784887152"
+        );
+        assert_eq!(sign(config().timestamp(false)), "");
+    }
 }
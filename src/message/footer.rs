@@ -0,0 +1,134 @@
+//! Footers appended to the textual bodies of messages
+
+use super::{
+    body,
+    header::{ContentTransferEncoding, Headers},
+    preview::{text_subtype, TextSubtype},
+    Body, Message, MessageBody, Part,
+};
+
+/// Append the footers to the textual bodies of `message`, see [`Message::append_footer`]
+pub(super) fn append_footer(message: &mut Message, plain: &str, html: &str) {
+    match &mut message.body {
+        MessageBody::Raw(raw) => {
+            if let Some(body) = with_footer(&message.headers, raw, plain, html) {
+                message.headers.set(body.encoding());
+                *raw = body.into_vec();
+            }
+        }
+        MessageBody::Mime(part) => part.for_each_mut(|part| {
+            if let Part::Single(single) = part {
+                if let Some(body) = with_footer(single.headers(), single.raw_body(), plain, html) {
+                    single.set_body(body);
+                }
+            }
+        }),
+    }
+}
+
+/// The body encoded as `raw` with the footer matching its type,
+/// or `None` if it isn't a textual UTF-8 body
+fn with_footer(headers: &Headers, raw: &[u8], plain: &str, html: &str) -> Option<Body> {
+    let subtype = text_subtype(headers)?;
+    let encoding = headers
+        .get::<ContentTransferEncoding>()
+        .unwrap_or(ContentTransferEncoding::SevenBit);
+    let mut text = String::from_utf8(body::decode(raw, encoding)?).ok()?;
+
+    match subtype {
+        TextSubtype::Plain => {
+            if !text.is_empty() && !text.ends_with('\n') {
+                text.push_str("\r\n");
+            }
+            text.push_str(plain);
+        }
+        TextSubtype::Html => {
+            // ASCII lowercasing keeps byte offsets identical
+            match text.to_ascii_lowercase().rfind("</body>") {
+                Some(pos) => text.insert_str(pos, html),
+                None => text.push_str(html),
+            }
+        }
+    }
+
+    Some(Body::new(text))
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use crate::message::{
+        header::ContentType, Attachment, Message, MessageBuilder, MultiPart, Part, SinglePart,
+    };
+
+    fn builder() -> MessageBuilder {
+        Message::builder()
+            .from("NoBody <nobody@domain.tld>".parse().unwrap())
+            .to("Hei <hei@domain.tld>".parse().unwrap())
+    }
+
+    fn decoded(part: &Part) -> String {
+        match part {
+            Part::Single(part) => String::from_utf8(part.decoded_bytes().unwrap()).unwrap(),
+            Part::Multi(_) => panic!("expected a single part"),
+        }
+    }
+
+    #[test]
+    fn footer_alternative() {
+        let mut message = builder()
+            .multipart(
+                MultiPart::mixed()
+                    .multipart(MultiPart::alternative_plain_html(
+                        String::from("Hello"),
+                        String::from("<html><body><p>Hello</p></BODY></html>"),
+                    ))
+                    .singlepart(
+                        Attachment::new(String::from("notes.txt"))
+                            .body(String::from("attached"), ContentType::TEXT_PLAIN),
+                    ),
+            )
+            .unwrap();
+
+        message.append_footer("-- \nSent by Company Ltd.", "<p>Sent by Company Ltd.</p>");
+
+        let parts = message.parts().collect::<Vec<_>>();
+        assert_eq!(decoded(parts[2]), "Hello\r\n-- \r\nSent by Company Ltd.");
+        assert_eq!(
+            decoded(parts[3]),
+            "<html><body><p>Hello</p><p>Sent by Company Ltd.</p></BODY></html>"
+        );
+        assert_eq!(decoded(parts[4]), "attached");
+    }
+
+    #[test]
+    fn footer_raw_body() {
+        let mut message = builder().body(String::from("Hello\n")).unwrap();
+
+        message.append_footer("Sent by Société Ltd.", "<p>Sent by Société Ltd.</p>");
+
+        let formatted = String::from_utf8(message.formatted()).unwrap();
+        assert!(formatted.contains("Content-Transfer-Encoding: quoted-printable\r\n"));
+        assert!(formatted.ends_with("\r\n\r\nHello\r\nSent by Soci=C3=A9t=C3=A9 Ltd."));
+    }
+
+    #[test]
+    fn footer_skips_non_text() {
+        let mut message = builder()
+            .singlepart(
+                SinglePart::builder()
+                    .header(ContentType::parse("image/png").unwrap())
+                    .body(vec![0xff; 4]),
+            )
+            .unwrap();
+
+        message.append_footer("Footer", "<p>Footer</p>");
+
+        let part = message.parts().next().unwrap();
+        let Part::Single(part) = part else {
+            panic!("expected a single part");
+        };
+        assert_eq!(part.decoded_bytes().unwrap(), [0xff; 4]);
+    }
+}
@@ -0,0 +1,168 @@
+use std::{
+    error::Error as StdError,
+    fmt::{self, Display},
+};
+
+use super::{Header, HeaderName, HeaderValue};
+use crate::BoxError;
+
+/// `BIMI-Selector` header, choosing the BIMI record used to display the
+/// logo of the brand next to the message
+///
+/// Defined in the
+/// [BIMI draft](https://datatracker.ietf.org/doc/html/draft-brand-indicators-for-message-identification).
+/// Receivers look up the record at `<selector>._bimi.<domain>`, and only
+/// trust the header if it is covered by the DKIM signature, so add
+/// [`HeaderName::BIMI_SELECTOR`] to the signed headers.
+///
+/// ```rust
+/// use lettre::message::{header::BimiSelector, Message};
+///
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// let email = Message::builder()
+///     .from("NoBody <nobody@domain.tld>".parse()?)
+///     .to("Hei <hei@domain.tld>".parse()?)
+///     .header(BimiSelector::new("brand2024")?)
+///     .body(String::from("Be happy!"))?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BimiSelector {
+    selector: String,
+}
+
+impl BimiSelector {
+    /// Creates a header choosing the record of `selector`
+    ///
+    /// Fails unless `selector` is made of DNS labels of letters, digits
+    /// and hyphens, separated by dots.
+    pub fn new(selector: impl Into<String>) -> Result<Self, BimiSelectorError> {
+        let selector = selector.into();
+        if !is_valid_selector(&selector) {
+            return Err(BimiSelectorError(selector));
+        }
+        Ok(Self { selector })
+    }
+
+    /// The selector of the record
+    pub fn selector(&self) -> &str {
+        &self.selector
+    }
+}
+
+/// The `default` selector, used by receivers when the header is missing
+impl Default for BimiSelector {
+    fn default() -> Self {
+        Self {
+            selector: "default".to_owned(),
+        }
+    }
+}
+
+/// Whether `selector` is a valid domain name part
+fn is_valid_selector(selector: &str) -> bool {
+    selector.len() <= 253
+        && selector.split('.').all(|label| {
+            (1..=63).contains(&label.len())
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label
+                    .bytes()
+                    .all(|b| b.is_ascii_alphanumeric() || b == b'-')
+        })
+}
+
+impl Header for BimiSelector {
+    fn name() -> HeaderName {
+        HeaderName::BIMI_SELECTOR
+    }
+
+    fn parse(s: &str) -> Result<Self, BoxError> {
+        let mut tags = s
+            .split(';')
+            .map(str::trim)
+            .filter(|tag| !tag.is_empty())
+            .map(|tag| match tag.split_once('=') {
+                Some((name, value)) => Ok((name.trim(), value.trim())),
+                None => Err(format!("invalid BIMI-Selector tag {tag:?}")),
+            });
+
+        match tags.next().transpose()? {
+            Some(("v", "BIMI1")) => {}
+            _ => return Err("BIMI-Selector doesn't start with v=BIMI1".into()),
+        }
+        let mut selector = Self::default();
+        for tag in tags {
+            match tag? {
+                ("s", value) => selector = Self::new(value)?,
+                (name, _) => return Err(format!("unknown BIMI-Selector tag {name:?}").into()),
+            }
+        }
+
+        Ok(selector)
+    }
+
+    fn display(&self) -> HeaderValue {
+        let val = format!("v=BIMI1; s={};", self.selector);
+        HeaderValue::dangerous_new_pre_encoded(Self::name(), val.clone(), val)
+    }
+}
+
+/// An invalid selector passed to [`BimiSelector::new`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BimiSelectorError(String);
+
+impl Display for BimiSelectorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid BIMI selector {:?}", self.0)
+    }
+}
+
+impl StdError for BimiSelectorError {}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use super::BimiSelector;
+    use crate::message::header::{Header, Headers};
+
+    #[test]
+    fn format_parse_bimi_selector() {
+        let selector = BimiSelector::new("brand2024").unwrap();
+
+        let mut headers = Headers::new();
+        headers.set(selector.clone());
+        assert_eq!(
+            headers.to_string(),
+            "BIMI-Selector: v=BIMI1; s=brand2024;\r\n"
+        );
+        assert_eq!(headers.get::<BimiSelector>(), Some(selector));
+
+        assert_eq!(
+            BimiSelector::parse("v=BIMI1;").unwrap(),
+            BimiSelector::default()
+        );
+        assert_eq!(
+            BimiSelector::parse(" v = BIMI1 ; s = spring.brand ").unwrap(),
+            BimiSelector::new("spring.brand").unwrap()
+        );
+        assert!(BimiSelector::parse("s=brand; v=BIMI1;").is_err());
+        assert!(BimiSelector::parse("v=BIMI2; s=brand;").is_err());
+        assert!(BimiSelector::parse("v=BIMI1; s=bad_selector;").is_err());
+        assert!(BimiSelector::parse("v=BIMI1; l=https://example.com;").is_err());
+    }
+
+    #[test]
+    fn validate_selector() {
+        for valid in ["default", "brand-2024", "spring.brand", "a"] {
+            assert!(BimiSelector::new(valid).is_ok(), "{valid}");
+        }
+        for invalid in ["", "-brand", "brand-", "brand..x", "brand;", "bränd", "s=1"] {
+            assert!(BimiSelector::new(invalid).is_err(), "{invalid}");
+        }
+        assert!(BimiSelector::new("a".repeat(64)).is_err());
+    }
+}
@@ -30,7 +30,7 @@ pub enum ContentTransferEncoding {
 
 impl Header for ContentTransferEncoding {
     fn name() -> HeaderName {
-        HeaderName::new_from_ascii_str("Content-Transfer-Encoding")
+        HeaderName::CONTENT_TRANSFER_ENCODING
     }
 
     fn parse(s: &str) -> Result<Self, BoxError> {
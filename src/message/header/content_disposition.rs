@@ -1,8 +1,8 @@
-use std::fmt::Write;
+use std::{fmt::Write, time::SystemTime};
 
 use email_encoding::headers::writer::EmailWriter;
 
-use super::{Header, HeaderName, HeaderValue};
+use super::{Date, Header, HeaderName, HeaderValue};
 use crate::BoxError;
 
 /// `Content-Disposition` of an attachment
@@ -24,52 +24,252 @@ impl ContentDisposition {
     /// An attachment which should be displayed inline into the message, but that also
     /// species the filename in case it is downloaded
     pub fn inline_with_name(file_name: &str) -> Self {
-        Self::with_name("inline", file_name)
+        ContentDispositionBuilder::inline()
+            .file_name(file_name)
+            .build()
     }
 
     /// An attachment which is separate from the body of the message, and can be downloaded separately
     pub fn attachment(file_name: &str) -> Self {
-        Self::with_name("attachment", file_name)
+        ContentDispositionBuilder::attachment()
+            .file_name(file_name)
+            .build()
     }
 
-    fn with_name(kind: &str, file_name: &str) -> Self {
-        let raw_value = format!("{kind}; filename=\"{file_name}\"");
+    /// Whether this is an `inline` disposition
+    pub fn is_inline(&self) -> bool {
+        self.kind() == "inline"
+    }
+
+    /// Whether this is an `attachment` disposition
+    pub fn is_attachment(&self) -> bool {
+        self.kind() == "attachment"
+    }
+
+    /// The `filename` parameter, if present
+    pub fn file_name(&self) -> Option<&str> {
+        self.param("filename")
+    }
+
+    /// The `size` parameter, if present and valid
+    pub fn size(&self) -> Option<u64> {
+        self.param("size").and_then(|size| size.parse().ok())
+    }
+
+    /// The `creation-date` parameter, if present and valid
+    pub fn creation_date(&self) -> Option<SystemTime> {
+        self.date_param("creation-date")
+    }
+
+    /// The `modification-date` parameter, if present and valid
+    pub fn modification_date(&self) -> Option<SystemTime> {
+        self.date_param("modification-date")
+    }
+
+    fn kind(&self) -> &str {
+        split_params(self.0.get_raw()).map_or("", |(kind, _)| kind)
+    }
+
+    fn param(&self, name: &str) -> Option<&str> {
+        let (_, params) = split_params(self.0.get_raw())?;
+        params
+            .into_iter()
+            .find(|(param, _)| param.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value)
+    }
+
+    fn date_param(&self, name: &str) -> Option<SystemTime> {
+        self.param(name)
+            .and_then(|date| Date::parse(date).ok())
+            .map(SystemTime::from)
+    }
+}
+
+/// Builder for a [`ContentDisposition`] carrying optional parameters
+///
+/// Supports the `filename`, `size`, `creation-date` and `modification-date`
+/// parameters defined in [RFC2183](https://tools.ietf.org/html/rfc2183#section-2).
+#[derive(Debug, Clone)]
+pub struct ContentDispositionBuilder {
+    kind: &'static str,
+    file_name: Option<String>,
+    size: Option<u64>,
+    creation_date: Option<SystemTime>,
+    modification_date: Option<SystemTime>,
+}
+
+impl ContentDispositionBuilder {
+    /// Start building an `inline` disposition
+    pub fn inline() -> Self {
+        Self::new("inline")
+    }
+
+    /// Start building an `attachment` disposition
+    pub fn attachment() -> Self {
+        Self::new("attachment")
+    }
+
+    fn new(kind: &'static str) -> Self {
+        Self {
+            kind,
+            file_name: None,
+            size: None,
+            creation_date: None,
+            modification_date: None,
+        }
+    }
+
+    /// Set the `filename` parameter
+    pub fn file_name(mut self, file_name: &str) -> Self {
+        self.file_name = Some(file_name.to_owned());
+        self
+    }
+
+    /// Set the `size` parameter, the approximate size of the file in bytes
+    pub fn size(mut self, size: u64) -> Self {
+        self.size = Some(size);
+        self
+    }
+
+    /// Set the `creation-date` parameter
+    pub fn creation_date(mut self, date: SystemTime) -> Self {
+        self.creation_date = Some(date);
+        self
+    }
+
+    /// Set the `modification-date` parameter
+    pub fn modification_date(mut self, date: SystemTime) -> Self {
+        self.modification_date = Some(date);
+        self
+    }
+
+    /// Build the [`ContentDisposition`]
+    pub fn build(self) -> ContentDisposition {
+        let mut params = Vec::new();
+        if let Some(size) = self.size {
+            params.push(format!("size={size}"));
+        }
+        if let Some(date) = self.creation_date {
+            params.push(format!("creation-date=\"{}\"", format_date(date)));
+        }
+        if let Some(date) = self.modification_date {
+            params.push(format!("modification-date=\"{}\"", format_date(date)));
+        }
+
+        let mut raw_value = String::from(self.kind);
+        if let Some(file_name) = &self.file_name {
+            write!(raw_value, "; filename=\"{file_name}\"").expect("writing to a String failed");
+        }
+        for param in &params {
+            raw_value.push_str("; ");
+            raw_value.push_str(param);
+        }
 
         let mut encoded_value = String::new();
         let line_len = "Content-Disposition: ".len();
         {
             let mut w = EmailWriter::new(&mut encoded_value, line_len, 0, false);
-            w.write_str(kind).expect("writing `kind` returned an error");
-            w.write_char(';').expect("writing `;` returned an error");
-            w.space();
+            w.write_str(self.kind)
+                .expect("writing `kind` returned an error");
 
-            email_encoding::headers::rfc2231::encode("filename", file_name, &mut w)
-                .expect("some Write implementation returned an error");
+            if let Some(file_name) = &self.file_name {
+                w.write_char(';').expect("writing `;` returned an error");
+                w.space();
+
+                email_encoding::headers::rfc2231::encode("filename", file_name, &mut w)
+                    .expect("some Write implementation returned an error");
+            }
+
+            for param in &params {
+                w.write_char(';').expect("writing `;` returned an error");
+                if w.line_len() + " ".len() + param.len() > 76 {
+                    w.new_line().expect("writing a new line returned an error");
+                }
+                w.space();
+                w.write_str(param)
+                    .expect("writing a parameter returned an error");
+            }
         }
 
-        Self(HeaderValue::dangerous_new_pre_encoded(
-            Self::name(),
+        ContentDisposition(HeaderValue::dangerous_new_pre_encoded(
+            ContentDisposition::name(),
             raw_value,
             encoded_value,
         ))
     }
 }
 
+fn format_date(date: SystemTime) -> String {
+    Date::new(date).display().get_raw().to_owned()
+}
+
+/// Split a raw `Content-Disposition` value into its type and parameters
+///
+/// Quoted parameter values are returned without the surrounding quotes.
+fn split_params(s: &str) -> Option<(&str, Vec<(&str, &str)>)> {
+    let (kind, mut rest) = s.split_once(';').unwrap_or((s, ""));
+
+    let mut params = Vec::new();
+    loop {
+        rest = rest.trim_start();
+        if rest.is_empty() {
+            break;
+        }
+
+        let (name, value) = rest.split_once('=')?;
+        let value = value.trim_start();
+        let (value, remaining) = match value.strip_prefix('"') {
+            Some(quoted) => {
+                // Values written by `ContentDispositionBuilder` aren't escaped,
+                // so the closing quote is the one followed by `;` or the end
+                let end = quoted.match_indices('"').map(|(i, _)| i).find(|&i| {
+                    let after = quoted[i + 1..].trim_start();
+                    after.is_empty() || after.starts_with(';')
+                })?;
+                let remaining = quoted[end + 1..].trim_start();
+                (
+                    &quoted[..end],
+                    remaining.strip_prefix(';').unwrap_or(remaining),
+                )
+            }
+            None => {
+                let (value, remaining) = value.split_once(';').unwrap_or((value, ""));
+                (value.trim_end(), remaining)
+            }
+        };
+
+        params.push((name.trim(), value));
+        rest = remaining;
+    }
+
+    Some((kind.trim(), params))
+}
+
 impl Header for ContentDisposition {
     fn name() -> HeaderName {
-        HeaderName::new_from_ascii_str("Content-Disposition")
+        HeaderName::CONTENT_DISPOSITION
     }
 
     fn parse(s: &str) -> Result<Self, BoxError> {
-        match (s.split_once(';'), s) {
-            (_, "inline") => Ok(Self::inline()),
-            (Some((kind @ ("inline" | "attachment"), file_name)), _) => file_name
-                .split_once(" filename=\"")
-                .and_then(|(_, file_name)| file_name.strip_suffix('"'))
-                .map(|file_name| Self::with_name(kind, file_name))
-                .ok_or_else(|| "Unsupported ContentDisposition value".into()),
-            _ => Err("Unsupported ContentDisposition value".into()),
+        let (kind, params) = split_params(s).ok_or("Unsupported ContentDisposition value")?;
+        let mut builder = match kind {
+            "inline" if params.is_empty() => return Ok(Self::inline()),
+            "inline" => ContentDispositionBuilder::inline(),
+            "attachment" => ContentDispositionBuilder::attachment(),
+            _ => return Err("Unsupported ContentDisposition value".into()),
+        };
+
+        for (name, value) in params {
+            builder = match name.to_ascii_lowercase().as_str() {
+                "filename" => builder.file_name(value),
+                "size" => builder.size(value.parse()?),
+                "creation-date" => builder.creation_date(Date::parse(value)?.into()),
+                "modification-date" => builder.modification_date(Date::parse(value)?.into()),
+                _ => return Err("Unsupported ContentDisposition parameter".into()),
+            };
         }
+
+        Ok(builder.build())
     }
 
     fn display(&self) -> HeaderValue {
@@ -79,9 +279,11 @@ impl Header for ContentDisposition {
 
 #[cfg(test)]
 mod test {
+    use std::time::{Duration, SystemTime};
+
     use pretty_assertions::assert_eq;
 
-    use super::ContentDisposition;
+    use super::{ContentDisposition, ContentDispositionBuilder};
     use crate::message::header::{HeaderName, HeaderValue, Headers};
 
     #[test]
@@ -124,4 +326,54 @@ mod test {
             Some(ContentDisposition::attachment("something.txt"))
         );
     }
+
+    #[test]
+    fn format_content_disposition_with_parameters() {
+        let mut headers = Headers::new();
+
+        headers.set(
+            ContentDispositionBuilder::attachment()
+                .file_name("report.pdf")
+                .size(4096)
+                .creation_date(SystemTime::UNIX_EPOCH + Duration::from_secs(855_160_191))
+                .modification_date(SystemTime::UNIX_EPOCH + Duration::from_secs(855_246_591))
+                .build(),
+        );
+
+        assert_eq!(
+            format!("{headers}"),
+            concat!(
+                "Content-Disposition: attachment; filename=\"report.pdf\"; size=4096;\r\n",
+                " creation-date=\"Wed, 05 Feb 1997 16:29:51 +0000\";\r\n",
+                " modification-date=\"Thu, 06 Feb 1997 16:29:51 +0000\"\r\n",
+            )
+        );
+    }
+
+    #[test]
+    fn parse_content_disposition_with_parameters() {
+        let mut headers = Headers::new();
+
+        headers.insert_raw(HeaderValue::new(
+            HeaderName::new_from_ascii_str("Content-Disposition"),
+            "attachment; filename=\"a; b.txt\"; size=12; modification-date=\"Thu, 06 Feb 1997 16:29:51 +0000\"".to_owned(),
+        ));
+
+        let disposition = headers.get::<ContentDisposition>().unwrap();
+        assert!(disposition.is_attachment());
+        assert!(!disposition.is_inline());
+        assert_eq!(disposition.file_name(), Some("a; b.txt"));
+        assert_eq!(disposition.size(), Some(12));
+        assert_eq!(disposition.creation_date(), None);
+        assert_eq!(
+            disposition.modification_date(),
+            Some(SystemTime::UNIX_EPOCH + Duration::from_secs(855_246_591))
+        );
+
+        headers.insert_raw(HeaderValue::new(
+            HeaderName::new_from_ascii_str("Content-Disposition"),
+            "attachment; size=twelve".to_owned(),
+        ));
+        assert_eq!(headers.get::<ContentDisposition>(), None);
+    }
 }
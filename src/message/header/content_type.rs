@@ -47,7 +47,7 @@ impl ContentType {
 
 impl Header for ContentType {
     fn name() -> HeaderName {
-        HeaderName::new_from_ascii_str("Content-Type")
+        HeaderName::CONTENT_TYPE
     }
 
     fn parse(s: &str) -> Result<Self, BoxError> {
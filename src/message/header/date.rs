@@ -1,4 +1,7 @@
-use std::time::SystemTime;
+use std::{
+    fmt::Write,
+    time::{Duration, SystemTime},
+};
 
 use httpdate::HttpDate;
 
@@ -7,14 +10,29 @@ use crate::BoxError;
 
 /// Message `Date` header
 ///
+/// Formatted in UTC by default, [`Date::with_utc_offset`]
+/// allows displaying it in another time zone.
+///
+/// Dates are compared on the instant they represent, so the same
+/// instant displayed with different offsets compares equal.
+///
 /// Defined in [RFC2822](https://tools.ietf.org/html/rfc2822#section-3.3)
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub struct Date(HttpDate);
+#[derive(Debug, Clone, Copy)]
+pub struct Date {
+    date: HttpDate,
+    utc_offset: i16,
+}
+
+/// Largest UTC offset allowed by the date format, in minutes (`+2359`)
+const MAX_UTC_OFFSET: i16 = 24 * 60 - 1;
 
 impl Date {
     /// Build a `Date` from [`SystemTime`]
     pub fn new(st: SystemTime) -> Self {
-        Self(st.into())
+        Self {
+            date: st.into(),
+            utc_offset: 0,
+        }
     }
 
     /// Get the current date
@@ -23,35 +41,113 @@ impl Date {
     pub fn now() -> Self {
         Self::new(SystemTime::now())
     }
+
+    /// Display the date with a fixed offset from UTC, in minutes
+    ///
+    /// The represented instant doesn't change, only the local time and
+    /// zone it gets displayed with. For example an offset of `120`
+    /// formats the date as `+0200` local time.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `offset_minutes` is outside of the `-1439..=1439` range
+    /// (`-2359` to `+2359`).
+    pub fn with_utc_offset(mut self, offset_minutes: i16) -> Self {
+        assert_utc_offset(offset_minutes);
+        self.utc_offset = offset_minutes;
+        self
+    }
+
+    /// Get the offset from UTC the date is displayed with, in minutes
+    pub fn utc_offset(&self) -> i16 {
+        self.utc_offset
+    }
+}
+
+impl PartialEq for Date {
+    fn eq(&self, other: &Self) -> bool {
+        self.date == other.date
+    }
+}
+
+impl Eq for Date {}
+
+/// Panics if `offset_minutes` can't be displayed by the date format
+pub(crate) fn assert_utc_offset(offset_minutes: i16) {
+    assert!(
+        (-MAX_UTC_OFFSET..=MAX_UTC_OFFSET).contains(&offset_minutes),
+        "UTC offset out of range: {offset_minutes} minutes"
+    );
+}
+
+/// Shift `st` by `offset_minutes`
+fn shift(st: SystemTime, offset_minutes: i16) -> Option<SystemTime> {
+    let offset = Duration::from_secs(u64::from(offset_minutes.unsigned_abs()) * 60);
+    if offset_minutes >= 0 {
+        st.checked_add(offset)
+    } else {
+        st.checked_sub(offset)
+    }
+}
+
+/// Parse a `+hhmm` or `-hhmm` UTC offset into minutes
+fn parse_utc_offset(zone: &str) -> Option<i16> {
+    let (sign, digits) = if let Some(digits) = zone.strip_prefix('+') {
+        (1, digits)
+    } else {
+        (-1, zone.strip_prefix('-')?)
+    };
+    if digits.len() != 4 || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+
+    let hours: i16 = digits[..2].parse().ok()?;
+    let minutes: i16 = digits[2..].parse().ok()?;
+    if minutes >= 60 {
+        return None;
+    }
+    Some(sign * (hours * 60 + minutes))
 }
 
 impl Header for Date {
     fn name() -> HeaderName {
-        HeaderName::new_from_ascii_str("Date")
+        HeaderName::DATE
     }
 
     fn parse(s: &str) -> Result<Self, BoxError> {
-        let mut s = String::from(s);
-        if s.ends_with("+0000") {
-            // The httpdate crate expects the `Date` to end in ` GMT`, but email
-            // uses `+0000` to indicate UTC, so we crudely fix this issue here.
+        let utc_offset = s
+            .rsplit_once(' ')
+            .and_then(|(date, zone)| parse_utc_offset(zone).map(|utc_offset| (date, utc_offset)));
+        let Some((date, utc_offset)) = utc_offset else {
+            return Ok(Self::new(s.parse::<HttpDate>()?.into()));
+        };
 
-            s.truncate(s.len() - "+0000".len());
-            s.push_str("GMT");
-        }
-
-        Ok(Self(s.parse::<HttpDate>()?))
+        // The httpdate crate expects the `Date` to end in ` GMT`, but email
+        // uses numeric offsets, so we parse the local time as if it was UTC
+        // and then shift it back.
+        let local = SystemTime::from(format!("{date} GMT").parse::<HttpDate>()?);
+        let date = shift(local, -utc_offset).ok_or("Date out of range")?;
+        Ok(Self::new(date).with_utc_offset(utc_offset))
     }
 
     fn display(&self) -> HeaderValue {
-        let mut val = self.0.to_string();
+        let utc = SystemTime::from(self.date);
+        let (local, utc_offset) = match shift(utc, self.utc_offset) {
+            Some(local) if local >= SystemTime::UNIX_EPOCH => (local, self.utc_offset),
+            _ => (utc, 0),
+        };
+
+        let mut val = HttpDate::from(local).to_string();
         if val.ends_with(" GMT") {
             // The httpdate crate always appends ` GMT` to the end of the string,
             // but this is considered an obsolete date format for email
             // https://tools.ietf.org/html/rfc2822#appendix-A.6.2,
-            // so we replace `GMT` with `+0000`
+            // so we replace `GMT` with the numeric offset
             val.truncate(val.len() - "GMT".len());
-            val.push_str("+0000");
+            let sign = if utc_offset < 0 { '-' } else { '+' };
+            let offset = utc_offset.unsigned_abs();
+            write!(val, "{sign}{:02}{:02}", offset / 60, offset % 60)
+                .expect("writing to a String failed");
         }
 
         HeaderValue::dangerous_new_pre_encoded(Self::name(), val.clone(), val)
@@ -66,7 +162,54 @@ impl From<SystemTime> for Date {
 
 impl From<Date> for SystemTime {
     fn from(this: Date) -> SystemTime {
-        this.0.into()
+        this.date.into()
+    }
+}
+
+/// Message `Resent-Date` header, the date a message was resent
+///
+/// Formatted like [`Date`].
+///
+/// Defined in [RFC5322](https://tools.ietf.org/html/rfc5322#section-3.6.6)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResentDate(Date);
+
+impl ResentDate {
+    /// Build a `Resent-Date` from [`SystemTime`]
+    pub fn new(st: SystemTime) -> Self {
+        Self(Date::new(st))
+    }
+
+    /// Get the current date
+    pub fn now() -> Self {
+        Self(Date::now())
+    }
+}
+
+impl Header for ResentDate {
+    fn name() -> HeaderName {
+        HeaderName::RESENT_DATE
+    }
+
+    fn parse(s: &str) -> Result<Self, BoxError> {
+        Date::parse(s).map(Self)
+    }
+
+    fn display(&self) -> HeaderValue {
+        let val = self.0.display().get_raw().to_owned();
+        HeaderValue::dangerous_new_pre_encoded(Self::name(), val.clone(), val)
+    }
+}
+
+impl From<Date> for ResentDate {
+    fn from(date: Date) -> Self {
+        Self(date)
+    }
+}
+
+impl From<ResentDate> for Date {
+    fn from(this: ResentDate) -> Date {
+        this.0
     }
 }
 
@@ -77,7 +220,7 @@ mod test {
     use pretty_assertions::assert_eq;
 
     use super::Date;
-    use crate::message::header::{HeaderName, HeaderValue, Headers};
+    use crate::message::header::{Header, HeaderName, HeaderValue, Headers};
 
     #[test]
     fn format_date() {
@@ -132,4 +275,57 @@ mod test {
             ))
         );
     }
+
+    #[test]
+    fn format_date_with_utc_offset() {
+        let date = Date::from(SystemTime::UNIX_EPOCH + Duration::from_secs(784887151));
+
+        assert_eq!(
+            date.with_utc_offset(90).display().get_raw(),
+            "Tue, 15 Nov 1994 09:42:31 +0130"
+        );
+        assert_eq!(
+            date.with_utc_offset(-10 * 60).display().get_raw(),
+            "Mon, 14 Nov 1994 22:12:31 -1000"
+        );
+        assert_eq!(
+            SystemTime::from(date.with_utc_offset(-10 * 60)),
+            SystemTime::from(date)
+        );
+        assert_eq!(date.with_utc_offset(-10 * 60), date);
+    }
+
+    #[test]
+    fn parse_date_with_utc_offset() {
+        let mut headers = Headers::new();
+
+        headers.insert_raw(HeaderValue::new(
+            HeaderName::new_from_ascii_str("Date"),
+            "Mon, 14 Nov 1994 22:12:31 -1000".to_owned(),
+        ));
+
+        let date = headers.get::<Date>().unwrap();
+        assert_eq!(date.utc_offset(), -600);
+        assert_eq!(
+            SystemTime::from(date),
+            SystemTime::UNIX_EPOCH + Duration::from_secs(784887151)
+        );
+
+        headers.insert_raw(HeaderValue::new(
+            HeaderName::new_from_ascii_str("Date"),
+            "Tue, 15 Nov 1994 08:12:31 GMT".to_owned(),
+        ));
+        assert_eq!(
+            headers.get::<Date>(),
+            Some(Date::from(
+                SystemTime::UNIX_EPOCH + Duration::from_secs(784887151),
+            ))
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "UTC offset out of range")]
+    fn utc_offset_out_of_range() {
+        let _ = Date::now().with_utc_offset(24 * 60);
+    }
 }
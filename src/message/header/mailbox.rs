@@ -19,7 +19,7 @@ macro_rules! mailbox_header {
 
         impl Header for $type_name {
             fn name() -> HeaderName {
-                HeaderName::new_from_ascii_str($header_name)
+                $header_name
             }
 
             fn parse(s: &str) -> Result<Self, BoxError> {
@@ -69,7 +69,7 @@ macro_rules! mailboxes_header {
 
         impl Header for $type_name {
             fn name() -> HeaderName {
-                HeaderName::new_from_ascii_str($header_name)
+                $header_name
             }
 
             fn parse(s: &str) -> Result<Self, BoxError> {
@@ -116,7 +116,7 @@ mailbox_header! {
     header::Sender("Mr. Sender <sender@example.com>".parse().unwrap())
     ```
      */
-    (Sender, "Sender")
+    (Sender, HeaderName::SENDER)
 }
 
 mailboxes_header! {
@@ -127,7 +127,7 @@ mailboxes_header! {
     This header contains [`Mailboxes`].
 
      */
-    (From, "From")
+    (From, HeaderName::FROM)
 }
 
 mailboxes_header! {
@@ -138,7 +138,7 @@ mailboxes_header! {
     This header contains [`Mailboxes`].
 
      */
-    (ReplyTo, "Reply-To")
+    (ReplyTo, HeaderName::REPLY_TO)
 }
 
 mailboxes_header! {
@@ -149,7 +149,7 @@ mailboxes_header! {
     This header contains [`Mailboxes`].
 
      */
-    (To, "To")
+    (To, HeaderName::TO)
 }
 
 mailboxes_header! {
@@ -160,7 +160,7 @@ mailboxes_header! {
     This header contains [`Mailboxes`].
 
      */
-    (Cc, "Cc")
+    (Cc, HeaderName::CC)
 }
 
 mailboxes_header! {
@@ -171,7 +171,62 @@ mailboxes_header! {
     This header contains [`Mailboxes`].
 
      */
-    (Bcc, "Bcc")
+    (Bcc, HeaderName::BCC)
+}
+
+mailbox_header! {
+    /**
+
+    `Resent-Sender` header
+
+    The sender of a resent message, required when it has several `Resent-From` mailboxes.
+
+     */
+    (ResentSender, HeaderName::RESENT_SENDER)
+}
+
+mailboxes_header! {
+    /**
+
+    `Resent-From` header
+
+    This header contains [`Mailboxes`] which resent the message.
+
+     */
+    (ResentFrom, HeaderName::RESENT_FROM)
+}
+
+mailboxes_header! {
+    /**
+
+    `Resent-To` header
+
+    This header contains [`Mailboxes`] the message is resent to.
+
+     */
+    (ResentTo, HeaderName::RESENT_TO)
+}
+
+mailboxes_header! {
+    /**
+
+    `Resent-Cc` header
+
+    This header contains [`Mailboxes`].
+
+     */
+    (ResentCc, HeaderName::RESENT_CC)
+}
+
+mailboxes_header! {
+    /**
+
+    `Resent-Bcc` header
+
+    This header contains [`Mailboxes`].
+
+     */
+    (ResentBcc, HeaderName::RESENT_BCC)
 }
 
 #[cfg(test)]
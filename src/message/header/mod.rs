@@ -5,26 +5,36 @@ use std::{
     error::Error,
     fmt::{self, Display, Formatter, Write},
     ops::Deref,
+    ptr,
 };
 
 use email_encoding::headers::writer::EmailWriter;
 
+pub(crate) use self::date::assert_utc_offset;
 pub use self::{
+    bimi::{BimiSelector, BimiSelectorError},
     content::*,
-    content_disposition::ContentDisposition,
+    content_disposition::{ContentDisposition, ContentDispositionBuilder},
     content_type::{ContentType, ContentTypeErr},
-    date::Date,
+    date::{Date, ResentDate},
     mailbox::*,
+    order::HeaderOrder,
+    received::Received,
     special::*,
     textual::*,
 };
 use crate::BoxError;
 
+mod bimi;
 mod content;
 mod content_disposition;
 mod content_type;
 mod date;
 mod mailbox;
+mod order;
+mod received;
+#[cfg(feature = "serde")]
+mod serde;
 mod special;
 mod textual;
 
@@ -39,6 +49,92 @@ pub trait Header: Clone {
     fn display(&self) -> HeaderValue;
 }
 
+/// How [`MessageBuilder`](crate::message::MessageBuilder) handles control
+/// characters, like CR and LF, in header values
+///
+/// They are never written as is, which would allow header injection,
+/// but encoded by default, which is rarely what's intended for values
+/// coming from users. To reject them instead, set the headers with
+/// [`MessageBuilder::try_header`](crate::message::MessageBuilder::try_header).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum HeaderSanitization {
+    /// Encode control characters in encoded-words
+    #[default]
+    Encode,
+    /// Remove control characters
+    ///
+    /// The value is parsed again once stripped. If the header type can't
+    /// parse it anymore, the stripped text is written as is.
+    Strip,
+}
+
+impl HeaderSanitization {
+    /// Whether `value` contains control characters other than tabs
+    pub(crate) fn needs_sanitizing(value: &str) -> bool {
+        value.chars().any(is_control)
+    }
+
+    /// Remove control characters other than tabs from `value`
+    pub(crate) fn strip(value: &str) -> String {
+        value.chars().filter(|&c| !is_control(c)).collect()
+    }
+}
+
+fn is_control(c: char) -> bool {
+    c.is_control() && c != '\t'
+}
+
+/// [RFC 2047](https://datatracker.ietf.org/doc/html/rfc2047) encoding
+/// of the encoded-words
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum WordEncoding {
+    /// The "B" encoding, using base64
+    #[default]
+    Base64,
+    /// The "Q" encoding, similar to quoted-printable
+    ///
+    /// Keeps ASCII characters readable, and is better handled
+    /// by some gateways than long base64 encoded-words.
+    QuotedPrintable,
+}
+
+/// How header values are encoded
+///
+/// Used by [`HeaderValue::new_with_encoding`], and by
+/// [`MessageBuilder::header_encoding`](crate::message::MessageBuilder::header_encoding)
+/// for the unstructured headers, `Subject` and `Comments`.
+/// By default, only the words containing non-ASCII characters are encoded,
+/// using [`WordEncoding::Base64`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct HeaderEncoding {
+    word_encoding: WordEncoding,
+    encode_all: bool,
+}
+
+impl HeaderEncoding {
+    /// Creates the default header encoding
+    pub const fn new() -> Self {
+        Self {
+            word_encoding: WordEncoding::Base64,
+            encode_all: false,
+        }
+    }
+
+    /// Set the encoding of the encoded-words
+    pub const fn word_encoding(mut self, word_encoding: WordEncoding) -> Self {
+        self.word_encoding = word_encoding;
+        self
+    }
+
+    /// Encode the whole value, even the words only containing ASCII characters
+    pub const fn encode_all(mut self, encode_all: bool) -> Self {
+        self.encode_all = encode_all;
+        self
+    }
+}
+
 /// A set of email headers
 #[derive(Debug, Clone, Default)]
 pub struct Headers {
@@ -123,10 +219,56 @@ impl Headers {
         self.find_header_index(name).map(|i| self.headers.remove(i))
     }
 
+    /// Iterates over the names and the raw values of the headers,
+    /// in the order they were set
+    pub(crate) fn iter_raw(&self) -> impl Iterator<Item = (&HeaderName, &str)> + '_ {
+        self.headers
+            .iter()
+            .map(|value| (&value.name, value.raw_value.as_str()))
+    }
+
     pub(crate) fn find_header(&self, name: &str) -> Option<&HeaderValue> {
         self.headers.iter().find(|value| name == value.name)
     }
 
+    /// Inserts the headers of `defaults` which aren't present
+    pub(crate) fn insert_missing(&mut self, defaults: &Headers) {
+        for value in &defaults.headers {
+            if self.find_header(&value.name).is_none() {
+                self.headers.push(value.clone());
+            }
+        }
+    }
+
+    /// Sets all the headers of `headers`, overriding the ones with the same name
+    pub(crate) fn set_all(&mut self, headers: Headers) {
+        for value in headers.headers {
+            self.insert_raw(value);
+        }
+    }
+
+    /// Whether there are no headers
+    pub(crate) fn is_empty(&self) -> bool {
+        self.headers.is_empty()
+    }
+
+    /// Inserts all the headers of `headers` before the existing ones
+    pub(crate) fn prepend(&mut self, headers: Headers) {
+        self.headers.splice(0..0, headers.headers);
+    }
+
+    /// Formats the headers in the preferred `order`
+    pub(crate) fn format_ordered(&self, order: &HeaderOrder, out: &mut Vec<u8>) {
+        let mut values = self.headers.iter().collect::<Vec<_>>();
+        values.sort_by_cached_key(|value| order.rank(&value.name));
+        for value in values {
+            out.extend_from_slice(value.name.as_bytes());
+            out.extend_from_slice(b": ");
+            out.extend_from_slice(value.encoded_value.as_bytes());
+            out.extend_from_slice(b"\r\n");
+        }
+    }
+
     fn find_header_mut(&mut self, name: &str) -> Option<&mut HeaderValue> {
         self.headers.iter_mut().find(|value| name == value.name)
     }
@@ -169,6 +311,32 @@ impl fmt::Display for InvalidHeaderName {
 
 impl Error for InvalidHeaderName {}
 
+/// An error returned when a header value contains control characters,
+/// like CR or LF
+#[derive(Debug, Clone)]
+pub struct InvalidHeaderValue {
+    name: HeaderName,
+}
+
+impl InvalidHeaderValue {
+    pub(crate) fn new(name: HeaderName) -> Self {
+        Self { name }
+    }
+
+    /// Name of the header
+    pub fn name(&self) -> &HeaderName {
+        &self.name
+    }
+}
+
+impl fmt::Display for InvalidHeaderValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "header \"{}\" contains control characters", self.name)
+    }
+}
+
+impl Error for InvalidHeaderValue {}
+
 /// A valid header name
 #[derive(Debug, Clone)]
 pub struct HeaderName(Cow<'static, str>);
@@ -209,6 +377,69 @@ impl HeaderName {
     }
 }
 
+macro_rules! standard_header_names {
+    ($($konst:ident => $name:literal,)+) => {
+        /// Names of the standard headers
+        ///
+        /// Using them avoids creating the names again, and makes comparing them
+        /// with the names of the headers of a message fast.
+        impl HeaderName {
+            $(
+                #[doc = concat!("`", $name, "`")]
+                pub const $konst: Self = Self::new_from_ascii_str($name);
+            )+
+        }
+    };
+}
+
+standard_header_names! {
+    BCC => "Bcc",
+    BIMI_SELECTOR => "BIMI-Selector",
+    CC => "Cc",
+    COMMENTS => "Comments",
+    CONTENT_DESCRIPTION => "Content-Description",
+    CONTENT_DISPOSITION => "Content-Disposition",
+    CONTENT_ID => "Content-ID",
+    CONTENT_LANGUAGE => "Content-Language",
+    CONTENT_LOCATION => "Content-Location",
+    CONTENT_TRANSFER_ENCODING => "Content-Transfer-Encoding",
+    CONTENT_TYPE => "Content-Type",
+    DATE => "Date",
+    DKIM_SIGNATURE => "DKIM-Signature",
+    FROM => "From",
+    IN_REPLY_TO => "In-Reply-To",
+    KEYWORDS => "Keywords",
+    LIST_ID => "List-Id",
+    LIST_UNSUBSCRIBE => "List-Unsubscribe",
+    LIST_UNSUBSCRIBE_POST => "List-Unsubscribe-Post",
+    MESSAGE_ID => "Message-ID",
+    MIME_VERSION => "MIME-Version",
+    RECEIVED => "Received",
+    REFERENCES => "References",
+    REPLY_TO => "Reply-To",
+    RESENT_BCC => "Resent-Bcc",
+    RESENT_CC => "Resent-Cc",
+    RESENT_DATE => "Resent-Date",
+    RESENT_FROM => "Resent-From",
+    RESENT_MESSAGE_ID => "Resent-Message-ID",
+    RESENT_SENDER => "Resent-Sender",
+    RESENT_TO => "Resent-To",
+    RETURN_PATH => "Return-Path",
+    SENDER => "Sender",
+    SUBJECT => "Subject",
+    TO => "To",
+    USER_AGENT => "User-Agent",
+}
+
+/// Whether the header names `a` and `b` are equal, ignoring case
+///
+/// Names are mostly compared to names written the same way,
+/// often the same constant, so check for that first.
+#[inline]
+fn names_eq(a: &str, b: &str) -> bool {
+    a.len() == b.len() && (ptr::eq(a.as_ptr(), b.as_ptr()) || a == b || a.eq_ignore_ascii_case(b))
+}
+
 impl Display for HeaderName {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         f.write_str(self)
@@ -241,19 +472,19 @@ impl AsRef<str> for HeaderName {
 
 impl PartialEq<HeaderName> for HeaderName {
     fn eq(&self, other: &HeaderName) -> bool {
-        self.eq_ignore_ascii_case(other)
+        names_eq(self, other)
     }
 }
 
 impl PartialEq<&str> for HeaderName {
     fn eq(&self, other: &&str) -> bool {
-        self.eq_ignore_ascii_case(other)
+        names_eq(self, other)
     }
 }
 
 impl PartialEq<HeaderName> for &str {
     fn eq(&self, other: &HeaderName) -> bool {
-        self.eq_ignore_ascii_case(other)
+        names_eq(self, other)
     }
 }
 
@@ -263,6 +494,9 @@ pub struct HeaderValue {
     name: HeaderName,
     raw_value: String,
     encoded_value: String,
+    /// Whether the value is unstructured text, encoded with the default
+    /// [`HeaderEncoding`], which can be encoded again with another one
+    unstructured: bool,
 }
 
 impl HeaderValue {
@@ -273,13 +507,36 @@ impl HeaderValue {
     ///
     /// [`RFC2047`]: https://datatracker.ietf.org/doc/html/rfc2047
     pub fn new(name: HeaderName, raw_value: String) -> Self {
+        Self::new_with_encoding(name, raw_value, HeaderEncoding::new())
+    }
+
+    /// Construct a new `HeaderValue` for unstructured text, like the subject,
+    /// which [`MessageBuilder::header_encoding`](crate::message::MessageBuilder::header_encoding)
+    /// applies to
+    pub(crate) fn new_unstructured(name: HeaderName, raw_value: String) -> Self {
+        Self {
+            unstructured: true,
+            ..Self::new(name, raw_value)
+        }
+    }
+
+    /// Construct a new `HeaderValue` and encode it using `encoding`
+    ///
+    /// Like [`HeaderValue::new`], but allows choosing the encoding
+    /// of the encoded-words and forcing the whole value to be encoded.
+    pub fn new_with_encoding(
+        name: HeaderName,
+        raw_value: String,
+        encoding: HeaderEncoding,
+    ) -> Self {
         let mut encoded_value = String::with_capacity(raw_value.len());
-        HeaderValueEncoder::encode(&name, &raw_value, &mut encoded_value).unwrap();
+        HeaderValueEncoder::encode(&name, &raw_value, encoding, &mut encoded_value).unwrap();
 
         Self {
             name,
             raw_value,
             encoded_value,
+            unstructured: false,
         }
     }
 
@@ -300,14 +557,31 @@ impl HeaderValue {
             name,
             raw_value,
             encoded_value,
+            unstructured: false,
         }
     }
 
-    #[cfg(feature = "dkim")]
     pub(crate) fn get_raw(&self) -> &str {
         &self.raw_value
     }
 
+    /// Replace the raw value with `raw_value`, and encode it again
+    pub(crate) fn with_raw(self, raw_value: String) -> Self {
+        Self {
+            unstructured: self.unstructured,
+            ..Self::new(self.name, raw_value)
+        }
+    }
+
+    /// Encode the value again using `encoding` if it's unstructured text
+    pub(crate) fn with_encoding(self, encoding: HeaderEncoding) -> Self {
+        if self.unstructured && encoding != HeaderEncoding::new() {
+            Self::new_with_encoding(self.name, self.raw_value, encoding)
+        } else {
+            self
+        }
+    }
+
     #[cfg(feature = "dkim")]
     pub(crate) fn get_encoded(&self) -> &str {
         &self.encoded_value
@@ -318,27 +592,34 @@ impl HeaderValue {
 struct HeaderValueEncoder<'a> {
     writer: EmailWriter<'a>,
     encode_buf: String,
+    encoding: HeaderEncoding,
 }
 
 impl<'a> HeaderValueEncoder<'a> {
-    fn encode(name: &str, value: &'a str, f: &'a mut impl fmt::Write) -> fmt::Result {
-        let encoder = Self::new(name, f);
+    fn encode(
+        name: &str,
+        value: &'a str,
+        encoding: HeaderEncoding,
+        f: &'a mut impl fmt::Write,
+    ) -> fmt::Result {
+        let encoder = Self::new(name, encoding, f);
         encoder.format(value.split_inclusive(' '))
     }
 
-    fn new(name: &str, writer: &'a mut dyn Write) -> Self {
+    fn new(name: &str, encoding: HeaderEncoding, writer: &'a mut dyn Write) -> Self {
         let line_len = name.len() + ": ".len();
         let writer = EmailWriter::new(writer, line_len, 0, false);
 
         Self {
             writer,
             encode_buf: String::new(),
+            encoding,
         }
     }
 
     fn format(mut self, words_iter: impl Iterator<Item = &'a str>) -> fmt::Result {
         for next_word in words_iter {
-            let allowed = allowed_str(next_word);
+            let allowed = !self.encoding.encode_all && allowed_str(next_word);
 
             if allowed {
                 // This word only contains allowed characters
@@ -365,7 +646,12 @@ impl<'a> HeaderValueEncoder<'a> {
         }
 
         let prefix = self.encode_buf.trim_end_matches(' ');
-        email_encoding::headers::rfc2047::encode(prefix, &mut self.writer)?;
+        match self.encoding.word_encoding {
+            WordEncoding::Base64 => {
+                email_encoding::headers::rfc2047::encode(prefix, &mut self.writer)?;
+            }
+            WordEncoding::QuotedPrintable => q_encode(prefix, &mut self.writer)?,
+        }
 
         // TODO: add a better API for doing this in email-encoding
         let spaces = self.encode_buf.len() - prefix.len();
@@ -378,6 +664,72 @@ impl<'a> HeaderValueEncoder<'a> {
     }
 }
 
+/// Encode `s` in RFC 2047 "Q" encoded-words, folding them to fit the lines
+fn q_encode(mut s: &str, w: &mut EmailWriter<'_>) -> fmt::Result {
+    const PREFIX: &str = "=?utf-8?q?";
+    const SUFFIX: &str = "?=";
+    const MAX_LINE_LEN: usize = 76;
+
+    let mut wrote = false;
+    while !s.is_empty() {
+        let remaining_line_len =
+            MAX_LINE_LEN.saturating_sub(PREFIX.len() + SUFFIX.len() + w.line_len() + "\r\n".len());
+
+        // Take as many characters as fit on the line, without splitting them
+        let mut encoded_len = 0;
+        let mut word_len = 0;
+        for c in s.chars() {
+            let len = c.len_utf8()
+                * if c.is_ascii() && q_allowed_char(c as u8) {
+                    1
+                } else {
+                    3
+                };
+            if encoded_len + len > remaining_line_len {
+                break;
+            }
+            encoded_len += len;
+            word_len += c.len_utf8();
+        }
+
+        if word_len == 0 {
+            let has_spaces = w.projected_line_len() > w.line_len();
+            if wrote || has_spaces {
+                // No space remaining on this line, go to a new one
+                w.new_line()?;
+                if !has_spaces {
+                    // Keep the folded encoded-words within the same header
+                    w.space();
+                }
+                continue;
+            }
+
+            word_len = s.chars().next().expect("`s` is empty").len_utf8();
+        }
+
+        let (word, rest) = s.split_at(word_len);
+        w.write_str(PREFIX)?;
+        for b in word.bytes() {
+            match b {
+                b' ' => w.write_char('_')?,
+                b if q_allowed_char(b) => w.write_char(char::from(b))?,
+                b => write!(w, "={b:02X}")?,
+            }
+        }
+        w.write_str(SUFFIX)?;
+
+        s = rest;
+        wrote = true;
+    }
+
+    Ok(())
+}
+
+/// Characters written as is in "Q" encoded-words, space being written as `_`
+const fn q_allowed_char(c: u8) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, b' ' | b'!' | b'*' | b'+' | b'-' | b'/')
+}
+
 fn allowed_str(s: &str) -> bool {
     s.bytes().all(allowed_char)
 }
@@ -390,7 +742,7 @@ const fn allowed_char(c: u8) -> bool {
 mod tests {
     use pretty_assertions::assert_eq;
 
-    use super::{HeaderName, HeaderValue, Headers, To};
+    use super::{HeaderEncoding, HeaderName, HeaderValue, Headers, To, WordEncoding};
     use crate::message::Mailboxes;
 
     #[test]
@@ -473,6 +825,15 @@ mod tests {
         );
     }
 
+    #[test]
+    fn standard_headername_eq() {
+        assert_eq!(HeaderName::FROM, HeaderName::FROM);
+        assert_eq!(HeaderName::FROM, HeaderName::new_from_ascii_str("FROM"));
+        assert_eq!(HeaderName::MESSAGE_ID, "message-id");
+        assert_ne!(HeaderName::TO, HeaderName::CC);
+        assert_ne!(HeaderName::RESENT_TO, "Resent-Cc");
+    }
+
     #[test]
     fn headername_str_eq_case_insensitive() {
         assert_eq!(HeaderName::new_from_ascii_str("From"), "from");
@@ -600,6 +961,52 @@ mod tests {
         );
     }
 
+    #[test]
+    fn format_special_q_encoding() {
+        let mut headers = Headers::new();
+        headers.insert_raw(HeaderValue::new_with_encoding(
+            HeaderName::new_from_ascii_str("Subject"),
+            "Café au lait = très bon, très très bon, très très très bon, très très très très bon"
+                .to_owned(),
+            HeaderEncoding::new().word_encoding(WordEncoding::QuotedPrintable),
+        ));
+
+        assert_eq!(
+            headers.to_string(),
+            concat!(
+                "Subject: =?utf-8?q?Caf=C3=A9?= au lait = =?utf-8?q?tr=C3=A8s?= bon,\r\n",
+                " =?utf-8?q?tr=C3=A8s_tr=C3=A8s?= bon, =?utf-8?q?tr=C3=A8s_tr=C3=A8s_tr?=\r\n",
+                " =?utf-8?q?=C3=A8s?= bon, =?utf-8?q?tr=C3=A8s_tr=C3=A8s_tr=C3=A8s_tr?=\r\n",
+                " =?utf-8?q?=C3=A8s?= bon\r\n"
+            )
+        );
+    }
+
+    #[test]
+    fn format_encode_all() {
+        let mut headers = Headers::new();
+        headers.insert_raw(HeaderValue::new_with_encoding(
+            HeaderName::new_from_ascii_str("Subject"),
+            "Hello, world".to_owned(),
+            HeaderEncoding::new()
+                .word_encoding(WordEncoding::QuotedPrintable)
+                .encode_all(true),
+        ));
+        headers.insert_raw(HeaderValue::new_with_encoding(
+            HeaderName::new_from_ascii_str("Comments"),
+            "Hello, world".to_owned(),
+            HeaderEncoding::new().encode_all(true),
+        ));
+
+        assert_eq!(
+            headers.to_string(),
+            concat!(
+                "Subject: =?utf-8?q?Hello=2C_world?=\r\n",
+                "Comments: =?utf-8?b?SGVsbG8sIHdvcmxk?=\r\n"
+            )
+        );
+    }
+
     #[test]
     fn format_special_emoji() {
         let mut headers = Headers::new();
@@ -0,0 +1,143 @@
+use super::HeaderName;
+
+/// Preferred order of the headers of a message
+///
+/// Headers are written in the order they were set by default, which some
+/// spam filters and DKIM policies frown upon. Set with
+/// [`MessageBuilder::header_order`](crate::message::MessageBuilder::header_order),
+/// the order is applied when formatting the message:
+///
+/// * the trace headers, `Return-Path`, `Received` and the `Resent-*` blocks,
+///   and the `DKIM-Signature`s stay on top, in the order they were set
+/// * then come the listed headers, in the order of the list
+/// * then the other headers, in the order they were set
+///
+/// Several headers with the same name keep their order.
+///
+/// ```rust
+/// use lettre::message::{header::HeaderOrder, Message};
+///
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// let email = Message::builder()
+///     .subject("Happy new year")
+///     .to("Hei <hei@domain.tld>".parse()?)
+///     .from("NoBody <nobody@domain.tld>".parse()?)
+///     .header_order(HeaderOrder::standard())
+///     .body(String::from("Be happy!"))?;
+///
+/// let formatted = String::from_utf8(email.formatted())?;
+/// assert!(formatted.starts_with("Date: "));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HeaderOrder {
+    names: Vec<HeaderName>,
+}
+
+impl HeaderOrder {
+    /// Creates an order putting the `names` headers first
+    pub fn new(names: Vec<HeaderName>) -> Self {
+        Self { names }
+    }
+
+    /// The usual order, from [RFC5322](https://tools.ietf.org/html/rfc5322#section-3.6)
+    ///
+    /// The date and the originator come first, then the destination,
+    /// identification and informational headers, then the MIME headers.
+    pub fn standard() -> Self {
+        Self::new(vec![
+            HeaderName::DATE,
+            HeaderName::FROM,
+            HeaderName::SENDER,
+            HeaderName::REPLY_TO,
+            HeaderName::TO,
+            HeaderName::CC,
+            HeaderName::BCC,
+            HeaderName::MESSAGE_ID,
+            HeaderName::IN_REPLY_TO,
+            HeaderName::REFERENCES,
+            HeaderName::SUBJECT,
+            HeaderName::COMMENTS,
+            HeaderName::KEYWORDS,
+            HeaderName::MIME_VERSION,
+            HeaderName::CONTENT_TYPE,
+            HeaderName::CONTENT_TRANSFER_ENCODING,
+        ])
+    }
+
+    /// Position of the header `name` in the order, the smallest first
+    pub(super) fn rank(&self, name: &HeaderName) -> usize {
+        let is_trace = *name == HeaderName::RETURN_PATH
+            || *name == HeaderName::RECEIVED
+            || *name == HeaderName::DKIM_SIGNATURE
+            || name
+                .get(..7)
+                .is_some_and(|prefix| prefix.eq_ignore_ascii_case("Resent-"));
+        if is_trace {
+            return 0;
+        }
+
+        match self.names.iter().position(|listed| listed == name) {
+            Some(i) => i + 1,
+            None => usize::MAX,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::{Duration, SystemTime};
+
+    use pretty_assertions::assert_eq;
+
+    use super::HeaderOrder;
+    use crate::message::{
+        header::{HeaderName, Received},
+        Message,
+    };
+
+    #[test]
+    fn standard_order() {
+        let date = SystemTime::UNIX_EPOCH + Duration::from_secs(784887151);
+        let mut email = Message::builder()
+            .user_agent("lettre".to_owned())
+            .subject("Happy new year")
+            .to("hei@domain.tld".parse().unwrap())
+            .from("nobody@domain.tld".parse().unwrap())
+            .date(date)
+            .message_id(Some("<id@domain.tld>".to_owned()))
+            .header_order(HeaderOrder::standard())
+            .body(String::from("Be happy!"))
+            .unwrap();
+        email.prepend_received(Received::new("relay.domain.tld".to_owned()).date(date.into()));
+
+        assert_eq!(
+            String::from_utf8(email.formatted()).unwrap(),
+            concat!(
+                "Received: by relay.domain.tld; Tue, 15 Nov 1994 08:12:31 +0000\r\n",
+                "Date: Tue, 15 Nov 1994 08:12:31 +0000\r\n",
+                "From: nobody@domain.tld\r\n",
+                "To: hei@domain.tld\r\n",
+                "Message-ID: <id@domain.tld>\r\n",
+                "Subject: Happy new year\r\n",
+                "Content-Transfer-Encoding: 7bit\r\n",
+                "User-Agent: lettre\r\n",
+                "\r\n",
+                "Be happy!"
+            )
+        );
+    }
+
+    #[test]
+    fn custom_order() {
+        let order = HeaderOrder::new(vec![HeaderName::SUBJECT, HeaderName::FROM]);
+
+        assert_eq!(order.rank(&HeaderName::new_from_ascii_str("subject")), 1);
+        assert_eq!(order.rank(&HeaderName::FROM), 2);
+        assert_eq!(order.rank(&HeaderName::TO), usize::MAX);
+        assert_eq!(order.rank(&HeaderName::RESENT_FROM), 0);
+    }
+}
@@ -0,0 +1,213 @@
+use std::fmt::Write;
+
+use super::{Date, Header, HeaderName, HeaderValue};
+use crate::BoxError;
+
+/// `Received` trace header, stamped by each relay of a message
+///
+/// Made of the clauses describing how the message was received, and the
+/// date it was received at, as defined in
+/// [RFC5321](https://tools.ietf.org/html/rfc5321#section-4.4).
+/// A message has one of them for each relay, the newest first, so use
+/// [`Message::prepend_received`](crate::Message::prepend_received) to add it
+/// to a message instead of setting it.
+///
+/// ```rust
+/// use lettre::message::header::Received;
+///
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// let received = Received::new("gateway.example.com".to_owned())
+///     .from(
+///         "client.example.org".to_owned(),
+///         Some("[192.0.2.1]".to_owned()),
+///     )
+///     .with("ESMTPS".to_owned())
+///     .id("4f3a9c".to_owned())
+///     .for_recipient("hei@domain.tld".parse()?);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Received {
+    from: Option<String>,
+    from_info: Option<String>,
+    by: String,
+    via: Option<String>,
+    with: Option<String>,
+    id: Option<String>,
+    for_recipient: Option<String>,
+    date: Date,
+}
+
+impl Received {
+    /// Creates a header stamped now by the host named `by`
+    pub fn new(by: String) -> Self {
+        Self {
+            from: None,
+            from_info: None,
+            by,
+            via: None,
+            with: None,
+            id: None,
+            for_recipient: None,
+            date: Date::now(),
+        }
+    }
+
+    /// Set the name the previous host greeted with, and optionally its
+    /// address, like `[192.0.2.1]` or `client.example.org [192.0.2.1]`
+    pub fn from(mut self, helo: String, info: Option<String>) -> Self {
+        self.from = Some(helo);
+        self.from_info = info;
+        self
+    }
+
+    /// Set the link the message was received over
+    pub fn via(mut self, link: String) -> Self {
+        self.via = Some(link);
+        self
+    }
+
+    /// Set the protocol the message was received with, like `ESMTP` or `ESMTPS`
+    pub fn with(mut self, protocol: String) -> Self {
+        self.with = Some(protocol);
+        self
+    }
+
+    /// Set the identifier the message got on this host, like a queue id
+    pub fn id(mut self, id: String) -> Self {
+        self.id = Some(id);
+        self
+    }
+
+    /// Set the recipient the message was received for
+    pub fn for_recipient(mut self, recipient: crate::Address) -> Self {
+        self.for_recipient = Some(format!("<{recipient}>"));
+        self
+    }
+
+    /// Set the date the message was received at, now by default
+    pub fn date(mut self, date: Date) -> Self {
+        self.date = date;
+        self
+    }
+}
+
+impl Header for Received {
+    fn name() -> HeaderName {
+        HeaderName::RECEIVED
+    }
+
+    fn parse(s: &str) -> Result<Self, BoxError> {
+        let (clauses, date) = s.rsplit_once(';').ok_or("Received header without date")?;
+        let mut received = Self::new(String::new()).date(Date::parse(date.trim())?);
+
+        let mut words = clauses.split_whitespace().peekable();
+        while let Some(keyword) = words.next() {
+            let value = words
+                .next()
+                .ok_or("Received clause without value")?
+                .to_owned();
+            match keyword.to_ascii_lowercase().as_str() {
+                "from" => {
+                    received.from = Some(value);
+                    if words.peek().is_some_and(|word| word.starts_with('(')) {
+                        let mut info = String::new();
+                        for word in words.by_ref() {
+                            info.push_str(word);
+                            if word.ends_with(')') {
+                                break;
+                            }
+                            info.push(' ');
+                        }
+                        let info = info.strip_prefix('(').and_then(|i| i.strip_suffix(')'));
+                        received.from_info =
+                            Some(info.ok_or("unclosed comment in Received")?.to_owned());
+                    }
+                }
+                "by" => received.by = value,
+                "via" => received.via = Some(value),
+                "with" => received.with = Some(value),
+                "id" => received.id = Some(value),
+                "for" => received.for_recipient = Some(value),
+                _ => return Err(format!("unknown Received clause {keyword:?}").into()),
+            }
+        }
+        if received.by.is_empty() {
+            return Err("Received header without by clause".into());
+        }
+
+        Ok(received)
+    }
+
+    fn display(&self) -> HeaderValue {
+        let mut val = String::new();
+        if let Some(from) = &self.from {
+            write!(val, "from {from} ").expect("writing to a String failed");
+            if let Some(info) = &self.from_info {
+                write!(val, "({info}) ").expect("writing to a String failed");
+            }
+        }
+        write!(val, "by {}", self.by).expect("writing to a String failed");
+        for (keyword, value) in [
+            ("via", &self.via),
+            ("with", &self.with),
+            ("id", &self.id),
+            ("for", &self.for_recipient),
+        ] {
+            if let Some(value) = value {
+                write!(val, " {keyword} {value}").expect("writing to a String failed");
+            }
+        }
+        write!(val, "; {}", self.date.display().get_raw()).expect("writing to a String failed");
+
+        HeaderValue::new(Self::name(), val)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::{Duration, SystemTime};
+
+    use pretty_assertions::assert_eq;
+
+    use super::Received;
+    use crate::message::header::{Date, Header, Headers};
+
+    #[test]
+    fn format_parse_received() {
+        let date = Date::new(SystemTime::UNIX_EPOCH + Duration::from_secs(784887151));
+        let received = Received::new("gateway.example.com".to_owned())
+            .from(
+                "client.example.org".to_owned(),
+                Some("client.example.org [192.0.2.1]".to_owned()),
+            )
+            .with("ESMTPS".to_owned())
+            .id("4f3a9c".to_owned())
+            .for_recipient("hei@domain.tld".parse().unwrap())
+            .date(date);
+
+        let mut headers = Headers::new();
+        headers.set(received.clone());
+        assert_eq!(
+            headers.to_string(),
+            concat!(
+                "Received: from client.example.org (client.example.org [192.0.2.1]) by\r\n",
+                " gateway.example.com with ESMTPS id 4f3a9c for <hei@domain.tld>; Tue, 15 Nov\r\n",
+                " 1994 08:12:31 +0000\r\n"
+            )
+        );
+        assert_eq!(headers.get::<Received>(), Some(received));
+
+        let minimal = Received::parse("by relay.example.com; Tue, 15 Nov 1994 08:12:31 +0000");
+        assert_eq!(
+            minimal.unwrap(),
+            Received::new("relay.example.com".to_owned()).date(date)
+        );
+        assert!(
+            Received::parse("from client.example.org; Tue, 15 Nov 1994 08:12:31 +0000").is_err()
+        );
+        assert!(Received::parse("by relay.example.com").is_err());
+    }
+}
@@ -0,0 +1,144 @@
+use serde::{
+    de::{Deserializer, Error as DeError},
+    ser::Serializer,
+    Deserialize, Serialize,
+};
+
+use super::{HeaderName, HeaderValue, Headers};
+
+impl Serialize for HeaderName {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for HeaderName {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let name = String::deserialize(deserializer)?;
+        HeaderName::new_from_ascii(name).map_err(DeError::custom)
+    }
+}
+
+/// A header as stored, keeping its encoded value so that
+/// the message is formatted the same way
+#[derive(Serialize, Deserialize)]
+struct StoredHeader {
+    name: HeaderName,
+    value: String,
+    encoded: String,
+    #[serde(default)]
+    unstructured: bool,
+}
+
+impl Serialize for HeaderValue {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        StoredHeader {
+            name: self.name.clone(),
+            value: self.raw_value.clone(),
+            encoded: self.encoded_value.clone(),
+            unstructured: self.unstructured,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for HeaderValue {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let header = StoredHeader::deserialize(deserializer)?;
+        if !is_folded_line(&header.encoded) {
+            return Err(DeError::custom(format!(
+                "the encoded value of the {} header contains a line break",
+                header.name
+            )));
+        }
+
+        Ok(HeaderValue {
+            name: header.name,
+            raw_value: header.value,
+            encoded_value: header.encoded,
+            unstructured: header.unstructured,
+        })
+    }
+}
+
+/// Whether the line breaks of `encoded` are all followed by whitespace,
+/// so that it can't start another header
+fn is_folded_line(encoded: &str) -> bool {
+    let bytes = encoded.as_bytes();
+    bytes.iter().enumerate().all(|(i, &b)| match b {
+        b'\r' => bytes.get(i + 1) == Some(&b'\n'),
+        b'\n' => i > 0 && bytes[i - 1] == b'\r' && matches!(bytes.get(i + 1), Some(b' ' | b'\t')),
+        _ => true,
+    })
+}
+
+impl Serialize for Headers {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_seq(&self.headers)
+    }
+}
+
+impl<'de> Deserialize<'de> for Headers {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let headers = Vec::<HeaderValue>::deserialize(deserializer)?;
+        Ok(Headers { headers })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use super::super::{HeaderName, HeaderValue, Headers, Subject};
+
+    #[test]
+    fn round_trip() {
+        let mut headers = Headers::new();
+        headers.set(Subject::from("Тема сообщения".to_owned()));
+        headers.insert_raw(HeaderValue::new(
+            HeaderName::new_from_ascii_str("X-Custom"),
+            "value".to_owned(),
+        ));
+
+        let json = serde_json::to_string(&headers).unwrap();
+        let decoded: Headers = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded.to_string(), headers.to_string());
+        assert_eq!(
+            decoded.get::<Subject>(),
+            Some(Subject::from("Тема сообщения".to_owned()))
+        );
+    }
+
+    #[test]
+    fn rejects_injected_headers() {
+        let json = r#"[{"name":"Subject","value":"hi","encoded":"hi\r\nBcc: evil@domain.tld"}]"#;
+        assert!(serde_json::from_str::<Headers>(json).is_err());
+
+        let json = r#"[{"name":"Subject","value":"hi","encoded":"hi\nthere"}]"#;
+        assert!(serde_json::from_str::<Headers>(json).is_err());
+
+        let json = r#"[{"name":"Bad Name","value":"hi","encoded":"hi"}]"#;
+        assert!(serde_json::from_str::<Headers>(json).is_err());
+
+        let json = r#"[{"name":"Subject","value":"hi","encoded":"hi\r\n there"}]"#;
+        assert!(serde_json::from_str::<Headers>(json).is_ok());
+    }
+}
@@ -36,7 +36,7 @@ impl MimeVersion {
 
 impl Header for MimeVersion {
     fn name() -> HeaderName {
-        HeaderName::new_from_ascii_str("MIME-Version")
+        HeaderName::MIME_VERSION
     }
 
     fn parse(s: &str) -> Result<Self, BoxError> {
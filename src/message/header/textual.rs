@@ -1,15 +1,21 @@
-use super::{Header, HeaderName, HeaderValue};
-use crate::BoxError;
+use super::{Header, HeaderName, HeaderValue, Headers};
+use crate::{message::make_content_id, BoxError};
 
 macro_rules! text_header {
     ($(#[$attr:meta])* Header($type_name: ident, $header_name: expr )) => {
+        text_header!($(#[$attr])* Header($type_name, $header_name), new);
+    };
+    ($(#[$attr:meta])* Unstructured($type_name: ident, $header_name: expr )) => {
+        text_header!($(#[$attr])* Header($type_name, $header_name), new_unstructured);
+    };
+    ($(#[$attr:meta])* Header($type_name: ident, $header_name: expr ), $new: ident) => {
         $(#[$attr])*
         #[derive(Debug, Clone, PartialEq, Eq)]
         pub struct $type_name(String);
 
         impl Header for $type_name {
             fn name() -> HeaderName {
-                HeaderName::new_from_ascii_str($header_name)
+                $header_name
             }
 
             fn parse(s: &str) -> Result<Self, BoxError> {
@@ -17,7 +23,7 @@ macro_rules! text_header {
             }
 
             fn display(&self) -> HeaderValue {
-             HeaderValue::new(Self::name(),   self.0.clone())
+             HeaderValue::$new(Self::name(),   self.0.clone())
             }
         }
 
@@ -39,55 +45,171 @@ macro_rules! text_header {
 
 text_header!(
     /// `Subject` of the message, defined in [RFC5322](https://tools.ietf.org/html/rfc5322#section-3.6.5)
-    Header(Subject, "Subject")
+    Unstructured(Subject, HeaderName::SUBJECT)
 );
 text_header!(
     /// `Comments` of the message, defined in [RFC5322](https://tools.ietf.org/html/rfc5322#section-3.6.5)
-    Header(Comments, "Comments")
+    Unstructured(Comments, HeaderName::COMMENTS)
 );
 text_header!(
     /// `Keywords` header. Should contain a comma-separated list of one or more
     /// words or quoted-strings, defined in [RFC5322](https://tools.ietf.org/html/rfc5322#section-3.6.5)
-    Header(Keywords, "Keywords")
+    Header(Keywords, HeaderName::KEYWORDS)
 );
 text_header!(
     /// `In-Reply-To` header. Contains one or more
     /// unique message identifiers,
     /// defined in [RFC5322](https://tools.ietf.org/html/rfc5322#section-3.6.4)
-    Header(InReplyTo, "In-Reply-To")
+    Header(InReplyTo, HeaderName::IN_REPLY_TO)
 );
 text_header!(
     /// `References` header. Contains one or more
     /// unique message identifiers,
     /// defined in [RFC5322](https://tools.ietf.org/html/rfc5322#section-3.6.4)
-    Header(References, "References")
+    Header(References, HeaderName::REFERENCES)
 );
+
+/// Maximum length of a header line, from
+/// [RFC5322](https://tools.ietf.org/html/rfc5322#section-2.1.1)
+const MAX_LINE_LEN: usize = 998;
+
+impl References {
+    /// The `References` of a reply to the message with the `parent` headers
+    ///
+    /// Made of the `References` of the parent, or its `In-Reply-To` if it
+    /// contains a single message identifier, followed by its `Message-ID`, as
+    /// described by [RFC5322](https://tools.ietf.org/html/rfc5322#section-3.6.4).
+    /// The chain is [trimmed](Self::trimmed) to fit on a single line.
+    /// Returns `None` if the parent has none of these headers.
+    ///
+    /// ```rust
+    /// use lettre::message::header::{Headers, MessageId, References};
+    ///
+    /// let mut parent = Headers::new();
+    /// parent.set(MessageId::from("<2@domain.tld>".to_owned()));
+    /// parent.set(References::from("<0@domain.tld> <1@domain.tld>".to_owned()));
+    ///
+    /// let references = References::reply_to(&parent).unwrap();
+    /// assert_eq!(
+    ///     references.as_ref(),
+    ///     "<0@domain.tld> <1@domain.tld> <2@domain.tld>"
+    /// );
+    /// ```
+    pub fn reply_to(parent: &Headers) -> Option<Self> {
+        let mut ids = match parent.get_raw(&HeaderName::REFERENCES) {
+            Some(references) => message_ids(references).collect::<Vec<_>>(),
+            None => {
+                let in_reply_to = parent.get_raw(&HeaderName::IN_REPLY_TO).unwrap_or("");
+                let mut ids = message_ids(in_reply_to).collect::<Vec<_>>();
+                if ids.len() > 1 {
+                    ids.clear();
+                }
+                ids
+            }
+        };
+        if let Some(message_id) = parent.get_raw(&HeaderName::MESSAGE_ID) {
+            ids.extend(message_ids(message_id));
+        }
+
+        if ids.is_empty() {
+            return None;
+        }
+        Some(Self(ids.join(" ")).trimmed(MAX_LINE_LEN - "References: ".len()))
+    }
+
+    /// The message identifiers, with their angle brackets, oldest first
+    pub fn ids(&self) -> impl Iterator<Item = &str> {
+        message_ids(&self.0)
+    }
+
+    /// Drops the message identifiers following the first one, the oldest
+    /// first, until the header fits in `max_len` characters
+    ///
+    /// The first one, the start of the thread, and the last one,
+    /// the parent, are always kept.
+    #[must_use]
+    pub fn trimmed(self, max_len: usize) -> Self {
+        if self.0.len() <= max_len {
+            return self;
+        }
+
+        let mut ids = self.ids().collect::<Vec<_>>();
+        let mut len = ids.iter().map(|id| id.len() + 1).sum::<usize>() - 1;
+        while len > max_len && ids.len() > 2 {
+            len -= ids.remove(1).len() + 1;
+        }
+        Self(ids.join(" "))
+    }
+}
+
+/// The `<...>` message identifiers of a header value
+fn message_ids(value: &str) -> impl Iterator<Item = &str> {
+    let mut rest = value;
+    std::iter::from_fn(move || {
+        let start = rest.find('<')?;
+        let end = start + rest[start..].find('>')?;
+        let id = &rest[start..=end];
+        rest = &rest[end + 1..];
+        Some(id)
+    })
+}
+
 text_header!(
     /// `Message-Id` header. Contains a unique message identifier,
     /// defined in [RFC5322](https://tools.ietf.org/html/rfc5322#section-3.6.4)
-    Header(MessageId, "Message-ID")
+    Header(MessageId, HeaderName::MESSAGE_ID)
+);
+text_header!(
+    /// `Resent-Message-ID` header. Contains a unique identifier of the resent message,
+    /// defined in [RFC5322](https://tools.ietf.org/html/rfc5322#section-3.6.6)
+    Header(ResentMessageId, HeaderName::RESENT_MESSAGE_ID)
 );
 text_header!(
     /// `User-Agent` header. Contains information about the client,
     /// defined in [draft-melnikov-email-user-agent-00](https://tools.ietf.org/html/draft-melnikov-email-user-agent-00#section-3)
-    Header(UserAgent, "User-Agent")
+    Header(UserAgent, HeaderName::USER_AGENT)
 );
 text_header! {
     /// `Content-Id` header,
     /// defined in [RFC2045](https://tools.ietf.org/html/rfc2045#section-7)
-    Header(ContentId, "Content-ID")
+    Header(ContentId, HeaderName::CONTENT_ID)
 }
+
+impl ContentId {
+    /// Generate a random `Content-ID`, like `<RANDOM@HOSTNAME>`
+    ///
+    /// Uses the same domain as the generated `Message-ID`s, see
+    /// [`MessageBuilder::message_id`](crate::message::MessageBuilder::message_id).
+    pub fn random() -> Self {
+        Self(format!("<{}>", make_content_id()))
+    }
+
+    /// The content id without the angle brackets, as referenced by `cid:` URLs
+    pub fn id(&self) -> &str {
+        self.0
+            .trim()
+            .strip_prefix('<')
+            .and_then(|id| id.strip_suffix('>'))
+            .unwrap_or(&self.0)
+    }
+}
+
 text_header! {
     /// `Content-Location` header,
     /// defined in [RFC2110](https://tools.ietf.org/html/rfc2110#section-4.3)
-    Header(ContentLocation, "Content-Location")
+    Header(ContentLocation, HeaderName::CONTENT_LOCATION)
+}
+text_header! {
+    /// `List-Unsubscribe` header. Contains one or more URLs between angle brackets,
+    /// defined in [RFC2369](https://tools.ietf.org/html/rfc2369#section-3.2)
+    Header(ListUnsubscribe, HeaderName::LIST_UNSUBSCRIBE)
 }
 
 #[cfg(test)]
 mod test {
     use pretty_assertions::assert_eq;
 
-    use super::Subject;
+    use super::{ContentId, InReplyTo, MessageId, References, Subject};
     use crate::message::header::{HeaderName, HeaderValue, Headers};
 
     #[test]
@@ -133,4 +255,71 @@ mod test {
             Some(Subject("Sample subject".into()))
         );
     }
+
+    #[test]
+    fn content_id_random() {
+        let content_id = ContentId::random();
+        let id = content_id.id();
+
+        assert_eq!(content_id.as_ref(), format!("<{id}>"));
+        assert_eq!(id.split('@').next().unwrap().len(), 36);
+        assert_ne!(ContentId::random(), content_id);
+    }
+
+    #[test]
+    fn references_reply_to() {
+        let mut parent = Headers::new();
+        assert_eq!(References::reply_to(&parent), None);
+
+        parent.set(InReplyTo::from("<0@domain.tld>".to_owned()));
+        parent.set(MessageId::from("<1@domain.tld>".to_owned()));
+        assert_eq!(
+            References::reply_to(&parent),
+            Some(References::from("<0@domain.tld> <1@domain.tld>".to_owned()))
+        );
+
+        // Several parents, the thread can't be known
+        parent.set(InReplyTo::from("<0@domain.tld> <a@domain.tld>".to_owned()));
+        assert_eq!(
+            References::reply_to(&parent),
+            Some(References::from("<1@domain.tld>".to_owned()))
+        );
+
+        parent.set(References::from(
+            "<0@domain.tld>\r\n (comment) <a@domain.tld>".to_owned(),
+        ));
+        let references = References::reply_to(&parent).unwrap();
+        assert_eq!(
+            references.ids().collect::<Vec<_>>(),
+            ["<0@domain.tld>", "<a@domain.tld>", "<1@domain.tld>"]
+        );
+    }
+
+    #[test]
+    fn references_trimmed() {
+        let references = References::from("<0@a> <1@a> <2@a> <3@a>".to_owned());
+        assert_eq!(references.clone().trimmed(100), references);
+        assert_eq!(
+            references.clone().trimmed(17),
+            References::from("<0@a> <2@a> <3@a>".to_owned())
+        );
+        assert_eq!(
+            references.trimmed(0),
+            References::from("<0@a> <3@a>".to_owned())
+        );
+
+        let mut parent = Headers::new();
+        let long = (0..200)
+            .map(|i| format!("<{i}@domain.tld>"))
+            .collect::<Vec<_>>();
+        parent.set(References::from(long.join(" ")));
+        parent.set(MessageId::from("<parent@domain.tld>".to_owned()));
+
+        let references = References::reply_to(&parent).unwrap();
+        assert!(references.as_ref().len() + "References: ".len() <= 998);
+        let ids = references.ids().collect::<Vec<_>>();
+        assert_eq!(ids[0], "<0@domain.tld>");
+        assert_eq!(ids[1..ids.len() - 1], long[long.len() - ids.len() + 2..]);
+        assert_eq!(ids[ids.len() - 1], "<parent@domain.tld>");
+    }
 }
@@ -1,6 +1,8 @@
 use std::{
+    error::Error,
     fmt::{Display, Formatter, Result as FmtResult, Write},
     mem,
+    ops::Range,
     slice::Iter,
     str::FromStr,
 };
@@ -256,6 +258,46 @@ impl Mailboxes {
         self.0.iter()
     }
 
+    /// Parses a comma-separated list of mailboxes, keeping the valid ones
+    ///
+    /// Unlike parsing with [`FromStr`], an invalid mailbox doesn't make the
+    /// whole list fail, it's reported with its position in `src`, for
+    /// example to highlight it in a form accepting a pasted recipient list.
+    /// Empty entries are ignored.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lettre::message::Mailboxes;
+    ///
+    /// let src = "Hei <hei@domain.tld>, invalid, \"Doe, John\" <john@domain.tld>";
+    /// let (mailboxes, errors) = Mailboxes::parse_list(src);
+    ///
+    /// assert_eq!(mailboxes.iter().count(), 2);
+    /// assert_eq!(errors.len(), 1);
+    /// assert_eq!(&src[errors[0].range()], "invalid");
+    /// ```
+    pub fn parse_list(src: &str) -> (Self, Vec<InvalidMailbox>) {
+        let mut mailboxes = Mailboxes::new();
+        let mut errors = Vec::new();
+
+        for range in split_list(src) {
+            let entry = &src[range.clone()];
+            let start = range.start + (entry.len() - entry.trim_start().len());
+            let range = start..start + entry.trim().len();
+            if range.is_empty() {
+                continue;
+            }
+
+            match src[range.clone()].parse() {
+                Ok(mailbox) => mailboxes.push(mailbox),
+                Err(error) => errors.push(InvalidMailbox { range, error }),
+            }
+        }
+
+        (mailboxes, errors)
+    }
+
     pub(crate) fn encode(&self, w: &mut EmailWriter<'_>) -> FmtResult {
         let mut first = true;
         for mailbox in self.iter() {
@@ -358,6 +400,74 @@ impl FromStr for Mailboxes {
     }
 }
 
+/// An invalid entry of a mailbox list parsed with [`Mailboxes::parse_list`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidMailbox {
+    range: Range<usize>,
+    error: AddressError,
+}
+
+impl InvalidMailbox {
+    /// Byte range of the entry in the parsed list, without the surrounding whitespace
+    pub fn range(&self) -> Range<usize> {
+        self.range.clone()
+    }
+
+    /// Why the entry is invalid
+    pub fn error(&self) -> AddressError {
+        self.error
+    }
+}
+
+impl Display for InvalidMailbox {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(
+            f,
+            "{} at bytes {}..{}",
+            self.error, self.range.start, self.range.end
+        )
+    }
+}
+
+impl Error for InvalidMailbox {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+/// Splits a mailbox list on the commas which aren't quoted,
+/// in an address or in a comment
+fn split_list(src: &str) -> Vec<Range<usize>> {
+    let mut ranges = Vec::new();
+    let mut start = 0;
+    let (mut quoted, mut escaped) = (false, false);
+    let (mut angle_depth, mut comment_depth) = (0_usize, 0_usize);
+
+    for (i, c) in src.char_indices() {
+        if escaped {
+            escaped = false;
+            continue;
+        }
+        match c {
+            '\\' if quoted || comment_depth > 0 => escaped = true,
+            '"' if comment_depth == 0 => quoted = !quoted,
+            _ if quoted => {}
+            '(' => comment_depth += 1,
+            ')' => comment_depth = comment_depth.saturating_sub(1),
+            _ if comment_depth > 0 => {}
+            '<' => angle_depth += 1,
+            '>' => angle_depth = angle_depth.saturating_sub(1),
+            ',' if angle_depth == 0 => {
+                ranges.push(start..i);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    ranges.push(start..src.len());
+    ranges
+}
+
 // https://datatracker.ietf.org/doc/html/rfc2822#section-3.2.6
 fn write_word(f: &mut Formatter<'_>, s: &str) -> FmtResult {
     if s.as_bytes().iter().copied().all(is_valid_atom_char) {
@@ -446,7 +556,24 @@ fn write_quoted_string_char(f: &mut Formatter<'_>, c: char) -> FmtResult {
 mod test {
     use pretty_assertions::assert_eq;
 
-    use super::Mailbox;
+    use super::{Mailbox, Mailboxes};
+    use crate::address::AddressError;
+
+    #[test]
+    fn parse_list() {
+        let src = "  Hei <hei@domain.tld>,,bad@,\"Doe, John\" <john@domain.tld> , bad (work, main), <x@y.z, \n";
+        let (mailboxes, errors) = Mailboxes::parse_list(src);
+
+        assert_eq!(
+            mailboxes.to_string(),
+            "Hei <hei@domain.tld>, \"Doe, John\" <john@domain.tld>"
+        );
+        assert_eq!(errors.len(), 3);
+        assert_eq!(&src[errors[0].range()], "bad@");
+        assert_eq!(errors[0].error(), AddressError::InvalidInput);
+        assert_eq!(&src[errors[1].range()], "bad (work, main)");
+        assert_eq!(&src[errors[2].range()], "<x@y.z,");
+    }
 
     #[test]
     fn mailbox_format_address_only() {
@@ -0,0 +1,112 @@
+//! Local copies of messages, as `.eml` files and in mailboxes
+
+use std::{
+    fs::OpenOptions,
+    io::{self, Write},
+    path::Path,
+    time::SystemTime,
+};
+
+use httpdate::HttpDate;
+
+use super::{header, Message};
+
+/// Appends `entry` to the mailbox at `path`, creating it if needed
+pub(super) fn append(path: &Path, entry: &[u8]) -> io::Result<()> {
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?
+        .write_all(entry)
+}
+
+/// Builds the mailbox entry of `message`, in the `mboxrd` format
+///
+/// The entry starts with a `From ` line with the envelope sender and the
+/// date of the message, and ends with a blank line. Lines of the message
+/// end with LF, and the ones starting with `From `, after any number of
+/// `>`, are quoted with another `>`.
+pub(super) fn entry(message: &Message) -> Vec<u8> {
+    let sender = message
+        .envelope()
+        .from()
+        .map_or("MAILER-DAEMON", |from| from.as_ref());
+    let date = message
+        .headers()
+        .get::<header::Date>()
+        .map_or_else(SystemTime::now, SystemTime::from);
+
+    let formatted = message.formatted();
+    let mut out = Vec::with_capacity(formatted.len() + 128);
+    writeln!(out, "From {sender} {}", asctime(date)).expect("writing to a Vec never fails");
+    for line in formatted.split_inclusive(|&b| b == b'\n') {
+        let line = line.strip_suffix(b"\n").unwrap_or(line);
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+        let quotes = line.iter().take_while(|&&b| b == b'>').count();
+        if line[quotes..].starts_with(b"From ") {
+            out.push(b'>');
+        }
+        out.extend_from_slice(line);
+        out.push(b'\n');
+    }
+    out.push(b'\n');
+    out
+}
+
+/// Formats `time` like the C `asctime` function, in UTC
+fn asctime(time: SystemTime) -> String {
+    // Tue, 15 Nov 1994 08:12:31 GMT
+    let date = HttpDate::from(time).to_string();
+    let fields = date.split(' ').collect::<Vec<_>>();
+    let [weekday, day, month, year, time, _] = fields[..] else {
+        unreachable!("HTTP dates have six fields");
+    };
+    format!(
+        "{} {month} {:>2} {time} {year}",
+        weekday.trim_end_matches(','),
+        day.trim_start_matches('0')
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::{Duration, SystemTime};
+
+    use pretty_assertions::assert_eq;
+
+    use super::{asctime, entry};
+    use crate::message::Message;
+
+    #[test]
+    fn asctime_dates() {
+        let date = SystemTime::UNIX_EPOCH + Duration::from_secs(784887151);
+        assert_eq!(asctime(date), "Tue Nov 15 08:12:31 1994");
+        assert_eq!(asctime(SystemTime::UNIX_EPOCH), "Thu Jan  1 00:00:00 1970");
+    }
+
+    #[test]
+    fn mbox_entry() {
+        let email = Message::builder()
+            .date(SystemTime::UNIX_EPOCH + Duration::from_secs(784887151))
+            .from("NoBody <nobody@domain.tld>".parse().unwrap())
+            .to("Hei <hei@domain.tld>".parse().unwrap())
+            .body(String::from("Be happy!\nFrom now on\n>From the start"))
+            .unwrap();
+
+        assert_eq!(
+            String::from_utf8(entry(&email)).unwrap(),
+            concat!(
+                "From nobody@domain.tld Tue Nov 15 08:12:31 1994\n",
+                "Date: Tue, 15 Nov 1994 08:12:31 +0000\n",
+                "From: NoBody <nobody@domain.tld>\n",
+                "To: Hei <hei@domain.tld>\n",
+                "Content-Transfer-Encoding: 7bit\n",
+                "\n",
+                "Be happy!\n",
+                ">From now on\n",
+                ">>From the start\n",
+                "\n",
+            )
+        );
+    }
+}
@@ -4,12 +4,13 @@ use mime::Mime;
 
 use crate::message::{
     header::{self, ContentTransferEncoding, ContentType, Header, Headers},
-    EmailFormat, IntoBody,
+    Attachment, Body, EmailFormat, IntoBody, Message,
 };
 
 /// MIME part variants
 #[derive(Debug, Clone)]
-pub(super) enum Part {
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Part {
     /// Single part with content
     Single(SinglePart),
 
@@ -18,6 +19,67 @@ pub(super) enum Part {
 }
 
 impl Part {
+    /// Get the headers of the part
+    pub fn headers(&self) -> &Headers {
+        match self {
+            Part::Single(part) => part.headers(),
+            Part::Multi(part) => part.headers(),
+        }
+    }
+
+    /// Get the `Content-Type` of the part
+    pub fn content_type(&self) -> Option<ContentType> {
+        self.headers().get::<ContentType>()
+    }
+
+    /// Get the `Content-ID` of the part, without the angle brackets
+    pub fn content_id(&self) -> Option<&str> {
+        self.headers().get_raw("Content-ID").map(|id| {
+            id.trim()
+                .strip_prefix('<')
+                .and_then(|id| id.strip_suffix('>'))
+                .unwrap_or(id)
+        })
+    }
+
+    /// Iterate over this part and the parts nested in it, depth-first
+    pub fn iter(&self) -> Parts<'_> {
+        Parts { stack: vec![self] }
+    }
+
+    /// Call `f` on this part and the parts nested in it, depth-first
+    ///
+    /// `f` is called on a part before its nested parts, so it may replace
+    /// the part entirely.
+    pub fn for_each_mut<F: FnMut(&mut Part)>(&mut self, mut f: F) {
+        self.for_each_mut_impl(&mut f);
+    }
+
+    fn for_each_mut_impl<F: FnMut(&mut Part)>(&mut self, f: &mut F) {
+        f(self);
+        if let Part::Multi(multi) = self {
+            for part in &mut multi.parts {
+                part.for_each_mut_impl(f);
+            }
+        }
+    }
+
+    /// Remove the parts nested in this part for which `f` returns `false`
+    ///
+    /// The nested parts of the removed parts aren't visited.
+    pub fn retain<F: FnMut(&Part) -> bool>(&mut self, mut f: F) {
+        self.retain_impl(&mut f);
+    }
+
+    fn retain_impl<F: FnMut(&Part) -> bool>(&mut self, f: &mut F) {
+        if let Part::Multi(multi) = self {
+            multi.parts.retain(|part| f(part));
+            for part in &mut multi.parts {
+                part.retain_impl(f);
+            }
+        }
+    }
+
     #[cfg(feature = "dkim")]
     pub(super) fn format_body(&self, out: &mut Vec<u8>) {
         match self {
@@ -36,6 +98,32 @@ impl EmailFormat for Part {
     }
 }
 
+/// Iterator over a MIME tree, returned by [`Part::iter`] and [`Message::parts`]
+#[derive(Debug, Clone)]
+pub struct Parts<'a> {
+    stack: Vec<&'a Part>,
+}
+
+impl<'a> Parts<'a> {
+    pub(super) fn new(part: Option<&'a Part>) -> Self {
+        Self {
+            stack: part.into_iter().collect(),
+        }
+    }
+}
+
+impl<'a> Iterator for Parts<'a> {
+    type Item = &'a Part;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let part = self.stack.pop()?;
+        if let Part::Multi(multi) = part {
+            self.stack.extend(multi.parts.iter().rev());
+        }
+        Some(part)
+    }
+}
+
 /// Creates builder for single part
 #[derive(Debug, Clone)]
 pub struct SinglePartBuilder {
@@ -82,6 +170,32 @@ impl Default for SinglePartBuilder {
     }
 }
 
+/// `Content-Type` of an embedded message
+pub(super) fn rfc822_content_type() -> ContentType {
+    ContentType::parse("message/rfc822").expect("message/rfc822 is a valid content type")
+}
+
+/// Formatted `message`, to be embedded in a `message/rfc822` part
+///
+/// RFC2046 forbids encoding `message/rfc822` parts using `quoted-printable`
+/// or `base64`, so the weakest encoding the message content allows is used.
+pub(super) fn rfc822_body(message: &Message) -> Body {
+    let formatted = message.formatted();
+    let encoding = if formatted.contains(&b'\0')
+        || formatted
+            .split(|&b| b == b'\n')
+            .any(|line| line.len() > 1000)
+    {
+        ContentTransferEncoding::Binary
+    } else if formatted.is_ascii() {
+        ContentTransferEncoding::SevenBit
+    } else {
+        ContentTransferEncoding::EightBit
+    };
+
+    Body::dangerous_pre_encoded(formatted, encoding)
+}
+
 /// Single part
 ///
 /// # Example
@@ -98,8 +212,10 @@ impl Default for SinglePartBuilder {
 /// # }
 /// ```
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SinglePart {
     headers: Headers,
+    #[cfg_attr(feature = "serde", serde(with = "super::body::serde_bytes"))]
     body: Vec<u8>,
 }
 
@@ -124,6 +240,17 @@ impl SinglePart {
             .body(body)
     }
 
+    /// Directly create a `message/rfc822` `SinglePart` embedding `message`
+    ///
+    /// The message is included as formatted, without being re-encoded, as
+    /// needed to forward a message as an attachment or to build abuse reports.
+    /// Use [`Attachment::rfc822`] to have it show up as a normal attachment.
+    pub fn rfc822(message: &Message) -> Self {
+        Self::builder()
+            .header(rfc822_content_type())
+            .body(rfc822_body(message))
+    }
+
     /// Get the headers from singlepart
     #[inline]
     pub fn headers(&self) -> &Headers {
@@ -136,6 +263,17 @@ impl SinglePart {
         &self.body
     }
 
+    /// Get the body, decoded according to its `Content-Transfer-Encoding`
+    ///
+    /// Returns `None` if the body isn't valid for its encoding.
+    pub fn decoded_bytes(&self) -> Option<Vec<u8>> {
+        let encoding = self
+            .headers
+            .get::<ContentTransferEncoding>()
+            .unwrap_or(ContentTransferEncoding::SevenBit);
+        crate::message::body::decode(&self.body, encoding)
+    }
+
     /// Get message content formatted for sending
     pub fn formatted(&self) -> Vec<u8> {
         let mut out = Vec::new();
@@ -143,6 +281,12 @@ impl SinglePart {
         out
     }
 
+    /// Replace the body, updating the `Content-Transfer-Encoding` header
+    pub(super) fn set_body(&mut self, body: Body) {
+        self.headers.set(body.encoding());
+        self.body = body.into_vec();
+    }
+
     /// Format only the signlepart body
     fn format_body(&self, out: &mut Vec<u8>) {
         out.extend_from_slice(&self.body);
@@ -182,6 +326,11 @@ pub enum MultiPartKind {
 
     /// Signed kind for signed messages
     Signed { protocol: String, micalg: String },
+
+    /// Report kind for machine readable reports, like delivery or abuse reports
+    ///
+    /// Defined in [RFC6522](https://tools.ietf.org/html/rfc6522)
+    Report { report_type: String },
 }
 
 /// Create a random MIME boundary.
@@ -202,12 +351,14 @@ impl MultiPartKind {
                 Self::Related => "related",
                 Self::Encrypted { .. } => "encrypted",
                 Self::Signed { .. } => "signed",
+                Self::Report { .. } => "report",
             },
             boundary,
             match self {
                 Self::Encrypted { protocol } => format!("; protocol=\"{protocol}\""),
                 Self::Signed { protocol, micalg } =>
                     format!("; protocol=\"{protocol}\"; micalg=\"{micalg}\""),
+                Self::Report { report_type } => format!("; report-type={report_type}"),
                 _ => String::new(),
             }
         )
@@ -229,6 +380,9 @@ impl MultiPartKind {
             "encrypted" => m.get_param("protocol").map(|p| Self::Encrypted {
                 protocol: p.as_str().to_owned(),
             }),
+            "report" => m.get_param("report-type").map(|report_type| Self::Report {
+                report_type: report_type.as_str().to_owned(),
+            }),
             _ => None,
         }
     }
@@ -296,6 +450,7 @@ impl Default for MultiPartBuilder {
 
 /// Multipart variant with parts
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct MultiPart {
     headers: Headers,
     parts: Vec<Part>,
@@ -342,6 +497,13 @@ impl MultiPart {
         MultiPart::builder().kind(MultiPartKind::Signed { protocol, micalg })
     }
 
+    /// Creates report multipart builder
+    ///
+    /// Shortcut for `MultiPart::builder().kind(MultiPartKind::Report{ report_type })`
+    pub fn report(report_type: String) -> MultiPartBuilder {
+        MultiPart::builder().kind(MultiPartKind::Report { report_type })
+    }
+
     /// Alias for HTML and plain text versions of an email
     pub fn alternative_plain_html<T: IntoBody, V: IntoBody>(plain: T, html: V) -> Self {
         Self::alternative()
@@ -349,6 +511,56 @@ impl MultiPart {
             .singlepart(SinglePart::html(html))
     }
 
+    /// Creates a related multipart with an HTML body and the resources it embeds
+    ///
+    /// `resources` maps the names referenced by `src="name"` attributes of `html`
+    /// to their content and type. Every resource gets a generated `Content-ID`,
+    /// the matching `src` attributes are rewritten to `src="cid:..."` and the
+    /// resources are added as inline parts after the HTML one.
+    ///
+    /// ```rust
+    /// # use std::error::Error;
+    /// use std::fs;
+    ///
+    /// use lettre::message::{header::ContentType, MultiPart};
+    ///
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// let logo = fs::read("docs/lettre.png")?;
+    /// let part = MultiPart::related_html(
+    ///     r#"<p>Hello!</p><img src="logo.png">"#,
+    ///     [("logo.png", (logo, ContentType::parse("image/png")?))],
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn related_html<I, N, T>(html: &str, resources: I) -> Self
+    where
+        I: IntoIterator<Item = (N, (T, ContentType))>,
+        N: AsRef<str>,
+        T: IntoBody,
+    {
+        let mut html = html.to_owned();
+        let mut inline_parts = Vec::new();
+        for (name, (body, content_type)) in resources {
+            let name = name.as_ref();
+            let (attachment, content_id) = Attachment::new_inline_random();
+
+            for quote in ['"', '\''] {
+                html = html.replace(
+                    &format!("src={quote}{name}{quote}"),
+                    &format!("src={quote}cid:{content_id}{quote}"),
+                );
+            }
+            inline_parts.push(attachment.body(body, content_type));
+        }
+
+        let mut multipart = MultiPart::related().singlepart(SinglePart::html(html));
+        for part in inline_parts {
+            multipart = multipart.singlepart(part);
+        }
+        multipart
+    }
+
     /// Add single part to multipart
     pub fn singlepart(mut self, part: SinglePart) -> Self {
         self.parts.push(Part::Single(part));
@@ -372,6 +584,17 @@ impl MultiPart {
             .into()
     }
 
+    /// Get the parts of the multipart
+    pub fn parts(&self) -> &[Part] {
+        &self.parts
+    }
+
+    /// Get a mutable reference to the parts of the multipart,
+    /// to add, replace or remove parts
+    pub fn parts_mut(&mut self) -> &mut Vec<Part> {
+        &mut self.parts
+    }
+
     /// Get the headers from the multipart
     pub fn headers(&self) -> &Headers {
         &self.headers
@@ -710,6 +933,129 @@ mod test {
                            "--0oVZ2r6AoLAhLlb0gPNSKy6BEqdS2IfwxrcbUuo1--\r\n"));
     }
 
+    #[test]
+    fn multi_part_related_html() {
+        let part = MultiPart::related_html(
+            "<p><img src=\"logo.png\"><img src='logo.png'><img src=\"other.png\"></p>",
+            [(
+                "logo.png",
+                (
+                    vec![0x89, b'P', b'N', b'G'],
+                    header::ContentType::parse("image/png").unwrap(),
+                ),
+            )],
+        );
+
+        assert_eq!(part.parts().len(), 2);
+        let (html, image) = match part.parts() {
+            [Part::Single(html), Part::Single(image)] => (html, image),
+            _ => panic!("unexpected parts"),
+        };
+
+        let content_id = image.headers().get_raw("Content-ID").unwrap();
+        let content_id = content_id
+            .strip_prefix('<')
+            .and_then(|id| id.strip_suffix('>'))
+            .unwrap();
+        assert!(content_id.ends_with(&format!("@{}", crate::message::message_id_domain())));
+        assert_eq!(
+            image.headers().get::<header::ContentDisposition>(),
+            Some(header::ContentDisposition::inline())
+        );
+        assert_eq!(
+            String::from_utf8(html.decoded_bytes().unwrap()).unwrap(),
+            format!(
+                "<p><img src=\"cid:{content_id}\"><img src='cid:{content_id}'><img src=\"other.png\"></p>"
+            )
+        );
+    }
+
+    #[test]
+    fn single_part_rfc822() {
+        let message = Message::builder()
+            .from("NoBody <nobody@domain.tld>".parse().unwrap())
+            .to("Hei <hei@domain.tld>".parse().unwrap())
+            .date(std::time::SystemTime::UNIX_EPOCH)
+            .subject("Original")
+            .body(String::from("Hello"))
+            .unwrap();
+
+        let part = SinglePart::rfc822(&message);
+
+        assert_eq!(
+            String::from_utf8(part.formatted()).unwrap(),
+            concat!(
+                "Content-Type: message/rfc822\r\n",
+                "Content-Transfer-Encoding: 7bit\r\n",
+                "\r\n",
+                "From: NoBody <nobody@domain.tld>\r\n",
+                "To: Hei <hei@domain.tld>\r\n",
+                "Date: Thu, 01 Jan 1970 00:00:00 +0000\r\n",
+                "Subject: Original\r\n",
+                "Content-Transfer-Encoding: 7bit\r\n",
+                "\r\n",
+                "Hello\r\n"
+            )
+        );
+    }
+
+    #[test]
+    fn part_traversal() {
+        let mut part = Part::Multi(
+            MultiPart::mixed()
+                .multipart(MultiPart::alternative_plain_html(
+                    String::from("Hello"),
+                    String::from("<p>Hello</p>"),
+                ))
+                .singlepart(
+                    Attachment::new_inline(String::from("logo"))
+                        .body(vec![0; 16], ContentType::parse("image/png").unwrap()),
+                ),
+        );
+
+        let content_types = part
+            .iter()
+            .map(|part| {
+                part.content_type()
+                    .unwrap()
+                    .as_ref()
+                    .essence_str()
+                    .to_owned()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            content_types,
+            [
+                "multipart/mixed",
+                "multipart/alternative",
+                "text/plain",
+                "text/html",
+                "image/png"
+            ]
+        );
+        assert!(part.iter().any(|part| part.content_id() == Some("logo")));
+
+        part.for_each_mut(|part| {
+            if part.content_id() == Some("logo") {
+                *part = Part::Single(SinglePart::plain(String::from("No logo")));
+            }
+        });
+        part.retain(|part| {
+            part.content_type()
+                .map_or(true, |content_type| content_type != ContentType::TEXT_HTML)
+        });
+
+        let Part::Multi(multi) = &part else {
+            panic!("expected a multipart");
+        };
+        let [Part::Multi(alternative), Part::Single(replaced)] = multi.parts() else {
+            panic!("unexpected parts");
+        };
+        assert_eq!(alternative.parts().len(), 1);
+        assert_eq!(replaced.raw_body(), b"No logo");
+        assert_eq!(part.iter().count(), 4);
+    }
+
     #[test]
     fn test_make_boundary() {
         let mut boundaries = std::collections::HashSet::with_capacity(10);
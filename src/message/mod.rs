@@ -198,26 +198,54 @@
 //! ```
 //! </details>
 
-use std::{io::Write, iter, time::SystemTime};
+use std::{
+    fs,
+    io::{self, Write},
+    iter,
+    path::Path,
+    sync::Arc,
+    time::SystemTime,
+};
 
 pub use attachment::Attachment;
-pub use body::{Body, IntoBody, MaybeString};
+pub use body::{Body, EncodingPolicy, IntoBody, MaybeString};
+pub use clock::{Clock, FixedClock, SystemClock};
 #[cfg(feature = "dkim")]
 pub use dkim::*;
 pub use mailbox::*;
 pub use mimebody::*;
+pub use params::{AttachmentParams, EmailParams};
+pub use policy::{AttachmentPolicy, PolicyError};
+pub use resent::Resent;
+pub use template::MessageTemplate;
 
+pub mod arf;
 mod attachment;
 mod body;
+mod clock;
+mod content_hash;
+mod debug_json;
 #[cfg(feature = "dkim")]
 pub mod dkim;
+mod footer;
 pub mod header;
 mod mailbox;
+mod mbox;
 mod mimebody;
-
+mod params;
+mod policy;
+mod preview;
+mod resent;
+mod template;
+
+#[cfg(any(feature = "tokio1", feature = "async-std1"))]
+use crate::Executor;
 use crate::{
     address::Envelope,
-    message::header::{ContentTransferEncoding, Header, Headers, MailboxesHeader},
+    message::header::{
+        ContentTransferEncoding, Header, HeaderEncoding, HeaderOrder, HeaderSanitization,
+        HeaderValue, Headers, InvalidHeaderValue, MailboxesHeader,
+    },
     Error as EmailError,
 };
 
@@ -229,12 +257,59 @@ trait EmailFormat {
     fn format(&self, out: &mut Vec<u8>);
 }
 
+/// Keeps the first `max` mailboxes of the `To` and `Cc` headers
+fn cap_header_recipients(headers: &mut Headers, max: usize) {
+    let left = cap_mailboxes::<header::To>(headers, max);
+    cap_mailboxes::<header::Cc>(headers, left);
+}
+
+/// Keeps the first `max` mailboxes of the `H` header, returning how many
+/// more can be kept
+///
+/// A `To` header left empty is replaced with `undisclosed-recipients:;`.
+fn cap_mailboxes<H>(headers: &mut Headers, max: usize) -> usize
+where
+    H: Header + From<Mailboxes> + Into<Mailboxes>,
+{
+    let Some(header) = headers.get::<H>() else {
+        return max;
+    };
+    let mut mailboxes: Vec<Mailbox> = header.into().into();
+    if mailboxes.len() <= max {
+        return max - mailboxes.len();
+    }
+
+    mailboxes.truncate(max);
+    if !mailboxes.is_empty() {
+        headers.set(H::from(mailboxes.into()));
+    } else if H::name() == header::To::name() {
+        let undisclosed = String::from("undisclosed-recipients:;");
+        headers.insert_raw(HeaderValue::dangerous_new_pre_encoded(
+            H::name(),
+            undisclosed.clone(),
+            undisclosed,
+        ));
+    } else {
+        headers.remove_raw(&H::name());
+    }
+    0
+}
+
 /// A builder for messages
 #[derive(Debug, Clone)]
 pub struct MessageBuilder {
     headers: Headers,
     envelope: Option<Envelope>,
     drop_bcc: bool,
+    date_utc_offset: i16,
+    template: Option<MessageTemplate>,
+    resent: Option<Resent>,
+    clock: Option<Arc<dyn Clock>>,
+    sanitization: HeaderSanitization,
+    header_encoding: HeaderEncoding,
+    header_order: Option<HeaderOrder>,
+    max_header_recipients: Option<usize>,
+    policy: Option<AttachmentPolicy>,
 }
 
 impl MessageBuilder {
@@ -244,6 +319,15 @@ impl MessageBuilder {
             headers: Headers::new(),
             envelope: None,
             drop_bcc: true,
+            date_utc_offset: 0,
+            template: None,
+            resent: None,
+            clock: None,
+            sanitization: HeaderSanitization::Encode,
+            header_encoding: HeaderEncoding::new(),
+            header_order: None,
+            max_header_recipients: None,
+            policy: None,
         }
     }
 
@@ -274,10 +358,32 @@ impl MessageBuilder {
 
     /// Set `Date` header using current date/time
     ///
-    /// Shortcut for `self.date(SystemTime::now())`, it is automatically inserted
-    /// if no date has been provided.
+    /// Shortcut for `self.date(clock.now())`, using the clock set by
+    /// [`MessageBuilder::clock`]. It is automatically inserted if no date
+    /// has been provided.
+    ///
+    /// The date is displayed with the offset set by [`MessageBuilder::date_utc_offset`]
+    /// before this call, so set the offset first.
     pub fn date_now(self) -> Self {
-        self.date(SystemTime::now())
+        let date = header::Date::new(self.now()).with_utc_offset(self.date_utc_offset);
+        self.header(date)
+    }
+
+    /// Display the automatically inserted `Date` header with a fixed offset from UTC, in minutes
+    ///
+    /// Defaults to `0`, formatting the date in UTC. Applies to the later calls to
+    /// [`MessageBuilder::date_now`] and to the `Date` header inserted when none
+    /// has been provided.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `offset_minutes` is outside of the `-1439..=1439` range.
+    /// See [`header::Date::with_utc_offset`].
+    pub fn date_utc_offset(mut self, offset_minutes: i16) -> Self {
+        // Validate the offset now, rather than when building
+        header::assert_utc_offset(offset_minutes);
+        self.date_utc_offset = offset_minutes;
+        self
     }
 
     /// Set or add mailbox to `ReplyTo` header
@@ -292,6 +398,8 @@ impl MessageBuilder {
     /// Set or add mailbox to `To` header
     ///
     /// Shortcut for `self.mailbox(header::To(mbox))`.
+    /// Each call parses the mailboxes already added, so pass long lists
+    /// of recipients at once with [`MessageBuilder::mailbox`].
     pub fn to(self, mbox: Mailbox) -> Self {
         self.mailbox(header::To(mbox.into()))
     }
@@ -322,6 +430,46 @@ impl MessageBuilder {
         self.header(header::References::from(id))
     }
 
+    /// Set the `In-Reply-To` and `References` headers of a reply to the
+    /// message with the `parent` headers
+    ///
+    /// `In-Reply-To` is set to the `Message-ID` of the parent, and
+    /// `References` is computed by [`References::reply_to`](header::References::reply_to).
+    ///
+    /// ```rust
+    /// use lettre::message::{header::References, Message};
+    ///
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// let parent = Message::builder()
+    ///     .from("NoBody <nobody@domain.tld>".parse()?)
+    ///     .to("Hei <hei@domain.tld>".parse()?)
+    ///     .message_id(Some("<1@domain.tld>".to_owned()))
+    ///     .subject("Happy new year")
+    ///     .body(String::from("Be happy!"))?;
+    ///
+    /// let reply = Message::builder()
+    ///     .from("Hei <hei@domain.tld>".parse()?)
+    ///     .to("NoBody <nobody@domain.tld>".parse()?)
+    ///     .reply_in_thread(parent.headers())
+    ///     .subject("Re: Happy new year")
+    ///     .body(String::from("You too!"))?;
+    ///
+    /// let references = reply.headers().get::<References>().unwrap();
+    /// assert_eq!(references.as_ref(), "<1@domain.tld>");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn reply_in_thread(mut self, parent: &Headers) -> Self {
+        if let Some(id) = parent.get::<header::MessageId>() {
+            self = self.in_reply_to(id.as_ref().to_owned());
+        }
+        match header::References::reply_to(parent) {
+            Some(references) => self.header(references),
+            None => self,
+        }
+    }
+
     /// Set `Subject` header to message
     ///
     /// Shortcut for `self.header(header::Subject(subject.into()))`.
@@ -330,6 +478,14 @@ impl MessageBuilder {
         self.header(header::Subject::from(s))
     }
 
+    /// Set `Subject` header to message, failing if it contains control characters
+    ///
+    /// Shortcut for `self.try_header(header::Subject(subject.into()))`.
+    pub fn try_subject<S: Into<String>>(self, subject: S) -> Result<Self, InvalidHeaderValue> {
+        let s: String = subject.into();
+        self.try_header(header::Subject::from(s))
+    }
+
     /// Set [Message-ID
     /// header](https://tools.ietf.org/html/rfc5322#section-3.6.4)
     ///
@@ -340,20 +496,10 @@ impl MessageBuilder {
     pub fn message_id(self, id: Option<String>) -> Self {
         match id {
             Some(i) => self.header(header::MessageId::from(i)),
-            None => {
-                #[cfg(feature = "hostname")]
-                let hostname = hostname::get()
-                    .map_err(|_| ())
-                    .and_then(|s| s.into_string().map_err(|_| ()))
-                    .unwrap_or_else(|()| DEFAULT_MESSAGE_ID_DOMAIN.to_owned());
-                #[cfg(not(feature = "hostname"))]
-                let hostname = DEFAULT_MESSAGE_ID_DOMAIN.to_owned();
-
-                self.header(header::MessageId::from(
-                    // https://tools.ietf.org/html/rfc5322#section-3.6.4
-                    format!("<{}@{}>", make_message_id(), hostname),
-                ))
-            }
+            None => self.header(header::MessageId::from(
+                // https://tools.ietf.org/html/rfc5322#section-3.6.4
+                format!("<{}@{}>", make_message_id(), message_id_domain()),
+            )),
         }
     }
 
@@ -363,9 +509,101 @@ impl MessageBuilder {
         self.header(header::UserAgent::from(id))
     }
 
+    /// Set custom header to message, failing if its value contains control
+    /// characters other than tabs, like CR and LF
+    ///
+    /// Guards against header injection from user supplied values, whatever
+    /// [`MessageBuilder::sanitize_headers`] is set to.
+    ///
+    /// ```rust
+    /// use lettre::message::{header::Subject, Message};
+    ///
+    /// let err = Message::builder()
+    ///     .try_header(Subject::from(String::from(
+    ///         "Hello!\r\nBcc: victim@domain.tld",
+    ///     )))
+    ///     .unwrap_err();
+    /// assert_eq!(
+    ///     err.to_string(),
+    ///     "header \"Subject\" contains control characters"
+    /// );
+    /// ```
+    pub fn try_header<H: Header>(self, header: H) -> Result<Self, InvalidHeaderValue> {
+        if HeaderSanitization::needs_sanitizing(header.display().get_raw()) {
+            return Err(InvalidHeaderValue::new(H::name()));
+        }
+        Ok(self.header(header))
+    }
+
     /// Set custom header to message
+    ///
+    /// Control characters in its value are handled according to
+    /// [`MessageBuilder::sanitize_headers`]. When stripped, they're removed
+    /// even if `H` can't parse the stripped value, which is then written as is.
     pub fn header<H: Header>(mut self, header: H) -> Self {
-        self.headers.set(header);
+        let mut value = header.display();
+        if HeaderSanitization::needs_sanitizing(value.get_raw()) {
+            match self.sanitization {
+                HeaderSanitization::Encode => {}
+                HeaderSanitization::Strip => {
+                    let stripped = HeaderSanitization::strip(value.get_raw());
+                    value = match H::parse(&stripped) {
+                        Ok(header) => header.display(),
+                        Err(_) => value.with_raw(stripped),
+                    };
+                }
+            }
+        }
+
+        self.headers
+            .insert_raw(value.with_encoding(self.header_encoding));
+        self
+    }
+
+    /// Set how control characters, like CR and LF, are handled in the values
+    /// of the headers set afterwards, like the subject
+    ///
+    /// Defaults to [`HeaderSanitization::Encode`]. To reject values with control
+    /// characters instead, see [`MessageBuilder::try_header`].
+    pub fn sanitize_headers(mut self, sanitization: HeaderSanitization) -> Self {
+        self.sanitization = sanitization;
+        self
+    }
+
+    /// Set how the unstructured headers set afterwards, like the subject,
+    /// are encoded
+    ///
+    /// Allows using the "Q" encoding instead of base64 for the non-ASCII
+    /// words, or encoding the whole value, for the gateways mishandling
+    /// the default encoding.
+    ///
+    /// ```rust
+    /// use lettre::message::{
+    ///     header::{HeaderEncoding, WordEncoding},
+    ///     Message,
+    /// };
+    ///
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// let m = Message::builder()
+    ///     .from("NoBody <nobody@domain.tld>".parse()?)
+    ///     .to("Hei <hei@domain.tld>".parse()?)
+    ///     .header_encoding(HeaderEncoding::new().word_encoding(WordEncoding::QuotedPrintable))
+    ///     .subject("Happy new year, Adrián")
+    ///     .body(String::from("Be happy!"))?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn header_encoding(mut self, encoding: HeaderEncoding) -> Self {
+        self.header_encoding = encoding;
+        self
+    }
+
+    /// Set the order the headers are written in, instead of the order they're set in
+    ///
+    /// See [`HeaderOrder`].
+    pub fn header_order(mut self, order: HeaderOrder) -> Self {
+        self.header_order = Some(order);
         self
     }
 
@@ -386,6 +624,40 @@ impl MessageBuilder {
         self
     }
 
+    /// Apply the defaults of `template`
+    ///
+    /// Its headers are added when they aren't set on this builder, and the
+    /// message gets signed with its DKIM configuration.
+    /// See [`MessageTemplate`].
+    pub fn template(mut self, template: MessageTemplate) -> Self {
+        self.template = Some(template);
+        self
+    }
+
+    /// Use `clock` to get the current time, instead of the system time
+    ///
+    /// Overrides the clock of the [`MessageTemplate`]. See [`Clock`].
+    pub fn clock<C: Clock + 'static>(mut self, clock: C) -> Self {
+        self.clock = Some(Arc::new(clock));
+        self
+    }
+
+    /// The current time according to the clock
+    fn now(&self) -> SystemTime {
+        self.clock
+            .as_ref()
+            .map_or_else(SystemTime::now, |clock| clock.now())
+    }
+
+    /// Resend the message, adding a block of `Resent-*` headers
+    ///
+    /// The message is sent to the resent recipients, unless an
+    /// envelope is set. See [`Resent`].
+    pub fn resent(mut self, resent: Resent) -> Self {
+        self.resent = Some(resent);
+        self
+    }
+
     /// Keep the `Bcc` header
     ///
     /// By default, the `Bcc` header is removed from the email after
@@ -400,10 +672,45 @@ impl MessageBuilder {
         self
     }
 
+    /// Limit the number of mailboxes listed in the `To` and `Cc` headers
+    ///
+    /// Only the first `max` mailboxes of the `To` header, then of the `Cc`
+    /// header, are kept in the headers. The others are still recipients of
+    /// the envelope, like the `Bcc` ones. If none of the `To` mailboxes is
+    /// kept, the header is replaced with `undisclosed-recipients:;`.
+    ///
+    /// Folding thousands of mailboxes into a header makes messages
+    /// slow to format and can exceed the header size accepted by providers.
+    /// Has no effect when the envelope is set with
+    /// [`MessageBuilder::envelope`] or [`MessageBuilder::resent`], as the
+    /// recipients missing from the headers may not be part of it.
+    pub fn max_header_recipients(mut self, max: usize) -> Self {
+        self.max_header_recipients = Some(max);
+        self
+    }
+
+    /// Enforce an [`AttachmentPolicy`] when building the message
+    ///
+    /// Building fails with [`Error::Policy`](crate::error::Error::Policy) if the message
+    /// doesn't comply with the policy.
+    pub fn policy(mut self, policy: AttachmentPolicy) -> Self {
+        self.policy = Some(policy);
+        self
+    }
+
     // TODO: High-level methods for attachments and embedded files
 
     /// Create message from body
-    fn build(self, body: MessageBody) -> Result<Message, EmailError> {
+    fn build(mut self, body: MessageBody) -> Result<Message, EmailError> {
+        let template = self.template.take();
+        if let Some(template) = &template {
+            self.headers.insert_missing(template.headers());
+            if self.clock.is_none() {
+                self.clock = template.template_clock();
+            }
+        }
+        let now = self.now();
+
         // Check for missing required headers
         // https://tools.ietf.org/html/rfc5322#section-3.6
 
@@ -427,9 +734,16 @@ impl MessageBuilder {
             }
         }
 
-        let envelope = match res.envelope {
-            Some(e) => e,
-            None => Envelope::try_from(&res.headers)?,
+        let envelope = match (res.envelope, &res.resent) {
+            (Some(e), _) => e,
+            (None, Some(resent)) => resent.envelope()?,
+            (None, None) => {
+                let envelope = Envelope::try_from(&res.headers)?;
+                if let Some(max) = res.max_header_recipients {
+                    cap_header_recipients(&mut res.headers, max);
+                }
+                envelope
+            }
         };
 
         if res.drop_bcc {
@@ -437,11 +751,30 @@ impl MessageBuilder {
             res.headers.remove::<header::Bcc>();
         }
 
-        Ok(Message {
+        if let Some(resent) = res.resent {
+            // Newer `Resent-*` blocks go before older ones and the other headers
+            // https://tools.ietf.org/html/rfc5322#section-3.6.6
+            res.headers.prepend(resent.into_headers(res.drop_bcc, now)?);
+        }
+
+        #[allow(unused_mut)]
+        let mut message = Message {
             headers: res.headers,
             body,
             envelope,
-        })
+            header_order: res.header_order,
+        };
+
+        if let Some(policy) = &res.policy {
+            policy.check(&message)?;
+        }
+
+        #[cfg(feature = "dkim")]
+        if let Some(dkim_config) = template.as_ref().and_then(MessageTemplate::dkim_config) {
+            dkim_sign_fixed_time(&mut message, dkim_config, now);
+        }
+
+        Ok(message)
     }
 
     /// Create [`Message`] using a [`Vec<u8>`], [`String`], or [`Body`] body
@@ -478,18 +811,28 @@ impl MessageBuilder {
 }
 
 /// Email message which can be formatted
+///
+/// With the `serde` feature, messages can be stored in a structured form,
+/// with their headers and MIME tree, and loaded back to be sent later.
+/// The encoded bodies are serialized as base64 strings in human-readable
+/// formats like JSON. Loading checks the header names and that the encoded
+/// header values can't start new headers, but otherwise trusts the stored
+/// data.
 #[cfg_attr(docsrs, doc(cfg(feature = "builder")))]
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Message {
     headers: Headers,
     body: MessageBody,
     envelope: Envelope,
+    header_order: Option<HeaderOrder>,
 }
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 enum MessageBody {
     Mime(Part),
-    Raw(Vec<u8>),
+    Raw(#[cfg_attr(feature = "serde", serde(with = "body::serde_bytes"))] Vec<u8>),
 }
 
 impl Message {
@@ -513,6 +856,85 @@ impl Message {
         &self.envelope
     }
 
+    /// Get the MIME body of the message
+    ///
+    /// Returns `None` if the message was built with [`MessageBuilder::body`].
+    pub fn mime_body(&self) -> Option<&Part> {
+        match &self.body {
+            MessageBody::Mime(part) => Some(part),
+            MessageBody::Raw(_) => None,
+        }
+    }
+
+    /// Get a mutable reference to the MIME body of the message
+    ///
+    /// Allows changing the parts of a built message, for example removing
+    /// large attachments or adding a part, without building it again.
+    /// Changes made after signing the message with DKIM invalidate the signature.
+    ///
+    /// ```rust
+    /// use lettre::message::{header::ContentType, Attachment, Message, MultiPart, Part, SinglePart};
+    ///
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// let mut message = Message::builder()
+    ///     .from("NoBody <nobody@domain.tld>".parse()?)
+    ///     .to("Hei <hei@domain.tld>".parse()?)
+    ///     .multipart(
+    ///         MultiPart::mixed()
+    ///             .singlepart(SinglePart::plain(String::from("Hello")))
+    ///             .singlepart(Attachment::new(String::from("big.bin")).body(
+    ///                 vec![0; 1024 * 1024],
+    ///                 ContentType::parse("application/octet-stream")?,
+    ///             )),
+    ///     )?;
+    ///
+    /// if let Some(body) = message.mime_body_mut() {
+    ///     body.retain(|part| match part {
+    ///         Part::Single(part) => part.raw_body().len() < 1024,
+    ///         Part::Multi(_) => true,
+    ///     });
+    /// }
+    /// assert_eq!(message.parts().count(), 2);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn mime_body_mut(&mut self) -> Option<&mut Part> {
+        match &mut self.body {
+            MessageBody::Mime(part) => Some(part),
+            MessageBody::Raw(_) => None,
+        }
+    }
+
+    /// Iterate over the MIME parts of the message, depth-first
+    ///
+    /// Starts with the MIME body itself, and is empty if the message was built
+    /// with [`MessageBuilder::body`].
+    ///
+    /// ```rust
+    /// use lettre::message::{header::ContentType, Message, MultiPart};
+    ///
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// let message = Message::builder()
+    ///     .from("NoBody <nobody@domain.tld>".parse()?)
+    ///     .to("Hei <hei@domain.tld>".parse()?)
+    ///     .multipart(MultiPart::alternative_plain_html(
+    ///         String::from("Hello"),
+    ///         String::from("<p>Hello</p>"),
+    ///     ))?;
+    ///
+    /// let html = message
+    ///     .parts()
+    ///     .find(|part| part.content_type() == Some(ContentType::TEXT_HTML));
+    /// assert!(html.is_some());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn parts(&self) -> Parts<'_> {
+        Parts::new(self.mime_body())
+    }
+
     /// Get message content formatted for SMTP
     pub fn formatted(&self) -> Vec<u8> {
         let mut out = Vec::new();
@@ -520,6 +942,208 @@ impl Message {
         out
     }
 
+    /// Get a stable hash of the content of the message
+    ///
+    /// The hash covers the headers and the body, leaving out what changes each
+    /// time the same message is built: the `Date`, `Message-ID` and
+    /// `DKIM-Signature` headers, and the multipart boundaries. The header names
+    /// are compared case-insensitively, in any order, and the whitespace of
+    /// their values is collapsed. The envelope isn't part of the hash.
+    ///
+    /// Useful for queues and archives deduplicating regenerated messages.
+    /// The hash is a 64 bits FNV-1a, which gives the same results across
+    /// versions and platforms, but isn't cryptographically secure.
+    ///
+    /// ```rust
+    /// use lettre::message::Message;
+    ///
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// let build = || -> Result<Message, Box<dyn Error>> {
+    ///     Ok(Message::builder()
+    ///         .from("NoBody <nobody@domain.tld>".parse()?)
+    ///         .to("Hei <hei@domain.tld>".parse()?)
+    ///         .subject("Happy new year")
+    ///         .body(String::from("Be happy!"))?)
+    /// };
+    ///
+    /// // The two messages have different `Message-ID`s
+    /// assert_eq!(build()?.content_hash(), build()?.content_hash());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn content_hash(&self) -> u64 {
+        content_hash::content_hash(self)
+    }
+
+    /// Get a JSON outline of the message, for admin interfaces and debugging
+    ///
+    /// Lists the headers of the message and of its MIME parts, with the
+    /// content type, the transfer encoding and the encoded size of the bodies,
+    /// and the size of the formatted message. The bodies themselves, like the
+    /// content of the attachments, are left out.
+    ///
+    /// ```rust
+    /// use lettre::message::Message;
+    ///
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// let email = Message::builder()
+    ///     .from("NoBody <nobody@domain.tld>".parse()?)
+    ///     .to("Hei <hei@domain.tld>".parse()?)
+    ///     .subject("Happy new year")
+    ///     .body(String::from("Be happy!"))?;
+    ///
+    /// let json = email.to_debug_json();
+    /// assert!(json.contains(r#"{"name":"Subject","value":"Happy new year"}"#));
+    /// assert!(!json.contains("Be happy!"));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn to_debug_json(&self) -> String {
+        debug_json::to_debug_json(self)
+    }
+
+    /// Get a short plain text preview of the message body
+    ///
+    /// Walks the MIME tree looking for the first `text/plain` part, falling back
+    /// to the first `text/html` one with its tags stripped, decodes it and collapses
+    /// whitespace. The preview is cut after `max_chars` characters.
+    ///
+    /// Only parts in UTF-8, US-ASCII or ISO-8859-1 are decoded, the others are skipped
+    /// like invalid text.
+    ///
+    /// Useful for logging, admin interfaces or generating the hidden "preheader" text.
+    /// Returns `None` if the message doesn't have a textual body.
+    pub fn plain_text_preview(&self, max_chars: usize) -> Option<String> {
+        preview::plain_text_preview(self, max_chars)
+    }
+
+    /// Append a footer, like a disclaimer, to the textual bodies of the message
+    ///
+    /// `plain` is appended to the `text/plain` bodies, and `html` is inserted
+    /// before the closing `</body>` tag of the `text/html` ones, or appended
+    /// if there is none. Attachments and parts which aren't valid UTF-8
+    /// are left untouched. The modified bodies are encoded again.
+    ///
+    /// Changes made after signing the message with DKIM invalidate the signature.
+    ///
+    /// ```rust
+    /// use lettre::message::{Message, MultiPart};
+    ///
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// let mut message = Message::builder()
+    ///     .from("NoBody <nobody@domain.tld>".parse()?)
+    ///     .to("Hei <hei@domain.tld>".parse()?)
+    ///     .multipart(MultiPart::alternative_plain_html(
+    ///         String::from("Hello"),
+    ///         String::from("<p>Hello</p>"),
+    ///     ))?;
+    ///
+    /// message.append_footer(
+    ///     "-- \nThis email is confidential.",
+    ///     "<hr><p>This email is confidential.</p>",
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn append_footer(&mut self, plain: &str, html: &str) {
+        footer::append_footer(self, plain, html);
+    }
+
+    /// Add a `Received` trace header at the top of the headers
+    ///
+    /// For relaying the message through an application-level gateway,
+    /// as each relay adds one before the ones of the previous relays.
+    ///
+    /// Changes made after signing the message with DKIM invalidate the signature.
+    pub fn prepend_received(&mut self, received: header::Received) {
+        let mut headers = Headers::new();
+        headers.set(received);
+        self.headers.prepend(headers);
+    }
+
+    /// Save a copy of the message to an `.eml` file
+    ///
+    /// The file contains the formatted message, with CRLF line endings,
+    /// and is replaced if it already exists.
+    pub fn write_eml<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        fs::write(path, self.formatted())
+    }
+
+    /// Append a copy of the message to the mailbox file at `path`
+    ///
+    /// The mailbox is in the `mboxrd` format and is created if needed.
+    /// The message is preceded by a `From ` line with the envelope sender
+    /// and the `Date` of the message, its line endings are converted to LF
+    /// and its lines starting with `From ` are quoted with `>`.
+    ///
+    /// ```rust,no_run
+    /// use lettre::message::Message;
+    ///
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// let email = Message::builder()
+    ///     .from("NoBody <nobody@domain.tld>".parse()?)
+    ///     .to("Hei <hei@domain.tld>".parse()?)
+    ///     .subject("Happy new year")
+    ///     .body(String::from("Be happy!"))?;
+    ///
+    /// email.append_mbox("sent.mbox")?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn append_mbox<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        mbox::append(path.as_ref(), &mbox::entry(self))
+    }
+
+    /// Save a copy of the message to an `.eml` file, on the blocking thread
+    /// pool of the executor
+    ///
+    /// See [`write_eml`](Self::write_eml).
+    #[cfg(any(feature = "tokio1", feature = "async-std1"))]
+    #[cfg_attr(docsrs, doc(cfg(any(feature = "tokio1", feature = "async-std1"))))]
+    pub async fn write_eml_async<E: Executor, P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let path = path.as_ref().to_owned();
+        let email = self.formatted();
+        E::spawn_blocking(move || fs::write(path, email)).await
+    }
+
+    /// Append a copy of the message to the mailbox file at `path`, on the
+    /// blocking thread pool of the executor
+    ///
+    /// See [`append_mbox`](Self::append_mbox).
+    #[cfg(any(feature = "tokio1", feature = "async-std1"))]
+    #[cfg_attr(docsrs, doc(cfg(any(feature = "tokio1", feature = "async-std1"))))]
+    pub async fn append_mbox_async<E: Executor, P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let path = path.as_ref().to_owned();
+        let entry = mbox::entry(self);
+        E::spawn_blocking(move || mbox::append(&path, &entry)).await
+    }
+
+    /// Approximate size of the formatted message, without formatting it
+    ///
+    /// Counts the headers and the encoded bodies, leaving out the MIME
+    /// boundaries and the headers of the parts.
+    #[cfg(all(
+        feature = "smtp-transport",
+        any(feature = "tokio1", feature = "async-std1")
+    ))]
+    pub(crate) fn estimated_len(&self) -> usize {
+        let body = match &self.body {
+            MessageBody::Mime(part) => part
+                .iter()
+                .map(|part| match part {
+                    Part::Single(part) => part.raw_body().len(),
+                    Part::Multi(_) => 0,
+                })
+                .sum(),
+            MessageBody::Raw(raw) => raw.len(),
+        };
+        self.headers.to_string().len() + body
+    }
+
     #[cfg(feature = "dkim")]
     /// Format body for signing
     pub(crate) fn body_raw(&self) -> Vec<u8> {
@@ -598,8 +1222,11 @@ impl Message {
 
 impl EmailFormat for Message {
     fn format(&self, out: &mut Vec<u8>) {
-        write!(out, "{}", self.headers)
-            .expect("A Write implementation panicked while formatting headers");
+        match &self.header_order {
+            Some(order) => self.headers.format_ordered(order, out),
+            None => write!(out, "{}", self.headers)
+                .expect("A Write implementation panicked while formatting headers"),
+        }
 
         match &self.body {
             MessageBody::Mime(p) => p.format(out),
@@ -623,13 +1250,36 @@ fn make_message_id() -> String {
     iter::repeat_with(fastrand::alphanumeric).take(36).collect()
 }
 
+/// Domain of the generated message and content ids, the hostname if available
+fn message_id_domain() -> String {
+    #[cfg(feature = "hostname")]
+    let hostname = hostname::get()
+        .map_err(|_| ())
+        .and_then(|s| s.into_string().map_err(|_| ()))
+        .unwrap_or_else(|()| DEFAULT_MESSAGE_ID_DOMAIN.to_owned());
+    #[cfg(not(feature = "hostname"))]
+    let hostname = DEFAULT_MESSAGE_ID_DOMAIN.to_owned();
+
+    hostname
+}
+
+/// Create a random content id, without the angle brackets
+fn make_content_id() -> String {
+    format!("{}@{}", make_message_id(), message_id_domain())
+}
+
 #[cfg(test)]
 mod test {
     use std::time::{Duration, SystemTime};
 
     use pretty_assertions::assert_eq;
 
-    use super::{header, mailbox::Mailbox, make_message_id, Message, MultiPart, SinglePart};
+    use super::{
+        header::{self, Header, HeaderName, HeaderSanitization, HeaderValue},
+        mailbox::Mailbox,
+        make_message_id, Message, MultiPart, SinglePart,
+    };
+    use crate::BoxError;
 
     #[test]
     fn email_missing_originator() {
@@ -656,6 +1306,26 @@ mod test {
             .is_err());
     }
 
+    #[test]
+    fn email_date_utc_offset() {
+        let email = Message::builder()
+            .from("NoBody <nobody@domain.tld>".parse().unwrap())
+            .to("NoBody <nobody@domain.tld>".parse().unwrap())
+            .date_utc_offset(-90)
+            .body(String::from("Happy new year!"))
+            .unwrap();
+
+        let date = email.headers().get::<header::Date>().unwrap();
+        assert_eq!(date.utc_offset(), -90);
+        assert!(email.headers().get_raw("Date").unwrap().ends_with(" -0130"));
+    }
+
+    #[test]
+    #[should_panic(expected = "UTC offset out of range")]
+    fn email_date_utc_offset_out_of_range() {
+        let _ = Message::builder().date_utc_offset(-24 * 60);
+    }
+
     #[test]
     fn email_message_no_bcc() {
         // Tue, 15 Nov 1994 08:12:31 GMT
@@ -692,6 +1362,41 @@ mod test {
         );
     }
 
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        let email = Message::builder()
+            .from("NoBody <nobody@domain.tld>".parse().unwrap())
+            .to("Hei <hei@domain.tld>".parse().unwrap())
+            .subject("Счастливого нового года")
+            .header_order(header::HeaderOrder::standard())
+            .multipart(
+                MultiPart::mixed()
+                    .multipart(MultiPart::alternative_plain_html(
+                        String::from("Be happy!"),
+                        String::from("<p>Be happy!</p>"),
+                    ))
+                    .singlepart(super::Attachment::new(String::from("data.bin")).body(
+                        vec![0, 159, 146, 150],
+                        "application/octet-stream".parse().unwrap(),
+                    )),
+            )
+            .unwrap();
+
+        let json = serde_json::to_string(&email).unwrap();
+        let decoded: Message = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded.formatted(), email.formatted());
+        assert_eq!(decoded.envelope(), email.envelope());
+
+        let raw = Message::builder()
+            .from("nobody@domain.tld".parse().unwrap())
+            .to("hei@domain.tld".parse().unwrap())
+            .body(vec![0xff, 0xfe])
+            .unwrap();
+        let decoded: Message = serde_json::from_str(&serde_json::to_string(&raw).unwrap()).unwrap();
+        assert_eq!(decoded.formatted(), raw.formatted());
+    }
+
     #[test]
     fn email_message_keep_bcc() {
         // Tue, 15 Nov 1994 08:12:31 GMT
@@ -730,6 +1435,89 @@ mod test {
         );
     }
 
+    #[test]
+    fn email_max_header_recipients() {
+        let date = SystemTime::UNIX_EPOCH + Duration::from_secs(784887151);
+        let builder = Message::builder()
+            .date(date)
+            .from("nobody@domain.tld".parse().unwrap())
+            .to("hei@domain.tld".parse().unwrap())
+            .to("yuin@domain.tld".parse().unwrap())
+            .cc("kayo@domain.tld".parse().unwrap())
+            .bcc("hidden@domain.tld".parse().unwrap());
+
+        let email = builder
+            .clone()
+            .max_header_recipients(1)
+            .body(String::from("Happy new year!"))
+            .unwrap();
+        assert_eq!(email.envelope().to().len(), 4);
+        assert_eq!(email.headers().get_raw("To"), Some("hei@domain.tld"));
+        assert_eq!(email.headers().get_raw("Cc"), None);
+
+        let email = builder
+            .clone()
+            .max_header_recipients(3)
+            .body(String::from("Happy new year!"))
+            .unwrap();
+        assert_eq!(
+            email.headers().get_raw("To"),
+            Some("hei@domain.tld, yuin@domain.tld")
+        );
+        assert_eq!(email.headers().get_raw("Cc"), Some("kayo@domain.tld"));
+
+        let email = builder
+            .max_header_recipients(0)
+            .body(String::from("Happy new year!"))
+            .unwrap();
+        assert_eq!(email.envelope().to().len(), 4);
+        assert_eq!(
+            String::from_utf8(email.formatted()).unwrap(),
+            concat!(
+                "Date: Tue, 15 Nov 1994 08:12:31 +0000\r\n",
+                "From: nobody@domain.tld\r\n",
+                "To: undisclosed-recipients:;\r\n",
+                "Content-Transfer-Encoding: 7bit\r\n",
+                "\r\n",
+                "Happy new year!"
+            )
+        );
+    }
+
+    #[test]
+    fn email_local_copies() {
+        let email = Message::builder()
+            .date(SystemTime::UNIX_EPOCH + Duration::from_secs(784887151))
+            .from("nobody@domain.tld".parse().unwrap())
+            .to("hei@domain.tld".parse().unwrap())
+            .body(String::from("Happy new year!"))
+            .unwrap();
+
+        let dir = std::env::temp_dir().join(format!("lettre-local-copies-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let eml = dir.join("email.eml");
+        let mbox = dir.join("sent.mbox");
+
+        email.write_eml(&eml).unwrap();
+        assert_eq!(std::fs::read(&eml).unwrap(), email.formatted());
+
+        email.append_mbox(&mbox).unwrap();
+        email.append_mbox(&mbox).unwrap();
+        let entry = concat!(
+            "From nobody@domain.tld Tue Nov 15 08:12:31 1994\n",
+            "Date: Tue, 15 Nov 1994 08:12:31 +0000\n",
+            "From: nobody@domain.tld\n",
+            "To: hei@domain.tld\n",
+            "Content-Transfer-Encoding: 7bit\n",
+            "\n",
+            "Happy new year!\n",
+            "\n",
+        );
+        assert_eq!(std::fs::read_to_string(&mbox).unwrap(), entry.repeat(2));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
     #[test]
     fn email_with_png() {
         // Tue, 15 Nov 1994 08:12:31 GMT
@@ -788,4 +1576,67 @@ mod test {
             assert_eq!(36, id.len());
         }
     }
+
+    #[test]
+    fn email_sanitize_headers() {
+        let builder = Message::builder()
+            .from("NoBody <nobody@domain.tld>".parse().unwrap())
+            .to("NoBody <nobody@domain.tld>".parse().unwrap());
+
+        let email = builder
+            .clone()
+            .sanitize_headers(HeaderSanitization::Strip)
+            .subject("Hello!\r\nBcc: victim@domain.tld\0")
+            .body(String::from("Happy new year!"))
+            .unwrap();
+        assert_eq!(
+            email.headers().get::<header::Subject>().unwrap(),
+            header::Subject::from(String::from("Hello!Bcc: victim@domain.tld"))
+        );
+
+        // A header which can't be parsed back once stripped
+        #[derive(Clone)]
+        struct Strict(String);
+
+        impl Header for Strict {
+            fn name() -> HeaderName {
+                HeaderName::new_from_ascii_str("X-Strict")
+            }
+
+            fn parse(_: &str) -> Result<Self, BoxError> {
+                Err("never parses".into())
+            }
+
+            fn display(&self) -> HeaderValue {
+                HeaderValue::new(Self::name(), self.0.clone())
+            }
+        }
+
+        let email = builder
+            .clone()
+            .sanitize_headers(HeaderSanitization::Strip)
+            .header(Strict(String::from("Hello!\r\nBcc: victim@domain.tld")))
+            .body(String::from("Happy new year!"))
+            .unwrap();
+        assert_eq!(
+            email.headers().get_raw("X-Strict"),
+            Some("Hello!Bcc: victim@domain.tld")
+        );
+
+        let err = builder
+            .clone()
+            .try_subject("Hello!\r\nBcc: victim@domain.tld")
+            .unwrap_err();
+        assert_eq!(err.name(), &"Subject");
+
+        let email = builder
+            .try_subject("Hello!\tHappy new year")
+            .unwrap()
+            .body(String::from("Happy new year!"))
+            .unwrap();
+        assert_eq!(
+            email.headers().get::<header::Subject>().unwrap(),
+            header::Subject::from(String::from("Hello!\tHappy new year"))
+        );
+    }
 }
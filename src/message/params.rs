@@ -0,0 +1,257 @@
+//! Plain structs describing simple emails, for services receiving them as JSON
+
+use super::{header::ContentType, Attachment, Mailbox, Message, MultiPart, SinglePart};
+use crate::error::Error as EmailError;
+
+/// The content of a simple email, with text and HTML bodies and attachments
+///
+/// With the `serde` feature, it can be deserialized from the requests of a
+/// web service and converted into a [`Message`], without mapping each field
+/// to the [`MessageBuilder`](super::MessageBuilder):
+///
+/// ```rust
+/// # #[cfg(feature = "serde")]
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// use lettre::message::{EmailParams, Message};
+///
+/// let params: EmailParams = serde_json::from_str(
+///     r#"{
+///         "from": "NoBody <nobody@domain.tld>",
+///         "to": ["Hei <hei@domain.tld>"],
+///         "subject": "Happy new year",
+///         "text": "Be happy!",
+///         "html": "<p>Be happy!</p>",
+///         "attachments": [
+///             {"filename": "card.txt", "content_type": "text/plain", "content": "SGFwcHkh"}
+///         ]
+///     }"#,
+/// )?;
+/// let email = Message::try_from(params)?;
+/// # Ok(())
+/// # }
+/// # #[cfg(not(feature = "serde"))]
+/// # fn main() {}
+/// ```
+///
+/// The bodies are sent as a `multipart/alternative` when both are set,
+/// inside a `multipart/mixed` with the attachments if there are any.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EmailParams {
+    /// The `From` mailbox
+    pub from: Mailbox,
+    /// The `Reply-To` mailbox
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub reply_to: Option<Mailbox>,
+    /// The `To` mailboxes
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub to: Vec<Mailbox>,
+    /// The `Cc` mailboxes
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub cc: Vec<Mailbox>,
+    /// The `Bcc` mailboxes
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub bcc: Vec<Mailbox>,
+    /// The subject
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub subject: String,
+    /// The plain text body
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub text: Option<String>,
+    /// The HTML body
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub html: Option<String>,
+    /// The attachments
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub attachments: Vec<AttachmentParams>,
+}
+
+/// An attachment of [`EmailParams`]
+///
+/// The content is serialized in base64.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AttachmentParams {
+    /// The file name
+    pub filename: String,
+    /// The content type
+    pub content_type: ContentType,
+    /// The content
+    #[cfg_attr(feature = "serde", serde(with = "base64_content"))]
+    pub content: Vec<u8>,
+}
+
+impl TryFrom<EmailParams> for Message {
+    type Error = EmailError;
+
+    fn try_from(params: EmailParams) -> Result<Self, Self::Error> {
+        let mut builder = Message::builder().from(params.from).subject(params.subject);
+        if let Some(reply_to) = params.reply_to {
+            builder = builder.reply_to(reply_to);
+        }
+        for mbox in params.to {
+            builder = builder.to(mbox);
+        }
+        for mbox in params.cc {
+            builder = builder.cc(mbox);
+        }
+        for mbox in params.bcc {
+            builder = builder.bcc(mbox);
+        }
+
+        let body = match (params.text, params.html) {
+            (Some(text), Some(html)) => Some(Body::Alternative(MultiPart::alternative_plain_html(
+                text, html,
+            ))),
+            (Some(text), None) => Some(Body::Single(SinglePart::plain(text))),
+            (None, Some(html)) => Some(Body::Single(SinglePart::html(html))),
+            (None, None) => None,
+        };
+
+        if params.attachments.is_empty() {
+            return match body {
+                Some(Body::Alternative(body)) => builder.multipart(body),
+                Some(Body::Single(body)) => builder.singlepart(body),
+                None => builder.singlepart(SinglePart::plain(String::new())),
+            };
+        }
+
+        let mut mixed = MultiPart::mixed().build();
+        match body {
+            Some(Body::Alternative(body)) => mixed = mixed.multipart(body),
+            Some(Body::Single(body)) => mixed = mixed.singlepart(body),
+            None => {}
+        }
+        for attachment in params.attachments {
+            mixed = mixed.singlepart(
+                Attachment::new(attachment.filename)
+                    .body(attachment.content, attachment.content_type),
+            );
+        }
+        builder.multipart(mixed)
+    }
+}
+
+/// The bodies of [`EmailParams`]
+enum Body {
+    Single(SinglePart),
+    Alternative(MultiPart),
+}
+
+#[cfg(feature = "serde")]
+mod base64_content {
+    use serde::{de::Error as _, Deserialize, Deserializer, Serializer};
+
+    pub(super) fn serialize<S: Serializer>(
+        content: &[u8],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&crate::base64::encode(content))
+    }
+
+    pub(super) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<u8>, D::Error> {
+        let encoded = String::deserialize(deserializer)?;
+        crate::base64::decode(encoded).map_err(D::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use super::{AttachmentParams, EmailParams};
+    use crate::message::{
+        header::{ContentType, Header},
+        Message,
+    };
+
+    fn params() -> EmailParams {
+        EmailParams {
+            from: "NoBody <nobody@domain.tld>".parse().unwrap(),
+            reply_to: None,
+            to: vec!["Hei <hei@domain.tld>".parse().unwrap()],
+            cc: Vec::new(),
+            bcc: Vec::new(),
+            subject: "Happy new year".to_owned(),
+            text: Some("Be happy!".to_owned()),
+            html: None,
+            attachments: Vec::new(),
+        }
+    }
+
+    fn content_types(message: &Message) -> Vec<String> {
+        message
+            .parts()
+            .filter_map(|part| part.content_type())
+            .map(|content_type| {
+                let value = content_type.display();
+                value.get_raw().split(';').next().unwrap().to_owned()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn text_only() {
+        let message = Message::try_from(params()).unwrap();
+        assert_eq!(content_types(&message), ["text/plain"]);
+        assert_eq!(message.envelope().to().len(), 1);
+    }
+
+    #[test]
+    fn bodies_and_attachments() {
+        let mut params = params();
+        params.html = Some("<p>Be happy!</p>".to_owned());
+        params.bcc = vec!["hidden@domain.tld".parse().unwrap()];
+        params.attachments.push(AttachmentParams {
+            filename: "card.txt".to_owned(),
+            content_type: ContentType::TEXT_PLAIN,
+            content: b"Happy!".to_vec(),
+        });
+
+        let message = Message::try_from(params).unwrap();
+        assert_eq!(
+            content_types(&message),
+            [
+                "multipart/mixed",
+                "multipart/alternative",
+                "text/plain",
+                "text/html",
+                "text/plain"
+            ]
+        );
+        assert_eq!(message.envelope().to().len(), 2);
+    }
+
+    #[test]
+    fn missing_recipients() {
+        let mut params = params();
+        params.to.clear();
+        assert!(Message::try_from(params).is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn deserialize() {
+        let params: EmailParams = serde_json::from_str(
+            r#"{
+                "from": "NoBody <nobody@domain.tld>",
+                "to": ["Hei <hei@domain.tld>"],
+                "subject": "Happy new year",
+                "text": "Be happy!",
+                "attachments": [
+                    {"filename": "card.txt", "content_type": "text/plain", "content": "SGFwcHkh"}
+                ]
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(params.attachments[0].content, b"Happy!");
+        assert_eq!(params.html, None);
+
+        let json = serde_json::to_string(&params).unwrap();
+        assert!(json.contains(r#""content":"SGFwcHkh""#));
+        let round_trip: EmailParams = serde_json::from_str(&json).unwrap();
+        assert_eq!(round_trip, params);
+    }
+}
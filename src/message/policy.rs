@@ -0,0 +1,351 @@
+use std::{
+    error::Error as StdError,
+    fmt::{self, Display, Formatter},
+};
+
+use super::{
+    header::{ContentDisposition, ContentType},
+    Message, MessageBody, Part, SinglePart,
+};
+
+/// Limits on the attachments and size of a message
+///
+/// Checked with [`AttachmentPolicy::check`], usually right after building
+/// the message, or enforced by the builder itself with
+/// [`MessageBuilder::policy`], so that applications can refuse oversized
+/// or dangerous messages before they reach the transport. All sizes are
+/// those of the encoded content, as it will be sent.
+///
+/// [`MessageBuilder::policy`]: crate::message::MessageBuilder::policy
+///
+/// ```rust
+/// use lettre::message::{header::ContentType, Attachment, AttachmentPolicy, Message, MultiPart};
+///
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// let policy = AttachmentPolicy::new()
+///     .max_attachment_size(10 * 1024 * 1024)
+///     .ban_extension("exe");
+///
+/// let message = Message::builder()
+///     .from("NoBody <nobody@domain.tld>".parse()?)
+///     .to("Hei <hei@domain.tld>".parse()?)
+///     .multipart(
+///         MultiPart::mixed().singlepart(
+///             Attachment::new(String::from("setup.exe"))
+///                 .body(vec![0; 16], ContentType::parse("application/octet-stream")?),
+///         ),
+///     )?;
+/// assert!(policy.check(&message).is_err());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct AttachmentPolicy {
+    max_attachment_size: Option<usize>,
+    max_message_size: Option<usize>,
+    banned_content_types: Vec<String>,
+    banned_extensions: Vec<String>,
+}
+
+impl AttachmentPolicy {
+    /// Create a new policy which doesn't restrict anything
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Maximum size of a single attachment, in bytes
+    ///
+    /// Attachments are the parts having a `Content-Disposition` header.
+    pub fn max_attachment_size(mut self, max_attachment_size: usize) -> Self {
+        self.max_attachment_size = Some(max_attachment_size);
+        self
+    }
+
+    /// Maximum size of the whole formatted message, in bytes
+    pub fn max_message_size(mut self, max_message_size: usize) -> Self {
+        self.max_message_size = Some(max_message_size);
+        self
+    }
+
+    /// Refuse parts of the given content type
+    ///
+    /// Takes a `type/subtype` pair, like `application/x-msdownload`,
+    /// or `type/*` to ban a whole top level type.
+    pub fn ban_content_type(mut self, content_type: &str) -> Self {
+        self.banned_content_types
+            .push(content_type.to_ascii_lowercase());
+        self
+    }
+
+    /// Refuse attachments whose file name has the given extension
+    ///
+    /// The extension is matched case insensitively and without the leading dot.
+    pub fn ban_extension(mut self, extension: &str) -> Self {
+        self.banned_extensions
+            .push(extension.trim_start_matches('.').to_ascii_lowercase());
+        self
+    }
+
+    /// Check `message` against this policy
+    pub fn check(&self, message: &Message) -> Result<(), PolicyError> {
+        if let MessageBody::Mime(part) = &message.body {
+            self.check_part(part)?;
+        }
+
+        if let Some(max) = self.max_message_size {
+            let size = message.formatted().len();
+            if size > max {
+                return Err(PolicyError::MessageTooLarge { size, max });
+            }
+        }
+
+        Ok(())
+    }
+
+    fn check_part(&self, part: &Part) -> Result<(), PolicyError> {
+        match part {
+            Part::Single(part) => self.check_single_part(part),
+            Part::Multi(multi) => multi
+                .parts()
+                .iter()
+                .try_for_each(|part| self.check_part(part)),
+        }
+    }
+
+    fn check_single_part(&self, part: &SinglePart) -> Result<(), PolicyError> {
+        let disposition = part.headers().get::<ContentDisposition>();
+        let file_name = disposition
+            .as_ref()
+            .and_then(|disposition| disposition.file_name().map(str::to_owned));
+
+        if let Some(content_type) = part.headers().get::<ContentType>() {
+            let mime = content_type.as_ref();
+            let essence = mime.essence_str().to_ascii_lowercase();
+            let wildcard = format!("{}/*", mime.type_().as_str().to_ascii_lowercase());
+            if self
+                .banned_content_types
+                .iter()
+                .any(|banned| *banned == essence || *banned == wildcard)
+            {
+                return Err(PolicyError::BannedAttachment {
+                    file_name,
+                    reason: format!("content type {essence} is not allowed"),
+                });
+            }
+        }
+
+        if let Some(extension) = file_name
+            .as_deref()
+            .and_then(|file_name| file_name.rsplit_once('.'))
+            .map(|(_, extension)| extension.to_ascii_lowercase())
+        {
+            if self.banned_extensions.contains(&extension) {
+                return Err(PolicyError::BannedAttachment {
+                    file_name,
+                    reason: format!("extension .{extension} is not allowed"),
+                });
+            }
+        }
+
+        if let (Some(max), Some(_)) = (self.max_attachment_size, &disposition) {
+            let size = part.raw_body().len();
+            if size > max {
+                return Err(PolicyError::AttachmentTooLarge {
+                    file_name,
+                    size,
+                    max,
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Error returned when a message doesn't comply with an [`AttachmentPolicy`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum PolicyError {
+    /// An attachment is bigger than the policy allows
+    AttachmentTooLarge {
+        /// File name of the attachment, if any
+        file_name: Option<String>,
+        /// Encoded size of the attachment
+        size: usize,
+        /// Maximum allowed size
+        max: usize,
+    },
+    /// The message is bigger than the policy allows
+    MessageTooLarge {
+        /// Formatted size of the message
+        size: usize,
+        /// Maximum allowed size
+        max: usize,
+    },
+    /// An attachment is refused by the policy
+    BannedAttachment {
+        /// File name of the attachment, if any
+        file_name: Option<String>,
+        /// Why the attachment was refused
+        reason: String,
+    },
+}
+
+impl Display for PolicyError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            PolicyError::AttachmentTooLarge {
+                file_name: Some(file_name),
+                size,
+                max,
+            } => write!(
+                f,
+                "attachment \"{file_name}\" is {size} bytes, exceeding the limit of {max} bytes"
+            ),
+            PolicyError::AttachmentTooLarge {
+                file_name: None,
+                size,
+                max,
+            } => write!(
+                f,
+                "attachment is {size} bytes, exceeding the limit of {max} bytes"
+            ),
+            PolicyError::MessageTooLarge { size, max } => write!(
+                f,
+                "message is {size} bytes, exceeding the limit of {max} bytes"
+            ),
+            PolicyError::BannedAttachment {
+                file_name: Some(file_name),
+                reason,
+            } => write!(f, "attachment \"{file_name}\" is not allowed: {reason}"),
+            PolicyError::BannedAttachment {
+                file_name: None,
+                reason,
+            } => write!(f, "attachment is not allowed: {reason}"),
+        }
+    }
+}
+
+impl StdError for PolicyError {}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use super::{AttachmentPolicy, PolicyError};
+    use crate::{
+        message::{
+            header::ContentType, Attachment, Message, MessageBuilder, MultiPart, SinglePart,
+        },
+        Error,
+    };
+
+    fn builder() -> MessageBuilder {
+        Message::builder()
+            .from("NoBody <nobody@domain.tld>".parse().unwrap())
+            .to("Hei <hei@domain.tld>".parse().unwrap())
+    }
+
+    fn attachment(file_name: &str, content_type: &str, len: usize) -> SinglePart {
+        Attachment::new(file_name.to_owned())
+            .body(vec![0; len], ContentType::parse(content_type).unwrap())
+    }
+
+    #[test]
+    fn attachment_too_large() {
+        let policy = AttachmentPolicy::new().max_attachment_size(100);
+
+        let message = builder()
+            .multipart(
+                MultiPart::mixed()
+                    .singlepart(SinglePart::plain("x".repeat(200)))
+                    .singlepart(attachment("small.bin", "application/octet-stream", 60)),
+            )
+            .unwrap();
+        assert_eq!(policy.check(&message), Ok(()));
+
+        let message = builder()
+            .multipart(
+                MultiPart::mixed()
+                    .singlepart(attachment("small.bin", "application/octet-stream", 60))
+                    .singlepart(attachment("big.bin", "application/octet-stream", 120)),
+            )
+            .unwrap();
+        let err = policy.check(&message).unwrap_err();
+        assert_eq!(
+            err,
+            PolicyError::AttachmentTooLarge {
+                file_name: Some("big.bin".to_owned()),
+                size: 164,
+                max: 100
+            }
+        );
+        assert_eq!(
+            err.to_string(),
+            "attachment \"big.bin\" is 164 bytes, exceeding the limit of 100 bytes"
+        );
+    }
+
+    #[test]
+    fn message_too_large() {
+        let policy = AttachmentPolicy::new().max_message_size(500);
+
+        let message = builder().body(String::from("Hello")).unwrap();
+        assert_eq!(policy.check(&message), Ok(()));
+        let message = builder().body("Hello".repeat(100)).unwrap();
+        assert!(matches!(
+            policy.check(&message),
+            Err(PolicyError::MessageTooLarge { max: 500, .. })
+        ));
+    }
+
+    #[test]
+    fn banned_attachments() {
+        let policy = AttachmentPolicy::new()
+            .ban_extension(".EXE")
+            .ban_content_type("application/x-msdownload")
+            .ban_content_type("video/*");
+        let check = |part: SinglePart| policy.check(&builder().singlepart(part).unwrap());
+
+        let err = check(attachment("setup.Exe", "application/octet-stream", 10)).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "attachment \"setup.Exe\" is not allowed: extension .exe is not allowed"
+        );
+
+        let err = check(attachment("setup", "application/x-msdownload", 10)).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "attachment \"setup\" is not allowed: content type application/x-msdownload is not allowed"
+        );
+
+        assert!(matches!(
+            check(attachment("clip.mp4", "video/mp4", 10)),
+            Err(PolicyError::BannedAttachment { .. })
+        ));
+        assert_eq!(check(attachment("notes.txt", "text/plain", 10)), Ok(()));
+    }
+
+    #[test]
+    fn builder_policy() {
+        let policy = AttachmentPolicy::new().ban_extension("exe");
+
+        assert!(builder()
+            .policy(policy.clone())
+            .singlepart(attachment("notes.txt", "text/plain", 10))
+            .is_ok());
+        let err = builder()
+            .policy(policy)
+            .singlepart(attachment("setup.exe", "application/octet-stream", 10))
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            Error::Policy(PolicyError::BannedAttachment { .. })
+        ));
+        assert_eq!(
+            err.to_string(),
+            "attachment \"setup.exe\" is not allowed: extension .exe is not allowed"
+        );
+    }
+}
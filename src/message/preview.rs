@@ -0,0 +1,294 @@
+//! Plain text previews of message bodies
+
+use std::iter;
+
+use super::{
+    body,
+    header::{ContentDisposition, ContentTransferEncoding, ContentType, Headers},
+    Message, MessageBody, Part, SinglePart,
+};
+
+/// Build a plain text preview of `message`, see [`Message::plain_text_preview`]
+pub(super) fn plain_text_preview(message: &Message, max_chars: usize) -> Option<String> {
+    let text = match &message.body {
+        MessageBody::Raw(raw) => match text_subtype(&message.headers)? {
+            TextSubtype::Plain => decode_text(&message.headers, raw)?,
+            TextSubtype::Html => strip_html(&decode_text(&message.headers, raw)?),
+        },
+        MessageBody::Mime(part) => find_text_part(part, TextSubtype::Plain)
+            .and_then(|plain| decode_text(plain.headers(), plain.raw_body()))
+            .or_else(|| {
+                let html = find_text_part(part, TextSubtype::Html)?;
+                Some(strip_html(&decode_text(html.headers(), html.raw_body())?))
+            })?,
+    };
+
+    Some(
+        text.split_whitespace()
+            .flat_map(|word| iter::once(' ').chain(word.chars()))
+            .skip(1)
+            .take(max_chars)
+            .collect(),
+    )
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum TextSubtype {
+    Plain,
+    Html,
+}
+
+/// The kind of text contained in a body with `headers`, if any
+pub(super) fn text_subtype(headers: &Headers) -> Option<TextSubtype> {
+    if headers
+        .get::<ContentDisposition>()
+        .is_some_and(|disposition| disposition.is_attachment())
+    {
+        return None;
+    }
+
+    // RFC2045 defaults to `text/plain` when `Content-Type` is missing
+    let Some(content_type) = headers.get::<ContentType>() else {
+        return Some(TextSubtype::Plain);
+    };
+    let mime = content_type.as_ref();
+    match (mime.type_(), mime.subtype()) {
+        (mime::TEXT, mime::PLAIN) => Some(TextSubtype::Plain),
+        (mime::TEXT, mime::HTML) => Some(TextSubtype::Html),
+        _ => None,
+    }
+}
+
+/// Find the first non-attachment part of the wanted subtype, depth first
+fn find_text_part(part: &Part, subtype: TextSubtype) -> Option<&SinglePart> {
+    match part {
+        Part::Single(single) if text_subtype(single.headers()) == Some(subtype) => Some(single),
+        Part::Single(_) => None,
+        Part::Multi(multi) => multi
+            .parts()
+            .iter()
+            .find_map(|part| find_text_part(part, subtype)),
+    }
+}
+
+/// Decode the text of a body with `headers`, according to its charset
+///
+/// Only UTF-8, US-ASCII and ISO-8859-1 are supported, `None` is returned
+/// for other charsets and for invalid text.
+fn decode_text(headers: &Headers, raw: &[u8]) -> Option<String> {
+    let encoding = headers
+        .get::<ContentTransferEncoding>()
+        .unwrap_or(ContentTransferEncoding::SevenBit);
+    let decoded = body::decode(raw, encoding)?;
+
+    // RFC2045 defaults to US-ASCII when the charset is missing
+    let charset = headers.get::<ContentType>().and_then(|content_type| {
+        let charset = content_type.as_ref().get_param(mime::CHARSET)?;
+        Some(charset.as_str().to_ascii_lowercase())
+    });
+    match charset.as_deref() {
+        None | Some("utf-8" | "utf8" | "us-ascii") => String::from_utf8(decoded).ok(),
+        Some("iso-8859-1" | "latin1") => Some(decoded.iter().map(|&b| char::from(b)).collect()),
+        Some(_) => None,
+    }
+}
+
+/// Crudely turn HTML into text, dropping tags, comments and
+/// the contents of `head`, `script` and `style` elements
+fn strip_html(html: &str) -> String {
+    // ASCII lowercasing keeps byte offsets identical
+    let lower = html.to_ascii_lowercase();
+
+    let mut text = String::with_capacity(html.len());
+    let mut pos = 0;
+    while let Some(start) = lower[pos..].find('<').map(|i| pos + i) {
+        text.push_str(&html[pos..start]);
+        text.push(' ');
+
+        let tag = &lower[start..];
+        if tag.starts_with("<!--") {
+            pos = tag
+                .find("-->")
+                .map_or(lower.len(), |end| start + end + "-->".len());
+            continue;
+        }
+
+        let tag_end = tag.find('>').map_or(lower.len(), |end| start + end + 1);
+        let name = tag[1..]
+            .split(|c: char| c.is_ascii_whitespace() || c == '>' || c == '/')
+            .next()
+            .unwrap_or_default();
+        pos = match name {
+            "head" | "script" | "style" => {
+                let closing = format!("</{name}");
+                lower[tag_end..]
+                    .find(&closing)
+                    .and_then(|end| {
+                        lower[tag_end + end..]
+                            .find('>')
+                            .map(|gt| tag_end + end + gt + 1)
+                    })
+                    .unwrap_or(lower.len())
+            }
+            _ => tag_end,
+        };
+    }
+    text.push_str(&html[pos..]);
+
+    decode_entities(&text)
+}
+
+fn decode_entities(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+
+        let entity = rest[1..]
+            .find(';')
+            .filter(|&end| end <= 10)
+            .and_then(|end| Some((decode_entity(&rest[1..=end])?, end + 2)));
+        match entity {
+            Some((c, len)) => {
+                out.push(c);
+                rest = &rest[len..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+fn decode_entity(name: &str) -> Option<char> {
+    match name {
+        "amp" => Some('&'),
+        "lt" => Some('<'),
+        "gt" => Some('>'),
+        "quot" => Some('"'),
+        "apos" => Some('\''),
+        "nbsp" => Some('\u{a0}'),
+        _ => {
+            let code = name.strip_prefix('#')?;
+            let code = match code.strip_prefix(['x', 'X']) {
+                Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                None => code.parse().ok()?,
+            };
+            char::from_u32(code)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use super::strip_html;
+    use crate::message::{header::ContentType, Attachment, Message, MultiPart, SinglePart};
+
+    fn builder() -> crate::message::MessageBuilder {
+        Message::builder()
+            .from("NoBody <nobody@domain.tld>".parse().unwrap())
+            .to("Hei <hei@domain.tld>".parse().unwrap())
+    }
+
+    #[test]
+    fn preview_plain_body() {
+        let message = builder()
+            .body(String::from("Hello,\r\n\r\n  this is   a test.\r\n"))
+            .unwrap();
+
+        assert_eq!(
+            message.plain_text_preview(100).as_deref(),
+            Some("Hello, this is a test.")
+        );
+        assert_eq!(message.plain_text_preview(8).as_deref(), Some("Hello, t"));
+    }
+
+    #[test]
+    fn preview_prefers_plain_part() {
+        let message = builder()
+            .multipart(
+                MultiPart::mixed()
+                    .multipart(MultiPart::alternative_plain_html(
+                        "Плain текст ".repeat(20),
+                        String::from("<p>HTML</p>"),
+                    ))
+                    .singlepart(
+                        Attachment::new(String::from("notes.txt"))
+                            .body(String::from("attached"), ContentType::TEXT_PLAIN),
+                    ),
+            )
+            .unwrap();
+
+        assert_eq!(
+            message.plain_text_preview(25).as_deref(),
+            Some("Плain текст Плain текст П")
+        );
+    }
+
+    #[test]
+    fn preview_falls_back_to_html() {
+        let message = builder()
+            .singlepart(SinglePart::html(String::from(
+                "<html><head><title>Ignored</title></head><body>\
+                 <style>p { color: red; }</style><!-- <p>hidden</p> -->\
+                 <p>Fish&nbsp;&amp;&#32;chips</p><SCRIPT>alert(1)</SCRIPT><p>&#x2714; done</p>\
+                 </body></html>",
+            )))
+            .unwrap();
+
+        assert_eq!(
+            message.plain_text_preview(100).as_deref(),
+            Some("Fish & chips ✔ done")
+        );
+    }
+
+    #[test]
+    fn preview_charsets() {
+        let part = |charset: &str, body: &[u8]| {
+            SinglePart::builder()
+                .header(ContentType::parse(&format!("text/plain; charset={charset}")).unwrap())
+                .body(body.to_vec())
+        };
+
+        let message = builder()
+            .singlepart(part("ISO-8859-1", b"Gr\xfc\xdfe"))
+            .unwrap();
+        assert_eq!(message.plain_text_preview(100).as_deref(), Some("Grüße"));
+
+        // Unsupported charsets are skipped
+        let message = builder()
+            .multipart(
+                MultiPart::alternative()
+                    .singlepart(part("shift_jis", b"\x82\xb1\x82\xf1"))
+                    .singlepart(SinglePart::html(String::from("<p>Hello</p>"))),
+            )
+            .unwrap();
+        assert_eq!(message.plain_text_preview(100).as_deref(), Some("Hello"));
+
+        let message = builder().singlepart(part("utf-8", b"\xff")).unwrap();
+        assert_eq!(message.plain_text_preview(100), None);
+    }
+
+    #[test]
+    fn preview_without_text() {
+        let message = builder()
+            .singlepart(
+                Attachment::new(String::from("notes.txt"))
+                    .body(String::from("attached"), ContentType::TEXT_PLAIN),
+            )
+            .unwrap();
+
+        assert_eq!(message.plain_text_preview(100), None);
+    }
+
+    #[test]
+    fn strip_html_unterminated() {
+        assert_eq!(strip_html("a <b>b</b> &amp c <script>x"), "a  b  &amp c  ");
+    }
+}
@@ -0,0 +1,253 @@
+//! Resending previously delivered messages
+
+use std::time::SystemTime;
+
+use super::{
+    header::{
+        Headers, ResentBcc, ResentCc, ResentDate, ResentFrom, ResentMessageId, ResentSender,
+        ResentTo,
+    },
+    make_message_id, message_id_domain, Mailbox, Mailboxes,
+};
+use crate::{address::Envelope, Address, Error};
+
+/// A block of `Resent-*` headers
+///
+/// Describes a message being resent by a user, like when forwarding it
+/// unchanged, as defined in [RFC5322](https://tools.ietf.org/html/rfc5322#section-3.6.6).
+/// Set it with [`MessageBuilder::resent`](super::MessageBuilder::resent):
+/// the block is added at the top of the headers, before any previous
+/// block, with a `Resent-Date` and a `Resent-Message-ID` generated if
+/// missing, and the message is sent to the resent recipients instead of
+/// the original ones.
+///
+/// ```rust
+/// use lettre::message::{Message, Resent};
+///
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// let resent = Resent::new("Forwarder <forwarder@example.com>".parse()?)
+///     .to("Archive <archive@example.com>".parse()?);
+///
+/// let email = Message::builder()
+///     .from("NoBody <nobody@domain.tld>".parse()?)
+///     .to("Hei <hei@domain.tld>".parse()?)
+///     .subject("Happy new year")
+///     .resent(resent)
+///     .body(String::from("Be happy!"))?;
+///
+/// assert_eq!(email.envelope().to(), ["archive@example.com".parse()?]);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Resent {
+    from: Mailboxes,
+    sender: Option<Mailbox>,
+    to: Option<Mailboxes>,
+    cc: Option<Mailboxes>,
+    bcc: Option<Mailboxes>,
+    date: Option<ResentDate>,
+    message_id: Option<String>,
+}
+
+impl Resent {
+    /// Creates a block for a message resent by `from`
+    pub fn new(from: Mailbox) -> Self {
+        Self {
+            from: from.into(),
+            sender: None,
+            to: None,
+            cc: None,
+            bcc: None,
+            date: None,
+            message_id: None,
+        }
+    }
+
+    /// Add a mailbox to `Resent-From`
+    ///
+    /// A `Resent-Sender` is required when there are several of them.
+    pub fn from(mut self, mbox: Mailbox) -> Self {
+        self.from.push(mbox);
+        self
+    }
+
+    /// Set `Resent-Sender`
+    pub fn sender(mut self, mbox: Mailbox) -> Self {
+        self.sender = Some(mbox);
+        self
+    }
+
+    /// Add a mailbox to `Resent-To`
+    pub fn to(mut self, mbox: Mailbox) -> Self {
+        self.to.get_or_insert_with(Mailboxes::new).push(mbox);
+        self
+    }
+
+    /// Add a mailbox to `Resent-Cc`
+    pub fn cc(mut self, mbox: Mailbox) -> Self {
+        self.cc.get_or_insert_with(Mailboxes::new).push(mbox);
+        self
+    }
+
+    /// Add a mailbox to `Resent-Bcc`
+    ///
+    /// Like `Bcc`, it's removed from the message unless
+    /// [`MessageBuilder::keep_bcc`](super::MessageBuilder::keep_bcc) is set.
+    pub fn bcc(mut self, mbox: Mailbox) -> Self {
+        self.bcc.get_or_insert_with(Mailboxes::new).push(mbox);
+        self
+    }
+
+    /// Set `Resent-Date`, the current date of the builder's [`Clock`](super::Clock) by default
+    pub fn date(mut self, date: ResentDate) -> Self {
+        self.date = Some(date);
+        self
+    }
+
+    /// Set `Resent-Message-ID`, generated like `Message-ID` by default
+    pub fn message_id(mut self, id: String) -> Self {
+        self.message_id = Some(id);
+        self
+    }
+
+    /// The envelope resending the message to the resent recipients
+    pub(super) fn envelope(&self) -> Result<Envelope, Error> {
+        let from = match &self.sender {
+            Some(sender) => sender.email.clone(),
+            None => self
+                .from
+                .iter()
+                .next()
+                .expect("Resent-From has a mailbox")
+                .email
+                .clone(),
+        };
+        let to = [&self.to, &self.cc, &self.bcc]
+            .into_iter()
+            .flatten()
+            .flat_map(Mailboxes::iter)
+            .map(|mbox| mbox.email.clone())
+            .collect::<Vec<Address>>();
+        Envelope::new(Some(from), to)
+    }
+
+    /// The headers of the block, in the order recommended by RFC5322
+    pub(super) fn into_headers(self, drop_bcc: bool, now: SystemTime) -> Result<Headers, Error> {
+        if self.from.iter().nth(1).is_some() && self.sender.is_none() {
+            return Err(Error::TooManyFrom);
+        }
+
+        let mut headers = Headers::new();
+        headers.set(self.date.unwrap_or_else(|| ResentDate::new(now)));
+        headers.set(ResentFrom::from(self.from));
+        if let Some(sender) = self.sender {
+            headers.set(ResentSender::from(sender));
+        }
+        if let Some(to) = self.to {
+            headers.set(ResentTo::from(to));
+        }
+        if let Some(cc) = self.cc {
+            headers.set(ResentCc::from(cc));
+        }
+        if let Some(bcc) = self.bcc.filter(|_| !drop_bcc) {
+            headers.set(ResentBcc::from(bcc));
+        }
+        let message_id = self
+            .message_id
+            .unwrap_or_else(|| format!("<{}@{}>", make_message_id(), message_id_domain()));
+        headers.set(ResentMessageId::from(message_id));
+        Ok(headers)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::{Duration, SystemTime};
+
+    use pretty_assertions::assert_eq;
+
+    use super::Resent;
+    use crate::message::{
+        header::{self, ResentDate},
+        Message,
+    };
+
+    #[test]
+    fn resent_block() {
+        let date = SystemTime::UNIX_EPOCH + Duration::from_secs(784887151);
+        let resent = Resent::new("Forwarder <forwarder@example.com>".parse().unwrap())
+            .to("archive@example.com".parse().unwrap())
+            .bcc("audit@example.com".parse().unwrap())
+            .date(ResentDate::new(date))
+            .message_id("<resent@example.com>".to_owned());
+
+        let email = Message::builder()
+            .from("nobody@domain.tld".parse().unwrap())
+            .to("hei@domain.tld".parse().unwrap())
+            .date(date)
+            .resent(resent)
+            .body(String::from("Be happy!"))
+            .unwrap();
+
+        let formatted = String::from_utf8(email.formatted()).unwrap();
+        assert!(formatted.starts_with(concat!(
+            "Resent-Date: Tue, 15 Nov 1994 08:12:31 +0000\r\n",
+            "Resent-From: Forwarder <forwarder@example.com>\r\n",
+            "Resent-To: archive@example.com\r\n",
+            "Resent-Message-ID: <resent@example.com>\r\n",
+            "From: nobody@domain.tld\r\n",
+        )));
+        assert_eq!(
+            email.envelope().from().unwrap().to_string(),
+            "forwarder@example.com"
+        );
+        assert_eq!(
+            email.envelope().to(),
+            [
+                "archive@example.com".parse().unwrap(),
+                "audit@example.com".parse().unwrap()
+            ]
+        );
+    }
+
+    #[test]
+    fn resent_defaults() {
+        let email = Message::builder()
+            .from("nobody@domain.tld".parse().unwrap())
+            .to("hei@domain.tld".parse().unwrap())
+            .resent(
+                Resent::new("forwarder@example.com".parse().unwrap())
+                    .to("archive@example.com".parse().unwrap()),
+            )
+            .body(String::from("Be happy!"))
+            .unwrap();
+
+        assert!(email.headers().get::<ResentDate>().is_some());
+        assert!(email.headers().get::<header::ResentMessageId>().is_some());
+    }
+
+    #[test]
+    fn resent_too_many_from() {
+        let resent = Resent::new("first@example.com".parse().unwrap())
+            .from("second@example.com".parse().unwrap())
+            .to("archive@example.com".parse().unwrap());
+        let builder = Message::builder()
+            .from("nobody@domain.tld".parse().unwrap())
+            .to("hei@domain.tld".parse().unwrap());
+
+        assert!(matches!(
+            builder.clone().resent(resent.clone()).body(String::new()),
+            Err(crate::error::Error::TooManyFrom)
+        ));
+        let email = builder
+            .resent(resent.sender("first@example.com".parse().unwrap()))
+            .body(String::new())
+            .unwrap();
+        assert_eq!(
+            email.envelope().from().unwrap().to_string(),
+            "first@example.com"
+        );
+    }
+}
@@ -0,0 +1,189 @@
+//! Defaults shared by the messages built by an application
+
+use std::sync::Arc;
+
+#[cfg(feature = "dkim")]
+use super::dkim::DkimConfig;
+use super::{
+    header::{self, Header, Headers},
+    Clock, Mailbox, Mailboxes, MessageBuilder,
+};
+
+/// Defaults stamped on every message built from it
+///
+/// Holds the headers repeated in all the messages of an application, like
+/// `From`, `Reply-To` or `List-Unsubscribe`, and the DKIM configuration used
+/// to sign them. The headers are only added when the message doesn't set them,
+/// so they can still be overridden.
+///
+/// ```rust
+/// use lettre::message::{header::ContentType, MessageTemplate};
+///
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// let template = MessageTemplate::new()
+///     .from("NoReply <noreply@example.com>".parse()?)
+///     .reply_to("Support <support@example.com>".parse()?)
+///     .user_agent(String::from("MyApp/1.0"));
+///
+/// let email = template
+///     .builder()
+///     .to("Hei <hei@domain.tld>".parse()?)
+///     .subject("Happy new year")
+///     .header(ContentType::TEXT_PLAIN)
+///     .body(String::from("Be happy!"))?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct MessageTemplate {
+    headers: Headers,
+    #[cfg(feature = "dkim")]
+    dkim_config: Option<Arc<DkimConfig>>,
+    clock: Option<Arc<dyn Clock>>,
+}
+
+impl MessageTemplate {
+    /// Creates an empty template
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the default `From` header
+    pub fn from(self, mbox: Mailbox) -> Self {
+        self.header(header::From::from(Mailboxes::from(mbox)))
+    }
+
+    /// Set the default `Reply-To` header
+    pub fn reply_to(self, mbox: Mailbox) -> Self {
+        self.header(header::ReplyTo(mbox.into()))
+    }
+
+    /// Set the default `User-Agent` header
+    pub fn user_agent(self, id: String) -> Self {
+        self.header(header::UserAgent::from(id))
+    }
+
+    /// Set a default header
+    pub fn header<H: Header>(mut self, header: H) -> Self {
+        self.headers.set(header);
+        self
+    }
+
+    /// Sign the messages with `dkim_config`
+    #[cfg(feature = "dkim")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "dkim")))]
+    pub fn dkim(mut self, dkim_config: DkimConfig) -> Self {
+        self.dkim_config = Some(Arc::new(dkim_config));
+        self
+    }
+
+    /// Use `clock` to get the current time of the messages
+    ///
+    /// See [`MessageBuilder::clock`].
+    pub fn clock<C: Clock + 'static>(mut self, clock: C) -> Self {
+        self.clock = Some(Arc::new(clock));
+        self
+    }
+
+    /// Creates a message builder using this template
+    ///
+    /// Shortcut for `MessageBuilder::new().template(self.clone())`.
+    pub fn builder(&self) -> MessageBuilder {
+        MessageBuilder::new().template(self.clone())
+    }
+
+    pub(super) fn headers(&self) -> &Headers {
+        &self.headers
+    }
+
+    pub(super) fn template_clock(&self) -> Option<Arc<dyn Clock>> {
+        self.clock.clone()
+    }
+
+    #[cfg(feature = "dkim")]
+    pub(super) fn dkim_config(&self) -> Option<&DkimConfig> {
+        self.dkim_config.as_deref()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::{Duration, SystemTime};
+
+    use super::MessageTemplate;
+    use crate::message::{
+        header::{self, ListUnsubscribe},
+        FixedClock,
+    };
+
+    #[test]
+    fn template_defaults() {
+        let template = MessageTemplate::new()
+            .from("NoReply <noreply@example.com>".parse().unwrap())
+            .reply_to("Support <support@example.com>".parse().unwrap())
+            .header(ListUnsubscribe::from(String::from(
+                "<https://example.com/unsubscribe>",
+            )));
+
+        let email = template
+            .builder()
+            .to("Hei <hei@domain.tld>".parse().unwrap())
+            .body(String::from("Be happy!"))
+            .unwrap();
+        let formatted = String::from_utf8(email.formatted()).unwrap();
+        assert!(formatted.contains("From: NoReply <noreply@example.com>\r\n"));
+        assert!(formatted.contains("Reply-To: Support <support@example.com>\r\n"));
+        assert!(formatted.contains("List-Unsubscribe: <https://example.com/unsubscribe>\r\n"));
+        assert_eq!(
+            email.envelope().from().unwrap().to_string(),
+            "noreply@example.com"
+        );
+    }
+
+    #[test]
+    fn template_clock() {
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(784887151);
+        let template = MessageTemplate::new()
+            .from("NoReply <noreply@example.com>".parse().unwrap())
+            .clock(FixedClock::new(now));
+
+        let email = template
+            .builder()
+            .to("Hei <hei@domain.tld>".parse().unwrap())
+            .body(String::from("Be happy!"))
+            .unwrap();
+        assert_eq!(
+            email.headers().get::<header::Date>(),
+            Some(header::Date::new(now))
+        );
+
+        // The clock of the builder wins
+        let later = now + Duration::from_secs(60);
+        let email = template
+            .builder()
+            .clock(FixedClock::new(later))
+            .to("Hei <hei@domain.tld>".parse().unwrap())
+            .body(String::from("Be happy!"))
+            .unwrap();
+        assert_eq!(
+            email.headers().get::<header::Date>(),
+            Some(header::Date::new(later))
+        );
+    }
+
+    #[test]
+    fn template_override() {
+        let template =
+            MessageTemplate::new().from("NoReply <noreply@example.com>".parse().unwrap());
+
+        let email = template
+            .builder()
+            .from("Alerts <alerts@example.com>".parse().unwrap())
+            .to("Hei <hei@domain.tld>".parse().unwrap())
+            .body(String::from("Be happy!"))
+            .unwrap();
+        let header::From(from) = email.headers().get::<header::From>().unwrap();
+        assert_eq!(from.to_string(), "Alerts <alerts@example.com>");
+    }
+}
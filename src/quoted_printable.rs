@@ -0,0 +1,182 @@
+//! Quoted-printable encoding of bodies, as defined in
+//! [RFC2045](https://tools.ietf.org/html/rfc2045#section-6.7)
+
+/// Maximum length of the encoded lines, soft line breaks included
+const LINE_LEN: usize = 76;
+
+const HEX_CHARS: &[u8; 16] = b"0123456789ABCDEF";
+
+/// Encodes `input` at the end of `out`
+///
+/// CRLF sequences are kept as line breaks, other lines are wrapped at
+/// 76 characters with soft line breaks. The spaces and tabs ending a line
+/// are encoded, as they may be stripped in transit otherwise.
+pub(crate) fn encode(input: &[u8], out: &mut Vec<u8>) {
+    // Most bodies encoded this way are text with a few non-ASCII characters
+    out.reserve(input.len() + input.len() / 16);
+
+    let mut rest = input;
+    loop {
+        match find_crlf(rest) {
+            Some(end) => {
+                encode_line(&rest[..end], out);
+                out.extend_from_slice(b"\r\n");
+                rest = &rest[end + 2..];
+            }
+            None => {
+                encode_line(rest, out);
+                break;
+            }
+        }
+    }
+}
+
+/// Position of the first CRLF of `input`, ignoring bare LFs
+fn find_crlf(input: &[u8]) -> Option<usize> {
+    let mut from = 0;
+    while let Some(pos) = input[from..].iter().position(|&b| b == b'\n') {
+        let pos = from + pos;
+        if pos > 0 && input[pos - 1] == b'\r' {
+            return Some(pos - 1);
+        }
+        from = pos + 1;
+    }
+    None
+}
+
+/// Encodes a line not containing CRLF, wrapping it with soft line breaks
+fn encode_line(line: &[u8], out: &mut Vec<u8>) {
+    let mut col = 0;
+    let mut i = 0;
+    while i < line.len() {
+        // Copy the run of bytes not needing encoding, keeping room for a soft line break
+        let room = LINE_LEN - 1 - col;
+        let mut run = line[i..]
+            .iter()
+            .take(room)
+            .take_while(|&&b| is_literal(b))
+            .count();
+        if i + run == line.len() && run > 0 && is_whitespace(line[i + run - 1]) {
+            run -= 1;
+        }
+        if run > 0 {
+            out.extend_from_slice(&line[i..i + run]);
+            col += run;
+            i += run;
+            continue;
+        }
+
+        let byte = line[i];
+        let is_last = i + 1 == line.len();
+        let encode = !is_literal(byte) || (is_last && is_whitespace(byte));
+        let len = if encode { 3 } else { 1 };
+        // The last token of the line doesn't need room for a soft line break
+        let fits = if is_last {
+            col + len <= LINE_LEN
+        } else {
+            col + len < LINE_LEN
+        };
+        if !fits {
+            out.extend_from_slice(b"=\r\n");
+            col = 0;
+        }
+        if encode {
+            out.extend_from_slice(&[
+                b'=',
+                HEX_CHARS[usize::from(byte >> 4)],
+                HEX_CHARS[usize::from(byte & 0x0f)],
+            ]);
+        } else {
+            out.push(byte);
+        }
+        col += len;
+        i += 1;
+    }
+}
+
+/// Whether `byte` can be written as is
+fn is_literal(byte: u8) -> bool {
+    matches!(byte, b'\t' | b' '..=b'<' | b'>'..=b'~')
+}
+
+fn is_whitespace(byte: u8) -> bool {
+    matches!(byte, b' ' | b'\t')
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use super::{encode, LINE_LEN};
+
+    fn encoded(input: &[u8]) -> String {
+        let mut out = Vec::new();
+        encode(input, &mut out);
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn trailing_whitespace() {
+        assert_eq!(encoded(b"a \r\nb\t\r\nc \t"), "a=20\r\nb=09\r\nc =09");
+        assert_eq!(encoded(b" \r\n\r\n"), "=20\r\n\r\n");
+        // Followed by a soft line break, it doesn't end the line
+        let line = format!("{} bc", "a".repeat(74));
+        assert_eq!(
+            encoded(line.as_bytes()),
+            format!("{} =\r\nbc", "a".repeat(74))
+        );
+    }
+
+    #[test]
+    fn soft_line_breaks() {
+        let line = "a".repeat(LINE_LEN);
+        assert_eq!(encoded(line.as_bytes()), line);
+        assert_eq!(
+            encoded("a".repeat(LINE_LEN + 1).as_bytes()),
+            format!("{}=\r\naa", "a".repeat(LINE_LEN - 1))
+        );
+
+        // Encoded bytes aren't split
+        let line = format!("{}é", "a".repeat(72));
+        assert_eq!(
+            encoded(line.as_bytes()),
+            format!("{}=C3=\r\n=A9", "a".repeat(72))
+        );
+        let line = format!("{}=", "a".repeat(73));
+        assert_eq!(encoded(line.as_bytes()), format!("{}=3D", "a".repeat(73)));
+    }
+
+    #[test]
+    fn bare_line_endings() {
+        assert_eq!(encoded(b"a\rb\nc\r\n"), "a=0Db=0Ac\r\n");
+        assert_eq!(encoded(b"a\n\r\nb"), "a=0A\r\nb");
+    }
+
+    #[test]
+    fn round_trip_random_input() {
+        let mut rng = fastrand::Rng::with_seed(4695);
+        let alphabet = b"ab \t=\r\n\xc3\xa9.";
+        for _ in 0..500 {
+            let len = rng.usize(..400);
+            let input = (0..len)
+                .map(|_| match rng.u8(..4) {
+                    0 => rng.u8(..),
+                    _ => alphabet[rng.usize(..alphabet.len())],
+                })
+                .collect::<Vec<u8>>();
+
+            let output = encoded(&input);
+            for line in output.split("\r\n") {
+                assert!(line.len() <= LINE_LEN, "{line:?} is too long");
+                assert!(
+                    !matches!(line.bytes().last(), Some(b' ' | b'\t')),
+                    "{line:?} ends with whitespace"
+                );
+                assert!(line.bytes().all(|b| super::is_literal(b) || b == b'='));
+            }
+            let decoded =
+                ::quoted_printable::decode(&output, ::quoted_printable::ParseMode::Strict);
+            assert_eq!(decoded.unwrap(), input, "{output:?}");
+        }
+    }
+}
@@ -0,0 +1,430 @@
+//! Checks of the formatted messages against the email RFCs
+//!
+//! Meant for the tests of applications building messages through their own
+//! abstractions on top of lettre, like templating systems, to catch the
+//! messages that servers would reject or mangle. The checks work on the
+//! formatted message, so headers and bodies set with the `dangerous_*`
+//! methods get checked too.
+//!
+//! ```rust
+//! use lettre::{message::header::ContentType, testing::conformance, Message};
+//!
+//! # use std::error::Error;
+//! # fn main() -> Result<(), Box<dyn Error>> {
+//! let email = Message::builder()
+//!     .from("NoBody <nobody@domain.tld>".parse()?)
+//!     .to("Hei <hei@domain.tld>".parse()?)
+//!     .subject("Happy new year")
+//!     .header(ContentType::TEXT_PLAIN)
+//!     .body(String::from("Be happy!"))?;
+//!
+//! conformance::assert_conformant(&email);
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{
+    error::Error,
+    fmt::{self, Display},
+};
+
+use crate::message::{
+    header::{self, Header},
+    Mailboxes, Message, Part,
+};
+
+/// Maximum length of a line, without the CRLF,
+/// defined in [RFC5322](https://tools.ietf.org/html/rfc5322#section-2.1.1)
+pub const MAX_LINE_LENGTH: usize = 998;
+
+/// A way a formatted message doesn't conform
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation {
+    line: Option<usize>,
+    reason: String,
+}
+
+impl Violation {
+    fn new(line: Option<usize>, reason: impl Into<String>) -> Self {
+        Self {
+            line,
+            reason: reason.into(),
+        }
+    }
+
+    /// The line of the formatted message where it was found, starting from 1
+    pub fn line(&self) -> Option<usize> {
+        self.line
+    }
+
+    /// What doesn't conform
+    pub fn reason(&self) -> &str {
+        &self.reason
+    }
+}
+
+impl Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "line {line}: {}", self.reason),
+            None => f.write_str(&self.reason),
+        }
+    }
+}
+
+impl Error for Violation {}
+
+/// Runs all the checks on `message`
+///
+/// # Panics
+///
+/// Panics with the first violation found.
+#[track_caller]
+pub fn assert_conformant(message: &Message) {
+    if let Err(violation) = check_message(message) {
+        panic!("message doesn't conform: {violation}");
+    }
+}
+
+/// Runs all the checks on `message`, returning the first violation found
+pub fn check_message(message: &Message) -> Result<(), Violation> {
+    check_formatted(&message.formatted())?;
+    check_round_trip(message)
+}
+
+/// Runs all the checks which only need the formatted message
+pub fn check_formatted(raw: &[u8]) -> Result<(), Violation> {
+    check_line_endings(raw)?;
+    check_line_lengths(raw)?;
+    check_headers(raw)?;
+    check_mime_structure(raw)
+}
+
+/// Checks that lines only end with CRLF, without bare CR or LF
+pub fn check_line_endings(raw: &[u8]) -> Result<(), Violation> {
+    let mut line = 1;
+    for (i, &b) in raw.iter().enumerate() {
+        match b {
+            b'\r' if raw.get(i + 1) != Some(&b'\n') => {
+                return Err(Violation::new(Some(line), "bare CR"));
+            }
+            b'\n' if i == 0 || raw[i - 1] != b'\r' => {
+                return Err(Violation::new(Some(line), "bare LF"));
+            }
+            b'\n' => line += 1,
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Checks that lines are at most [`MAX_LINE_LENGTH`] bytes long
+pub fn check_line_lengths(raw: &[u8]) -> Result<(), Violation> {
+    for (i, line) in lines(raw).enumerate() {
+        if line.len() > MAX_LINE_LENGTH {
+            return Err(Violation::new(
+                Some(i + 1),
+                format!("line is {} bytes long", line.len()),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Checks that the header section is made of valid, correctly folded, ASCII header fields
+pub fn check_headers(raw: &[u8]) -> Result<(), Violation> {
+    let (headers, _) = split_message(raw)?;
+    for (i, line) in lines(headers).enumerate() {
+        let line_number = Some(i + 1);
+        if !line.is_ascii() {
+            return Err(Violation::new(line_number, "non-ASCII header"));
+        }
+
+        if line.starts_with(b" ") || line.starts_with(b"\t") {
+            if i == 0 {
+                return Err(Violation::new(line_number, "header section starts folded"));
+            }
+            if line.iter().all(|b| matches!(b, b' ' | b'\t')) {
+                return Err(Violation::new(line_number, "whitespace-only folded line"));
+            }
+            continue;
+        }
+
+        let Some(colon) = line.iter().position(|&b| b == b':') else {
+            return Err(Violation::new(line_number, "header without colon"));
+        };
+        let name = &line[..colon];
+        if name.is_empty() || !name.iter().all(|&b| matches!(b, 33..=126)) {
+            return Err(Violation::new(line_number, "invalid header name"));
+        }
+    }
+    Ok(())
+}
+
+/// Checks that multipart bodies are delimited by their boundaries, recursively
+pub fn check_mime_structure(raw: &[u8]) -> Result<(), Violation> {
+    parse_part(raw).map(|_| ())
+}
+
+/// Checks that the formatted message parses back to `message`
+///
+/// The MIME tree must have the same shape, the bodies must be identical and
+/// valid for their `Content-Transfer-Encoding`, and the address and date
+/// headers must parse to the same values.
+pub fn check_round_trip(message: &Message) -> Result<(), Violation> {
+    let parsed = parse_part(&message.formatted())?;
+
+    for (name, value) in &parsed.headers {
+        let reparsed = match name.to_ascii_lowercase().as_str() {
+            // Encoded-words aren't decoded
+            _ if value.contains("=?") => continue,
+            "from" | "to" | "cc" | "bcc" | "reply-to" | "sender" => value
+                .parse::<Mailboxes>()
+                .map(|mailboxes| mailboxes.to_string())
+                .ok(),
+            "date" => header::Date::parse(value.trim())
+                .ok()
+                .map(|date| date.display().get_raw().to_owned()),
+            _ => continue,
+        };
+        let Some(raw_value) = message.headers().get_raw(name) else {
+            continue;
+        };
+        if reparsed.as_deref() != Some(raw_value) {
+            return Err(Violation::new(
+                None,
+                format!("{name} header doesn't parse back to {raw_value:?}"),
+            ));
+        }
+    }
+
+    match message.mime_body() {
+        Some(Part::Single(single)) => {
+            let body = parsed.body.strip_suffix(b"\r\n").unwrap_or(&parsed.body);
+            compare_leaf(single.raw_body(), single.decoded_bytes().is_some(), body)
+        }
+        Some(part) => compare_part(part, &parsed),
+        None => Ok(()),
+    }
+}
+
+fn compare_part(part: &Part, parsed: &ParsedPart) -> Result<(), Violation> {
+    match part {
+        Part::Single(single) => compare_leaf(
+            single.raw_body(),
+            single.decoded_bytes().is_some(),
+            &parsed.body,
+        ),
+        Part::Multi(multi) => {
+            if multi.parts().len() != parsed.children.len() {
+                return Err(Violation::new(
+                    None,
+                    format!(
+                        "multipart has {} parts instead of {}",
+                        parsed.children.len(),
+                        multi.parts().len()
+                    ),
+                ));
+            }
+            multi
+                .parts()
+                .iter()
+                .zip(&parsed.children)
+                .try_for_each(|(part, parsed)| compare_part(part, parsed))
+        }
+    }
+}
+
+fn compare_leaf(body: &[u8], decodes: bool, parsed: &[u8]) -> Result<(), Violation> {
+    if body != parsed {
+        return Err(Violation::new(None, "body doesn't parse back"));
+    }
+    if !decodes {
+        return Err(Violation::new(
+            None,
+            "body isn't valid for its Content-Transfer-Encoding",
+        ));
+    }
+    Ok(())
+}
+
+/// A part of a formatted message
+struct ParsedPart {
+    /// Unfolded headers
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+    children: Vec<ParsedPart>,
+}
+
+fn parse_part(raw: &[u8]) -> Result<ParsedPart, Violation> {
+    let (header_section, body) = split_message(raw)?;
+
+    let mut headers = Vec::<(String, String)>::new();
+    for line in lines(header_section) {
+        let line = String::from_utf8_lossy(line);
+        if line.starts_with([' ', '\t']) {
+            if let Some((_, value)) = headers.last_mut() {
+                value.push_str(&line);
+            }
+        } else if let Some((name, value)) = line.split_once(':') {
+            headers.push((name.to_owned(), value.trim_start().to_owned()));
+        }
+    }
+
+    let boundary = headers
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case("Content-Type"))
+        .filter(|(_, value)| value.to_ascii_lowercase().starts_with("multipart/"))
+        .map(|(_, value)| {
+            boundary(value).ok_or_else(|| Violation::new(None, "multipart without boundary"))
+        })
+        .transpose()?;
+    let children = match boundary {
+        Some(boundary) => split_multipart(body, &boundary)?
+            .into_iter()
+            .map(parse_part)
+            .collect::<Result<_, _>>()?,
+        None => Vec::new(),
+    };
+
+    Ok(ParsedPart {
+        headers,
+        body: body.to_vec(),
+        children,
+    })
+}
+
+/// Splits the header section, without its final CRLF, and the body
+fn split_message(raw: &[u8]) -> Result<(&[u8], &[u8]), Violation> {
+    if let Some(body) = raw.strip_prefix(b"\r\n") {
+        return Ok((&[], body));
+    }
+    raw.windows(4)
+        .position(|w| w == b"\r\n\r\n")
+        .map(|i| (&raw[..i + 2], &raw[i + 4..]))
+        .ok_or_else(|| Violation::new(None, "no empty line after the headers"))
+}
+
+/// Extracts the `boundary` parameter of a `Content-Type`
+fn boundary(content_type: &str) -> Option<String> {
+    let start = content_type.to_ascii_lowercase().find("boundary=")? + "boundary=".len();
+    let value = &content_type[start..];
+    match value.strip_prefix('"') {
+        Some(quoted) => quoted.split_once('"').map(|(b, _)| b.to_owned()),
+        None => value
+            .split([';', ' ', '\t'])
+            .next()
+            .filter(|b| !b.is_empty())
+            .map(str::to_owned),
+    }
+}
+
+/// Splits a multipart body on its delimiter lines
+fn split_multipart<'a>(body: &'a [u8], boundary: &str) -> Result<Vec<&'a [u8]>, Violation> {
+    let delimiter = format!("--{boundary}");
+    let close_delimiter = format!("--{boundary}--");
+
+    let mut parts = Vec::new();
+    let mut part_start = None;
+    let mut offset = 0;
+    for line in lines(body) {
+        let line_start = offset;
+        offset += line.len() + 2;
+
+        let trimmed_len = line.len()
+            - line
+                .iter()
+                .rev()
+                .take_while(|&&b| b == b' ' || b == b'\t')
+                .count();
+        let trimmed = &line[..trimmed_len];
+        let is_close = trimmed == close_delimiter.as_bytes();
+        if is_close || trimmed == delimiter.as_bytes() {
+            if let Some(start) = part_start {
+                // The CRLF before the delimiter belongs to it
+                parts.push(&body[start..line_start.saturating_sub(2).max(start)]);
+            }
+            if is_close {
+                return Ok(parts);
+            }
+            part_start = Some(offset.min(body.len()));
+        }
+    }
+
+    Err(Violation::new(
+        None,
+        format!("multipart with boundary {boundary:?} isn't closed"),
+    ))
+}
+
+/// Lines of `raw`, without their CRLF
+fn lines(raw: &[u8]) -> impl Iterator<Item = &[u8]> {
+    let raw = raw.strip_suffix(b"\r\n").unwrap_or(raw);
+    raw.split(|&b| b == b'\n')
+        .map(|line| line.strip_suffix(b"\r").unwrap_or(line))
+        .filter(move |_| !raw.is_empty())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{assert_conformant, check_formatted};
+    use crate::message::{header::ContentType, Attachment, Message, MultiPart, SinglePart};
+
+    #[test]
+    fn conformant_messages() {
+        let builder = Message::builder()
+            .from("NoBody <nobody@domain.tld>".parse().unwrap())
+            .to("Hei <hei@domain.tld>".parse().unwrap())
+            .to("\"Doe, John\" <john@domain.tld>".parse().unwrap())
+            .subject("Happy new year — Ünïcödé and a rather long subject which must be folded");
+
+        assert_conformant(&builder.clone().body(String::from("Be happy!")).unwrap());
+        assert_conformant(
+            &builder
+                .clone()
+                .singlepart(SinglePart::plain(String::from("Ünïcödé\n").repeat(200)))
+                .unwrap(),
+        );
+        assert_conformant(
+            &builder
+                .multipart(
+                    MultiPart::mixed()
+                        .multipart(MultiPart::alternative_plain_html(
+                            String::from("Hello"),
+                            String::from("<p>Hello</p>"),
+                        ))
+                        .singlepart(
+                            Attachment::new(String::from("data.bin"))
+                                .body(vec![0xde, 0xad, 0xbe, 0xef], ContentType::TEXT_PLAIN),
+                        ),
+                )
+                .unwrap(),
+        );
+    }
+
+    #[test]
+    fn violations() {
+        for (raw, reason) in [
+            (&b"Subject: Hi\n\r\nBody"[..], "bare LF"),
+            (b"Subject: Hi\r\n\r\nBody\r", "bare CR"),
+            (b"Subject: Hi\r\nBody", "no empty line after the headers"),
+            (b" Subject: Hi\r\n\r\nBody", "header section starts folded"),
+            (b"Subject Hi\r\n\r\nBody", "header without colon"),
+            (
+                b"Subject: Hi\r\n \r\n\r\nBody",
+                "whitespace-only folded line",
+            ),
+            (b"Subject: H\xc3\xafi\r\n\r\nBody", "non-ASCII header"),
+            (
+                b"Content-Type: multipart/mixed; boundary=\"b\"\r\n\r\n--b\r\n\r\nBody\r\n",
+                "multipart with boundary \"b\" isn't closed",
+            ),
+        ] {
+            assert_eq!(check_formatted(raw).unwrap_err().reason(), reason);
+        }
+
+        let long_line = format!("Subject: Hi\r\n\r\n{}\r\n", "a".repeat(999));
+        let violation = check_formatted(long_line.as_bytes()).unwrap_err();
+        assert_eq!(violation.to_string(), "line 3: line is 999 bytes long");
+    }
+}
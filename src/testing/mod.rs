@@ -0,0 +1,6 @@
+//! Helpers for testing code sending emails with lettre
+//!
+//! See also the [stub transport](crate::transport::stub), which records the
+//! messages sent through it.
+
+pub mod conformance;
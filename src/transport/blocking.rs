@@ -0,0 +1,104 @@
+//! Using blocking transports from async code
+//!
+//! [`BlockingBridge`] wraps a [`Transport`], like the [`SendmailTransport`] or
+//! the [`FileTransport`], and implements [`AsyncTransport`] by sending on the
+//! blocking thread pool of the [`Executor`], so that the sends don't block
+//! the async runtime.
+//!
+//! ```rust
+//! # #[cfg(all(feature = "tokio1", feature = "builder"))]
+//! # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! use lettre::{
+//!     transport::{blocking::BlockingBridge, stub::StubTransport},
+//!     AsyncTransport, Message, Tokio1Executor,
+//! };
+//!
+//! let email = Message::builder()
+//!     .from("NoBody <nobody@domain.tld>".parse()?)
+//!     .to("Hei <hei@domain.tld>".parse()?)
+//!     .subject("Happy new year")
+//!     .body(String::from("Be happy!"))?;
+//!
+//! let mailer = BlockingBridge::<_, Tokio1Executor>::new(StubTransport::new_ok());
+//! mailer.send(email).await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`SendmailTransport`]: crate::SendmailTransport
+//! [`FileTransport`]: crate::FileTransport
+
+use std::{
+    fmt::{self, Debug},
+    marker::PhantomData,
+    sync::Arc,
+};
+
+use async_trait::async_trait;
+
+use super::{AsyncTransport, Transport};
+use crate::{Envelope, Executor};
+
+/// Async transport sending with a blocking transport on the blocking
+/// thread pool of the executor
+///
+/// The envelope and the message are copied to the blocking task.
+pub struct BlockingBridge<T, E> {
+    transport: Arc<T>,
+    marker_: PhantomData<E>,
+}
+
+impl<T, E> BlockingBridge<T, E>
+where
+    E: Executor,
+{
+    /// Wraps the blocking `transport`
+    pub fn new(transport: T) -> Self {
+        Self {
+            transport: Arc::new(transport),
+            marker_: PhantomData,
+        }
+    }
+
+    /// The wrapped transport
+    pub fn transport(&self) -> &T {
+        &self.transport
+    }
+}
+
+impl<T: Debug, E> Debug for BlockingBridge<T, E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BlockingBridge")
+            .field("transport", &self.transport)
+            .finish()
+    }
+}
+
+impl<T, E> Clone for BlockingBridge<T, E> {
+    fn clone(&self) -> Self {
+        Self {
+            transport: Arc::clone(&self.transport),
+            marker_: PhantomData,
+        }
+    }
+}
+
+#[async_trait]
+impl<T, E> AsyncTransport for BlockingBridge<T, E>
+where
+    T: Transport + Send + Sync + 'static,
+    T::Ok: Send + 'static,
+    T::Error: Send + 'static,
+    E: Executor,
+{
+    type Ok = T::Ok;
+    type Error = T::Error;
+
+    async fn send_raw(&self, envelope: &Envelope, email: &[u8]) -> Result<Self::Ok, Self::Error> {
+        let transport = Arc::clone(&self.transport);
+        let envelope = envelope.clone();
+        let email = email.to_vec();
+
+        E::spawn_blocking(move || transport.send_raw(&envelope, &email)).await
+    }
+}
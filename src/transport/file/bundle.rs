@@ -0,0 +1,88 @@
+//! Single file format holding the message and its envelope
+//!
+//! Like Apple Mail's `.emlx` files, a bundle starts with the length of the
+//! message in bytes on its own line, followed by the raw message, followed
+//! by the envelope in json format:
+//!
+//! ```text
+//! 99
+//! From: NoBody <nobody@domain.tld>
+//! ...
+//! Be happy!{"forward_path":["hei@domain.tld"],"reverse_path":"nobody@domain.tld"}
+//! ```
+
+use super::{error, Error};
+use crate::address::Envelope;
+
+/// Extension of the bundle files
+pub(super) const EXTENSION: &str = "emlb";
+
+/// Encodes `envelope` and `email` into a bundle
+pub(super) fn encode(envelope: &Envelope, email: &[u8]) -> Result<Vec<u8>, Error> {
+    let json = serde_json::to_vec(envelope).map_err(error::envelope)?;
+    let len = email.len().to_string();
+
+    let mut bundle = Vec::with_capacity(len.len() + 1 + email.len() + json.len());
+    bundle.extend_from_slice(len.as_bytes());
+    bundle.push(b'\n');
+    bundle.extend_from_slice(email);
+    bundle.extend_from_slice(&json);
+    Ok(bundle)
+}
+
+/// Decodes a bundle into the envelope and the raw message
+pub(super) fn decode(bundle: &[u8]) -> Result<(Envelope, Vec<u8>), Error> {
+    let invalid = || error::envelope("invalid bundle");
+
+    let newline = bundle
+        .iter()
+        .position(|&b| b == b'\n')
+        .ok_or_else(invalid)?;
+    let len = std::str::from_utf8(&bundle[..newline])
+        .ok()
+        .and_then(|len| len.trim_end_matches('\r').parse::<usize>().ok())
+        .ok_or_else(invalid)?;
+
+    let rest = &bundle[newline + 1..];
+    if rest.len() < len {
+        return Err(invalid());
+    }
+    let (email, json) = rest.split_at(len);
+    let envelope = serde_json::from_slice(json).map_err(error::envelope)?;
+
+    Ok((envelope, email.to_vec()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{decode, encode};
+    use crate::address::Envelope;
+
+    #[test]
+    fn round_trip() {
+        let envelope = Envelope::new(
+            Some("nobody@domain.tld".parse().unwrap()),
+            vec!["hei@domain.tld".parse().unwrap()],
+        )
+        .unwrap();
+        let email = b"Subject: Happy new year\r\n\r\nBe happy!\r\n";
+
+        let bundle = encode(&envelope, email).unwrap();
+        assert!(bundle.starts_with(b"38\nSubject: "));
+        assert!(bundle.ends_with(
+            b"Be happy!\r\n{\"forward_path\":[\"hei@domain.tld\"],\"reverse_path\":\"nobody@domain.tld\"}"
+        ));
+
+        let (decoded_envelope, decoded_email) = decode(&bundle).unwrap();
+        assert_eq!(decoded_envelope, envelope);
+        assert_eq!(decoded_email, email);
+    }
+
+    #[test]
+    fn invalid() {
+        assert!(decode(b"").is_err());
+        assert!(decode(b"no length\nSubject: test").is_err());
+        assert!(decode(b"100\nSubject: test").is_err());
+        assert!(decode(b"4\ntest{}").is_err());
+    }
+}
@@ -145,12 +145,37 @@
 //! ```json
 //! {"forward_path":["hei@domain.tld"],"reverse_path":"nobody@domain.tld"}
 //! ```
+//!
+//! ## Single file output
+//!
+//! With the `with_bundle` builder, the message and its envelope are
+//! written together to a single `message_id.emlb` file, so that they can't
+//! get out of sync. Like Apple Mail's `.emlx` files, it starts with the
+//! length of the message on its own line, followed by the message and the
+//! envelope in json format. `read` reads them back.
+//!
+//! ## Sharding by date
+//!
+//! Directories holding millions of files are slow on most filesystems.
+//! With [`FileTransport::shard_by_date`], the emails are written to
+//! subdirectories named after the current UTC date, like
+//! `2024/06/15/message_id.eml`, created when needed. The returned id is
+//! then the relative path without the extension,
+//! which can still be passed to `read`.
+//!
+//! ## Cleaning up
+//!
+//! [`FileTransport::prune`] deletes the emails older than a given age, so
+//! that test and archival directories don't grow forever, and
+//! [`FileTransport::prune_dry_run`] lists them without deleting them.
 
 #[cfg(any(feature = "async-std1", feature = "tokio1"))]
 use std::marker::PhantomData;
 use std::{
+    fs, io,
     path::{Path, PathBuf},
     str,
+    time::{Duration, SystemTime},
 };
 
 #[cfg(any(feature = "async-std1", feature = "tokio1"))]
@@ -158,10 +183,14 @@ use async_trait::async_trait;
 use uuid::Uuid;
 
 pub use self::error::Error;
-use crate::{address::Envelope, Transport};
+#[cfg(feature = "builder")]
+use crate::Message;
+use crate::{address::Envelope, transport::Receipt, Transport};
 #[cfg(any(feature = "async-std1", feature = "tokio1"))]
 use crate::{AsyncTransport, Executor};
 
+#[cfg(feature = "file-transport-envelope")]
+mod bundle;
 mod error;
 
 type Id = String;
@@ -174,6 +203,11 @@ pub struct FileTransport {
     path: PathBuf,
     #[cfg(feature = "file-transport-envelope")]
     save_envelope: bool,
+    #[cfg(feature = "file-transport-envelope")]
+    #[cfg_attr(feature = "serde", serde(default))]
+    bundle: bool,
+    #[cfg_attr(feature = "serde", serde(default))]
+    shard_by_date: bool,
 }
 
 /// Asynchronously writes the content and the envelope information to a file
@@ -195,6 +229,9 @@ impl FileTransport {
             path: PathBuf::from(path.as_ref()),
             #[cfg(feature = "file-transport-envelope")]
             save_envelope: false,
+            #[cfg(feature = "file-transport-envelope")]
+            bundle: false,
+            shard_by_date: false,
         }
     }
 
@@ -208,6 +245,22 @@ impl FileTransport {
             path: PathBuf::from(path.as_ref()),
             #[cfg(feature = "file-transport-envelope")]
             save_envelope: true,
+            bundle: false,
+            shard_by_date: false,
+        }
+    }
+
+    /// Creates a new transport to the given directory
+    ///
+    /// Writes the email content and the envelope to a single file,
+    /// in the `emlb` format.
+    #[cfg(feature = "file-transport-envelope")]
+    pub fn with_bundle<P: AsRef<Path>>(path: P) -> FileTransport {
+        FileTransport {
+            path: PathBuf::from(path.as_ref()),
+            save_envelope: true,
+            bundle: true,
+            shard_by_date: false,
         }
     }
 
@@ -216,7 +269,10 @@ impl FileTransport {
     /// Reads the envelope and the raw message content.
     #[cfg(feature = "file-transport-envelope")]
     pub fn read(&self, email_id: &str) -> Result<(Envelope, Vec<u8>), Error> {
-        use std::fs;
+        if self.bundle {
+            let bundle = fs::read(self.path(email_id, bundle::EXTENSION)).map_err(error::io)?;
+            return bundle::decode(&bundle);
+        }
 
         let eml_file = self.path.join(format!("{email_id}.eml"));
         let eml = fs::read(eml_file).map_err(error::io)?;
@@ -228,11 +284,134 @@ impl FileTransport {
         Ok((envelope, eml))
     }
 
-    fn path(&self, email_id: &Uuid, extension: &str) -> PathBuf {
+    /// Writes the emails to subdirectories named after the current UTC
+    /// date, like `2024/06/15`, creating them when needed
+    pub fn shard_by_date(mut self, shard: bool) -> Self {
+        self.shard_by_date = shard;
+        self
+    }
+
+    /// Deletes the emails written more than `older_than` ago
+    ///
+    /// Removes the `.eml` files, the `.json` envelopes and the `.emlb` bundles
+    /// written by the transport, including those in date subdirectories, which are removed
+    /// when left empty. Other files are left alone. Returns the paths of the
+    /// deleted files.
+    pub fn prune(&self, older_than: Duration) -> Result<Vec<PathBuf>, Error> {
+        self.prune_files(older_than, false)
+    }
+
+    /// Lists the files [`prune`](Self::prune) would delete, without deleting them
+    pub fn prune_dry_run(&self, older_than: Duration) -> Result<Vec<PathBuf>, Error> {
+        self.prune_files(older_than, true)
+    }
+
+    fn prune_files(&self, older_than: Duration, dry_run: bool) -> Result<Vec<PathBuf>, Error> {
+        let mut pruned = Vec::new();
+        if let Some(cutoff) = SystemTime::now().checked_sub(older_than) {
+            prune_dir(&self.path, 0, cutoff, dry_run, &mut pruned).map_err(error::io)?;
+        }
+        pruned.sort();
+        Ok(pruned)
+    }
+
+    /// Id of a new email, prefixed with its directory when sharding
+    fn email_id(&self) -> String {
+        let uuid = Uuid::new_v4();
+        if self.shard_by_date {
+            let (year, month, day) = utc_date(SystemTime::now());
+            format!("{year:04}/{month:02}/{day:02}/{uuid}")
+        } else {
+            uuid.to_string()
+        }
+    }
+
+    /// Directory to create before writing the email, if sharding
+    fn shard_dir(&self, email_id: &str) -> Option<PathBuf> {
+        if !self.shard_by_date {
+            return None;
+        }
+        let (dir, _) = email_id.rsplit_once('/')?;
+        Some(self.path.join(dir))
+    }
+
+    fn path(&self, email_id: &str, extension: &str) -> PathBuf {
         self.path.join(format!("{email_id}.{extension}"))
     }
 }
 
+/// Deletes the emails in `dir` last modified at `cutoff` or before,
+/// going down the `depth` levels of date subdirectories left
+fn prune_dir(
+    dir: &Path,
+    depth: usize,
+    cutoff: SystemTime,
+    dry_run: bool,
+    pruned: &mut Vec<PathBuf>,
+) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        let file_type = entry.file_type()?;
+
+        if file_type.is_dir() {
+            let is_shard = entry
+                .file_name()
+                .to_str()
+                .is_some_and(|name| !name.is_empty() && name.bytes().all(|b| b.is_ascii_digit()));
+            if depth < 3 && is_shard {
+                prune_dir(&path, depth + 1, cutoff, dry_run, pruned)?;
+                if !dry_run && fs::read_dir(&path)?.next().is_none() {
+                    fs::remove_dir(&path)?;
+                }
+            }
+        } else if file_type.is_file()
+            && is_email_file(&path)
+            && entry.metadata()?.modified()? <= cutoff
+        {
+            if !dry_run {
+                fs::remove_file(&path)?;
+            }
+            pruned.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Whether `path` was written by the transport, named after a uuid
+fn is_email_file(path: &Path) -> bool {
+    let has_extension = path
+        .extension()
+        .is_some_and(|extension| extension == "eml" || extension == "json" || extension == "emlb");
+    let is_uuid = path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .is_some_and(|stem| Uuid::try_parse(stem).is_ok());
+    has_extension && is_uuid
+}
+
+/// Year, month and day of `time` in UTC
+fn utc_date(time: SystemTime) -> (i64, u32, u32) {
+    let secs = match time.duration_since(SystemTime::UNIX_EPOCH) {
+        Ok(elapsed) => elapsed.as_secs() as i64,
+        Err(err) => -(err.duration().as_secs() as i64),
+    };
+    let days = secs.div_euclid(86400);
+
+    // From Howard Hinnant's `civil_from_days`, with eras of 400 years
+    // starting on March 1st
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
 #[cfg(any(feature = "async-std1", feature = "tokio1"))]
 impl<E> AsyncFileTransport<E>
 where
@@ -260,11 +439,51 @@ where
         }
     }
 
+    /// Creates a new transport to the given directory
+    ///
+    /// Writes the email content and the envelope to a single file,
+    /// in the `emlb` format.
+    #[cfg(feature = "file-transport-envelope")]
+    pub fn with_bundle<P: AsRef<Path>>(path: P) -> Self {
+        Self {
+            inner: FileTransport::with_bundle(path),
+            marker_: PhantomData,
+        }
+    }
+
+    /// Writes the emails to subdirectories named after the current UTC
+    /// date, like `2024/06/15`, creating them when needed
+    pub fn shard_by_date(mut self, shard: bool) -> Self {
+        self.inner = self.inner.shard_by_date(shard);
+        self
+    }
+
+    /// Deletes the emails written more than `older_than` ago
+    ///
+    /// See [`FileTransport::prune`], the files are deleted on the blocking
+    /// thread pool of the executor.
+    pub async fn prune(&self, older_than: Duration) -> Result<Vec<PathBuf>, Error> {
+        let inner = self.inner.clone();
+        E::spawn_blocking(move || inner.prune(older_than)).await
+    }
+
+    /// Lists the files [`prune`](Self::prune) would delete, without deleting them
+    pub async fn prune_dry_run(&self, older_than: Duration) -> Result<Vec<PathBuf>, Error> {
+        let inner = self.inner.clone();
+        E::spawn_blocking(move || inner.prune_dry_run(older_than)).await
+    }
+
     /// Read a message that was written using the file transport.
     ///
     /// Reads the envelope and the raw message content.
     #[cfg(feature = "file-transport-envelope")]
     pub async fn read(&self, email_id: &str) -> Result<(Envelope, Vec<u8>), Error> {
+        if self.inner.bundle {
+            let bundle_file = self.inner.path(email_id, bundle::EXTENSION);
+            let bundle = E::fs_read(&bundle_file).await.map_err(error::io)?;
+            return bundle::decode(&bundle);
+        }
+
         let eml_file = self.inner.path.join(format!("{email_id}.eml"));
         let eml = E::fs_read(&eml_file).await.map_err(error::io)?;
 
@@ -276,14 +495,71 @@ where
     }
 }
 
+impl FileTransport {
+    /// Writes the email like [`Transport::send`], returning a [`Receipt`]
+    /// whose id is the one returned by `send`
+    #[cfg(feature = "builder")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "builder")))]
+    pub fn send_with_receipt(&self, message: &Message) -> Result<Receipt, Error> {
+        self.send_raw_with_receipt(message.envelope(), &message.formatted())
+    }
+
+    /// Writes the email like [`Transport::send_raw`], returning a [`Receipt`]
+    /// whose id is the one returned by `send_raw`
+    pub fn send_raw_with_receipt(
+        &self,
+        envelope: &Envelope,
+        email: &[u8],
+    ) -> Result<Receipt, Error> {
+        let id = self.send_raw(envelope, email)?;
+        Ok(Receipt::new(email, Some(id)))
+    }
+}
+
+#[cfg(any(feature = "async-std1", feature = "tokio1"))]
+impl<E> AsyncFileTransport<E>
+where
+    E: Executor,
+{
+    /// Writes the email like [`AsyncTransport::send`], returning a [`Receipt`]
+    /// whose id is the one returned by `send`
+    #[cfg(feature = "builder")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "builder")))]
+    pub async fn send_with_receipt(&self, message: &Message) -> Result<Receipt, Error> {
+        self.send_raw_with_receipt(message.envelope(), &message.formatted())
+            .await
+    }
+
+    /// Writes the email like [`AsyncTransport::send_raw`], returning a [`Receipt`]
+    /// whose id is the one returned by `send_raw`
+    pub async fn send_raw_with_receipt(
+        &self,
+        envelope: &Envelope,
+        email: &[u8],
+    ) -> Result<Receipt, Error> {
+        let id = self.send_raw(envelope, email).await?;
+        Ok(Receipt::new(email, Some(id)))
+    }
+}
+
 impl Transport for FileTransport {
     type Ok = Id;
     type Error = Error;
 
     fn send_raw(&self, envelope: &Envelope, email: &[u8]) -> Result<Self::Ok, Self::Error> {
-        use std::fs;
+        let email_id = self.email_id();
+        if let Some(dir) = self.shard_dir(&email_id) {
+            fs::create_dir_all(dir).map_err(error::io)?;
+        }
 
-        let email_id = Uuid::new_v4();
+        #[cfg(feature = "file-transport-envelope")]
+        if self.bundle {
+            let file = self.path(&email_id, bundle::EXTENSION);
+            #[cfg(feature = "tracing")]
+            tracing::debug!(?file, "writing email to");
+            fs::write(file, bundle::encode(envelope, email)?).map_err(error::io)?;
+            return Ok(email_id);
+        }
 
         let file = self.path(&email_id, "eml");
         #[cfg(feature = "tracing")]
@@ -301,7 +577,7 @@ impl Transport for FileTransport {
         // use envelope anyway
         let _ = envelope;
 
-        Ok(email_id.to_string())
+        Ok(email_id)
     }
 }
 
@@ -315,7 +591,20 @@ where
     type Error = Error;
 
     async fn send_raw(&self, envelope: &Envelope, email: &[u8]) -> Result<Self::Ok, Self::Error> {
-        let email_id = Uuid::new_v4();
+        let email_id = self.inner.email_id();
+        if let Some(dir) = self.inner.shard_dir(&email_id) {
+            E::fs_create_dir_all(&dir).await.map_err(error::io)?;
+        }
+
+        #[cfg(feature = "file-transport-envelope")]
+        if self.inner.bundle {
+            let file = self.inner.path(&email_id, bundle::EXTENSION);
+            #[cfg(feature = "tracing")]
+            tracing::debug!(?file, "writing email to");
+            let buf = bundle::encode(envelope, email)?;
+            E::fs_write(&file, &buf).await.map_err(error::io)?;
+            return Ok(email_id);
+        }
 
         let file = self.inner.path(&email_id, "eml");
         #[cfg(feature = "tracing")]
@@ -333,6 +622,29 @@ where
         // use envelope anyway
         let _ = envelope;
 
-        Ok(email_id.to_string())
+        Ok(email_id)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::{Duration, SystemTime};
+
+    use super::utc_date;
+
+    #[test]
+    fn utc_dates() {
+        let at = |secs| SystemTime::UNIX_EPOCH + Duration::from_secs(secs);
+
+        assert_eq!(utc_date(SystemTime::UNIX_EPOCH), (1970, 1, 1));
+        assert_eq!(utc_date(at(784887151)), (1994, 11, 15));
+        assert_eq!(utc_date(at(951782399)), (2000, 2, 28));
+        assert_eq!(utc_date(at(951782400)), (2000, 2, 29));
+        assert_eq!(utc_date(at(1718452800)), (2024, 6, 15));
+        assert_eq!(utc_date(at(1735689599)), (2024, 12, 31));
+        assert_eq!(
+            utc_date(SystemTime::UNIX_EPOCH - Duration::from_secs(1)),
+            (1969, 12, 31)
+        );
     }
 }
@@ -0,0 +1,284 @@
+//! Sending raw messages to the recipients of their headers
+//!
+//! Messages produced elsewhere often come without a separate envelope.
+//! [`HeaderEnvelope`] wraps a transport and derives the envelope of each
+//! message from its headers at send time, like `sendmail -t`: the sender
+//! comes from the `Sender` or `From` header, and the recipients from the
+//! `To`, `Cc` and `Bcc` headers. The `Bcc` header is then removed from the
+//! message.
+//!
+//! ```rust
+//! use lettre::{
+//!     address::Envelope,
+//!     transport::{header_envelope::HeaderEnvelope, stub::StubTransport},
+//!     Transport,
+//! };
+//!
+//! # use std::error::Error;
+//! # fn main() -> Result<(), Box<dyn Error>> {
+//! let raw = concat!(
+//!     "From: NoBody <nobody@domain.tld>\r\n",
+//!     "To: Hei <hei@domain.tld>\r\n",
+//!     "Bcc: yuin@domain.tld\r\n",
+//!     "Subject: Happy new year\r\n",
+//!     "\r\n",
+//!     "Be happy!\r\n",
+//! );
+//!
+//! let mailer = HeaderEnvelope::new(StubTransport::new_ok());
+//! // The envelope passed to `send_raw` is ignored
+//! let ignored = Envelope::new(None, vec!["ignored@domain.tld".parse()?])?;
+//! mailer.send_raw(&ignored, raw.as_bytes())?;
+//!
+//! let (envelope, email) = &mailer.inner().messages()[0];
+//! assert_eq!(envelope.to().len(), 2);
+//! assert!(!email.contains("Bcc"));
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{
+    borrow::Cow,
+    error::Error as StdError,
+    fmt::{self, Display},
+};
+
+#[cfg(any(feature = "tokio1", feature = "async-std1"))]
+use async_trait::async_trait;
+
+#[cfg(any(feature = "tokio1", feature = "async-std1"))]
+use super::AsyncTransport;
+use super::Transport;
+use crate::{
+    message::header::{HeaderName, HeaderValue, Headers},
+    Envelope, Error,
+};
+
+/// Headers the envelope is derived from
+const ENVELOPE_HEADERS: [&str; 5] = ["Sender", "From", "To", "Cc", "Bcc"];
+
+/// Derives the envelope of the raw `email` from its headers
+///
+/// The sender is the address of the `Sender` header, or else of the `From`
+/// header, and the recipients are the addresses of the `To`, `Cc` and
+/// `Bcc` headers. Fails if there is no recipient, if the `From` header has
+/// several addresses without a `Sender` header, or if an address is invalid.
+pub fn envelope_from_headers(email: &[u8]) -> Result<Envelope, Error> {
+    let mut headers = Headers::new();
+    for (name, value) in header_fields(email) {
+        let Some(name) = ENVELOPE_HEADERS
+            .into_iter()
+            .find(|known| known.as_bytes().eq_ignore_ascii_case(name))
+        else {
+            continue;
+        };
+
+        // Unfold the value, the line breaks are followed by whitespace
+        let value = String::from_utf8_lossy(value).replace(['\r', '\n'], "");
+        headers.insert_raw(HeaderValue::new(
+            HeaderName::new_from_ascii_str(name),
+            value.trim().to_owned(),
+        ));
+    }
+
+    Envelope::try_from(&headers)
+}
+
+/// Iterates over the name and the raw value of the header fields of `email`
+fn header_fields(email: &[u8]) -> impl Iterator<Item = (&[u8], &[u8])> {
+    field_ranges(email).filter_map(move |(start, end)| {
+        let field = &email[start..end];
+        let colon = field.iter().position(|&b| b == b':')?;
+        Some((&field[..colon], &field[colon + 1..]))
+    })
+}
+
+/// Iterates over the ranges of the header fields of `email`, including
+/// their folded lines and line endings
+fn field_ranges(email: &[u8]) -> impl Iterator<Item = (usize, usize)> + '_ {
+    let mut pos = 0;
+    std::iter::from_fn(move || {
+        let start = pos;
+        loop {
+            let rest = &email[pos..];
+            let line_len = rest
+                .iter()
+                .position(|&b| b == b'\n')
+                .map_or(rest.len(), |len| len + 1);
+            let line = &rest[..line_len];
+            let is_blank = line.is_empty() || line == b"\n" || line == b"\r\n";
+            let is_continuation = matches!(line.first(), Some(b' ' | b'\t'));
+            if pos > start && !is_continuation || pos == start && is_blank {
+                break;
+            }
+            pos += line_len;
+        }
+        (pos > start).then_some((start, pos))
+    })
+}
+
+/// Removes the `Bcc` header fields of `email`
+fn remove_bcc(email: &[u8]) -> Cow<'_, [u8]> {
+    let mut bcc = field_ranges(email).filter(|&(start, end)| {
+        let field = &email[start..end];
+        field.len() > 3 && field[..3].eq_ignore_ascii_case(b"bcc") && field[3] == b':'
+    });
+    let Some(first) = bcc.next() else {
+        return Cow::Borrowed(email);
+    };
+
+    let mut stripped = Vec::with_capacity(email.len());
+    let mut pos = 0;
+    for (start, end) in std::iter::once(first).chain(bcc) {
+        stripped.extend_from_slice(&email[pos..start]);
+        pos = end;
+    }
+    stripped.extend_from_slice(&email[pos..]);
+    Cow::Owned(stripped)
+}
+
+/// Transport sending raw messages to the recipients of their headers
+///
+/// The envelope given to [`Transport::send_raw`] is ignored, see the
+/// [module documentation](self). Messages built with lettre already have
+/// an envelope derived from their headers, and their `Bcc` header is
+/// removed when they are built, so send them with the wrapped transport.
+#[derive(Debug, Clone)]
+pub struct HeaderEnvelope<T> {
+    inner: T,
+}
+
+impl<T> HeaderEnvelope<T> {
+    /// Wraps `inner`, sending the messages to the recipients of their headers
+    pub fn new(inner: T) -> Self {
+        Self { inner }
+    }
+
+    /// The wrapped transport
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+}
+
+impl<T: Transport> Transport for HeaderEnvelope<T> {
+    type Ok = T::Ok;
+    type Error = HeaderEnvelopeError<T::Error>;
+
+    fn send_raw(&self, _envelope: &Envelope, email: &[u8]) -> Result<Self::Ok, Self::Error> {
+        let envelope = envelope_from_headers(email).map_err(HeaderEnvelopeError::Envelope)?;
+        self.inner
+            .send_raw(&envelope, &remove_bcc(email))
+            .map_err(HeaderEnvelopeError::Transport)
+    }
+}
+
+#[cfg(any(feature = "tokio1", feature = "async-std1"))]
+#[async_trait]
+impl<T: AsyncTransport + Sync> AsyncTransport for HeaderEnvelope<T> {
+    type Ok = T::Ok;
+    type Error = HeaderEnvelopeError<T::Error>;
+
+    async fn send_raw(&self, _envelope: &Envelope, email: &[u8]) -> Result<Self::Ok, Self::Error> {
+        let envelope = envelope_from_headers(email).map_err(HeaderEnvelopeError::Envelope)?;
+        self.inner
+            .send_raw(&envelope, &remove_bcc(email))
+            .await
+            .map_err(HeaderEnvelopeError::Transport)
+    }
+}
+
+/// Error of a [`HeaderEnvelope`] transport
+#[derive(Debug)]
+pub enum HeaderEnvelopeError<E> {
+    /// The envelope couldn't be derived from the headers of the message
+    Envelope(Error),
+    /// The wrapped transport failed to send the message
+    Transport(E),
+}
+
+impl<E: Display> Display for HeaderEnvelopeError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Envelope(err) => write!(f, "invalid envelope in the message headers: {err}"),
+            Self::Transport(err) => Display::fmt(err, f),
+        }
+    }
+}
+
+impl<E: StdError + 'static> StdError for HeaderEnvelopeError<E> {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match self {
+            Self::Envelope(err) => Some(err),
+            Self::Transport(err) => err.source(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use super::{envelope_from_headers, remove_bcc};
+    use crate::Error;
+
+    #[test]
+    fn envelope() {
+        let email = concat!(
+            "Sender: list@domain.tld\r\n",
+            "From: NoBody <nobody@domain.tld>, Yuin <yuin@domain.tld>\r\n",
+            "to: Hei <hei@domain.tld>,\r\n",
+            " \"Doe, John\" <john@domain.tld>\r\n",
+            "Bcc: hidden@domain.tld\r\n",
+            "\r\n",
+            "To: body@domain.tld\r\n",
+        );
+        let envelope = envelope_from_headers(email.as_bytes()).unwrap();
+        assert_eq!(
+            AsRef::<str>::as_ref(envelope.from().unwrap()),
+            "list@domain.tld"
+        );
+        assert_eq!(
+            envelope
+                .to()
+                .iter()
+                .map(AsRef::as_ref)
+                .collect::<Vec<&str>>(),
+            ["hei@domain.tld", "john@domain.tld", "hidden@domain.tld"]
+        );
+
+        assert!(matches!(
+            envelope_from_headers(b"From: nobody@domain.tld\n\nTo: body@domain.tld\n"),
+            Err(Error::MissingTo)
+        ));
+        assert!(matches!(
+            envelope_from_headers(b"From: a@domain.tld, b@domain.tld\nTo: hei@domain.tld\n\n"),
+            Err(Error::TooManyFrom)
+        ));
+    }
+
+    #[test]
+    fn bcc_removed() {
+        let email = concat!(
+            "From: nobody@domain.tld\r\n",
+            "BCC: hidden@domain.tld,\r\n",
+            "\tsecret@domain.tld\r\n",
+            "To: hei@domain.tld\r\n",
+            "Bcc: other@domain.tld\r\n",
+            "\r\n",
+            "Bcc: body@domain.tld\r\n",
+        );
+        assert_eq!(
+            String::from_utf8_lossy(&remove_bcc(email.as_bytes())),
+            concat!(
+                "From: nobody@domain.tld\r\n",
+                "To: hei@domain.tld\r\n",
+                "\r\n",
+                "Bcc: body@domain.tld\r\n",
+            )
+        );
+        assert_eq!(
+            remove_bcc(b"To: hei@domain.tld\n\nBody"),
+            &b"To: hei@domain.tld\n\nBody"[..]
+        );
+    }
+}
@@ -0,0 +1,498 @@
+//! Skipping messages which were already sent
+//!
+//! When a send fails ambiguously, for example with a timeout after the
+//! message content was transferred, the server may have accepted the message
+//! anyway, and sending it again delivers it twice. [`Idempotent`] wraps a
+//! transport and records the messages it accepted in an [`IdempotencyStore`],
+//! so that retrying them is skipped. Messages whose send failed ambiguously
+//! are recorded as pending, and retrying them fails with
+//! [`IdempotentError::Pending`] until the application decides whether they
+//! were delivered.
+//!
+//! Messages are identified by their `Message-ID` header and their envelope,
+//! messages without a `Message-ID` are always sent.
+//!
+//! ```rust
+//! # #[cfg(feature = "builder")]
+//! # {
+//! use lettre::{
+//!     message::header::ContentType,
+//!     transport::{
+//!         idempotent::{Idempotent, MemoryStore},
+//!         stub::StubTransport,
+//!     },
+//!     Message, Transport,
+//! };
+//!
+//! # use std::error::Error;
+//! # fn main() -> Result<(), Box<dyn Error>> {
+//! let email = Message::builder()
+//!     .from("NoBody <nobody@domain.tld>".parse()?)
+//!     .to("Hei <hei@domain.tld>".parse()?)
+//!     .subject("Happy new year")
+//!     .header(ContentType::TEXT_PLAIN)
+//!     .body(String::from("Be happy!"))?;
+//!
+//! let mailer = Idempotent::new(StubTransport::new_ok(), MemoryStore::new());
+//! assert!(mailer.send(&email)?.is_some());
+//! // Retrying doesn't send the message again
+//! assert!(mailer.send(&email)?.is_none());
+//! # Ok(())
+//! # }
+//! # }
+//! ```
+
+use std::{
+    collections::HashMap,
+    error::Error as StdError,
+    fmt::{self, Debug, Display},
+    sync::Mutex,
+};
+
+#[cfg(any(feature = "tokio1", feature = "async-std1"))]
+use async_trait::async_trait;
+
+#[cfg(any(feature = "tokio1", feature = "async-std1"))]
+use super::AsyncTransport;
+use super::{receipt::message_id, Transport};
+use crate::Envelope;
+
+/// Identifies a message sent with a given envelope
+///
+/// Made of the `Message-ID` of the message and a hash of its envelope,
+/// which is stable across processes and versions, so that the keys can
+/// be persisted.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct IdempotencyKey(String);
+
+impl IdempotencyKey {
+    /// Creates the key of the raw `email` sent with `envelope`,
+    /// `None` if it doesn't have a `Message-ID` header
+    pub fn new(envelope: &Envelope, email: &[u8]) -> Option<Self> {
+        let message_id = message_id(email)?;
+
+        let mut hash = Fnv1a::new();
+        if let Some(from) = envelope.from() {
+            hash.write(AsRef::<str>::as_ref(from).as_bytes());
+        }
+        for to in envelope.to() {
+            hash.write(b"\0");
+            hash.write(AsRef::<str>::as_ref(to).as_bytes());
+        }
+
+        Some(Self(format!("{message_id}:{:016x}", hash.0)))
+    }
+
+    /// The key as a string, for storing it
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Display for IdempotencyKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// 64-bit FNV-1a, used instead of the std hasher whose output may change
+struct Fnv1a(u64);
+
+impl Fnv1a {
+    fn new() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.0 ^= u64::from(b);
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+}
+
+/// State of a message recorded in an [`IdempotencyStore`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SendState {
+    /// The transport accepted the message
+    Sent,
+    /// Sending the message failed once its content started being sent,
+    /// so it may have been delivered anyway
+    Pending,
+}
+
+/// Records the messages accepted by a transport
+///
+/// Implement it on top of a database or a key-value store to skip
+/// duplicates across restarts, for example when sending from a
+/// persistent queue.
+pub trait IdempotencyStore: Send + Sync {
+    /// The state of the message identified by `key`, `None` if it
+    /// wasn't sent yet
+    fn get(&self, key: &IdempotencyKey) -> Option<SendState>;
+
+    /// Records the state of the message identified by `key`
+    fn insert(&self, key: IdempotencyKey, state: SendState);
+
+    /// Forgets the message identified by `key`
+    ///
+    /// Used by applications to send again a pending message
+    /// which wasn't delivered.
+    fn remove(&self, key: &IdempotencyKey);
+}
+
+/// Keeps the keys of the sent messages in memory
+///
+/// The keys are never evicted, so it's best suited to short-lived
+/// processes or to tests.
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+    keys: Mutex<HashMap<IdempotencyKey, SendState>>,
+}
+
+impl MemoryStore {
+    /// Creates an empty store
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl IdempotencyStore for MemoryStore {
+    fn get(&self, key: &IdempotencyKey) -> Option<SendState> {
+        self.keys.lock().unwrap().get(key).copied()
+    }
+
+    fn insert(&self, key: IdempotencyKey, state: SendState) {
+        self.keys.lock().unwrap().insert(key, state);
+    }
+
+    fn remove(&self, key: &IdempotencyKey) {
+        self.keys.lock().unwrap().remove(key);
+    }
+}
+
+/// Errors telling whether the message may have been delivered anyway
+///
+/// Implemented by the errors of the transports of lettre. Only the SMTP
+/// transport fails ambiguously, when the connection breaks or times out
+/// once the message content started being sent.
+pub trait AmbiguousError {
+    /// Whether the server may have accepted the message despite the error
+    fn is_ambiguous(&self) -> bool;
+}
+
+#[cfg(feature = "smtp-transport")]
+impl AmbiguousError for super::smtp::Error {
+    fn is_ambiguous(&self) -> bool {
+        self.is_ambiguous()
+    }
+}
+
+#[cfg(feature = "sendmail-transport")]
+impl AmbiguousError for super::sendmail::Error {
+    fn is_ambiguous(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(feature = "file-transport")]
+impl AmbiguousError for super::file::Error {
+    fn is_ambiguous(&self) -> bool {
+        false
+    }
+}
+
+impl AmbiguousError for super::stub::Error {
+    fn is_ambiguous(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(feature = "builder")]
+impl<E: AmbiguousError> AmbiguousError for super::header_envelope::HeaderEnvelopeError<E> {
+    fn is_ambiguous(&self) -> bool {
+        match self {
+            Self::Envelope(_) => false,
+            Self::Transport(err) => err.is_ambiguous(),
+        }
+    }
+}
+
+/// Transport skipping the messages already accepted by the wrapped transport
+///
+/// Returns `None` instead of sending a message recorded in the store as
+/// sent, and fails with [`IdempotentError::Pending`] for a message whose
+/// previous send failed ambiguously. Concurrent sends of the same message
+/// aren't deduplicated, only the ones following a completed send.
+#[derive(Debug, Clone)]
+pub struct Idempotent<T, S> {
+    transport: T,
+    store: S,
+}
+
+impl<T, S: IdempotencyStore> Idempotent<T, S> {
+    /// Wraps `transport`, recording the sent messages in `store`
+    pub fn new(transport: T, store: S) -> Self {
+        Self { transport, store }
+    }
+
+    /// The wrapped transport
+    pub fn transport(&self) -> &T {
+        &self.transport
+    }
+
+    /// The store recording the sent messages
+    pub fn store(&self) -> &S {
+        &self.store
+    }
+}
+
+impl<T, S: IdempotencyStore> Idempotent<T, S> {
+    /// Whether the message identified by `key` must be sent
+    fn check<E>(&self, key: &IdempotencyKey) -> Result<bool, IdempotentError<E>> {
+        match self.store.get(key) {
+            None => Ok(true),
+            Some(SendState::Sent) => {
+                #[cfg(feature = "tracing")]
+                tracing::debug!("skipping already sent message {key}");
+                Ok(false)
+            }
+            Some(SendState::Pending) => Err(IdempotentError::Pending(key.clone())),
+        }
+    }
+
+    /// Records the outcome of sending the message identified by `key`
+    fn record<O, E: AmbiguousError>(
+        &self,
+        key: IdempotencyKey,
+        result: Result<O, E>,
+    ) -> Result<Option<O>, IdempotentError<E>> {
+        match result {
+            Ok(ok) => {
+                self.store.insert(key, SendState::Sent);
+                Ok(Some(ok))
+            }
+            Err(err) => {
+                if err.is_ambiguous() {
+                    self.store.insert(key, SendState::Pending);
+                }
+                Err(IdempotentError::Transport(err))
+            }
+        }
+    }
+}
+
+impl<T, S> Transport for Idempotent<T, S>
+where
+    T: Transport,
+    T::Error: AmbiguousError,
+    S: IdempotencyStore,
+{
+    type Ok = Option<T::Ok>;
+    type Error = IdempotentError<T::Error>;
+
+    fn send_raw(&self, envelope: &Envelope, email: &[u8]) -> Result<Self::Ok, Self::Error> {
+        let Some(key) = IdempotencyKey::new(envelope, email) else {
+            return self
+                .transport
+                .send_raw(envelope, email)
+                .map(Some)
+                .map_err(IdempotentError::Transport);
+        };
+        if !self.check(&key)? {
+            return Ok(None);
+        }
+
+        self.record(key, self.transport.send_raw(envelope, email))
+    }
+}
+
+#[cfg(any(feature = "tokio1", feature = "async-std1"))]
+#[async_trait]
+impl<T, S> AsyncTransport for Idempotent<T, S>
+where
+    T: AsyncTransport + Sync,
+    T::Error: AmbiguousError,
+    S: IdempotencyStore,
+{
+    type Ok = Option<T::Ok>;
+    type Error = IdempotentError<T::Error>;
+
+    async fn send_raw(&self, envelope: &Envelope, email: &[u8]) -> Result<Self::Ok, Self::Error> {
+        let Some(key) = IdempotencyKey::new(envelope, email) else {
+            return self
+                .transport
+                .send_raw(envelope, email)
+                .await
+                .map(Some)
+                .map_err(IdempotentError::Transport);
+        };
+        if !self.check(&key)? {
+            return Ok(None);
+        }
+
+        let result = self.transport.send_raw(envelope, email).await;
+        self.record(key, result)
+    }
+}
+
+/// Error of an [`Idempotent`] transport
+#[derive(Debug)]
+pub enum IdempotentError<E> {
+    /// A previous send of the message failed ambiguously, it may have
+    /// been delivered
+    ///
+    /// Once the application knows whether it was, it can record it as
+    /// sent or remove it from the store to send it again.
+    Pending(IdempotencyKey),
+    /// The wrapped transport failed to send the message
+    Transport(E),
+}
+
+impl<E: Display> Display for IdempotentError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Pending(key) => {
+                write!(f, "message {key} may have been sent by a previous attempt")
+            }
+            Self::Transport(err) => Display::fmt(err, f),
+        }
+    }
+}
+
+impl<E: StdError + 'static> StdError for IdempotentError<E> {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match self {
+            Self::Pending(_) => None,
+            Self::Transport(err) => err.source(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        cell::Cell,
+        fmt::{self, Display},
+    };
+
+    use super::{
+        AmbiguousError, IdempotencyKey, IdempotencyStore, Idempotent, IdempotentError, MemoryStore,
+        SendState,
+    };
+    use crate::{transport::stub::StubTransport, Address, Envelope, Transport};
+
+    /// Transport timing out after the message content was sent, once
+    struct TimeoutOnce(Cell<bool>);
+
+    #[derive(Debug)]
+    struct Timeout;
+
+    impl Display for Timeout {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str("timed out")
+        }
+    }
+
+    impl AmbiguousError for Timeout {
+        fn is_ambiguous(&self) -> bool {
+            true
+        }
+    }
+
+    impl Transport for TimeoutOnce {
+        type Ok = ();
+        type Error = Timeout;
+
+        fn send_raw(&self, _envelope: &Envelope, _email: &[u8]) -> Result<(), Timeout> {
+            if self.0.replace(false) {
+                Err(Timeout)
+            } else {
+                Ok(())
+            }
+        }
+    }
+
+    fn envelope(to: &str) -> Envelope {
+        Envelope::new(
+            Some("from@example.com".parse().unwrap()),
+            vec![to.parse::<Address>().unwrap()],
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn idempotency_key() {
+        let email = b"Message-ID: <123@example.com>\r\n\r\nHello";
+        let key = IdempotencyKey::new(&envelope("to@example.com"), email).unwrap();
+        assert_eq!(key.as_str(), "<123@example.com>:165409b2a6ca8fbc");
+        assert_ne!(
+            IdempotencyKey::new(&envelope("other@example.com"), email),
+            Some(key)
+        );
+        assert_eq!(
+            IdempotencyKey::new(&envelope("to@example.com"), b"Subject: Hi\r\n\r\nHello"),
+            None
+        );
+    }
+
+    #[test]
+    fn idempotent_send() {
+        let mailer = Idempotent::new(StubTransport::new_ok(), MemoryStore::new());
+        let email = b"Message-ID: <123@example.com>\r\n\r\nHello";
+
+        let send = |to: &str| mailer.send_raw(&envelope(to), email).unwrap();
+        assert!(send("to@example.com").is_some());
+        assert!(send("to@example.com").is_none());
+        assert!(send("other@example.com").is_some());
+        assert_eq!(mailer.transport().messages().len(), 2);
+
+        // Messages without a Message-ID are always sent
+        let email = b"Subject: Hi\r\n\r\nHello";
+        for _ in 0..2 {
+            let receipt = mailer.send_raw(&envelope("to@example.com"), email).unwrap();
+            assert!(receipt.is_some());
+        }
+        assert_eq!(mailer.transport().messages().len(), 4);
+    }
+
+    #[test]
+    fn idempotent_failed_send() {
+        let mailer = Idempotent::new(StubTransport::new_error(), MemoryStore::new());
+        let email = b"Message-ID: <123@example.com>\r\n\r\nHello";
+
+        // Failed sends aren't recorded
+        for _ in 0..2 {
+            assert!(matches!(
+                mailer.send_raw(&envelope("to@example.com"), email),
+                Err(IdempotentError::Transport(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn idempotent_ambiguous_send() {
+        let mailer = Idempotent::new(TimeoutOnce(Cell::new(true)), MemoryStore::new());
+        let email = b"Message-ID: <123@example.com>\r\n\r\nHello";
+        let envelope = envelope("to@example.com");
+        let key = IdempotencyKey::new(&envelope, email).unwrap();
+
+        assert!(matches!(
+            mailer.send_raw(&envelope, email),
+            Err(IdempotentError::Transport(Timeout))
+        ));
+        assert_eq!(mailer.store().get(&key), Some(SendState::Pending));
+
+        // Retrying is refused until the application resolves the pending state
+        let err = mailer.send_raw(&envelope, email).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "message <123@example.com>:165409b2a6ca8fbc may have been sent by a previous attempt"
+        );
+        assert!(matches!(err, IdempotentError::Pending(pending) if pending == key));
+
+        mailer.store().remove(&key);
+        assert!(mailer.send_raw(&envelope, email).unwrap().is_some());
+        assert_eq!(mailer.store().get(&key), Some(SendState::Sent));
+    }
+}
@@ -106,19 +106,31 @@
 #[cfg(any(feature = "async-std1", feature = "tokio1"))]
 use async_trait::async_trait;
 
+pub use self::receipt::Receipt;
 use crate::Envelope;
 #[cfg(feature = "builder")]
 use crate::Message;
 
+#[cfg(any(feature = "tokio1", feature = "async-std1"))]
+#[cfg_attr(docsrs, doc(cfg(any(feature = "tokio1", feature = "async-std1"))))]
+pub mod blocking;
 #[cfg(feature = "file-transport")]
 #[cfg_attr(docsrs, doc(cfg(feature = "file-transport")))]
 pub mod file;
+#[cfg(feature = "builder")]
+#[cfg_attr(docsrs, doc(cfg(feature = "builder")))]
+pub mod header_envelope;
+pub mod idempotent;
+mod receipt;
 #[cfg(feature = "sendmail-transport")]
 #[cfg_attr(docsrs, doc(cfg(feature = "sendmail-transport")))]
 pub mod sendmail;
 #[cfg(feature = "smtp-transport")]
 #[cfg_attr(docsrs, doc(cfg(feature = "smtp-transport")))]
 pub mod smtp;
+#[cfg(feature = "builder")]
+#[cfg_attr(docsrs, doc(cfg(feature = "builder")))]
+pub mod stamp;
 pub mod stub;
 
 /// Blocking Transport method for emails
@@ -139,6 +151,22 @@ pub trait Transport {
         self.send_raw(message.envelope(), &raw)
     }
 
+    /// Sends the email with the given envelope instead of the message one
+    ///
+    /// Useful to send the same message with a different envelope sender
+    /// for each recipient, like with [VERP](https://en.wikipedia.org/wiki/Variable_envelope_return_path),
+    /// without rebuilding it.
+    #[cfg(feature = "builder")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "builder")))]
+    fn send_with_envelope(
+        &self,
+        message: &Message,
+        envelope: &Envelope,
+    ) -> Result<Self::Ok, Self::Error> {
+        let raw = message.formatted();
+        self.send_raw(envelope, &raw)
+    }
+
     fn send_raw(&self, envelope: &Envelope, email: &[u8]) -> Result<Self::Ok, Self::Error>;
 }
 
@@ -165,5 +193,19 @@ pub trait AsyncTransport {
         self.send_raw(envelope, &raw).await
     }
 
+    /// Sends the email with the given envelope instead of the message one
+    ///
+    /// See [`Transport::send_with_envelope`]
+    #[cfg(feature = "builder")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "builder")))]
+    async fn send_with_envelope(
+        &self,
+        message: &Message,
+        envelope: &Envelope,
+    ) -> Result<Self::Ok, Self::Error> {
+        let raw = message.formatted();
+        self.send_raw(envelope, &raw).await
+    }
+
     async fn send_raw(&self, envelope: &Envelope, email: &[u8]) -> Result<Self::Ok, Self::Error>;
 }
@@ -0,0 +1,152 @@
+//! Information about sent messages
+
+use std::time::SystemTime;
+
+/// Information about a message handed to a transport
+///
+/// Returned by the `send_with_receipt` and `send_raw_with_receipt` methods
+/// of the file, sendmail and stub transports. The SMTP transports embed it
+/// in the [`Response`](crate::transport::smtp::response::Response) they
+/// return, which converts into it with `Receipt::from`.
+///
+/// ```rust
+/// # #[cfg(feature = "builder")]
+/// # {
+/// use lettre::{message::header::ContentType, transport::stub::StubTransport, Message};
+///
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// let email = Message::builder()
+///     .from("NoBody <nobody@domain.tld>".parse()?)
+///     .to("Hei <hei@domain.tld>".parse()?)
+///     .subject("Happy new year")
+///     .header(ContentType::TEXT_PLAIN)
+///     .body(String::from("Be happy!"))?;
+///
+/// let receipt = StubTransport::new_ok().send_with_receipt(&email)?;
+/// println!("sent {:?} ({} bytes)", receipt.message_id(), receipt.size());
+/// # Ok(())
+/// # }
+/// # }
+/// ```
+///
+/// It allows correlating logs across transports through the `Message-ID` header.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Receipt {
+    message_id: Option<String>,
+    id: Option<String>,
+    timestamp: SystemTime,
+    size: usize,
+}
+
+impl Receipt {
+    /// Create a receipt for the raw `email`, with an `id` given by the transport
+    pub(crate) fn new(email: &[u8], id: Option<String>) -> Self {
+        Self {
+            message_id: message_id(email),
+            id,
+            timestamp: SystemTime::now(),
+            size: email.len(),
+        }
+    }
+
+    /// Sets the size of the message, for messages streamed by the transport
+    /// of which only the header section is kept
+    #[cfg(feature = "smtp-transport")]
+    pub(crate) fn with_size(mut self, size: usize) -> Self {
+        self.size = size;
+        self
+    }
+
+    /// The `Message-ID` header of the message, if any
+    pub fn message_id(&self) -> Option<&str> {
+        self.message_id.as_deref()
+    }
+
+    /// The identifier given to the message by the transport, if any
+    ///
+    /// For the [`FileTransport`](crate::FileTransport), it's the name of the
+    /// written files, without their extension. For the SMTP transports, it's
+    /// the last line of the server reply, which usually contains the queue id.
+    pub fn id(&self) -> Option<&str> {
+        self.id.as_deref()
+    }
+
+    /// When the message was accepted by the transport
+    pub fn timestamp(&self) -> SystemTime {
+        self.timestamp
+    }
+
+    /// Size of the message in bytes, `0` when it wasn't sent by the transport
+    /// like with SMTP `BURL`
+    pub fn size(&self) -> usize {
+        self.size
+    }
+}
+
+/// Finds the value of the `Message-ID` header of a raw message
+pub(super) fn message_id(email: &[u8]) -> Option<String> {
+    let mut value: Option<String> = None;
+    for line in email.split(|&b| b == b'\n') {
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+        if line.is_empty() {
+            // End of headers
+            break;
+        }
+
+        if line[0] == b' ' || line[0] == b'\t' {
+            // Folded line, continuing the previous header
+            if let Some(value) = &mut value {
+                value.push_str(&String::from_utf8_lossy(line));
+            }
+        } else if value.is_some() {
+            break;
+        } else if let Some(rest) = strip_prefix_ignore_case(line, b"message-id:") {
+            value = Some(String::from_utf8_lossy(rest).into_owned());
+        }
+    }
+
+    value
+        .map(|value| value.trim().to_owned())
+        .filter(|value| !value.is_empty())
+}
+
+fn strip_prefix_ignore_case<'a>(line: &'a [u8], prefix: &[u8]) -> Option<&'a [u8]> {
+    if line.len() >= prefix.len() && line[..prefix.len()].eq_ignore_ascii_case(prefix) {
+        Some(&line[prefix.len()..])
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Receipt;
+
+    #[test]
+    fn receipt_message_id() {
+        let receipt = Receipt::new(
+            concat!(
+                "From: NoBody <nobody@domain.tld>\r\n",
+                "message-id: <123@domain.tld>\r\n",
+                "\r\n",
+                "Message-ID: <456@domain.tld>\r\n"
+            )
+            .as_bytes(),
+            Some("abc".to_owned()),
+        );
+        assert_eq!(receipt.message_id(), Some("<123@domain.tld>"));
+        assert_eq!(receipt.id(), Some("abc"));
+        assert_eq!(receipt.size(), 96);
+
+        let receipt = Receipt::new(
+            b"Message-ID:\r\n <123@domain.tld>\r\nSubject: Hi\r\n\r\nHello",
+            None,
+        );
+        assert_eq!(receipt.message_id(), Some("<123@domain.tld>"));
+
+        let receipt = Receipt::new(b"Subject: Hi\r\n\r\nMessage-ID: <123@domain.tld>", None);
+        assert_eq!(receipt.message_id(), None);
+    }
+}
@@ -91,9 +91,11 @@ use async_trait::async_trait;
 pub use self::error::Error;
 #[cfg(feature = "async-std1")]
 use crate::AsyncStd1Executor;
+#[cfg(feature = "builder")]
+use crate::Message;
 #[cfg(feature = "tokio1")]
 use crate::Tokio1Executor;
-use crate::{address::Envelope, Transport};
+use crate::{address::Envelope, transport::Receipt, Transport};
 #[cfg(any(feature = "async-std1", feature = "tokio1"))]
 use crate::{AsyncTransport, Executor};
 
@@ -230,6 +232,50 @@ where
     }
 }
 
+impl SendmailTransport {
+    /// Sends the email like [`Transport::send`], returning a [`Receipt`]
+    #[cfg(feature = "builder")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "builder")))]
+    pub fn send_with_receipt(&self, message: &Message) -> Result<Receipt, Error> {
+        self.send_raw_with_receipt(message.envelope(), &message.formatted())
+    }
+
+    /// Sends the email like [`Transport::send_raw`], returning a [`Receipt`]
+    pub fn send_raw_with_receipt(
+        &self,
+        envelope: &Envelope,
+        email: &[u8],
+    ) -> Result<Receipt, Error> {
+        self.send_raw(envelope, email)?;
+        Ok(Receipt::new(email, None))
+    }
+}
+
+#[cfg(any(feature = "async-std1", feature = "tokio1"))]
+impl<E> AsyncSendmailTransport<E>
+where
+    E: Executor,
+    Self: AsyncTransport<Ok = (), Error = Error>,
+{
+    /// Sends the email like [`AsyncTransport::send`], returning a [`Receipt`]
+    #[cfg(feature = "builder")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "builder")))]
+    pub async fn send_with_receipt(&self, message: &Message) -> Result<Receipt, Error> {
+        self.send_raw_with_receipt(message.envelope(), &message.formatted())
+            .await
+    }
+
+    /// Sends the email like [`AsyncTransport::send_raw`], returning a [`Receipt`]
+    pub async fn send_raw_with_receipt(
+        &self,
+        envelope: &Envelope,
+        email: &[u8],
+    ) -> Result<Receipt, Error> {
+        self.send_raw(envelope, email).await?;
+        Ok(Receipt::new(email, None))
+    }
+}
+
 impl Transport for SendmailTransport {
     type Ok = ();
     type Error = Error;
@@ -1,17 +1,30 @@
-#[cfg(feature = "pool")]
-use std::sync::Arc;
+#[cfg(feature = "tokio1")]
+use std::io;
 use std::{
-    fmt::{self, Debug},
+    collections::HashMap,
+    fmt::{self, Debug, Display},
+    future::Future,
     marker::PhantomData,
-    time::Duration,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
 };
 
 use async_trait::async_trait;
+#[cfg(feature = "tokio1")]
+use futures_util::future::{BoxFuture, FutureExt};
+use futures_util::{
+    future::{self, Either},
+    io::{AsyncRead, AsyncReadExt, BufReader},
+};
+#[cfg(feature = "builder")]
+use futures_util::{Stream, StreamExt};
 
+#[cfg(feature = "tokio1")]
+use super::client::AsyncTokioStream;
+#[cfg(feature = "oauth2-helpers")]
+use super::oauth2::{OAuth2Credentials, TokenProvider};
 #[cfg(feature = "pool")]
 use super::pool::async_impl::Pool;
-#[cfg(feature = "pool")]
-use super::PoolConfig;
 #[cfg(any(
     feature = "tokio1-native-tls",
     feature = "tokio1-rustls-tls",
@@ -19,12 +32,26 @@ use super::PoolConfig;
 ))]
 use super::Tls;
 use super::{
-    client::AsyncSmtpConnection, ClientId, Credentials, Error, Mechanism, Response, SmtpInfo,
+    client::AsyncSmtpConnection,
+    commands::Noop,
+    dns_cache::{self, DnsCache},
+    error,
+    extension::{CustomExtension, ServerInfo},
+    health::HealthCheck,
+    response::ReplyLimits,
+    semaphore::{Permit, Semaphore},
+    stats::SendObserver,
+    util::read_header_section_async,
+    AsyncSession, ClientId, Credentials, DryRun, Error, HelloNameFn, Mechanism, Response, SmtpInfo,
 };
+#[cfg(feature = "pool")]
+use super::{CircuitState, PoolConfig};
 #[cfg(feature = "async-std1")]
 use crate::AsyncStd1Executor;
 #[cfg(any(feature = "tokio1", feature = "async-std1"))]
 use crate::AsyncTransport;
+#[cfg(feature = "builder")]
+use crate::Message;
 #[cfg(feature = "tokio1")]
 use crate::Tokio1Executor;
 use crate::{Envelope, Executor};
@@ -32,10 +59,27 @@ use crate::{Envelope, Executor};
 /// Asynchronously sends emails using the SMTP protocol
 #[cfg_attr(docsrs, doc(cfg(any(feature = "tokio1", feature = "async-std1"))))]
 pub struct AsyncSmtpTransport<E: Executor> {
-    #[cfg(feature = "pool")]
-    inner: Arc<Pool<E>>,
-    #[cfg(not(feature = "pool"))]
-    inner: AsyncSmtpClient<E>,
+    inner: Inner<E>,
+    concurrency_limit: Option<Arc<Semaphore>>,
+    memory_budget: Option<Arc<Semaphore>>,
+    partitions: Arc<Partitions<E>>,
+}
+
+#[cfg(feature = "pool")]
+type Inner<E> = Arc<Pool<E>>;
+#[cfg(not(feature = "pool"))]
+type Inner<E> = AsyncSmtpClient<E>;
+
+/// The partitions of a transport, see [`AsyncSmtpTransport::partition`]
+struct Partitions<E: Executor> {
+    builder: AsyncSmtpTransportBuilder,
+    partitions: Mutex<HashMap<String, Partition<E>>>,
+}
+
+/// The connection pool and the send limit of a partition
+struct Partition<E: Executor> {
+    inner: Inner<E>,
+    concurrency_limit: Option<Arc<Semaphore>>,
 }
 
 #[cfg(feature = "tokio1")]
@@ -44,11 +88,59 @@ impl AsyncTransport for AsyncSmtpTransport<Tokio1Executor> {
     type Ok = Response;
     type Error = Error;
 
+    /// Sends an email
+    #[cfg(feature = "builder")]
+    async fn send(&self, message: Message) -> Result<Self::Ok, Self::Error> {
+        #[cfg(feature = "tracing")]
+        tracing::trace!("starting to send an email");
+
+        let len = message.estimated_len();
+        let budget = self.reserve_memory(len).await;
+        self.retry_greylisted(len, budget, || async {
+            let raw = message.formatted();
+            self.deliver_once(message.envelope(), &raw).await
+        })
+        .await
+    }
+
+    /// Sends the email with the given envelope instead of the message one
+    #[cfg(feature = "builder")]
+    async fn send_with_envelope(
+        &self,
+        message: &Message,
+        envelope: &Envelope,
+    ) -> Result<Self::Ok, Self::Error> {
+        let len = message.estimated_len();
+        let budget = self.reserve_memory(len).await;
+        self.retry_greylisted(len, budget, || async {
+            let raw = message.formatted();
+            self.deliver_once(envelope, &raw).await
+        })
+        .await
+    }
+
     /// Sends an email
     async fn send_raw(&self, envelope: &Envelope, email: &[u8]) -> Result<Self::Ok, Self::Error> {
+        let budget = self.reserve_memory(email.len()).await;
+        self.retry_greylisted(email.len(), budget, || self.deliver_once(envelope, email))
+            .await
+    }
+}
+
+#[cfg(feature = "tokio1")]
+impl AsyncSmtpTransport<Tokio1Executor> {
+    /// Sends a formatted email, without retrying
+    async fn deliver_once(&self, envelope: &Envelope, email: &[u8]) -> Result<Response, Error> {
+        if self.client().info.dry_run {
+            return self.dry_run(envelope).await?.into_result();
+        }
+
+        let _permit = self.acquire_permit().await;
         let mut conn = self.inner.connection().await?;
 
-        let result = conn.send(envelope, email).await?;
+        let result = conn
+            .send(envelope, &self.client().info.stamp_user_agent(email))
+            .await?;
 
         #[cfg(not(feature = "pool"))]
         conn.abort().await;
@@ -63,11 +155,59 @@ impl AsyncTransport for AsyncSmtpTransport<AsyncStd1Executor> {
     type Ok = Response;
     type Error = Error;
 
+    /// Sends an email
+    #[cfg(feature = "builder")]
+    async fn send(&self, message: Message) -> Result<Self::Ok, Self::Error> {
+        #[cfg(feature = "tracing")]
+        tracing::trace!("starting to send an email");
+
+        let len = message.estimated_len();
+        let budget = self.reserve_memory(len).await;
+        self.retry_greylisted(len, budget, || async {
+            let raw = message.formatted();
+            self.deliver_once(message.envelope(), &raw).await
+        })
+        .await
+    }
+
+    /// Sends the email with the given envelope instead of the message one
+    #[cfg(feature = "builder")]
+    async fn send_with_envelope(
+        &self,
+        message: &Message,
+        envelope: &Envelope,
+    ) -> Result<Self::Ok, Self::Error> {
+        let len = message.estimated_len();
+        let budget = self.reserve_memory(len).await;
+        self.retry_greylisted(len, budget, || async {
+            let raw = message.formatted();
+            self.deliver_once(envelope, &raw).await
+        })
+        .await
+    }
+
     /// Sends an email
     async fn send_raw(&self, envelope: &Envelope, email: &[u8]) -> Result<Self::Ok, Self::Error> {
+        let budget = self.reserve_memory(email.len()).await;
+        self.retry_greylisted(email.len(), budget, || self.deliver_once(envelope, email))
+            .await
+    }
+}
+
+#[cfg(feature = "async-std1")]
+impl AsyncSmtpTransport<AsyncStd1Executor> {
+    /// Sends a formatted email, without retrying
+    async fn deliver_once(&self, envelope: &Envelope, email: &[u8]) -> Result<Response, Error> {
+        if self.client().info.dry_run {
+            return self.dry_run(envelope).await?.into_result();
+        }
+
+        let _permit = self.acquire_permit().await;
         let mut conn = self.inner.connection().await?;
 
-        let result = conn.send(envelope, email).await?;
+        let result = conn
+            .send(envelope, &self.client().info.stamp_user_agent(email))
+            .await?;
 
         conn.quit().await?;
 
@@ -142,6 +282,82 @@ where
             .tls(Tls::Required(tls_parameters)))
     }
 
+    /// Transport for Gmail, using SMTP over TLS to `smtp.gmail.com`
+    ///
+    /// Authenticates with `PLAIN`, which requires an [app password] when
+    /// two-step verification is enabled. To authenticate with an OAuth 2.0
+    /// access token instead, set the mechanism to [`Mechanism::Xoauth2`].
+    ///
+    /// [app password]: https://support.google.com/accounts/answer/185833
+    #[cfg(any(
+        feature = "tokio1-native-tls",
+        feature = "tokio1-rustls-tls",
+        feature = "async-std1-rustls-tls"
+    ))]
+    #[cfg_attr(
+        docsrs,
+        doc(cfg(any(
+            feature = "tokio1-native-tls",
+            feature = "tokio1-rustls-tls",
+            feature = "async-std1-rustls-tls"
+        )))
+    )]
+    pub fn gmail_relay() -> Result<AsyncSmtpTransportBuilder, Error> {
+        Ok(Self::relay("smtp.gmail.com")?.authentication(vec![Mechanism::Plain]))
+    }
+
+    /// Transport for Microsoft 365, using STARTTLS to `smtp.office365.com`
+    ///
+    /// The server only accepts `STARTTLS` on the submission port, and doesn't
+    /// support `PLAIN`, so `LOGIN` is used. SMTP AUTH must be enabled for the
+    /// mailbox. To authenticate with an OAuth 2.0 access token instead,
+    /// set the mechanism to [`Mechanism::Xoauth2`].
+    #[cfg(any(
+        feature = "tokio1-native-tls",
+        feature = "tokio1-rustls-tls",
+        feature = "async-std1-rustls-tls"
+    ))]
+    #[cfg_attr(
+        docsrs,
+        doc(cfg(any(
+            feature = "tokio1-native-tls",
+            feature = "tokio1-rustls-tls",
+            feature = "async-std1-rustls-tls"
+        )))
+    )]
+    pub fn office365_relay() -> Result<AsyncSmtpTransportBuilder, Error> {
+        Ok(Self::starttls_relay("smtp.office365.com")?.authentication(vec![Mechanism::Login]))
+    }
+
+    /// Transport for Amazon SES in `region`, like `us-east-1`, using SMTP over TLS
+    ///
+    /// The credentials are the SES SMTP credentials, which are different
+    /// from the AWS access keys.
+    #[cfg(any(
+        feature = "tokio1-native-tls",
+        feature = "tokio1-rustls-tls",
+        feature = "async-std1-rustls-tls"
+    ))]
+    #[cfg_attr(
+        docsrs,
+        doc(cfg(any(
+            feature = "tokio1-native-tls",
+            feature = "tokio1-rustls-tls",
+            feature = "async-std1-rustls-tls"
+        )))
+    )]
+    pub fn ses_relay(region: &str) -> Result<AsyncSmtpTransportBuilder, Error> {
+        if region.is_empty()
+            || !region
+                .bytes()
+                .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-')
+        {
+            return Err(error::connection(format!("invalid SES region '{region}'")));
+        }
+
+        Self::relay(&format!("email-smtp.{region}.amazonaws.com"))
+    }
+
     /// Creates a new local SMTP client to port 25
     ///
     /// Shortcut for local unencrypted relay (typical local email daemon that will handle relaying)
@@ -259,6 +475,24 @@ where
         super::connection_url::from_connection_url(connection_url)
     }
 
+    /// Creates a new transport builder from the environment
+    ///
+    /// Makes configuring containerized deployments easy. The connection URL
+    /// is read from the `LETTRE_SMTP_URL` environment variable, in the format
+    /// described in [`from_url`](Self::from_url). If `LETTRE_CA_BUNDLE` is set,
+    /// the PEM encoded certificates in the file it points to are trusted
+    /// in addition to the root certificates of the TLS backend.
+    ///
+    /// Fails if `LETTRE_SMTP_URL` isn't set or if the CA bundle can't be read.
+    #[cfg(any(feature = "native-tls", feature = "rustls-tls", feature = "boring-tls"))]
+    #[cfg_attr(
+        docsrs,
+        doc(cfg(any(feature = "native-tls", feature = "rustls-tls", feature = "boring-tls")))
+    )]
+    pub fn from_env() -> Result<AsyncSmtpTransportBuilder, Error> {
+        super::connection_url::from_env()
+    }
+
     /// Tests the SMTP connection
     ///
     /// `test_connection()` tests the connection by using the SMTP NOOP command.
@@ -273,16 +507,342 @@ where
 
         Ok(is_connected)
     }
+
+    /// Checks that the server can be reached, for a health endpoint
+    ///
+    /// Unlike [`Self::test_connection`], the check doesn't fail, and the
+    /// [`HealthCheck`] gives the latency and the error, if any.
+    pub async fn health_check(&self) -> HealthCheck {
+        let start = Instant::now();
+        let result = self.noop().await;
+        HealthCheck::new(
+            start.elapsed(),
+            result,
+            #[cfg(feature = "pool")]
+            self.circuit_state(),
+        )
+    }
+
+    async fn noop(&self) -> Result<(), Error> {
+        let mut conn = self.inner.connection().await?;
+
+        conn.command(Noop).await?;
+
+        #[cfg(not(feature = "pool"))]
+        conn.quit().await?;
+
+        Ok(())
+    }
+
+    /// Gets the capabilities announced by the server in its `EHLO` response
+    ///
+    /// Useful for adapting to the relay or for diagnostics, for example to check
+    /// that it supports `STARTTLS` or `SMTPUTF8`. The connection is closed afterward
+    /// if a connection pool is not used.
+    pub async fn capabilities(&self) -> Result<ServerInfo, Error> {
+        #[cfg_attr(feature = "pool", allow(unused_mut))]
+        let mut conn = self.inner.connection().await?;
+
+        let server_info = conn.server_info().clone();
+
+        #[cfg(not(feature = "pool"))]
+        conn.quit().await?;
+
+        Ok(server_info)
+    }
+
+    /// Gets the state of the circuit breaker of the connection pool
+    ///
+    /// See [`PoolConfig::circuit_breaker`]
+    #[cfg(feature = "pool")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "pool")))]
+    pub fn circuit_state(&self) -> CircuitState {
+        self.inner.circuit_state()
+    }
+
+    /// Forgets the cached addresses of the server, resolving its hostname
+    /// again for the next connection
+    ///
+    /// See [`AsyncSmtpTransportBuilder::dns_cache_ttl`].
+    pub fn flush_dns_cache(&self) {
+        if let Some(cache) = &self.client().info.dns_cache {
+            cache.flush();
+        }
+    }
+
+    /// Gets the transport of the `key` partition, like a tenant or a campaign
+    ///
+    /// Each partition has its own connection pool, with its own circuit
+    /// breaker, and its own [limit](AsyncSmtpTransportBuilder::max_concurrent_sends)
+    /// of emails sent at the same time, so that the traffic and the failures
+    /// of a partition don't affect the others. The
+    /// [memory budget](AsyncSmtpTransportBuilder::memory_budget) is shared.
+    ///
+    /// Partitions are created with the configuration of the transport when
+    /// first used, and shared by all its clones until they are
+    /// [removed](Self::remove_partition).
+    ///
+    /// ```rust,no_run
+    /// # #[cfg(feature = "tokio1")]
+    /// # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+    /// use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+    ///
+    /// let mailer: AsyncSmtpTransport<Tokio1Executor> =
+    ///     AsyncSmtpTransport::<Tokio1Executor>::relay("smtp.example.com")?.build();
+    ///
+    /// let email = Message::builder()
+    ///     .from("NoBody <nobody@domain.tld>".parse()?)
+    ///     .to("Hei <hei@domain.tld>".parse()?)
+    ///     .subject("Happy new year")
+    ///     .body(String::from("Be happy!"))?;
+    /// mailer.partition("tenant-42").send(email).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn partition(&self, key: &str) -> Self {
+        let mut partitions = self.partitions.partitions.lock().unwrap();
+        let partition = partitions
+            .entry(key.to_owned())
+            .or_insert_with(|| self.partitions.builder.build_partition())
+            .clone();
+
+        Self {
+            inner: partition.inner,
+            concurrency_limit: partition.concurrency_limit,
+            memory_budget: self.memory_budget.clone(),
+            partitions: Arc::clone(&self.partitions),
+        }
+    }
+
+    /// Removes the `key` partition, returning whether it existed
+    ///
+    /// Its connections are closed once the transports of the partition
+    /// still in use are dropped.
+    pub fn remove_partition(&self, key: &str) -> bool {
+        let mut partitions = self.partitions.partitions.lock().unwrap();
+        partitions.remove(key).is_some()
+    }
+
+    /// Sends a message stored on an IMAP server, referenced by an URLAUTH `url`
+    ///
+    /// The message content isn't uploaded again, the server fetches it using
+    /// the `BURL` extension. See [`AsyncSmtpConnection::send_burl`](super::client::AsyncSmtpConnection::send_burl).
+    pub async fn send_burl(&self, envelope: &Envelope, url: &str) -> Result<Response, Error> {
+        let _permit = self.acquire_permit().await;
+        let mut conn = self.inner.connection().await?;
+
+        let result = conn.send_burl(envelope, url).await?;
+
+        #[cfg(not(feature = "pool"))]
+        conn.quit().await?;
+
+        Ok(result)
+    }
+
+    /// Sends the message read from `reader`, streaming it to the server
+    ///
+    /// Unlike [`AsyncTransport::send_raw`](crate::AsyncTransport::send_raw),
+    /// the message isn't loaded in memory, only its header section, to add
+    /// the configured `User-Agent`. As the message can't be read again, it
+    /// isn't sent again when greylisted.
+    /// See [`AsyncSmtpConnection::send_from_async_read`].
+    pub async fn send_raw_from_async_read<R: AsyncRead + Unpin>(
+        &self,
+        envelope: &Envelope,
+        reader: R,
+    ) -> Result<Response, Error> {
+        let mut reader = BufReader::new(reader);
+        let headers = read_header_section_async(&mut reader)
+            .await
+            .map_err(error::client)?;
+        let headers = self.client().info.stamp_user_agent(&headers);
+
+        let _permit = self.acquire_permit().await;
+        let mut conn = self.inner.connection().await?;
+
+        let result = conn
+            .send_from_async_read(envelope, headers.as_ref().chain(reader))
+            .await?;
+
+        #[cfg(not(feature = "pool"))]
+        conn.quit().await?;
+
+        Ok(result)
+    }
+
+    /// Checks which recipients of `envelope` the server accepts, without
+    /// sending a message
+    ///
+    /// See [`AsyncSmtpConnection::dry_run`](super::client::AsyncSmtpConnection::dry_run).
+    pub async fn dry_run(&self, envelope: &Envelope) -> Result<DryRun, Error> {
+        let _permit = self.acquire_permit().await;
+        let mut conn = self.inner.connection().await?;
+
+        let dry_run = conn.dry_run(envelope).await?;
+
+        #[cfg(not(feature = "pool"))]
+        conn.quit().await?;
+
+        Ok(dry_run)
+    }
+
+    /// Checks out a connection for running transactions manually
+    ///
+    /// See [`AsyncSession`]
+    pub async fn session(&self) -> Result<AsyncSession<E>, Error> {
+        Ok(AsyncSession::new(self.inner.connection().await?))
+    }
+
+    /// Sends all the `messages`, with up to `concurrency` of them being sent at once
+    ///
+    /// Returns the results in the order of the messages. With a connection
+    /// pool, the sends are spread over the pooled connections, which is much
+    /// more efficient than spawning a task per message.
+    ///
+    /// ```rust,no_run
+    /// # #[cfg(feature = "tokio1")]
+    /// # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+    /// use futures_util::{stream, StreamExt};
+    /// use lettre::{AsyncSmtpTransport, Message, Tokio1Executor};
+    ///
+    /// let mailer: AsyncSmtpTransport<Tokio1Executor> =
+    ///     AsyncSmtpTransport::<Tokio1Executor>::relay("smtp.example.com")?.build();
+    ///
+    /// let messages = ["alice@example.com", "bob@example.com"].map(|to| {
+    ///     Message::builder()
+    ///         .from("NoBody <nobody@domain.tld>".parse().unwrap())
+    ///         .to(to.parse().unwrap())
+    ///         .subject("Happy new year")
+    ///         .body(String::from("Be happy!"))
+    ///         .unwrap()
+    /// });
+    ///
+    /// let mut results = mailer.send_all(stream::iter(messages), 4);
+    /// while let Some(result) = results.next().await {
+    ///     result?;
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// If `concurrency` is zero.
+    #[cfg(feature = "builder")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "builder")))]
+    pub fn send_all<'a, S>(
+        &'a self,
+        messages: S,
+        concurrency: usize,
+    ) -> impl Stream<Item = Result<Response, Error>> + 'a
+    where
+        S: Stream<Item = Message> + 'a,
+        Self: AsyncTransport<Ok = Response, Error = Error> + Sync,
+    {
+        assert!(concurrency > 0, "concurrency must not be zero");
+        messages
+            .map(move |message| self.send(message))
+            .buffered(concurrency)
+    }
+
+    pub(super) fn client(&self) -> &AsyncSmtpClient<E> {
+        #[cfg(feature = "pool")]
+        let client = self.inner.client();
+        #[cfg(not(feature = "pool"))]
+        let client = &self.inner;
+
+        client
+    }
+
+    /// Sends a formatted email of about `len` bytes with `deliver_once`, and
+    /// once more after the configured delay if the server greylisted it
+    ///
+    /// The `budget` reserved for the message is released while waiting, and
+    /// reserved again before sending it again. `deliver_once` formats the
+    /// message itself, so that the formatted copy is dropped while waiting.
+    async fn retry_greylisted<'a, F, Fut>(
+        &'a self,
+        len: usize,
+        budget: Option<Permit<'a>>,
+        deliver_once: F,
+    ) -> Result<Response, Error>
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = Result<Response, Error>> + 'a,
+    {
+        match deliver_once().await {
+            Err(err) if err.is_greylisted() => match self.client().info.greylist_retry {
+                Some(delay) => {
+                    #[cfg(feature = "tracing")]
+                    tracing::debug!("greylisted, sending again in {:?}", delay);
+                    drop(budget);
+                    E::sleep(delay).await;
+                    let _budget = self.reserve_memory(len).await;
+                    deliver_once().await
+                }
+                None => Err(err),
+            },
+            result => result,
+        }
+    }
+
+    /// Wait for a send slot when a concurrency limit is set
+    async fn acquire_permit(&self) -> Option<Permit<'_>> {
+        match &self.concurrency_limit {
+            Some(semaphore) => Some(semaphore.acquire().await),
+            None => None,
+        }
+    }
+
+    /// Wait for room to format and send a message of about `len` bytes
+    /// when a memory budget is set
+    ///
+    /// Sending holds both the formatted message and its dot-stuffed copy.
+    async fn reserve_memory(&self, len: usize) -> Option<Permit<'_>> {
+        match &self.memory_budget {
+            Some(semaphore) => Some(semaphore.acquire_many(len.saturating_mul(2)).await),
+            None => None,
+        }
+    }
+}
+
+/// Formats the configuration of the transport for logging
+///
+/// The secrets are left out, and only the start of the username is kept.
+/// See the [`SmtpTransport`](super::SmtpTransport) implementation.
+impl<E: Executor> Display for AsyncSmtpTransport<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        #[cfg(feature = "pool")]
+        let pool_max_size = Some(self.inner.max_size());
+        #[cfg(not(feature = "pool"))]
+        let pool_max_size = None;
+
+        self.client().info.fmt_summary(f, pool_max_size)
+    }
 }
 
 impl<E: Executor> Debug for AsyncSmtpTransport<E> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let mut builder = f.debug_struct("AsyncSmtpTransport");
         builder.field("inner", &self.inner);
+        builder.field("concurrency_limit", &self.concurrency_limit);
+        builder.field("memory_budget", &self.memory_budget);
         builder.finish()
     }
 }
 
+impl<E: Executor> Clone for Partition<E> {
+    fn clone(&self) -> Self {
+        Self {
+            #[cfg(feature = "pool")]
+            inner: Arc::clone(&self.inner),
+            #[cfg(not(feature = "pool"))]
+            inner: self.inner.clone(),
+            concurrency_limit: self.concurrency_limit.clone(),
+        }
+    }
+}
+
 impl<E> Clone for AsyncSmtpTransport<E>
 where
     E: Executor,
@@ -293,18 +853,28 @@ where
             inner: Arc::clone(&self.inner),
             #[cfg(not(feature = "pool"))]
             inner: self.inner.clone(),
+            concurrency_limit: self.concurrency_limit.clone(),
+            memory_budget: self.memory_budget.clone(),
+            partitions: Arc::clone(&self.partitions),
         }
     }
 }
 
 /// Contains client configuration.
 /// Instances of this struct can be created using functions of [`AsyncSmtpTransport`].
+///
+/// Clone the builder to build variations of the same base configuration,
+/// like transports authenticating with the credentials of each tenant.
 #[derive(Debug, Clone)]
 #[cfg_attr(docsrs, doc(cfg(any(feature = "tokio1", feature = "async-std1"))))]
 pub struct AsyncSmtpTransportBuilder {
     info: SmtpInfo,
     #[cfg(feature = "pool")]
     pool_config: PoolConfig,
+    max_concurrent_sends: Option<usize>,
+    memory_budget: Option<usize>,
+    #[cfg(feature = "tokio1")]
+    connector: Option<Connector>,
 }
 
 /// Builder for the SMTP `AsyncSmtpTransport`
@@ -320,12 +890,53 @@ impl AsyncSmtpTransportBuilder {
             info,
             #[cfg(feature = "pool")]
             pool_config: PoolConfig::default(),
+            max_concurrent_sends: None,
+            memory_budget: None,
+            #[cfg(feature = "tokio1")]
+            connector: None,
         }
     }
 
     /// Set the name used during EHLO
     pub fn hello_name(mut self, name: ClientId) -> Self {
         self.info.hello_name = name;
+        self.info.hello_name_fn = None;
+        self
+    }
+
+    /// Register a hook adding parameters to the `MAIL FROM` and `RCPT TO` commands
+    ///
+    /// See [`CustomExtension`]
+    pub fn custom_extension<X>(mut self, extension: X) -> Self
+    where
+        X: CustomExtension + 'static,
+    {
+        self.info.custom_extensions.push(Arc::new(extension));
+        self
+    }
+
+    /// Register a hook called for each message accepted by the server,
+    /// with its size and the time taken to send it
+    ///
+    /// See [`SendObserver`]
+    pub fn send_observer<X>(mut self, observer: Arc<X>) -> Self
+    where
+        X: SendObserver + 'static,
+    {
+        self.info.send_observers.push(observer);
+        self
+    }
+
+    /// Set a function producing the name used during EHLO, called for each new connection
+    ///
+    /// Useful when connections may go out from different source addresses,
+    /// like behind a NAT with several public IPs. Replaces the name set via
+    /// [`Self::hello_name`].
+    pub fn hello_name_fn<F>(mut self, hello_name_fn: F) -> Self
+    where
+        F: Fn() -> ClientId + Send + Sync + 'static,
+    {
+        self.info.hello_name_fn = Some(HelloNameFn(Arc::new(hello_name_fn)));
         self
     }
 
@@ -335,6 +946,32 @@ impl AsyncSmtpTransportBuilder {
         self
     }
 
+    /// Authenticate with OAuth 2.0 tokens from `provider` using `XOAUTH2`
+    ///
+    /// A token is requested for each new connection, replacing the
+    /// [`Self::credentials`] and [`Self::authentication`] settings.
+    #[cfg(feature = "oauth2-helpers")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "oauth2-helpers")))]
+    pub fn oauth2_token_provider<P>(mut self, user: String, provider: P) -> Self
+    where
+        P: TokenProvider + 'static,
+    {
+        self.info.oauth2 = Some(OAuth2Credentials::new(user, Arc::new(provider)));
+        self.info.authentication = vec![Mechanism::Xoauth2];
+        self
+    }
+
+    /// Allow authenticating over unencrypted connections
+    ///
+    /// By default, sending fails instead of sending the credentials in
+    /// cleartext when the connection isn't encrypted, for example with
+    /// [`Tls::None`](super::client::Tls::None) or a TLS misconfiguration.
+    /// Only use it for test servers in a trusted network.
+    pub fn allow_plaintext_auth(mut self) -> Self {
+        self.info.plaintext_auth = true;
+        self
+    }
+
     /// Set the authentication mechanism to use
     pub fn authentication(mut self, mechanisms: Vec<Mechanism>) -> Self {
         self.info.authentication = mechanisms;
@@ -364,6 +1001,101 @@ impl AsyncSmtpTransportBuilder {
         self
     }
 
+    /// Set the maximum time to wait for the whole reply to each command
+    ///
+    /// The [timeout](Self::timeout) applies to each network read, so a server
+    /// sending its reply a few bytes at a time can make a command last forever.
+    /// The deadline bounds the total time, failing with an error for which
+    /// [`Error::is_timeout`] is true. Disabled by default.
+    pub fn command_deadline(mut self, deadline: Option<Duration>) -> Self {
+        self.info.command_deadline = deadline;
+        self
+    }
+
+    /// Set the maximum time to set up a connection
+    ///
+    /// It covers connecting, the TLS handshake and authentication, failing
+    /// with an error for which [`Error::is_timeout`] is true. Disabled by default.
+    pub fn connect_deadline(mut self, deadline: Option<Duration>) -> Self {
+        self.info.connect_deadline = deadline;
+        self
+    }
+
+    /// Keep the addresses the server hostname resolved to for `ttl`
+    ///
+    /// By default the hostname is resolved again for each new connection,
+    /// including the ones opened by the pool to replace closed connections.
+    /// The cached addresses are shared by the [partitions](AsyncSmtpTransport::partition),
+    /// forgotten when connecting to all of them fails, and can be forgotten at
+    /// any time with [`AsyncSmtpTransport::flush_dns_cache`]. They aren't used
+    /// with a custom [connector](Self::connector). Disabled by default.
+    pub fn dns_cache_ttl(mut self, ttl: Option<Duration>) -> Self {
+        self.info.dns_cache = ttl.map(DnsCache::new);
+        self
+    }
+
+    /// Send `HELO` if the server rejects `EHLO` with a permanent error
+    ///
+    /// Allows talking to legacy appliances which don't support ESMTP. No
+    /// extension is available after `HELO`, including `STARTTLS` and `AUTH`.
+    /// Disabled by default.
+    pub fn helo_fallback(mut self, enabled: bool) -> Self {
+        self.info.helo_fallback = enabled;
+        self
+    }
+
+    /// Check the recipients of the messages instead of sending them
+    ///
+    /// Sending a message starts a transaction with `MAIL` and a `RCPT` for
+    /// each recipient, then cancels it with `RSET` instead of sending `DATA`.
+    /// It fails like a real send when a recipient is rejected, otherwise
+    /// the reply to `RSET` is returned. Use [`AsyncSmtpTransport::dry_run`] to get all the
+    /// rejected recipients. Disabled by default.
+    pub fn dry_run(mut self, enabled: bool) -> Self {
+        self.info.dry_run = enabled;
+        self
+    }
+
+    /// Send a message again once, after `delay`, if the server greylisted it
+    ///
+    /// Greylisting servers defer the first delivery attempt from an unknown
+    /// sender, see [`Error::is_greylisted`]. The send waits for the whole delay,
+    /// which should be a few minutes, without holding its share of the
+    /// [memory budget](Self::memory_budget): the formatted message is dropped
+    /// while waiting, and formatted again. With
+    /// [`send_raw`](crate::AsyncTransport::send_raw), the caller still owns the
+    /// raw message during the delay. The server may also say how long
+    /// to wait with [`Error::retry_after`]. Disabled by default.
+    pub fn greylist_retry(mut self, delay: Option<Duration>) -> Self {
+        self.info.greylist_retry = delay;
+        self
+    }
+
+    /// Add a `User-Agent` header to the messages which have neither a
+    /// `User-Agent` nor an `X-Mailer` header
+    ///
+    /// Helps identifying which application sent a message across a fleet of
+    /// services. Use [`DEFAULT_USER_AGENT`](super::DEFAULT_USER_AGENT) to
+    /// identify lettre and its version. Line breaks in `user_agent` are
+    /// replaced by spaces. Disabled by default.
+    ///
+    /// The header is added to the formatted message, after any DKIM signature
+    /// was computed, so it shouldn't be part of the signed headers.
+    pub fn user_agent(mut self, user_agent: Option<String>) -> Self {
+        self.info.user_agent = user_agent
+            .map(|user_agent| user_agent.split_whitespace().collect::<Vec<_>>().join(" "));
+        self
+    }
+
+    /// Set the limits on the size of server replies
+    ///
+    /// Replies read while setting up the connection, up to the `EHLO` reply,
+    /// are bound by the default limits.
+    pub fn reply_limits(mut self, limits: ReplyLimits) -> Self {
+        self.info.reply_limits = limits;
+        self
+    }
+
     /// Set the TLS settings to use
     ///
     /// # ⚠️⚠️⚠️ You probably don't need to call this method ⚠️⚠️⚠️
@@ -404,26 +1136,181 @@ impl AsyncSmtpTransportBuilder {
         self
     }
 
+    /// Get the hostname or address of the server
+    pub fn get_host(&self) -> &str {
+        &self.info.server
+    }
+
+    /// Get the port to connect to
+    pub fn get_port(&self) -> u16 {
+        self.info.port
+    }
+
+    /// Get the TLS settings
+    #[cfg(any(
+        feature = "tokio1-native-tls",
+        feature = "tokio1-rustls-tls",
+        feature = "async-std1-rustls-tls"
+    ))]
+    #[cfg_attr(
+        docsrs,
+        doc(cfg(any(
+            feature = "tokio1-native-tls",
+            feature = "tokio1-rustls-tls",
+            feature = "async-std1-rustls-tls"
+        )))
+    )]
+    pub fn get_tls(&self) -> &Tls {
+        &self.info.tls
+    }
+
+    /// Get the configuration of the connection pool
+    #[cfg(feature = "pool")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "pool")))]
+    pub fn get_pool_config(&self) -> &PoolConfig {
+        &self.pool_config
+    }
+
+    /// Limit the number of emails being sent at the same time
+    ///
+    /// Additional `send` calls wait for a slot to be freed. Unlike the pool
+    /// size, this prevents a burst of sends from opening many connections at
+    /// once, which can trip connection rate limits at email providers.
+    /// The limit is shared by all clones of the built transport.
+    ///
+    /// # Panics
+    ///
+    /// If `max_concurrent_sends` is zero.
+    pub fn max_concurrent_sends(mut self, max_concurrent_sends: usize) -> Self {
+        assert!(
+            max_concurrent_sends > 0,
+            "max_concurrent_sends must not be zero"
+        );
+        self.max_concurrent_sends = Some(max_concurrent_sends);
+        self
+    }
+
+    /// Limit the memory used by the emails being sent at the same time to about `bytes`
+    ///
+    /// Formatting and sending an email holds about twice its size in memory,
+    /// which adds up when sending many big emails at once. Additional sends
+    /// wait, before formatting the email, for the ones in progress to free
+    /// enough of the budget. An email bigger than the whole budget waits for
+    /// all of it, and is then sent alone. The budget is shared by all clones
+    /// of the built transport.
+    ///
+    /// # Panics
+    ///
+    /// If `bytes` is zero.
+    pub fn memory_budget(mut self, bytes: usize) -> Self {
+        assert!(bytes > 0, "memory_budget must not be zero");
+        self.memory_budget = Some(bytes);
+        self
+    }
+
+    /// Open the network streams of new connections with `connector`
+    ///
+    /// `connector` is called with the server and port of the transport,
+    /// instead of resolving them and connecting over TCP. This allows
+    /// sending emails over another kind of stream, like a proxy or
+    /// a simulated network such as [turmoil](https://docs.rs/turmoil),
+    /// for deterministic tests of connection failures.
+    /// Encryption and authentication are handled like for TCP streams,
+    /// and the [timeout](Self::timeout) applies to `connector`.
+    ///
+    /// ```rust,no_run
+    /// use lettre::{transport::smtp::client::AsyncTokioStream, AsyncSmtpTransport, Tokio1Executor};
+    /// use tokio1_crate::net::TcpStream;
+    ///
+    /// let mailer = AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous("mail.internal")
+    ///     .connector(|_server, _port| async move {
+    ///         // Connect through a tunnel, a Unix socket, a simulated network...
+    ///         let stream = TcpStream::connect(("127.0.0.1", 2525)).await?;
+    ///         Ok(Box::new(stream) as Box<dyn AsyncTokioStream>)
+    ///     })
+    ///     .build::<Tokio1Executor>();
+    /// ```
+    #[cfg(feature = "tokio1")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "tokio1")))]
+    pub fn connector<F, Fut>(mut self, connector: F) -> Self
+    where
+        F: Fn(String, u16) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = io::Result<Box<dyn AsyncTokioStream>>> + Send + 'static,
+    {
+        self.connector = Some(Connector(Arc::new(move |server, port| {
+            connector(server, port).boxed()
+        })));
+        self
+    }
+
     /// Build the transport
     pub fn build<E>(self) -> AsyncSmtpTransport<E>
+    where
+        E: Executor,
+    {
+        let partition = self.build_partition();
+
+        AsyncSmtpTransport {
+            inner: partition.inner,
+            concurrency_limit: partition.concurrency_limit,
+            memory_budget: self
+                .memory_budget
+                .map(|bytes| Arc::new(Semaphore::new(bytes))),
+            partitions: Arc::new(Partitions {
+                builder: self,
+                partitions: Mutex::new(HashMap::new()),
+            }),
+        }
+    }
+
+    /// Build the connection pool and the send limit of a partition
+    fn build_partition<E>(&self) -> Partition<E>
     where
         E: Executor,
     {
         let client = AsyncSmtpClient {
-            info: self.info,
+            info: self.info.clone(),
+            #[cfg(feature = "tokio1")]
+            connector: self.connector.clone(),
             marker_: PhantomData,
         };
 
         #[cfg(feature = "pool")]
-        let client = Pool::new(self.pool_config, client);
+        let client = Pool::new(self.pool_config.clone(), client);
+
+        Partition {
+            inner: client,
+            concurrency_limit: self
+                .max_concurrent_sends
+                .map(|permits| Arc::new(Semaphore::new(permits))),
+        }
+    }
+}
+
+/// Opens the network streams of new connections, see [`AsyncSmtpTransportBuilder::connector`]
+#[cfg(feature = "tokio1")]
+#[derive(Clone)]
+#[allow(clippy::type_complexity)]
+struct Connector(
+    Arc<
+        dyn Fn(String, u16) -> BoxFuture<'static, io::Result<Box<dyn AsyncTokioStream>>>
+            + Send
+            + Sync,
+    >,
+);
 
-        AsyncSmtpTransport { inner: client }
+#[cfg(feature = "tokio1")]
+impl Debug for Connector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Connector")
     }
 }
 
 /// Build client
 pub struct AsyncSmtpClient<E> {
-    info: SmtpInfo,
+    pub(super) info: SmtpInfo,
+    #[cfg(feature = "tokio1")]
+    connector: Option<Connector>,
     marker_: PhantomData<E>,
 }
 
@@ -435,18 +1322,132 @@ where
     ///
     /// Handles encryption and authentication
     pub async fn connection(&self) -> Result<AsyncSmtpConnection, Error> {
-        let mut conn = E::connect(
+        let Some(deadline) = self.info.connect_deadline else {
+            return self.setup_connection().await;
+        };
+
+        match future::select(
+            Box::pin(self.setup_connection()),
+            Box::pin(E::sleep(deadline)),
+        )
+        .await
+        {
+            Either::Left((result, _)) => result,
+            Either::Right(_) => Err(error::timeout()),
+        }
+    }
+
+    async fn setup_connection(&self) -> Result<AsyncSmtpConnection, Error> {
+        #[cfg(feature = "tokio1")]
+        let mut conn = match &self.connector {
+            Some(connector) => self.connect_with(connector).await?,
+            None => self.connect().await?,
+        };
+        #[cfg(not(feature = "tokio1"))]
+        let mut conn = self.connect().await?;
+        conn.set_command_deadline(self.info.command_deadline);
+        conn.set_reply_limits(self.info.reply_limits);
+
+        self.info.check_plaintext_auth(conn.is_encrypted())?;
+        #[cfg(feature = "oauth2-helpers")]
+        let credentials = match self.info.oauth2.clone() {
+            // Refreshing the token may block, keep it off the executor
+            Some(oauth2) => Some(E::spawn_blocking(move || oauth2.credentials()).await?),
+            None => self.info.credentials.clone(),
+        };
+        #[cfg(not(feature = "oauth2-helpers"))]
+        let credentials = self.info.credentials.clone();
+        if let Some(credentials) = credentials {
+            conn.auth(&self.info.authentication, &credentials).await?;
+        }
+        conn.set_custom_extensions(self.info.custom_extensions.clone());
+        conn.set_send_observers(self.info.send_observers.clone());
+        Ok(conn)
+    }
+
+    /// Connects over TCP, handling encryption
+    async fn connect(&self) -> Result<AsyncSmtpConnection, Error> {
+        let Some(cache) = &self.info.dns_cache else {
+            return E::connect(
+                &self.info.server,
+                self.info.port,
+                None,
+                self.info.timeout,
+                &self.info.hello_name(),
+                &self.info.tls,
+                self.info.helo_fallback,
+            )
+            .await;
+        };
+
+        let addrs = match cache.get(&self.info.server, self.info.port) {
+            Some(addrs) => addrs,
+            None => {
+                let (server, port) = (self.info.server.clone(), self.info.port);
+                let addrs = E::spawn_blocking(move || dns_cache::resolve(&server, port)).await?;
+                cache.insert(&self.info.server, self.info.port, &addrs);
+                addrs
+            }
+        };
+        E::connect(
             &self.info.server,
             self.info.port,
+            Some(&addrs),
             self.info.timeout,
-            &self.info.hello_name,
+            &self.info.hello_name(),
             &self.info.tls,
+            self.info.helo_fallback,
+        )
+        .await
+        .map_err(|err| {
+            if err.is_connection() {
+                // None of the cached addresses may be valid anymore
+                cache.flush();
+            }
+            err
+        })
+    }
+
+    /// Connects over a stream opened by `connector`, handling encryption
+    #[cfg(feature = "tokio1")]
+    async fn connect_with(&self, connector: &Connector) -> Result<AsyncSmtpConnection, Error> {
+        let hello_name = self.info.hello_name();
+        let connect = (connector.0)(self.info.server.clone(), self.info.port);
+        let stream = match self.info.timeout {
+            Some(timeout) => match future::select(connect, Box::pin(E::sleep(timeout))).await {
+                Either::Left((stream, _)) => stream,
+                Either::Right(_) => Err(io::ErrorKind::TimedOut.into()),
+            },
+            None => connect.await,
+        }
+        .map_err(error::connection)?;
+
+        #[allow(clippy::match_single_binding)]
+        let tls_parameters = match &self.info.tls {
+            #[cfg(any(feature = "tokio1-native-tls", feature = "tokio1-rustls-tls"))]
+            Tls::Wrapper(tls_parameters) => Some(tls_parameters.clone()),
+            _ => None,
+        };
+        #[allow(unused_mut)]
+        let mut conn = AsyncSmtpConnection::connect_with_transport_tls(
+            stream,
+            &hello_name,
+            tls_parameters,
+            self.info.helo_fallback,
         )
         .await?;
 
-        if let Some(credentials) = &self.info.credentials {
-            conn.auth(&self.info.authentication, credentials).await?;
+        #[cfg(any(feature = "tokio1-native-tls", feature = "tokio1-rustls-tls"))]
+        match &self.info.tls {
+            Tls::Opportunistic(tls_parameters) if conn.can_starttls() => {
+                conn.starttls(tls_parameters.clone(), &hello_name).await?;
+            }
+            Tls::Required(tls_parameters) => {
+                conn.starttls(tls_parameters.clone(), &hello_name).await?;
+            }
+            _ => (),
         }
+
         Ok(conn)
     }
 }
@@ -468,6 +1469,8 @@ where
     fn clone(&self) -> Self {
         Self {
             info: self.info.clone(),
+            #[cfg(feature = "tokio1")]
+            connector: self.connector.clone(),
             marker_: PhantomData,
         }
     }
@@ -25,6 +25,11 @@ impl Credentials {
             secret: password,
         }
     }
+
+    /// The username, to identify the credentials in logs
+    pub(crate) fn username(&self) -> &str {
+        &self.authentication_identity
+    }
 }
 
 impl<S, T> From<(S, T)> for Credentials
@@ -125,6 +130,74 @@ impl Mechanism {
     }
 }
 
+/// Likely cause of an authentication failure, recognized from the server reply
+///
+/// Given by [`Error::auth_hint`] for the replies of major providers,
+/// so applications can tell their users what to do.
+#[derive(PartialEq, Eq, Copy, Clone, Hash, Debug)]
+#[non_exhaustive]
+pub enum AuthHint {
+    /// The username or password is wrong
+    InvalidCredentials,
+    /// The account password can't be used, an app password or OAuth 2.0 is
+    /// required, like with Gmail accounts
+    AppPasswordOrOAuthRequired,
+    /// Password authentication is disabled, OAuth 2.0 is required,
+    /// like with Microsoft 365 tenants without basic authentication
+    OAuthRequired,
+    /// SMTP authentication is disabled for the account or the organization,
+    /// like with Microsoft 365 by default
+    SmtpAuthDisabled,
+    /// The provider requires signing in with a web browser first,
+    /// usually after detecting suspicious activity
+    WebLoginRequired,
+}
+
+impl AuthHint {
+    /// Recognizes the reply `message` of a major provider
+    pub(crate) fn from_message(message: &str) -> Option<Self> {
+        let message = message.to_ascii_lowercase();
+        let hint = if message.contains("application-specific password required")
+            || message.contains("?p=invalidsecondfactor")
+            || message.contains("?p=badcredentials")
+        {
+            // Gmail, which doesn't accept the account password anymore
+            Self::AppPasswordOrOAuthRequired
+        } else if message.contains("?p=webloginrequired")
+            || message.contains("log in via your web browser")
+        {
+            Self::WebLoginRequired
+        } else if message.contains("smtpclientauthentication is disabled") {
+            // Microsoft 365
+            Self::SmtpAuthDisabled
+        } else if message.contains("basic authentication is disabled") {
+            Self::OAuthRequired
+        } else if message.contains("user credentials were incorrect")
+            || message.contains("authentication credentials invalid")
+        {
+            // Microsoft 365 and Amazon SES
+            Self::InvalidCredentials
+        } else {
+            return None;
+        };
+        Some(hint)
+    }
+}
+
+impl Display for AuthHint {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match *self {
+            AuthHint::InvalidCredentials => "the username or password is incorrect",
+            AuthHint::AppPasswordOrOAuthRequired => {
+                "an app password or OAuth 2.0 is required instead of the account password"
+            }
+            AuthHint::OAuthRequired => "password authentication is disabled, use OAuth 2.0",
+            AuthHint::SmtpAuthDisabled => "SMTP authentication is disabled for the account",
+            AuthHint::WebLoginRequired => "sign in with a web browser, then retry",
+        })
+    }
+}
+
 fn contains_ignore_ascii_case<'a>(
     haystack: &str,
     needles: impl IntoIterator<Item = &'a str>,
@@ -136,7 +209,7 @@ fn contains_ignore_ascii_case<'a>(
 
 #[cfg(test)]
 mod test {
-    use super::{Credentials, Mechanism};
+    use super::{AuthHint, Credentials, Mechanism};
 
     #[test]
     fn test_plain() {
@@ -208,4 +281,38 @@ mod test {
             Credentials::from(("alice", "wonderland"))
         );
     }
+
+    #[test]
+    fn auth_hint() {
+        for (message, hint) in [
+            (
+                "5.7.8 Username and Password not accepted. For more information, go to5.7.8  https://support.google.com/mail/?p=BadCredentials a640c23a62f3a-a9a5 - gsmtp",
+                Some(AuthHint::AppPasswordOrOAuthRequired),
+            ),
+            (
+                "5.7.9 Application-specific password required. For more information, go to5.7.9  https://support.google.com/mail/?p=InvalidSecondFactor",
+                Some(AuthHint::AppPasswordOrOAuthRequired),
+            ),
+            (
+                "5.7.14 <https://accounts.google.com/signin/continue>5.7.14 Please log in via your web browser and then try again.",
+                Some(AuthHint::WebLoginRequired),
+            ),
+            (
+                "5.7.139 Authentication unsuccessful, SmtpClientAuthentication is disabled for the Tenant.",
+                Some(AuthHint::SmtpAuthDisabled),
+            ),
+            (
+                "5.7.139 Authentication unsuccessful, basic authentication is disabled.",
+                Some(AuthHint::OAuthRequired),
+            ),
+            (
+                "5.7.139 Authentication unsuccessful, the user credentials were incorrect.",
+                Some(AuthHint::InvalidCredentials),
+            ),
+            ("Authentication Credentials Invalid", Some(AuthHint::InvalidCredentials)),
+            ("5.7.8 Error: authentication failed", None),
+        ] {
+            assert_eq!(AuthHint::from_message(message), hint, "{message}");
+        }
+    }
 }
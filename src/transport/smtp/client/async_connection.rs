@@ -1,22 +1,37 @@
-use std::{fmt::Display, net::IpAddr, time::Duration};
+use std::{
+    fmt::Display,
+    net::{IpAddr, SocketAddr},
+    pin::pin,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
-use futures_util::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use futures_util::{
+    future::{self, Either},
+    io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWriteExt, BufReader},
+};
 
 #[cfg(feature = "tokio1")]
 use super::async_net::AsyncTokioStream;
 #[cfg(feature = "tracing")]
 use super::escape_crlf;
-use super::{AsyncNetworkStream, ClientCodec, TlsParameters};
+use super::{check_limits, AsyncNetworkStream, ClientCodec, TlsParameters, STREAM_CHUNK_SIZE};
 use crate::{
     transport::smtp::{
         authentication::{Credentials, Mechanism},
-        commands::{Auth, Data, Ehlo, Mail, Noop, Quit, Rcpt, Starttls},
+        commands::{Auth, Burl, Command, Data, Ehlo, Helo, Mail, Noop, Quit, Rcpt, Rset, Starttls},
+        dry_run::DryRun,
         error,
         error::Error,
-        extension::{ClientId, Extension, MailBodyParameter, MailParameter, ServerInfo},
-        response::{parse_response, Response},
+        extension::{
+            ClientId, CustomExtension, Extension, MailBodyParameter, MailParameter, RcptParameter,
+            ServerInfo,
+        },
+        response::{parse_error, parse_response, ReplyLimits, Response},
+        stats::{SendObserver, SendStats},
+        util::read_header_section_async,
     },
-    Envelope,
+    Address, Envelope,
 };
 
 macro_rules! try_smtp (
@@ -38,8 +53,30 @@ pub struct AsyncSmtpConnection {
     stream: BufReader<AsyncNetworkStream>,
     /// Panic state
     panic: bool,
+    /// Whether a command was sent and its reply wasn't read yet
+    ///
+    /// It stays set if reading the reply failed or, for async connections,
+    /// if the future sending the command was dropped, as the next reply
+    /// read would then be the stale one.
+    awaiting_reply: bool,
+    /// Whether a mail transaction was started with `MAIL` and wasn't completed
+    in_transaction: bool,
     /// Information about the server
     server_info: ServerInfo,
+    /// Name sent during the last EHLO
+    hello_name: ClientId,
+    /// Hooks adding parameters to `MAIL FROM` and `RCPT TO`
+    custom_extensions: Vec<Arc<dyn CustomExtension>>,
+    /// Hooks called for each message accepted by the server
+    send_observers: Vec<Arc<dyn SendObserver>>,
+    /// Number of bytes written to the server
+    bytes_written: usize,
+    /// Number of transactions started with `MAIL`
+    transactions: usize,
+    /// Maximum time to wait for a whole reply
+    command_deadline: Option<Duration>,
+    /// Limits on the size of replies
+    reply_limits: ReplyLimits,
 }
 
 impl AsyncSmtpConnection {
@@ -48,6 +85,33 @@ impl AsyncSmtpConnection {
         &self.server_info
     }
 
+    /// Set the hooks adding parameters to `MAIL FROM` and `RCPT TO` in [`Self::send`]
+    pub fn set_custom_extensions(&mut self, custom_extensions: Vec<Arc<dyn CustomExtension>>) {
+        self.custom_extensions = custom_extensions;
+    }
+
+    /// Set the hooks called for each message accepted by the server in [`Self::send`]
+    pub fn set_send_observers(&mut self, send_observers: Vec<Arc<dyn SendObserver>>) {
+        self.send_observers = send_observers;
+    }
+
+    /// Set the maximum time to wait for the whole reply to a command
+    ///
+    /// It can't be extended by a server sending its reply a few bytes at a time.
+    pub fn set_command_deadline(&mut self, deadline: Option<Duration>) {
+        self.command_deadline = deadline;
+    }
+
+    /// Set the limits on the size of replies
+    pub fn set_reply_limits(&mut self, limits: ReplyLimits) {
+        self.reply_limits = limits;
+    }
+
+    /// Get the name sent to the server during the last EHLO
+    pub fn hello_name(&self) -> &ClientId {
+        &self.hello_name
+    }
+
     /// Connects with existing async stream
     ///
     /// Sends EHLO and parses server information
@@ -57,7 +121,25 @@ impl AsyncSmtpConnection {
         hello_name: &ClientId,
     ) -> Result<AsyncSmtpConnection, Error> {
         let stream = AsyncNetworkStream::use_existing_tokio1(stream);
-        Self::connect_impl(stream, hello_name).await
+        Self::connect_impl(stream, hello_name, false).await
+    }
+
+    /// Connects over an existing stream, using Implicit TLS if `tls_parameters` is `Some`
+    ///
+    /// Sends EHLO and parses server information. With `helo_fallback`, `HELO`
+    /// is sent if the server rejects `EHLO`.
+    #[cfg(feature = "tokio1")]
+    pub(crate) async fn connect_with_transport_tls(
+        stream: Box<dyn AsyncTokioStream>,
+        hello_name: &ClientId,
+        tls_parameters: Option<TlsParameters>,
+        helo_fallback: bool,
+    ) -> Result<AsyncSmtpConnection, Error> {
+        let mut stream = AsyncNetworkStream::use_existing_tokio1(stream);
+        if let Some(tls_parameters) = tls_parameters {
+            stream.upgrade_tls(tls_parameters).await?;
+        }
+        Self::connect_impl(stream, hello_name, helo_fallback).await
     }
 
     /// Connects to the configured server
@@ -98,15 +180,41 @@ impl AsyncSmtpConnection {
         hello_name: &ClientId,
         tls_parameters: Option<TlsParameters>,
         local_address: Option<IpAddr>,
+    ) -> Result<AsyncSmtpConnection, Error> {
+        Self::connect_tokio1_with_fallback(
+            server,
+            timeout,
+            hello_name,
+            tls_parameters,
+            local_address,
+            false,
+        )
+        .await
+    }
+
+    /// Connects to the configured server like [`AsyncSmtpConnection::connect_tokio1`]
+    ///
+    /// With `helo_fallback`, `HELO` is sent if the server rejects `EHLO`.
+    #[cfg(feature = "tokio1")]
+    pub(crate) async fn connect_tokio1_with_fallback<T: tokio1_crate::net::ToSocketAddrs>(
+        server: T,
+        timeout: Option<Duration>,
+        hello_name: &ClientId,
+        tls_parameters: Option<TlsParameters>,
+        local_address: Option<IpAddr>,
+        helo_fallback: bool,
     ) -> Result<AsyncSmtpConnection, Error> {
         let stream =
             AsyncNetworkStream::connect_tokio1(server, timeout, tls_parameters, local_address)
                 .await?;
-        Self::connect_impl(stream, hello_name).await
+        Self::connect_impl(stream, hello_name, helo_fallback).await
     }
 
     /// Connects to the configured server
     ///
+    /// If `tls_parameters` is `Some`, then the connection will use Implicit TLS (sometimes
+    /// referred to as `SMTPS`). See also [`AsyncSmtpConnection::starttls`].
+    ///
     /// Sends EHLO and parses server information
     #[cfg(feature = "async-std1")]
     pub async fn connect_asyncstd1<T: async_std::net::ToSocketAddrs>(
@@ -114,39 +222,310 @@ impl AsyncSmtpConnection {
         timeout: Option<Duration>,
         hello_name: &ClientId,
         tls_parameters: Option<TlsParameters>,
+    ) -> Result<AsyncSmtpConnection, Error> {
+        Self::connect_asyncstd1_with_fallback(server, timeout, hello_name, tls_parameters, false)
+            .await
+    }
+
+    /// Connects to the configured server like [`AsyncSmtpConnection::connect_asyncstd1`]
+    ///
+    /// With `helo_fallback`, `HELO` is sent if the server rejects `EHLO`.
+    #[cfg(feature = "async-std1")]
+    pub(crate) async fn connect_asyncstd1_with_fallback<T: async_std::net::ToSocketAddrs>(
+        server: T,
+        timeout: Option<Duration>,
+        hello_name: &ClientId,
+        tls_parameters: Option<TlsParameters>,
+        helo_fallback: bool,
     ) -> Result<AsyncSmtpConnection, Error> {
         let stream = AsyncNetworkStream::connect_asyncstd1(server, timeout, tls_parameters).await?;
-        Self::connect_impl(stream, hello_name).await
+        Self::connect_impl(stream, hello_name, helo_fallback).await
     }
 
     async fn connect_impl(
         stream: AsyncNetworkStream,
         hello_name: &ClientId,
+        helo_fallback: bool,
     ) -> Result<AsyncSmtpConnection, Error> {
         let stream = BufReader::new(stream);
         let mut conn = AsyncSmtpConnection {
             stream,
             panic: false,
+            awaiting_reply: false,
+            in_transaction: false,
             server_info: ServerInfo::default(),
+            hello_name: hello_name.clone(),
+            custom_extensions: Vec::new(),
+            send_observers: Vec::new(),
+            bytes_written: 0,
+            transactions: 0,
+            command_deadline: None,
+            reply_limits: ReplyLimits::default(),
         };
         // TODO log
         let _response = conn.read_response().await?;
 
-        conn.ehlo(hello_name).await?;
+        if helo_fallback {
+            conn.ehlo_or_helo(hello_name).await?;
+        } else {
+            conn.ehlo(hello_name).await?;
+        }
 
         // Print server information
         #[cfg(feature = "tracing")]
-        tracing::debug!("server {}", conn.server_info);
+        tracing::debug!(
+            "server {} (sent EHLO {})",
+            conn.server_info,
+            conn.hello_name
+        );
         Ok(conn)
     }
 
+    /// Sends an email
+    ///
+    /// Fails before starting the transaction if the message exceeds the `SIZE`
+    /// limit of the server, if the envelope has more recipients than its
+    /// `RCPTMAX` limit, or if the connection reached its `MAILMAX` limit.
     pub async fn send(&mut self, envelope: &Envelope, email: &[u8]) -> Result<Response, Error> {
+        check_limits(
+            &self.server_info,
+            envelope,
+            Some(email.len()),
+            self.transactions,
+        )?;
+
+        let start = Instant::now();
+        let bytes_written = self.bytes_written;
+
         // Mail
-        let mut mail_options = vec![];
+        let mut mail_options = self.envelope_parameters(envelope)?;
 
         // Internationalization handling
         //
         // * 8BITMIME: https://tools.ietf.org/html/rfc6152
+
+        // Check for non-ascii content in the message
+        if !email.is_ascii() {
+            if !self.server_info().supports_feature(Extension::EightBitMime) {
+                return Err(error::client(
+                    "Message contains non-ascii chars but server does not support 8BITMIME",
+                ));
+            }
+            mail_options.push(MailParameter::Body(MailBodyParameter::EightBitMime));
+        }
+
+        self.mail_and_rcpt(envelope, mail_options).await?;
+        let data_start = Instant::now();
+
+        // Data
+        try_smtp!(self.command(Data).await, self);
+
+        // Message content
+        let result = try_smtp!(self.message(email).await.map_err(Error::after_data), self)
+            .with_receipt(email);
+        self.in_transaction = false;
+        self.notify_sent(
+            envelope,
+            &result,
+            SendStats {
+                message_size: email.len(),
+                bytes_written: self.bytes_written - bytes_written,
+                envelope: data_start - start,
+                data: data_start.elapsed(),
+            },
+        );
+        Ok(result)
+    }
+
+    /// Checks which recipients of `envelope` the server accepts, without
+    /// sending a message
+    ///
+    /// Sends `MAIL` and a `RCPT` for each recipient, then `RSET` instead of
+    /// `DATA`. Rejected recipients are reported in the [`DryRun`] rather than
+    /// failing, the connection can be reused afterwards.
+    pub async fn dry_run(&mut self, envelope: &Envelope) -> Result<DryRun, Error> {
+        check_limits(&self.server_info, envelope, None, self.transactions)?;
+        let mut dry_run = DryRun::new();
+        let mail_options = self.envelope_parameters(envelope)?;
+        self.mail(envelope, mail_options).await?;
+
+        for (index, to_address) in envelope.to().iter().enumerate() {
+            let parameters = self.rcpt_parameters(to_address);
+            match self
+                .command(Rcpt::new(to_address.clone(), parameters))
+                .await
+            {
+                Ok(_) => dry_run.accepted.push(to_address.clone()),
+                Err(err) if err.status().is_some() => {
+                    let err = err.with_recipient(index);
+                    dry_run.rejected.push((to_address.clone(), err));
+                }
+                Err(err) => {
+                    self.abort().await;
+                    return Err(err.with_recipient(index));
+                }
+            }
+        }
+
+        dry_run.response = Some(try_smtp!(self.command(Rset).await, self));
+        self.in_transaction = false;
+        Ok(dry_run)
+    }
+
+    /// Sends an email read from `reader`, streaming it to the server
+    ///
+    /// Unlike [`AsyncSmtpConnection::send`], the message isn't loaded in memory,
+    /// only its header section is kept for the receipt. As its content isn't
+    /// known when the transaction starts, `BODY=8BITMIME` is declared whenever
+    /// the server supports it. Otherwise, the connection is aborted if the
+    /// message turns out to contain 8-bit data.
+    pub async fn send_from_async_read<R: AsyncRead + Unpin>(
+        &mut self,
+        envelope: &Envelope,
+        reader: R,
+    ) -> Result<Response, Error> {
+        check_limits(&self.server_info, envelope, None, self.transactions)?;
+
+        let start = Instant::now();
+        let bytes_written = self.bytes_written;
+
+        let mut mail_options = self.envelope_parameters(envelope)?;
+        let eight_bit_mime = self.server_info().supports_feature(Extension::EightBitMime);
+        if eight_bit_mime {
+            mail_options.push(MailParameter::Body(MailBodyParameter::EightBitMime));
+        }
+
+        self.mail_and_rcpt(envelope, mail_options).await?;
+        let data_start = Instant::now();
+
+        // Data
+        try_smtp!(self.command(Data).await, self);
+
+        // Message content
+        let (headers, size) = try_smtp!(
+            self.stream_message(reader, eight_bit_mime)
+                .await
+                .map_err(Error::after_data),
+            self
+        );
+        let result = try_smtp!(self.read_response().await.map_err(Error::after_data), self)
+            .with_streamed_receipt(&headers, size);
+        self.in_transaction = false;
+        self.notify_sent(
+            envelope,
+            &result,
+            SendStats {
+                message_size: size,
+                bytes_written: self.bytes_written - bytes_written,
+                envelope: data_start - start,
+                data: data_start.elapsed(),
+            },
+        );
+        Ok(result)
+    }
+
+    /// Writes the message read from `reader` and the final dot, returning
+    /// its header section and size
+    async fn stream_message<R: AsyncRead + Unpin>(
+        &mut self,
+        reader: R,
+        eight_bit_mime: bool,
+    ) -> Result<(Vec<u8>, usize), Error> {
+        let mut reader = BufReader::new(reader);
+        let headers = read_header_section_async(&mut reader)
+            .await
+            .map_err(error::client)?;
+
+        let mut codec = ClientCodec::new();
+        self.write_chunk(&mut codec, &headers, eight_bit_mime)
+            .await?;
+        let mut size = headers.len();
+        let mut chunk = vec![0; STREAM_CHUNK_SIZE];
+        loop {
+            let len = match reader.read(&mut chunk).await {
+                Ok(0) => break,
+                Ok(len) => len,
+                Err(err) if err.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(err) => return Err(error::client(err)),
+            };
+            self.write_chunk(&mut codec, &chunk[..len], eight_bit_mime)
+                .await?;
+            size += len;
+        }
+        self.write(b"\r\n.\r\n", false).await?;
+
+        Ok((headers, size))
+    }
+
+    /// Dot-stuffs and writes a chunk of a streamed message
+    async fn write_chunk(
+        &mut self,
+        codec: &mut ClientCodec,
+        chunk: &[u8],
+        eight_bit_mime: bool,
+    ) -> Result<(), Error> {
+        if !eight_bit_mime && !chunk.is_ascii() {
+            return Err(error::client(
+                "Message contains non-ascii chars but server does not support 8BITMIME",
+            ));
+        }
+        let mut out_buf = Vec::with_capacity(chunk.len());
+        codec.encode(chunk, &mut out_buf);
+        self.write(&out_buf, false).await
+    }
+
+    /// Sends a message stored on an IMAP server, referenced by an URLAUTH `url`
+    ///
+    /// Uses the `BURL` command, defined in [RFC 4468](https://tools.ietf.org/html/rfc4468),
+    /// instead of uploading the message content with `DATA`.
+    /// The server has to announce the [`Extension::Burl`] extension.
+    pub async fn send_burl(&mut self, envelope: &Envelope, url: &str) -> Result<Response, Error> {
+        if !self.server_info().supports_feature(Extension::Burl) {
+            return Err(error::client("Server does not support BURL"));
+        }
+
+        check_limits(&self.server_info, envelope, None, self.transactions)?;
+
+        let start = Instant::now();
+        let bytes_written = self.bytes_written;
+
+        let mail_options = self.envelope_parameters(envelope)?;
+        self.mail_and_rcpt(envelope, mail_options).await?;
+        let data_start = Instant::now();
+
+        let result = try_smtp!(
+            self.command(Burl::new(url.to_owned(), true))
+                .await
+                .map_err(Error::after_data),
+            self
+        )
+        .with_receipt(&[]);
+        self.in_transaction = false;
+        self.notify_sent(
+            envelope,
+            &result,
+            SendStats {
+                message_size: 0,
+                bytes_written: self.bytes_written - bytes_written,
+                envelope: data_start - start,
+                data: data_start.elapsed(),
+            },
+        );
+        Ok(result)
+    }
+
+    fn notify_sent(&self, envelope: &Envelope, response: &Response, stats: SendStats) {
+        for observer in &self.send_observers {
+            observer.sent(envelope, response, &stats);
+        }
+    }
+
+    /// `MAIL` parameters required by the envelope
+    fn envelope_parameters(&self, envelope: &Envelope) -> Result<Vec<MailParameter>, Error> {
+        let mut mail_options = vec![];
+
+        // Internationalization handling
+        //
         // * SMTPUTF8: https://tools.ietf.org/html/rfc653
 
         // Check for non-ascii addresses and use the SMTPUTF8 option if any.
@@ -160,42 +539,77 @@ impl AsyncSmtpConnection {
             mail_options.push(MailParameter::SmtpUtfEight);
         }
 
-        // Check for non-ascii content in the message
-        if !email.is_ascii() {
-            if !self.server_info().supports_feature(Extension::EightBitMime) {
-                return Err(error::client(
-                    "Message contains non-ascii chars but server does not support 8BITMIME",
-                ));
-            }
-            mail_options.push(MailParameter::Body(MailBodyParameter::EightBitMime));
-        }
+        Ok(mail_options)
+    }
 
-        try_smtp!(
-            self.command(Mail::new(envelope.from().cloned(), mail_options))
-                .await,
-            self
-        );
+    /// Starts a transaction, sending `MAIL` and a `RCPT` for each recipient
+    async fn mail_and_rcpt(
+        &mut self,
+        envelope: &Envelope,
+        mail_options: Vec<MailParameter>,
+    ) -> Result<(), Error> {
+        self.mail(envelope, mail_options).await?;
 
         // Recipient
-        for to_address in envelope.to() {
+        for (index, to_address) in envelope.to().iter().enumerate() {
+            let parameters = self.rcpt_parameters(to_address);
             try_smtp!(
-                self.command(Rcpt::new(to_address.clone(), vec![])).await,
+                self.command(Rcpt::new(to_address.clone(), parameters))
+                    .await
+                    .map_err(|err| err.with_recipient(index)),
                 self
             );
         }
 
-        // Data
-        try_smtp!(self.command(Data).await, self);
+        Ok(())
+    }
 
-        // Message content
-        let result = try_smtp!(self.message(email).await, self);
-        Ok(result)
+    /// Starts a transaction, sending `MAIL`
+    async fn mail(
+        &mut self,
+        envelope: &Envelope,
+        mut mail_options: Vec<MailParameter>,
+    ) -> Result<(), Error> {
+        for extension in &self.custom_extensions {
+            mail_options.extend(extension.mail_parameters(&self.server_info, envelope));
+        }
+
+        try_smtp!(
+            self.command(Mail::new(envelope.from().cloned(), mail_options))
+                .await,
+            self
+        );
+        self.in_transaction = true;
+        self.transactions += 1;
+        Ok(())
+    }
+
+    /// Parameters of the `RCPT` command of `to_address`
+    fn rcpt_parameters(&self, to_address: &Address) -> Vec<RcptParameter> {
+        self.custom_extensions
+            .iter()
+            .flat_map(|extension| extension.rcpt_parameters(&self.server_info, to_address))
+            .collect()
     }
 
+    /// Whether the connection can't be used anymore
+    ///
+    /// This is the case after an error aborting the connection, or when
+    /// the reply to a command wasn't read or a transaction was interrupted.
     pub fn has_broken(&self) -> bool {
-        self.panic
+        self.panic || self.awaiting_reply || self.in_transaction
+    }
+
+    /// Whether the server doesn't accept more transactions on the connection,
+    /// as it reached the `MAILMAX` limit announced by the server
+    pub fn is_exhausted(&self) -> bool {
+        self.server_info
+            .limits()
+            .mail_max()
+            .is_some_and(|mail_max| self.transactions >= mail_max)
     }
 
+    /// Whether the connection is in plaintext and the server advertised `STARTTLS`
     pub fn can_starttls(&self) -> bool {
         !self.is_encrypted() && self.server_info.supports_feature(Extension::StartTls)
     }
@@ -204,6 +618,33 @@ impl AsyncSmtpConnection {
     ///
     /// As described in [rfc3207]. Note that this mechanism has been deprecated in [rfc8314].
     ///
+    /// `EHLO` is sent again with `hello_name` once the connection is encrypted,
+    /// replacing the [`server_info`](Self::server_info), as servers often advertise
+    /// more extensions, like authentication mechanisms, over TLS. Fails without
+    /// sending anything if the connection is already encrypted or if the server
+    /// didn't advertise `STARTTLS`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use lettre::transport::smtp::{client::{AsyncSmtpConnection, TlsParameters}, extension::ClientId};
+    /// # use tokio1_crate::{self as tokio, net::ToSocketAddrs as _};
+    /// #
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let hello_name = ClientId::default();
+    /// let mut connection =
+    ///     AsyncSmtpConnection::connect_tokio1(("example.com", 587), None, &hello_name, None, None)
+    ///         .await?;
+    /// if connection.can_starttls() {
+    ///     let tls_parameters = TlsParameters::new("example.com".to_owned())?;
+    ///     connection.starttls(tls_parameters, &hello_name).await?;
+    /// }
+    /// println!("{}", connection.server_info());
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
     /// [rfc3207]: https://www.rfc-editor.org/rfc/rfc3207
     /// [rfc8314]: https://www.rfc-editor.org/rfc/rfc8314
     #[allow(unused_variables)]
@@ -212,7 +653,9 @@ impl AsyncSmtpConnection {
         tls_parameters: TlsParameters,
         hello_name: &ClientId,
     ) -> Result<(), Error> {
-        if self.server_info.supports_feature(Extension::StartTls) {
+        if self.is_encrypted() {
+            Err(error::client("the connection is already encrypted"))
+        } else if self.server_info.supports_feature(Extension::StartTls) {
             try_smtp!(self.command(Starttls).await, self);
             self.stream.get_mut().upgrade_tls(tls_parameters).await?;
             #[cfg(feature = "tracing")]
@@ -229,6 +672,25 @@ impl AsyncSmtpConnection {
     async fn ehlo(&mut self, hello_name: &ClientId) -> Result<(), Error> {
         let ehlo_response = try_smtp!(self.command(Ehlo::new(hello_name.clone())).await, self);
         self.server_info = try_smtp!(ServerInfo::from_response(&ehlo_response), self);
+        self.hello_name = hello_name.clone();
+        Ok(())
+    }
+
+    /// Sends `EHLO`, or `HELO` if the server rejects it, for servers which
+    /// don't support ESMTP
+    async fn ehlo_or_helo(&mut self, hello_name: &ClientId) -> Result<(), Error> {
+        let response = match self.command(Ehlo::new(hello_name.clone())).await {
+            Err(err) if err.is_permanent() => {
+                #[cfg(feature = "tracing")]
+                tracing::debug!("EHLO rejected, falling back to HELO: {}", err);
+
+                let _ = err;
+                try_smtp!(self.command(Helo::new(hello_name.clone())).await, self)
+            }
+            result => try_smtp!(result, self),
+        };
+        self.server_info = try_smtp!(ServerInfo::from_response(&response), self);
+        self.hello_name = hello_name.clone();
         Ok(())
     }
 
@@ -274,13 +736,13 @@ impl AsyncSmtpConnection {
         // Limit challenges to avoid blocking
         let mut challenges: u8 = 10;
         let mut response = self
-            .command(Auth::new(mechanism, credentials.clone(), None)?)
+            .send_command(Auth::new(mechanism, credentials.clone(), None)?)
             .await?;
 
         while challenges > 0 && response.has_code(334) {
             challenges -= 1;
             response = try_smtp!(
-                self.command(Auth::new_from_response(
+                self.send_command(Auth::new_from_response(
                     mechanism,
                     credentials.clone(),
                     &response,
@@ -302,19 +764,42 @@ impl AsyncSmtpConnection {
         let mut out_buf: Vec<u8> = vec![];
         let mut codec = ClientCodec::new();
         codec.encode(message, &mut out_buf);
-        self.write(out_buf.as_slice()).await?;
-        self.write(b"\r\n.\r\n").await?;
+        self.write(out_buf.as_slice(), false).await?;
+        self.write(b"\r\n.\r\n", false).await?;
         self.read_response().await
     }
 
     /// Sends an SMTP command
+    ///
+    /// Fails if the response isn't positive. Use
+    /// [`send_command`](Self::send_command) to send a typed [`Command`].
     pub async fn command<C: Display>(&mut self, command: C) -> Result<Response, Error> {
-        self.write(command.to_string().as_bytes()).await?;
-        self.read_response().await
+        self.send_command(command.to_string()).await
+    }
+
+    /// Sends a typed SMTP command
+    ///
+    /// Fails if the response isn't expected by the command, see [`Command::is_expected`].
+    /// Commands carrying secrets are kept out of the logs and errors.
+    pub async fn send_command<C: Command>(&mut self, command: C) -> Result<Response, Error> {
+        let line = command.to_string();
+        let sensitive = command.is_sensitive();
+        let result = match self.write(line.as_bytes(), sensitive).await {
+            Ok(()) => self.read_any_response().await,
+            Err(err) => Err(err),
+        };
+        match result {
+            Ok(response) if command.is_expected(&response) => Ok(response),
+            Ok(response) => {
+                Err(error::unexpected_response(&response).with_command(&line, sensitive))
+            }
+            Err(err) => Err(err.with_command(&line, sensitive)),
+        }
     }
 
-    /// Writes a string to the server
-    async fn write(&mut self, string: &[u8]) -> Result<(), Error> {
+    /// Writes a string to the server, keeping it out of the logs if `sensitive`
+    async fn write(&mut self, string: &[u8], sensitive: bool) -> Result<(), Error> {
+        self.awaiting_reply = true;
         self.stream
             .get_mut()
             .write_all(string)
@@ -325,42 +810,71 @@ impl AsyncSmtpConnection {
             .flush()
             .await
             .map_err(error::network)?;
+        self.bytes_written += string.len();
 
         #[cfg(feature = "tracing")]
-        tracing::debug!("Wrote: {}", escape_crlf(&String::from_utf8_lossy(string)));
+        if sensitive {
+            tracing::debug!("Wrote: <redacted>");
+        } else {
+            tracing::debug!("Wrote: {}", escape_crlf(&String::from_utf8_lossy(string)));
+        }
+        #[cfg(not(feature = "tracing"))]
+        let _ = sensitive;
         Ok(())
     }
 
-    /// Gets the SMTP response
+    /// Gets the SMTP response, failing if it isn't positive
     pub async fn read_response(&mut self) -> Result<Response, Error> {
-        let mut buffer = String::with_capacity(100);
+        let response = self.read_any_response().await?;
+        if response.is_positive() {
+            Ok(response)
+        } else {
+            Err(error::unexpected_response(&response))
+        }
+    }
+
+    /// Gets the SMTP response, whatever its code
+    async fn read_any_response(&mut self) -> Result<Response, Error> {
+        match self.command_deadline {
+            Some(deadline) => {
+                let sleep = self.stream.get_ref().sleep(deadline);
+                match future::select(pin!(self.read_reply()), sleep).await {
+                    Either::Left((result, _)) => result,
+                    Either::Right(_) => Err(error::timeout()),
+                }
+            }
+            None => self.read_reply().await,
+        }
+    }
+
+    async fn read_reply(&mut self) -> Result<Response, Error> {
+        let mut buffer = Vec::with_capacity(100);
+
+        loop {
+            let limit = self.reply_limits.next_read(buffer.len());
+            let read = (&mut self.stream)
+                .take(limit as u64)
+                .read_until(b'\n', &mut buffer)
+                .await
+                .map_err(error::network)?;
+            if read == 0 {
+                break;
+            }
+            self.reply_limits.check(read, buffer.len())?;
 
-        while self
-            .stream
-            .read_line(&mut buffer)
-            .await
-            .map_err(error::network)?
-            > 0
-        {
             #[cfg(feature = "tracing")]
-            tracing::debug!("<< {}", escape_crlf(&buffer));
+            tracing::debug!("<< {}", escape_crlf(&String::from_utf8_lossy(&buffer)));
             match parse_response(&buffer) {
                 Ok((_remaining, response)) => {
-                    return if response.is_positive() {
-                        Ok(response)
-                    } else {
-                        Err(error::code(
-                            response.code(),
-                            Some(response.message().collect()),
-                        ))
-                    }
+                    self.awaiting_reply = false;
+                    return Ok(response);
                 }
                 Err(nom::Err::Failure(e)) => {
-                    return Err(error::response(e.to_string()));
+                    return Err(parse_error(e));
                 }
                 Err(nom::Err::Incomplete(_)) => { /* read more */ }
                 Err(nom::Err::Error(e)) => {
-                    return Err(error::response(e.to_string()));
+                    return Err(parse_error(e));
                 }
             }
         }
@@ -368,6 +882,11 @@ impl AsyncSmtpConnection {
         Err(error::response("incomplete response"))
     }
 
+    /// The address of the server the connection was established to
+    pub fn peer_addr(&self) -> Result<SocketAddr, Error> {
+        self.stream.get_ref().peer_addr().map_err(error::network)
+    }
+
     /// The X509 certificate of the server (DER encoded)
     #[cfg(any(feature = "native-tls", feature = "rustls-tls", feature = "boring-tls"))]
     pub fn peer_certificate(&self) -> Result<Vec<u8>, Error> {
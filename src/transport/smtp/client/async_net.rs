@@ -1,5 +1,7 @@
 use std::{
-    fmt, io, mem,
+    fmt,
+    future::{self, Future},
+    io, mem,
     net::{IpAddr, SocketAddr},
     pin::Pin,
     task::{Context, Poll},
@@ -40,7 +42,10 @@ use super::InnerTlsParameters;
 use super::TlsParameters;
 #[cfg(feature = "tokio1")]
 use crate::transport::smtp::client::net::resolved_address_filter;
-use crate::transport::smtp::{error, Error};
+use crate::transport::smtp::{
+    client::net::{log_connect_failed, log_connected},
+    error, Error,
+};
 
 /// A network stream
 #[derive(Debug)]
@@ -149,7 +154,7 @@ impl AsyncNetworkStream {
 
             let mut last_err = None;
 
-            for addr in addrs {
+            for (attempt, addr) in addrs.enumerate() {
                 let socket = match addr.ip() {
                     IpAddr::V4(_) => Tokio1TcpSocket::new_v4(),
                     IpAddr::V6(_) => Tokio1TcpSocket::new_v6(),
@@ -162,21 +167,25 @@ impl AsyncNetworkStream {
                 }
 
                 let connect_future = socket.connect(addr);
-                if let Some(timeout) = timeout {
-                    match tokio1_crate::time::timeout(timeout, connect_future).await {
-                        Ok(Ok(stream)) => return Ok(stream),
-                        Ok(Err(err)) => last_err = Some(err),
-                        Err(_) => {
-                            last_err = Some(io::Error::new(
+                let result = match timeout {
+                    Some(timeout) => tokio1_crate::time::timeout(timeout, connect_future)
+                        .await
+                        .unwrap_or_else(|_| {
+                            Err(io::Error::new(
                                 io::ErrorKind::TimedOut,
                                 "connection timed out",
                             ))
-                        }
+                        }),
+                    None => connect_future.await,
+                };
+                match result {
+                    Ok(stream) => {
+                        log_connected(&addr, attempt + 1);
+                        return Ok(stream);
                     }
-                } else {
-                    match connect_future.await {
-                        Ok(stream) => return Ok(stream),
-                        Err(err) => last_err = Some(err),
+                    Err(err) => {
+                        log_connect_failed(&addr, &err);
+                        last_err = Some(err);
                     }
                 }
             }
@@ -213,16 +222,24 @@ impl AsyncNetworkStream {
 
             let mut last_err = None;
 
-            for addr in addrs {
+            for (attempt, addr) in addrs.enumerate() {
                 let connect_future = AsyncStd1TcpStream::connect(&addr);
-                match async_std::future::timeout(timeout, connect_future).await {
-                    Ok(Ok(stream)) => return Ok(stream),
-                    Ok(Err(err)) => last_err = Some(err),
-                    Err(_) => {
-                        last_err = Some(io::Error::new(
+                let result = async_std::future::timeout(timeout, connect_future)
+                    .await
+                    .unwrap_or_else(|_| {
+                        Err(io::Error::new(
                             io::ErrorKind::TimedOut,
                             "connection timed out",
                         ))
+                    });
+                match result {
+                    Ok(stream) => {
+                        log_connected(&addr, attempt + 1);
+                        return Ok(stream);
+                    }
+                    Err(err) => {
+                        log_connect_failed(&addr, &err);
+                        last_err = Some(err);
                     }
                 }
             }
@@ -413,6 +430,36 @@ impl AsyncNetworkStream {
         }
     }
 
+    /// Waits for `duration` using the timer of the runtime the stream belongs to
+    pub(super) fn sleep(&self, duration: Duration) -> Pin<Box<dyn Future<Output = ()> + Send>> {
+        match &self.inner {
+            #[cfg(feature = "tokio1")]
+            InnerAsyncNetworkStream::Tokio1Tcp(_) => Box::pin(tokio1_crate::time::sleep(duration)),
+            #[cfg(feature = "tokio1-native-tls")]
+            InnerAsyncNetworkStream::Tokio1NativeTls(_) => {
+                Box::pin(tokio1_crate::time::sleep(duration))
+            }
+            #[cfg(feature = "tokio1-rustls-tls")]
+            InnerAsyncNetworkStream::Tokio1RustlsTls(_) => {
+                Box::pin(tokio1_crate::time::sleep(duration))
+            }
+            #[cfg(feature = "tokio1-boring-tls")]
+            InnerAsyncNetworkStream::Tokio1BoringTls(_) => {
+                Box::pin(tokio1_crate::time::sleep(duration))
+            }
+            #[cfg(feature = "async-std1")]
+            InnerAsyncNetworkStream::AsyncStd1Tcp(_) => Box::pin(async_std::task::sleep(duration)),
+            #[cfg(feature = "async-std1-rustls-tls")]
+            InnerAsyncNetworkStream::AsyncStd1RustlsTls(_) => {
+                Box::pin(async_std::task::sleep(duration))
+            }
+            InnerAsyncNetworkStream::None => {
+                debug_assert!(false, "InnerAsyncNetworkStream::None must never be built");
+                Box::pin(future::pending())
+            }
+        }
+    }
+
     pub fn is_encrypted(&self) -> bool {
         match &self.inner {
             #[cfg(feature = "tokio1")]
@@ -1,22 +1,29 @@
 use std::{
     fmt::Display,
-    io::{self, BufRead, BufReader, Write},
-    net::{IpAddr, ToSocketAddrs},
-    time::Duration,
+    io::{self, BufRead, BufReader, Read, Write},
+    net::{IpAddr, SocketAddr, ToSocketAddrs},
+    sync::Arc,
+    time::{Duration, Instant},
 };
 
 #[cfg(feature = "tracing")]
 use super::escape_crlf;
-use super::{ClientCodec, NetworkStream, TlsParameters};
+use super::{check_limits, ClientCodec, NetworkStream, TlsParameters, STREAM_CHUNK_SIZE};
 use crate::{
-    address::Envelope,
+    address::{Address, Envelope},
     transport::smtp::{
         authentication::{Credentials, Mechanism},
-        commands::{Auth, Data, Ehlo, Mail, Noop, Quit, Rcpt, Starttls},
+        commands::{Auth, Burl, Command, Data, Ehlo, Helo, Mail, Noop, Quit, Rcpt, Rset, Starttls},
+        dry_run::DryRun,
         error,
         error::Error,
-        extension::{ClientId, Extension, MailBodyParameter, MailParameter, ServerInfo},
-        response::{parse_response, Response},
+        extension::{
+            ClientId, CustomExtension, Extension, MailBodyParameter, MailParameter, RcptParameter,
+            ServerInfo,
+        },
+        response::{parse_error, parse_response, ReplyLimits, Response},
+        stats::{SendObserver, SendStats},
+        util::read_header_section,
     },
 };
 
@@ -39,8 +46,34 @@ pub struct SmtpConnection {
     stream: BufReader<NetworkStream>,
     /// Panic state
     panic: bool,
+    /// Whether a command was sent and its reply wasn't read yet
+    ///
+    /// It stays set if reading the reply failed or, for async connections,
+    /// if the future sending the command was dropped, as the next reply
+    /// read would then be the stale one.
+    awaiting_reply: bool,
+    /// Whether a mail transaction was started with `MAIL` and wasn't completed
+    in_transaction: bool,
     /// Information about the server
     server_info: ServerInfo,
+    /// Name sent during the last EHLO
+    hello_name: ClientId,
+    /// Hooks adding parameters to `MAIL FROM` and `RCPT TO`
+    custom_extensions: Vec<Arc<dyn CustomExtension>>,
+    /// Hooks called for each message accepted by the server
+    send_observers: Vec<Arc<dyn SendObserver>>,
+    /// Number of bytes written to the server
+    bytes_written: usize,
+    /// Number of transactions started with `MAIL`
+    transactions: usize,
+    /// Network timeout, for each read and write
+    timeout: Option<Duration>,
+    /// Maximum time to wait for a whole reply
+    command_deadline: Option<Duration>,
+    /// Limits on the size of replies
+    reply_limits: ReplyLimits,
+    /// Time by which all replies must be received
+    deadline: Option<Instant>,
 }
 
 impl SmtpConnection {
@@ -49,10 +82,29 @@ impl SmtpConnection {
         &self.server_info
     }
 
+    /// Set the hooks adding parameters to `MAIL FROM` and `RCPT TO` in [`Self::send`]
+    pub fn set_custom_extensions(&mut self, custom_extensions: Vec<Arc<dyn CustomExtension>>) {
+        self.custom_extensions = custom_extensions;
+    }
+
+    /// Set the hooks called for each message accepted by the server in [`Self::send`]
+    pub fn set_send_observers(&mut self, send_observers: Vec<Arc<dyn SendObserver>>) {
+        self.send_observers = send_observers;
+    }
+
+    /// Get the name sent to the server during the last EHLO
+    pub fn hello_name(&self) -> &ClientId {
+        &self.hello_name
+    }
+
     // FIXME add simple connect and rename this one
 
     /// Connects to the configured server
     ///
+    /// If `tls_parameters` is `Some`, then the connection will use Implicit TLS (sometimes
+    /// referred to as `SMTPS`). Otherwise it starts in plaintext, and can be upgraded
+    /// with [`SmtpConnection::starttls`] after looking at the [`server_info`](Self::server_info).
+    ///
     /// Sends EHLO and parses server information
     pub fn connect<A: ToSocketAddrs>(
         server: A,
@@ -61,44 +113,104 @@ impl SmtpConnection {
         tls_parameters: Option<&TlsParameters>,
         local_address: Option<IpAddr>,
     ) -> Result<SmtpConnection, Error> {
-        let stream = NetworkStream::connect(server, timeout, tls_parameters, local_address)?;
+        Self::connect_with_deadline(
+            server,
+            timeout,
+            hello_name,
+            tls_parameters,
+            local_address,
+            None,
+            false,
+            &mut 0,
+        )
+    }
+
+    /// Connects to the configured server, failing if the server greeting
+    /// and `EHLO` response aren't received before `deadline`
+    ///
+    /// With `helo_fallback`, `HELO` is sent if the server rejects `EHLO`.
+    /// `attempts` is set to the number of addresses a connection was attempted to.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn connect_with_deadline<A: ToSocketAddrs>(
+        server: A,
+        timeout: Option<Duration>,
+        hello_name: &ClientId,
+        tls_parameters: Option<&TlsParameters>,
+        local_address: Option<IpAddr>,
+        deadline: Option<Instant>,
+        helo_fallback: bool,
+        attempts: &mut usize,
+    ) -> Result<SmtpConnection, Error> {
+        let connect_timeout = match deadline {
+            Some(deadline) => Some(timeout_before(timeout, deadline)?),
+            None => timeout,
+        };
+        let stream = NetworkStream::connect_counting(
+            server,
+            connect_timeout,
+            tls_parameters,
+            local_address,
+            attempts,
+        )?;
         let stream = BufReader::new(stream);
         let mut conn = SmtpConnection {
             stream,
             panic: false,
+            awaiting_reply: false,
+            in_transaction: false,
             server_info: ServerInfo::default(),
+            hello_name: hello_name.clone(),
+            custom_extensions: Vec::new(),
+            send_observers: Vec::new(),
+            bytes_written: 0,
+            transactions: 0,
+            timeout: None,
+            command_deadline: None,
+            reply_limits: ReplyLimits::default(),
+            deadline,
         };
         conn.set_timeout(timeout).map_err(error::network)?;
         // TODO log
         let _response = conn.read_response()?;
 
-        conn.ehlo(hello_name)?;
+        if helo_fallback {
+            conn.ehlo_or_helo(hello_name)?;
+        } else {
+            conn.ehlo(hello_name)?;
+        }
 
         // Print server information
         #[cfg(feature = "tracing")]
-        tracing::debug!("server {}", conn.server_info);
+        tracing::debug!(
+            "server {} (sent EHLO {})",
+            conn.server_info,
+            conn.hello_name
+        );
         Ok(conn)
     }
 
+    /// Sends an email
+    ///
+    /// Fails before starting the transaction if the message exceeds the `SIZE`
+    /// limit of the server, if the envelope has more recipients than its
+    /// `RCPTMAX` limit, or if the connection reached its `MAILMAX` limit.
     pub fn send(&mut self, envelope: &Envelope, email: &[u8]) -> Result<Response, Error> {
+        check_limits(
+            &self.server_info,
+            envelope,
+            Some(email.len()),
+            self.transactions,
+        )?;
+
+        let start = Instant::now();
+        let bytes_written = self.bytes_written;
+
         // Mail
-        let mut mail_options = vec![];
+        let mut mail_options = self.envelope_parameters(envelope)?;
 
         // Internationalization handling
         //
         // * 8BITMIME: https://tools.ietf.org/html/rfc6152
-        // * SMTPUTF8: https://tools.ietf.org/html/rfc653
-
-        // Check for non-ascii addresses and use the SMTPUTF8 option if any.
-        if envelope.has_non_ascii_addresses() {
-            if !self.server_info().supports_feature(Extension::SmtpUtfEight) {
-                // don't try to send non-ascii addresses (per RFC)
-                return Err(error::client(
-                    "Envelope contains non-ascii chars but server does not support SMTPUTF8",
-                ));
-            }
-            mail_options.push(MailParameter::SmtpUtfEight);
-        }
 
         // Check for non-ascii content in the message
         if !email.is_ascii() {
@@ -110,43 +222,345 @@ impl SmtpConnection {
             mail_options.push(MailParameter::Body(MailBodyParameter::EightBitMime));
         }
 
-        try_smtp!(
-            self.command(Mail::new(envelope.from().cloned(), mail_options)),
-            self
+        self.mail_and_rcpt(envelope, mail_options)?;
+        let data_start = Instant::now();
+
+        // Data
+        try_smtp!(self.command(Data), self);
+
+        // Message content
+        let result =
+            try_smtp!(self.message(email).map_err(Error::after_data), self).with_receipt(email);
+        self.in_transaction = false;
+        self.notify_sent(
+            envelope,
+            &result,
+            SendStats {
+                message_size: email.len(),
+                bytes_written: self.bytes_written - bytes_written,
+                envelope: data_start - start,
+                data: data_start.elapsed(),
+            },
         );
+        Ok(result)
+    }
 
-        // Recipient
-        for to_address in envelope.to() {
-            try_smtp!(self.command(Rcpt::new(to_address.clone(), vec![])), self);
+    /// Checks which recipients of `envelope` the server accepts, without
+    /// sending a message
+    ///
+    /// Sends `MAIL` and a `RCPT` for each recipient, then `RSET` instead of
+    /// `DATA`. Rejected recipients are reported in the [`DryRun`] rather than
+    /// failing, the connection can be reused afterwards.
+    pub fn dry_run(&mut self, envelope: &Envelope) -> Result<DryRun, Error> {
+        check_limits(&self.server_info, envelope, None, self.transactions)?;
+        let mut dry_run = DryRun::new();
+        let mail_options = self.envelope_parameters(envelope)?;
+        self.mail(envelope, mail_options)?;
+
+        for (index, to_address) in envelope.to().iter().enumerate() {
+            let parameters = self.rcpt_parameters(to_address);
+            match self.command(Rcpt::new(to_address.clone(), parameters)) {
+                Ok(_) => dry_run.accepted.push(to_address.clone()),
+                Err(err) if err.status().is_some() => {
+                    let err = err.with_recipient(index);
+                    dry_run.rejected.push((to_address.clone(), err));
+                }
+                Err(err) => {
+                    self.abort();
+                    return Err(err.with_recipient(index));
+                }
+            }
+        }
+
+        dry_run.response = Some(try_smtp!(self.command(Rset), self));
+        self.in_transaction = false;
+        Ok(dry_run)
+    }
+
+    /// Sends an email read from `reader`, streaming it to the server
+    ///
+    /// Unlike [`SmtpConnection::send`], the message isn't loaded in memory,
+    /// only its header section is kept for the receipt. As its content isn't
+    /// known when the transaction starts, `BODY=8BITMIME` is declared whenever
+    /// the server supports it. Otherwise, the connection is aborted if the
+    /// message turns out to contain 8-bit data.
+    pub fn send_from_reader<R: Read>(
+        &mut self,
+        envelope: &Envelope,
+        reader: R,
+    ) -> Result<Response, Error> {
+        check_limits(&self.server_info, envelope, None, self.transactions)?;
+
+        let start = Instant::now();
+        let bytes_written = self.bytes_written;
+
+        let mut mail_options = self.envelope_parameters(envelope)?;
+        let eight_bit_mime = self.server_info().supports_feature(Extension::EightBitMime);
+        if eight_bit_mime {
+            mail_options.push(MailParameter::Body(MailBodyParameter::EightBitMime));
         }
 
+        self.mail_and_rcpt(envelope, mail_options)?;
+        let data_start = Instant::now();
+
         // Data
         try_smtp!(self.command(Data), self);
 
         // Message content
-        let result = try_smtp!(self.message(email), self);
+        let (headers, size) = try_smtp!(
+            self.stream_message(reader, eight_bit_mime)
+                .map_err(Error::after_data),
+            self
+        );
+        let result = try_smtp!(self.read_response().map_err(Error::after_data), self)
+            .with_streamed_receipt(&headers, size);
+        self.in_transaction = false;
+        self.notify_sent(
+            envelope,
+            &result,
+            SendStats {
+                message_size: size,
+                bytes_written: self.bytes_written - bytes_written,
+                envelope: data_start - start,
+                data: data_start.elapsed(),
+            },
+        );
         Ok(result)
     }
 
+    /// Writes the message read from `reader` and the final dot, returning
+    /// its header section and size
+    fn stream_message<R: Read>(
+        &mut self,
+        reader: R,
+        eight_bit_mime: bool,
+    ) -> Result<(Vec<u8>, usize), Error> {
+        let mut reader = BufReader::new(reader);
+        let headers = read_header_section(&mut reader).map_err(error::client)?;
+
+        let mut codec = ClientCodec::new();
+        self.write_chunk(&mut codec, &headers, eight_bit_mime)?;
+        let mut size = headers.len();
+        let mut chunk = vec![0; STREAM_CHUNK_SIZE];
+        loop {
+            let len = match reader.read(&mut chunk) {
+                Ok(0) => break,
+                Ok(len) => len,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => return Err(error::client(err)),
+            };
+            self.write_chunk(&mut codec, &chunk[..len], eight_bit_mime)?;
+            size += len;
+        }
+        self.write(b"\r\n.\r\n", false)?;
+
+        Ok((headers, size))
+    }
+
+    /// Dot-stuffs and writes a chunk of a streamed message
+    fn write_chunk(
+        &mut self,
+        codec: &mut ClientCodec,
+        chunk: &[u8],
+        eight_bit_mime: bool,
+    ) -> Result<(), Error> {
+        if !eight_bit_mime && !chunk.is_ascii() {
+            return Err(error::client(
+                "Message contains non-ascii chars but server does not support 8BITMIME",
+            ));
+        }
+        let mut out_buf = Vec::with_capacity(chunk.len());
+        codec.encode(chunk, &mut out_buf);
+        self.write(&out_buf, false)
+    }
+
+    /// Sends a message stored on an IMAP server, referenced by an URLAUTH `url`
+    ///
+    /// Uses the `BURL` command, defined in [RFC 4468](https://tools.ietf.org/html/rfc4468),
+    /// instead of uploading the message content with `DATA`.
+    /// The server has to announce the [`Extension::Burl`] extension.
+    pub fn send_burl(&mut self, envelope: &Envelope, url: &str) -> Result<Response, Error> {
+        if !self.server_info().supports_feature(Extension::Burl) {
+            return Err(error::client("Server does not support BURL"));
+        }
+
+        check_limits(&self.server_info, envelope, None, self.transactions)?;
+
+        let start = Instant::now();
+        let bytes_written = self.bytes_written;
+
+        let mail_options = self.envelope_parameters(envelope)?;
+        self.mail_and_rcpt(envelope, mail_options)?;
+        let data_start = Instant::now();
+
+        let result = try_smtp!(
+            self.command(Burl::new(url.to_owned(), true))
+                .map_err(Error::after_data),
+            self
+        )
+        .with_receipt(&[]);
+        self.in_transaction = false;
+        self.notify_sent(
+            envelope,
+            &result,
+            SendStats {
+                message_size: 0,
+                bytes_written: self.bytes_written - bytes_written,
+                envelope: data_start - start,
+                data: data_start.elapsed(),
+            },
+        );
+        Ok(result)
+    }
+
+    fn notify_sent(&self, envelope: &Envelope, response: &Response, stats: SendStats) {
+        for observer in &self.send_observers {
+            observer.sent(envelope, response, &stats);
+        }
+    }
+
+    /// `MAIL` parameters required by the envelope
+    fn envelope_parameters(&self, envelope: &Envelope) -> Result<Vec<MailParameter>, Error> {
+        let mut mail_options = vec![];
+
+        // Internationalization handling
+        //
+        // * SMTPUTF8: https://tools.ietf.org/html/rfc653
+
+        // Check for non-ascii addresses and use the SMTPUTF8 option if any.
+        if envelope.has_non_ascii_addresses() {
+            if !self.server_info().supports_feature(Extension::SmtpUtfEight) {
+                // don't try to send non-ascii addresses (per RFC)
+                return Err(error::client(
+                    "Envelope contains non-ascii chars but server does not support SMTPUTF8",
+                ));
+            }
+            mail_options.push(MailParameter::SmtpUtfEight);
+        }
+
+        Ok(mail_options)
+    }
+
+    /// Starts a transaction, sending `MAIL` and a `RCPT` for each recipient
+    fn mail_and_rcpt(
+        &mut self,
+        envelope: &Envelope,
+        mail_options: Vec<MailParameter>,
+    ) -> Result<(), Error> {
+        self.mail(envelope, mail_options)?;
+
+        // Recipient
+        for (index, to_address) in envelope.to().iter().enumerate() {
+            let parameters = self.rcpt_parameters(to_address);
+            try_smtp!(
+                self.command(Rcpt::new(to_address.clone(), parameters))
+                    .map_err(|err| err.with_recipient(index)),
+                self
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Starts a transaction, sending `MAIL`
+    fn mail(
+        &mut self,
+        envelope: &Envelope,
+        mut mail_options: Vec<MailParameter>,
+    ) -> Result<(), Error> {
+        for extension in &self.custom_extensions {
+            mail_options.extend(extension.mail_parameters(&self.server_info, envelope));
+        }
+
+        try_smtp!(
+            self.command(Mail::new(envelope.from().cloned(), mail_options)),
+            self
+        );
+        self.in_transaction = true;
+        self.transactions += 1;
+        Ok(())
+    }
+
+    /// Parameters of the `RCPT` command of `to_address`
+    fn rcpt_parameters(&self, to_address: &Address) -> Vec<RcptParameter> {
+        self.custom_extensions
+            .iter()
+            .flat_map(|extension| extension.rcpt_parameters(&self.server_info, to_address))
+            .collect()
+    }
+
+    /// Whether the connection can't be used anymore
+    ///
+    /// This is the case after an error aborting the connection, or when
+    /// the reply to a command wasn't read or a transaction was interrupted.
     pub fn has_broken(&self) -> bool {
-        self.panic
+        self.panic || self.awaiting_reply || self.in_transaction
     }
 
+    /// Whether the server doesn't accept more transactions on the connection,
+    /// as it reached the `MAILMAX` limit announced by the server
+    pub fn is_exhausted(&self) -> bool {
+        self.server_info
+            .limits()
+            .mail_max()
+            .is_some_and(|mail_max| self.transactions >= mail_max)
+    }
+
+    /// Whether the connection is in plaintext and the server advertised `STARTTLS`
     pub fn can_starttls(&self) -> bool {
         !self.is_encrypted() && self.server_info.supports_feature(Extension::StartTls)
     }
 
+    /// Upgrade the plaintext connection using `STARTTLS`
+    ///
+    /// As described in [rfc3207]. `EHLO` is sent again with `hello_name` once
+    /// the connection is encrypted, replacing the [`server_info`](Self::server_info),
+    /// as servers often advertise more extensions, like authentication mechanisms,
+    /// over TLS. Fails without sending anything if the connection is already
+    /// encrypted or if the server didn't advertise `STARTTLS`.
+    ///
+    /// ```rust,no_run
+    /// use lettre::transport::smtp::{
+    ///     client::{SmtpConnection, TlsParameters},
+    ///     extension::ClientId,
+    /// };
+    ///
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// let hello_name = ClientId::default();
+    /// let mut conn =
+    ///     SmtpConnection::connect(("smtp.example.com", 587), None, &hello_name, None, None)?;
+    /// if conn.can_starttls() {
+    ///     let tls_parameters = TlsParameters::new("smtp.example.com".to_owned())?;
+    ///     conn.starttls(&tls_parameters, &hello_name)?;
+    /// }
+    /// println!("{}", conn.server_info());
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [rfc3207]: https://www.rfc-editor.org/rfc/rfc3207
     #[allow(unused_variables)]
     pub fn starttls(
         &mut self,
         tls_parameters: &TlsParameters,
         hello_name: &ClientId,
     ) -> Result<(), Error> {
-        if self.server_info.supports_feature(Extension::StartTls) {
+        if self.is_encrypted() {
+            Err(error::client("the connection is already encrypted"))
+        } else if self.server_info.supports_feature(Extension::StartTls) {
             #[cfg(any(feature = "native-tls", feature = "rustls-tls", feature = "boring-tls"))]
             {
                 try_smtp!(self.command(Starttls), self);
+                if let Some(deadline) = self.deadline {
+                    // Bound the TLS handshake too
+                    let timeout = timeout_before(self.timeout, deadline)?;
+                    self.set_socket_timeout(Some(timeout))
+                        .map_err(error::network)?;
+                }
                 self.stream.get_mut().upgrade_tls(tls_parameters)?;
+                self.set_socket_timeout(self.timeout)
+                    .map_err(error::network)?;
                 #[cfg(feature = "tracing")]
                 tracing::debug!("connection encrypted");
                 // Send EHLO again
@@ -170,6 +584,25 @@ impl SmtpConnection {
     fn ehlo(&mut self, hello_name: &ClientId) -> Result<(), Error> {
         let ehlo_response = try_smtp!(self.command(Ehlo::new(hello_name.clone())), self);
         self.server_info = try_smtp!(ServerInfo::from_response(&ehlo_response), self);
+        self.hello_name = hello_name.clone();
+        Ok(())
+    }
+
+    /// Sends `EHLO`, or `HELO` if the server rejects it, for servers which
+    /// don't support ESMTP
+    fn ehlo_or_helo(&mut self, hello_name: &ClientId) -> Result<(), Error> {
+        let response = match self.command(Ehlo::new(hello_name.clone())) {
+            Err(err) if err.is_permanent() => {
+                #[cfg(feature = "tracing")]
+                tracing::debug!("EHLO rejected, falling back to HELO: {}", err);
+
+                let _ = err;
+                try_smtp!(self.command(Helo::new(hello_name.clone())), self)
+            }
+            result => try_smtp!(result, self),
+        };
+        self.server_info = try_smtp!(ServerInfo::from_response(&response), self);
+        self.hello_name = hello_name.clone();
         Ok(())
     }
 
@@ -198,10 +631,37 @@ impl SmtpConnection {
 
     /// Set timeout
     pub fn set_timeout(&mut self, duration: Option<Duration>) -> io::Result<()> {
+        self.set_socket_timeout(duration)?;
+        self.timeout = duration;
+        Ok(())
+    }
+
+    fn set_socket_timeout(&mut self, duration: Option<Duration>) -> io::Result<()> {
         self.stream.get_mut().set_read_timeout(duration)?;
         self.stream.get_mut().set_write_timeout(duration)
     }
 
+    /// Set a time by which all replies must be received
+    ///
+    /// Unlike the command deadline, it spans several commands, for example
+    /// to bound the time taken to set up the connection.
+    pub fn set_deadline(&mut self, deadline: Option<Instant>) {
+        self.deadline = deadline;
+    }
+
+    /// Set the maximum time to wait for the whole reply to a command
+    ///
+    /// Unlike the timeout, which applies to each read, it can't be extended
+    /// by a server sending its reply a few bytes at a time.
+    pub fn set_command_deadline(&mut self, deadline: Option<Duration>) {
+        self.command_deadline = deadline;
+    }
+
+    /// Set the limits on the size of replies
+    pub fn set_reply_limits(&mut self, limits: ReplyLimits) {
+        self.reply_limits = limits;
+    }
+
     /// Checks if the server is connected using the NOOP SMTP command
     pub fn test_connected(&mut self) -> bool {
         self.command(Noop).is_ok()
@@ -220,12 +680,12 @@ impl SmtpConnection {
 
         // Limit challenges to avoid blocking
         let mut challenges = 10;
-        let mut response = self.command(Auth::new(mechanism, credentials.clone(), None)?)?;
+        let mut response = self.send_command(Auth::new(mechanism, credentials.clone(), None)?)?;
 
         while challenges > 0 && response.has_code(334) {
             challenges -= 1;
             response = try_smtp!(
-                self.command(Auth::new_from_response(
+                self.send_command(Auth::new_from_response(
                     mechanism,
                     credentials.clone(),
                     &response,
@@ -246,55 +706,108 @@ impl SmtpConnection {
         let mut codec = ClientCodec::new();
         let mut out_buf = Vec::with_capacity(message.len());
         codec.encode(message, &mut out_buf);
-        self.write(out_buf.as_slice())?;
-        self.write(b"\r\n.\r\n")?;
+        self.write(out_buf.as_slice(), false)?;
+        self.write(b"\r\n.\r\n", false)?;
 
         self.read_response()
     }
 
     /// Sends an SMTP command
+    ///
+    /// Fails if the response isn't positive. Use
+    /// [`send_command`](Self::send_command) to send a typed [`Command`].
     pub fn command<C: Display>(&mut self, command: C) -> Result<Response, Error> {
-        self.write(command.to_string().as_bytes())?;
-        self.read_response()
+        self.send_command(command.to_string())
+    }
+
+    /// Sends a typed SMTP command
+    ///
+    /// Fails if the response isn't expected by the command, see [`Command::is_expected`].
+    /// Commands carrying secrets are kept out of the logs and errors.
+    pub fn send_command<C: Command>(&mut self, command: C) -> Result<Response, Error> {
+        let line = command.to_string();
+        let sensitive = command.is_sensitive();
+        let result = match self.write(line.as_bytes(), sensitive) {
+            Ok(()) => self.read_any_response(),
+            Err(err) => Err(err),
+        };
+        match result {
+            Ok(response) if command.is_expected(&response) => Ok(response),
+            Ok(response) => {
+                Err(error::unexpected_response(&response).with_command(&line, sensitive))
+            }
+            Err(err) => Err(err.with_command(&line, sensitive)),
+        }
     }
 
-    /// Writes a string to the server
-    fn write(&mut self, string: &[u8]) -> Result<(), Error> {
+    /// Writes a string to the server, keeping it out of the logs if `sensitive`
+    fn write(&mut self, string: &[u8], sensitive: bool) -> Result<(), Error> {
+        self.awaiting_reply = true;
         self.stream
             .get_mut()
             .write_all(string)
             .map_err(error::network)?;
         self.stream.get_mut().flush().map_err(error::network)?;
+        self.bytes_written += string.len();
 
         #[cfg(feature = "tracing")]
-        tracing::debug!("Wrote: {}", escape_crlf(&String::from_utf8_lossy(string)));
+        if sensitive {
+            tracing::debug!("Wrote: <redacted>");
+        } else {
+            tracing::debug!("Wrote: {}", escape_crlf(&String::from_utf8_lossy(string)));
+        }
+        #[cfg(not(feature = "tracing"))]
+        let _ = sensitive;
         Ok(())
     }
 
-    /// Gets the SMTP response
+    /// Gets the SMTP response, failing if it isn't positive
     pub fn read_response(&mut self) -> Result<Response, Error> {
-        let mut buffer = String::with_capacity(100);
+        let response = self.read_any_response()?;
+        if response.is_positive() {
+            Ok(response)
+        } else {
+            Err(error::unexpected_response(&response))
+        }
+    }
+
+    /// Gets the SMTP response, whatever its code
+    fn read_any_response(&mut self) -> Result<Response, Error> {
+        let command_deadline = self
+            .command_deadline
+            .map(|deadline| Instant::now() + deadline);
+        let deadline = match (command_deadline, self.deadline) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+        let result = self.read_reply(deadline);
+        if deadline.is_some() {
+            // Restore the read timeout, shortened to respect the deadline
+            self.stream
+                .get_mut()
+                .set_read_timeout(self.timeout)
+                .map_err(error::network)?;
+        }
+        result
+    }
 
-        while self.stream.read_line(&mut buffer).map_err(error::network)? > 0 {
+    fn read_reply(&mut self, deadline: Option<Instant>) -> Result<Response, Error> {
+        let mut buffer = Vec::with_capacity(100);
+
+        while self.read_line(&mut buffer, deadline)? > 0 {
             #[cfg(feature = "tracing")]
-            tracing::debug!("<< {}", escape_crlf(&buffer));
+            tracing::debug!("<< {}", escape_crlf(&String::from_utf8_lossy(&buffer)));
             match parse_response(&buffer) {
                 Ok((_remaining, response)) => {
-                    return if response.is_positive() {
-                        Ok(response)
-                    } else {
-                        Err(error::code(
-                            response.code(),
-                            Some(response.message().collect()),
-                        ))
-                    };
+                    self.awaiting_reply = false;
+                    return Ok(response);
                 }
                 Err(nom::Err::Failure(e)) => {
-                    return Err(error::response(e.to_string()));
+                    return Err(parse_error(e));
                 }
                 Err(nom::Err::Incomplete(_)) => { /* read more */ }
                 Err(nom::Err::Error(e)) => {
-                    return Err(error::response(e.to_string()));
+                    return Err(parse_error(e));
                 }
             }
         }
@@ -302,6 +815,55 @@ impl SmtpConnection {
         Err(error::response("incomplete response"))
     }
 
+    /// Reads a line into `buffer`, returning its length
+    ///
+    /// Fails when the deadline is exceeded or the reply exceeds the limits.
+    fn read_line(
+        &mut self,
+        buffer: &mut Vec<u8>,
+        deadline: Option<Instant>,
+    ) -> Result<usize, Error> {
+        let start = buffer.len();
+
+        loop {
+            if let Some(deadline) = deadline {
+                let timeout = timeout_before(self.timeout, deadline)?;
+                self.stream
+                    .get_mut()
+                    .set_read_timeout(Some(timeout))
+                    .map_err(error::network)?;
+            }
+
+            let available = match self.stream.fill_buf() {
+                Ok(available) => available,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(_) if deadline.is_some_and(|deadline| Instant::now() >= deadline) => {
+                    return Err(error::timeout());
+                }
+                Err(err) => return Err(error::network(err)),
+            };
+
+            let (done, used) = match available.iter().position(|&b| b == b'\n') {
+                Some(i) => (true, i + 1),
+                // An empty buffer means the end of the stream
+                None => (available.is_empty(), available.len()),
+            };
+            self.reply_limits
+                .check(buffer.len() - start + used, buffer.len() + used)?;
+            buffer.extend_from_slice(&available[..used]);
+            self.stream.consume(used);
+
+            if done {
+                return Ok(buffer.len() - start);
+            }
+        }
+    }
+
+    /// The address of the server the connection was established to
+    pub fn peer_addr(&self) -> Result<SocketAddr, Error> {
+        self.stream.get_ref().peer_addr().map_err(error::network)
+    }
+
     /// The X509 certificate of the server (DER encoded)
     #[cfg(any(feature = "native-tls", feature = "rustls-tls", feature = "boring-tls"))]
     pub fn peer_certificate(&self) -> Result<Vec<u8>, Error> {
@@ -314,3 +876,12 @@ impl SmtpConnection {
         self.stream.get_ref().certificate_chain()
     }
 }
+
+/// Timeout for a network operation which must complete before `deadline`
+fn timeout_before(timeout: Option<Duration>, deadline: Instant) -> Result<Duration, Error> {
+    let remaining = deadline.saturating_duration_since(Instant::now());
+    if remaining.is_zero() {
+        return Err(error::timeout());
+    }
+    Ok(timeout.map_or(remaining, |timeout| timeout.min(remaining)))
+}
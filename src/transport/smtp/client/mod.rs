@@ -38,8 +38,17 @@ pub(super) use self::tls::InnerTlsParameters;
 pub use self::tls::TlsVersion;
 pub use self::{
     connection::SmtpConnection,
-    tls::{Certificate, CertificateStore, Identity, Tls, TlsParameters, TlsParametersBuilder},
+    tls::{
+        Certificate, CertificateStore, Crl, Identity, RevocationCheck, Tls, TlsParameters,
+        TlsParametersBuilder,
+    },
 };
+use super::{
+    commands::{Data, Mail, Rcpt},
+    error::{self, Error},
+    extension::{MailBodyParameter, MailParameter, ServerInfo},
+};
+use crate::address::Envelope;
 
 #[cfg(any(feature = "tokio1", feature = "async-std1"))]
 mod async_connection;
@@ -49,6 +58,9 @@ mod connection;
 mod net;
 mod tls;
 
+/// Size of the chunks read from streamed messages
+const STREAM_CHUNK_SIZE: usize = 8 * 1024;
+
 /// The codec used for transparency
 #[derive(Debug)]
 struct ClientCodec {
@@ -101,6 +113,72 @@ enum CodecStatus {
     StartOfNewLine,
 }
 
+/// Checks a message of `size` bytes sent to the recipients of `envelope`
+/// against the limits announced by the server
+///
+/// Fails if the message is larger than `SIZE`, if the envelope has more
+/// recipients than `RCPTMAX`, or if the `transactions` already started on the
+/// connection reached `MAILMAX`.
+fn check_limits(
+    server_info: &ServerInfo,
+    envelope: &Envelope,
+    size: Option<usize>,
+    transactions: usize,
+) -> Result<(), Error> {
+    if let (Some(size), Some(max_size)) = (size, server_info.max_size()) {
+        if size > max_size {
+            return Err(error::client(format!(
+                "Message size of {size} bytes exceeds the maximum of {max_size} bytes accepted by the server"
+            )));
+        }
+    }
+
+    let recipients = envelope.to().len();
+    if let Some(rcpt_max) = server_info.limits().rcpt_max() {
+        if recipients > rcpt_max {
+            return Err(error::client(format!(
+                "Envelope has {recipients} recipients but the server accepts at most {rcpt_max} per transaction"
+            )));
+        }
+    }
+
+    if let Some(mail_max) = server_info.limits().mail_max() {
+        if transactions >= mail_max {
+            return Err(error::client(format!(
+                "Connection reached the maximum of {mail_max} transactions accepted by the server"
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// The bytes written to send `email` in a transaction, from `MAIL` to the
+/// final dot, to a server supporting the `8BITMIME` and `SMTPUTF8` extensions
+pub(crate) fn transaction(envelope: &Envelope, email: &[u8]) -> Vec<u8> {
+    let mut mail_options = Vec::new();
+    if envelope.has_non_ascii_addresses() {
+        mail_options.push(MailParameter::SmtpUtfEight);
+    }
+    if !email.is_ascii() {
+        mail_options.push(MailParameter::Body(MailBodyParameter::EightBitMime));
+    }
+
+    let mut out = Mail::new(envelope.from().cloned(), mail_options)
+        .to_string()
+        .into_bytes();
+    for to_address in envelope.to() {
+        out.extend_from_slice(
+            Rcpt::new(to_address.clone(), Vec::new())
+                .to_string()
+                .as_bytes(),
+        );
+    }
+    out.extend_from_slice(Data.to_string().as_bytes());
+    ClientCodec::new().encode(email, &mut out);
+    out.extend_from_slice(b"\r\n.\r\n");
+    out
+}
+
 /// Returns the string replacing all the CRLF with "\<CRLF\>"
 /// Used for debug displays
 #[cfg(feature = "tracing")]
@@ -111,6 +189,21 @@ pub(super) fn escape_crlf(string: &str) -> String {
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::transport::smtp::response::{Category, Code, Detail, Response, Severity};
+
+    fn server_info(keywords: &[&str]) -> ServerInfo {
+        let mut message = vec!["me".to_owned()];
+        message.extend(keywords.iter().map(|keyword| (*keyword).to_owned()));
+        let response = Response::new(
+            Code::new(
+                Severity::PositiveCompletion,
+                Category::MailSystem,
+                Detail::Zero,
+            ),
+            message,
+        );
+        ServerInfo::from_response(&response).unwrap()
+    }
 
     #[test]
     fn test_codec() {
@@ -148,4 +241,40 @@ mod test {
             "EHLO my_name<CRLF>SIZE 42<CRLF>"
         );
     }
+
+    #[test]
+    fn test_check_limits() {
+        let envelope = Envelope::new(
+            Some("from@domain.tld".parse().unwrap()),
+            vec![
+                "a@domain.tld".parse().unwrap(),
+                "b@domain.tld".parse().unwrap(),
+                "c@domain.tld".parse().unwrap(),
+            ],
+        )
+        .unwrap();
+
+        let unlimited = server_info(&["SIZE 0"]);
+        check_limits(&unlimited, &envelope, Some(100), 10).unwrap();
+
+        let limited = server_info(&["SIZE 50", "LIMITS RCPTMAX=3 MAILMAX=2"]);
+        check_limits(&limited, &envelope, Some(50), 1).unwrap();
+
+        let err = check_limits(&limited, &envelope, None, 2).unwrap_err();
+        assert!(err.is_client());
+        assert!(err
+            .to_string()
+            .contains("Connection reached the maximum of 2 transactions"));
+        let err = check_limits(&limited, &envelope, Some(51), 0).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("Message size of 51 bytes exceeds the maximum of 50 bytes"));
+
+        let limited = server_info(&["LIMITS RCPTMAX=2"]);
+        let err = check_limits(&limited, &envelope, None, 0).unwrap_err();
+        assert!(err.is_client());
+        assert!(err
+            .to_string()
+            .contains("Envelope has 3 recipients but the server accepts at most 2"));
+    }
 }
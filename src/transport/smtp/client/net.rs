@@ -95,11 +95,24 @@ impl NetworkStream {
         timeout: Option<Duration>,
         tls_parameters: Option<&TlsParameters>,
         local_addr: Option<IpAddr>,
+    ) -> Result<NetworkStream, Error> {
+        Self::connect_counting(server, timeout, tls_parameters, local_addr, &mut 0)
+    }
+
+    /// Connects like [`NetworkStream::connect`], setting `attempts` to the
+    /// number of resolved addresses a connection was attempted to
+    pub(crate) fn connect_counting<T: ToSocketAddrs>(
+        server: T,
+        timeout: Option<Duration>,
+        tls_parameters: Option<&TlsParameters>,
+        local_addr: Option<IpAddr>,
+        attempts: &mut usize,
     ) -> Result<NetworkStream, Error> {
         fn try_connect<T: ToSocketAddrs>(
             server: T,
             timeout: Option<Duration>,
             local_addr: Option<IpAddr>,
+            attempts: &mut usize,
         ) -> Result<TcpStream, Error> {
             let addrs = server
                 .to_socket_addrs()
@@ -108,7 +121,8 @@ impl NetworkStream {
 
             let mut last_err = None;
 
-            for addr in addrs {
+            for (attempt, addr) in addrs.enumerate() {
+                *attempts = attempt + 1;
                 let socket = socket2::Socket::new(
                     Domain::for_address(addr),
                     Type::STREAM,
@@ -117,15 +131,18 @@ impl NetworkStream {
                 .map_err(error::connection)?;
                 bind_local_address(&socket, &addr, local_addr)?;
 
-                if let Some(timeout) = timeout {
-                    match socket.connect_timeout(&addr.into(), timeout) {
-                        Ok(()) => return Ok(socket.into()),
-                        Err(err) => last_err = Some(err),
+                let result = match timeout {
+                    Some(timeout) => socket.connect_timeout(&addr.into(), timeout),
+                    None => socket.connect(&addr.into()),
+                };
+                match result {
+                    Ok(()) => {
+                        log_connected(&addr, attempt + 1);
+                        return Ok(socket.into());
                     }
-                } else {
-                    match socket.connect(&addr.into()) {
-                        Ok(()) => return Ok(socket.into()),
-                        Err(err) => last_err = Some(err),
+                    Err(err) => {
+                        log_connect_failed(&addr, &err);
+                        last_err = Some(err);
                     }
                 }
             }
@@ -136,7 +153,14 @@ impl NetworkStream {
             })
         }
 
-        let tcp_stream = try_connect(server, timeout, local_addr)?;
+        let tcp_stream = try_connect(server, timeout, local_addr, attempts)?;
+        // Don't let the TLS handshake hang
+        tcp_stream
+            .set_read_timeout(timeout)
+            .map_err(error::connection)?;
+        tcp_stream
+            .set_write_timeout(timeout)
+            .map_err(error::connection)?;
         let mut stream = NetworkStream::new(InnerNetworkStream::Tcp(tcp_stream));
         if let Some(tls_parameters) = tls_parameters {
             stream.upgrade_tls(tls_parameters)?;
@@ -409,3 +433,22 @@ pub(crate) fn resolved_address_filter(
         None => true,
     }
 }
+
+/// Logs the address a connection was established to, after `attempts` tries
+#[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+pub(crate) fn log_connected(addr: &SocketAddr, attempts: usize) {
+    #[cfg(feature = "tracing")]
+    tracing::debug!(
+        peer_addr = %addr,
+        ipv6 = addr.is_ipv6(),
+        attempts,
+        "connected"
+    );
+}
+
+/// Logs a failed connection attempt to one of the resolved addresses
+#[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+pub(crate) fn log_connect_failed(addr: &SocketAddr, err: &io::Error) {
+    #[cfg(feature = "tracing")]
+    tracing::debug!(peer_addr = %addr, "connection failed: {err}");
+}
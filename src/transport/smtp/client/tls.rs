@@ -1,6 +1,9 @@
-use std::fmt::{self, Debug};
 #[cfg(feature = "rustls-tls")]
-use std::{io, sync::Arc};
+use std::io;
+use std::{
+    fmt::{self, Debug},
+    sync::Arc,
+};
 
 #[cfg(feature = "boring-tls")]
 use boring::{
@@ -12,12 +15,18 @@ use boring::{
 use native_tls::{Protocol, TlsConnector};
 #[cfg(feature = "rustls-tls")]
 use rustls::{
-    client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier},
+    client::{
+        danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier},
+        Resumption, WebPkiServerVerifier,
+    },
     crypto::WebPkiSupportedAlgorithms,
     crypto::{verify_tls12_signature, verify_tls13_signature},
-    pki_types::{CertificateDer, PrivateKeyDer, ServerName, UnixTime},
+    pki_types::{
+        CertificateDer, CertificateRevocationListDer, PrivateKeyDer, ServerName, UnixTime,
+    },
     server::ParsedCertificate,
-    ClientConfig, DigitallySignedStruct, Error as TlsError, RootCertStore, SignatureScheme,
+    CertificateError, ClientConfig, DigitallySignedStruct, Error as TlsError, RootCertStore,
+    SignatureScheme,
 };
 
 #[cfg(any(feature = "native-tls", feature = "rustls-tls", feature = "boring-tls"))]
@@ -181,6 +190,10 @@ pub struct TlsParametersBuilder {
     accept_invalid_certs: bool,
     #[cfg(any(feature = "native-tls", feature = "rustls-tls", feature = "boring-tls"))]
     min_tls_version: TlsVersion,
+    session_cache_size: Option<usize>,
+    crls: Vec<Crl>,
+    require_ocsp_stapling: bool,
+    revocation_check: Option<Arc<dyn RevocationCheck>>,
 }
 
 impl TlsParametersBuilder {
@@ -195,6 +208,10 @@ impl TlsParametersBuilder {
             accept_invalid_certs: false,
             #[cfg(any(feature = "native-tls", feature = "rustls-tls", feature = "boring-tls"))]
             min_tls_version: TlsVersion::Tlsv12,
+            session_cache_size: None,
+            crls: Vec::new(),
+            require_ocsp_stapling: false,
+            revocation_check: None,
         }
     }
 
@@ -250,6 +267,52 @@ impl TlsParametersBuilder {
         self
     }
 
+    /// Set the number of TLS sessions cached for resumption, `0` disabling it
+    ///
+    /// Resuming a previous session with session tickets or ids skips
+    /// most of the TLS handshake, which cuts the latency of reconnecting,
+    /// for example when the connection pool opens new connections.
+    /// The cache is shared by all the connections using these parameters.
+    ///
+    /// Only supported by rustls, where sessions are cached by default.
+    /// With native-tls, resumption is left to the platform TLS library.
+    pub fn session_cache_size(mut self, size: usize) -> Self {
+        self.session_cache_size = Some(size);
+        self
+    }
+
+    /// Add a certificate revocation list (CRL)
+    ///
+    /// Once a CRL is added, the certificates of the whole chain are checked
+    /// against the CRLs, and rejected when their revocation status
+    /// can't be determined from them.
+    ///
+    /// Only supported by rustls.
+    pub fn add_crl(mut self, crl: Crl) -> Self {
+        self.crls.push(crl);
+        self
+    }
+
+    /// Controls whether the server must staple an OCSP response to its certificate
+    ///
+    /// The stapled response is given to the [`RevocationCheck`], if any,
+    /// which is responsible for validating it.
+    ///
+    /// Defaults to `false`. Only supported by rustls.
+    pub fn require_ocsp_stapling(mut self, require_ocsp_stapling: bool) -> Self {
+        self.require_ocsp_stapling = require_ocsp_stapling;
+        self
+    }
+
+    /// Set a callback deciding whether to trust the server certificate based
+    /// on revocation data
+    ///
+    /// Only supported by rustls.
+    pub fn revocation_check<C: RevocationCheck + 'static>(mut self, check: C) -> Self {
+        self.revocation_check = Some(Arc::new(check));
+        self
+    }
+
     /// Controls whether invalid certificates are accepted
     ///
     /// Defaults to `false`.
@@ -291,6 +354,12 @@ impl TlsParametersBuilder {
     #[cfg(feature = "native-tls")]
     #[cfg_attr(docsrs, doc(cfg(feature = "native-tls")))]
     pub fn build_native(self) -> Result<TlsParameters, Error> {
+        if self.checks_revocation() {
+            return Err(error::tls(
+                "revocation checking is not supported in native tls",
+            ));
+        }
+
         let mut tls_builder = TlsConnector::builder();
 
         match self.cert_store {
@@ -326,6 +395,11 @@ impl TlsParametersBuilder {
         if let Some(identity) = self.identity {
             tls_builder.identity(identity.native_tls);
         }
+        if self.session_cache_size.is_some() {
+            return Err(error::tls(
+                "configuring the session cache is not supported in native tls",
+            ));
+        }
 
         let connector = tls_builder.build().map_err(error::tls)?;
         Ok(TlsParameters {
@@ -342,6 +416,12 @@ impl TlsParametersBuilder {
     pub fn build_boring(self) -> Result<TlsParameters, Error> {
         use boring::ssl::{SslMethod, SslVerifyMode};
 
+        if self.checks_revocation() {
+            return Err(error::tls(
+                "revocation checking is not supported in boring tls",
+            ));
+        }
+
         let mut tls_builder = SslConnector::builder(SslMethod::tls_client()).map_err(error::tls)?;
 
         if self.accept_invalid_certs {
@@ -388,6 +468,11 @@ impl TlsParametersBuilder {
         tls_builder
             .set_min_proto_version(Some(min_tls_version))
             .map_err(error::tls)?;
+        if self.session_cache_size.is_some() {
+            return Err(error::tls(
+                "configuring the session cache is not supported in boring tls",
+            ));
+        }
         let connector = tls_builder.build();
         Ok(TlsParameters {
             connector: InnerTlsParameters::BoringTls(connector),
@@ -400,6 +485,7 @@ impl TlsParametersBuilder {
     #[cfg(feature = "rustls-tls")]
     #[cfg_attr(docsrs, doc(cfg(feature = "rustls-tls")))]
     pub fn build_rustls(self) -> Result<TlsParameters, Error> {
+        let checks_revocation = self.checks_revocation();
         let just_version3 = &[&rustls::version::TLS13];
         let supported_versions = match self.min_tls_version {
             TlsVersion::Tlsv10 => {
@@ -460,26 +546,56 @@ impl TlsParametersBuilder {
             }
         }
 
-        let tls = if self.accept_invalid_certs || self.accept_invalid_hostnames {
-            let verifier = InvalidCertsVerifier {
-                ignore_invalid_hostnames: self.accept_invalid_hostnames,
-                ignore_invalid_certs: self.accept_invalid_certs,
-                roots: root_cert_store,
-                signature_algorithms,
+        let root_cert_store = Arc::new(root_cert_store);
+        let verifier: Option<Arc<dyn ServerCertVerifier>> =
+            if self.accept_invalid_certs || self.accept_invalid_hostnames {
+                Some(Arc::new(InvalidCertsVerifier {
+                    ignore_invalid_hostnames: self.accept_invalid_hostnames,
+                    ignore_invalid_certs: self.accept_invalid_certs,
+                    roots: Arc::clone(&root_cert_store),
+                    signature_algorithms,
+                }))
+            } else if checks_revocation {
+                let crls = self.crls.into_iter().flat_map(|crl| crl.rustls);
+                Some(
+                    WebPkiServerVerifier::builder_with_provider(
+                        Arc::clone(&root_cert_store),
+                        Arc::clone(&provider),
+                    )
+                    .with_crls(crls)
+                    .build()
+                    .map_err(error::tls)?,
+                )
+            } else {
+                None
             };
-            tls.dangerous()
-                .with_custom_certificate_verifier(Arc::new(verifier))
-        } else {
-            tls.with_root_certificates(root_cert_store)
+
+        let tls = match verifier {
+            Some(inner) if self.require_ocsp_stapling || self.revocation_check.is_some() => {
+                let verifier = RevocationVerifier {
+                    inner,
+                    require_ocsp_stapling: self.require_ocsp_stapling,
+                    check: self.revocation_check,
+                };
+                tls.dangerous()
+                    .with_custom_certificate_verifier(Arc::new(verifier))
+            }
+            Some(verifier) => tls.dangerous().with_custom_certificate_verifier(verifier),
+            None => tls.with_root_certificates(root_cert_store),
         };
 
-        let tls = if let Some(identity) = self.identity {
+        let mut tls = if let Some(identity) = self.identity {
             let (client_certificates, private_key) = identity.rustls_tls;
             tls.with_client_auth_cert(client_certificates, private_key)
                 .map_err(error::tls)?
         } else {
             tls.with_no_client_auth()
         };
+        match self.session_cache_size {
+            Some(0) => tls.resumption = Resumption::disabled(),
+            Some(size) => tls.resumption = Resumption::in_memory_sessions(size),
+            None => {}
+        }
 
         Ok(TlsParameters {
             connector: InnerTlsParameters::RustlsTls(Arc::new(tls)),
@@ -488,6 +604,10 @@ impl TlsParametersBuilder {
             accept_invalid_hostnames: self.accept_invalid_hostnames,
         })
     }
+
+    fn checks_revocation(&self) -> bool {
+        !self.crls.is_empty() || self.require_ocsp_stapling || self.revocation_check.is_some()
+    }
 }
 
 #[derive(Clone)]
@@ -518,6 +638,19 @@ impl TlsParameters {
         TlsParametersBuilder::new(domain)
     }
 
+    /// Name of the TLS library used by the connector
+    #[cfg(any(feature = "native-tls", feature = "rustls-tls", feature = "boring-tls"))]
+    pub(crate) fn backend(&self) -> &'static str {
+        match self.connector {
+            #[cfg(feature = "native-tls")]
+            InnerTlsParameters::NativeTls(_) => "native-tls",
+            #[cfg(feature = "rustls-tls")]
+            InnerTlsParameters::RustlsTls(_) => "rustls",
+            #[cfg(feature = "boring-tls")]
+            InnerTlsParameters::BoringTls(_) => "boring-tls",
+        }
+    }
+
     /// Creates a new `TlsParameters` using native-tls
     #[cfg(feature = "native-tls")]
     #[cfg_attr(docsrs, doc(cfg(feature = "native-tls")))]
@@ -539,6 +672,67 @@ impl TlsParameters {
         TlsParametersBuilder::new(domain).build_boring()
     }
 
+    /// Creates a new `TlsParameters` from a native-tls connector configured by the caller
+    ///
+    /// Gives full control over the TLS configuration, for needs not covered by
+    /// [`TlsParametersBuilder`]. The server certificate is checked against `domain`
+    /// according to the connector configuration.
+    #[cfg(feature = "native-tls")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "native-tls")))]
+    pub fn from_native_connector(domain: String, connector: TlsConnector) -> Self {
+        Self {
+            connector: InnerTlsParameters::NativeTls(connector),
+            domain,
+            #[cfg(feature = "boring-tls")]
+            accept_invalid_hostnames: false,
+        }
+    }
+
+    /// Creates a new `TlsParameters` from a rustls configuration built by the caller
+    ///
+    /// Gives full control over the TLS configuration, for example to set
+    /// ALPN protocols, use a custom certificate verifier or log the session
+    /// keys with [`KeyLogFile`](rustls::KeyLogFile) for debugging.
+    ///
+    /// ```rust,no_run
+    /// use std::sync::Arc;
+    ///
+    /// use lettre::transport::smtp::client::TlsParameters;
+    /// use rustls::{ClientConfig, KeyLogFile, RootCertStore};
+    ///
+    /// let roots = RootCertStore::from_iter(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+    /// let mut config = ClientConfig::builder()
+    ///     .with_root_certificates(roots)
+    ///     .with_no_client_auth();
+    /// config.key_log = Arc::new(KeyLogFile::new());
+    ///
+    /// let tls = TlsParameters::from_rustls_config("smtp.example.com".to_owned(), Arc::new(config));
+    /// ```
+    #[cfg(feature = "rustls-tls")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "rustls-tls")))]
+    pub fn from_rustls_config(domain: String, config: Arc<ClientConfig>) -> Self {
+        Self {
+            connector: InnerTlsParameters::RustlsTls(config),
+            domain,
+            #[cfg(feature = "boring-tls")]
+            accept_invalid_hostnames: false,
+        }
+    }
+
+    /// Creates a new `TlsParameters` from a boring connector configured by the caller
+    ///
+    /// Gives full control over the TLS configuration, for needs not covered by
+    /// [`TlsParametersBuilder`]. The server hostname is always verified against `domain`.
+    #[cfg(feature = "boring-tls")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "boring-tls")))]
+    pub fn from_boring_connector(domain: String, connector: SslConnector) -> Self {
+        Self {
+            connector: InnerTlsParameters::BoringTls(connector),
+            domain,
+            accept_invalid_hostnames: false,
+        }
+    }
+
     pub fn domain(&self) -> &str {
         &self.domain
     }
@@ -611,6 +805,69 @@ impl Debug for Certificate {
     }
 }
 
+/// A certificate revocation list that can be used with [`TlsParametersBuilder::add_crl`]
+#[derive(Clone)]
+#[allow(missing_copy_implementations)]
+pub struct Crl {
+    #[cfg(feature = "rustls-tls")]
+    rustls: Vec<CertificateRevocationListDer<'static>>,
+}
+
+#[cfg(any(feature = "native-tls", feature = "rustls-tls", feature = "boring-tls"))]
+impl Crl {
+    /// Create a `Crl` from a DER encoded certificate revocation list
+    pub fn from_der(der: Vec<u8>) -> Result<Self, Error> {
+        #[cfg(not(feature = "rustls-tls"))]
+        let _ = der;
+
+        Ok(Self {
+            #[cfg(feature = "rustls-tls")]
+            rustls: vec![der.into()],
+        })
+    }
+
+    /// Create a `Crl` from PEM encoded certificate revocation lists
+    pub fn from_pem(pem: &[u8]) -> Result<Self, Error> {
+        #[cfg(not(feature = "rustls-tls"))]
+        let _ = pem;
+
+        #[cfg(feature = "rustls-tls")]
+        let rustls_crls = {
+            use std::io::Cursor;
+
+            let mut pem = Cursor::new(pem);
+            rustls_pemfile::crls(&mut pem)
+                .collect::<io::Result<Vec<_>>>()
+                .map_err(|_| error::tls("invalid certificate revocation lists"))?
+        };
+
+        Ok(Self {
+            #[cfg(feature = "rustls-tls")]
+            rustls: rustls_crls,
+        })
+    }
+}
+
+impl Debug for Crl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Crl").finish()
+    }
+}
+
+/// Callback deciding whether to trust a server certificate based on revocation data
+///
+/// Registered with [`TlsParametersBuilder::revocation_check`], it's called
+/// once the certificate chain has been verified, for example to validate
+/// the stapled OCSP response or to look the certificate up in a revocation
+/// service.
+pub trait RevocationCheck: Debug + Send + Sync {
+    /// Whether to trust the DER encoded `end_entity` certificate
+    ///
+    /// `ocsp_response` is the OCSP response stapled by the server,
+    /// empty when there is none.
+    fn check(&self, end_entity: &[u8], intermediates: &[&[u8]], ocsp_response: &[u8]) -> bool;
+}
+
 /// An identity that can be used with [`TlsParametersBuilder::identify_with`]
 #[allow(missing_copy_implementations)]
 pub struct Identity {
@@ -685,7 +942,7 @@ impl Identity {
 struct InvalidCertsVerifier {
     ignore_invalid_hostnames: bool,
     ignore_invalid_certs: bool,
-    roots: RootCertStore,
+    roots: Arc<RootCertStore>,
     signature_algorithms: WebPkiSupportedAlgorithms,
 }
 
@@ -751,3 +1008,101 @@ impl ServerCertVerifier for InvalidCertsVerifier {
             .supported_schemes()
     }
 }
+
+#[cfg(feature = "rustls-tls")]
+#[derive(Debug)]
+struct RevocationVerifier {
+    inner: Arc<dyn ServerCertVerifier>,
+    require_ocsp_stapling: bool,
+    check: Option<Arc<dyn RevocationCheck>>,
+}
+
+#[cfg(feature = "rustls-tls")]
+impl ServerCertVerifier for RevocationVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+        server_name: &ServerName<'_>,
+        ocsp_response: &[u8],
+        now: UnixTime,
+    ) -> Result<ServerCertVerified, TlsError> {
+        let verified = self.inner.verify_server_cert(
+            end_entity,
+            intermediates,
+            server_name,
+            ocsp_response,
+            now,
+        )?;
+
+        if self.require_ocsp_stapling && ocsp_response.is_empty() {
+            return Err(TlsError::General(
+                "missing stapled OCSP response".to_owned(),
+            ));
+        }
+        if let Some(check) = &self.check {
+            let intermediates = intermediates
+                .iter()
+                .map(|cert| cert.as_ref())
+                .collect::<Vec<_>>();
+            if !check.check(end_entity, &intermediates, ocsp_response) {
+                return Err(TlsError::InvalidCertificate(CertificateError::Revoked));
+            }
+        }
+        Ok(verified)
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, TlsError> {
+        self.inner.verify_tls12_signature(message, cert, dss)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, TlsError> {
+        self.inner.verify_tls13_signature(message, cert, dss)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.inner.supported_verify_schemes()
+    }
+}
+
+#[cfg(all(test, any(feature = "native-tls", feature = "rustls-tls")))]
+mod test {
+    use super::{RevocationCheck, TlsParameters};
+
+    #[derive(Debug)]
+    struct RejectAll;
+
+    impl RevocationCheck for RejectAll {
+        fn check(&self, _: &[u8], _: &[&[u8]], _: &[u8]) -> bool {
+            false
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "rustls-tls")]
+    fn rustls_revocation_options() {
+        TlsParameters::builder("smtp.example.com".to_owned())
+            .require_ocsp_stapling(true)
+            .revocation_check(RejectAll)
+            .build_rustls()
+            .unwrap();
+    }
+
+    #[test]
+    #[cfg(feature = "native-tls")]
+    fn native_revocation_unsupported() {
+        let builder =
+            TlsParameters::builder("smtp.example.com".to_owned()).revocation_check(RejectAll);
+        assert!(builder.build_native().is_err());
+    }
+}
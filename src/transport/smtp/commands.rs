@@ -1,6 +1,6 @@
 //! SMTP commands
 
-use std::fmt::{self, Display, Formatter};
+use std::fmt::{self, Debug, Display, Formatter};
 
 use crate::{
     address::Address,
@@ -12,6 +12,52 @@ use crate::{
     },
 };
 
+/// An SMTP command
+///
+/// Implemented by the commands of this module, it can also be implemented
+/// for commands unknown to lettre, like proprietary verbs, to send them with
+/// [`SmtpConnection::send_command`](super::client::SmtpConnection::send_command).
+/// The [`Display`] implementation must write the whole command line,
+/// including the trailing `CRLF`.
+///
+/// ```rust
+/// use std::fmt::{self, Display, Formatter};
+///
+/// use lettre::transport::smtp::{commands::Command, response::Response};
+///
+/// /// ATRN command, defined in RFC 2645
+/// struct Atrn(String);
+///
+/// impl Display for Atrn {
+///     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+///         write!(f, "ATRN {}\r\n", self.0)
+///     }
+/// }
+///
+/// impl Command for Atrn {
+///     fn is_expected(&self, response: &Response) -> bool {
+///         response.has_code(250)
+///     }
+/// }
+/// ```
+pub trait Command: Display {
+    /// Whether `response` means the command succeeded
+    ///
+    /// Defaults to positive responses, with `2xx` and `3xx` codes
+    fn is_expected(&self, response: &Response) -> bool {
+        response.is_positive()
+    }
+
+    /// Whether the command carries secrets, which are kept out of the logs
+    fn is_sensitive(&self) -> bool {
+        false
+    }
+}
+
+impl Command for String {}
+
+impl Command for &str {}
+
 /// EHLO command
 #[derive(PartialEq, Eq, Clone, Debug)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
@@ -25,6 +71,8 @@ impl Display for Ehlo {
     }
 }
 
+impl Command for Ehlo {}
+
 impl Ehlo {
     /// Creates a EHLO command
     pub fn new(client_id: ClientId) -> Ehlo {
@@ -32,6 +80,31 @@ impl Ehlo {
     }
 }
 
+/// HELO command
+///
+/// Used instead of [`Ehlo`] with servers which don't support ESMTP,
+/// it doesn't return the supported extensions.
+#[derive(PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Helo {
+    client_id: ClientId,
+}
+
+impl Display for Helo {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "HELO {}\r\n", self.client_id)
+    }
+}
+
+impl Command for Helo {}
+
+impl Helo {
+    /// Creates a HELO command
+    pub fn new(client_id: ClientId) -> Helo {
+        Helo { client_id }
+    }
+}
+
 /// STARTTLS command
 #[derive(PartialEq, Eq, Clone, Debug, Copy)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
@@ -43,6 +116,8 @@ impl Display for Starttls {
     }
 }
 
+impl Command for Starttls {}
+
 /// MAIL command
 #[derive(PartialEq, Eq, Clone, Debug)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
@@ -65,6 +140,8 @@ impl Display for Mail {
     }
 }
 
+impl Command for Mail {}
+
 impl Mail {
     /// Creates a MAIL command
     pub fn new(sender: Option<Address>, parameters: Vec<MailParameter>) -> Mail {
@@ -90,6 +167,8 @@ impl Display for Rcpt {
     }
 }
 
+impl Command for Rcpt {}
+
 impl Rcpt {
     /// Creates an RCPT command
     pub fn new(recipient: Address, parameters: Vec<RcptParameter>) -> Rcpt {
@@ -111,6 +190,8 @@ impl Display for Data {
     }
 }
 
+impl Command for Data {}
+
 /// QUIT command
 #[derive(PartialEq, Eq, Clone, Debug, Copy)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
@@ -122,6 +203,8 @@ impl Display for Quit {
     }
 }
 
+impl Command for Quit {}
+
 /// NOOP command
 #[derive(PartialEq, Eq, Clone, Debug, Copy)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
@@ -133,6 +216,8 @@ impl Display for Noop {
     }
 }
 
+impl Command for Noop {}
+
 /// HELP command
 #[derive(PartialEq, Eq, Clone, Debug)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
@@ -150,6 +235,8 @@ impl Display for Help {
     }
 }
 
+impl Command for Help {}
+
 impl Help {
     /// Creates an HELP command
     pub fn new(argument: Option<String>) -> Help {
@@ -170,6 +257,8 @@ impl Display for Vrfy {
     }
 }
 
+impl Command for Vrfy {}
+
 impl Vrfy {
     /// Creates a VRFY command
     pub fn new(argument: String) -> Vrfy {
@@ -190,6 +279,8 @@ impl Display for Expn {
     }
 }
 
+impl Command for Expn {}
+
 impl Expn {
     /// Creates an EXPN command
     pub fn new(argument: String) -> Expn {
@@ -197,6 +288,36 @@ impl Expn {
     }
 }
 
+/// BURL command
+///
+/// Defined in [RFC 4468](https://tools.ietf.org/html/rfc4468), it replaces `DATA`
+/// by a reference to a message stored on an IMAP server.
+#[derive(PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Burl {
+    url: String,
+    last: bool,
+}
+
+impl Display for Burl {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "BURL {}", self.url)?;
+        if self.last {
+            f.write_str(" LAST")?;
+        }
+        f.write_str("\r\n")
+    }
+}
+
+impl Command for Burl {}
+
+impl Burl {
+    /// Creates a BURL command, `last` being set on the command completing the message
+    pub fn new(url: String, last: bool) -> Burl {
+        Burl { url, last }
+    }
+}
+
 /// RSET command
 #[derive(PartialEq, Eq, Clone, Debug, Copy)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
@@ -208,8 +329,10 @@ impl Display for Rset {
     }
 }
 
+impl Command for Rset {}
+
 /// AUTH command
-#[derive(PartialEq, Eq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Auth {
     mechanism: Mechanism,
@@ -234,6 +357,23 @@ impl Display for Auth {
     }
 }
 
+impl Debug for Auth {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Auth")
+            .field("mechanism", &self.mechanism)
+            .field("credentials", &self.credentials)
+            .field("challenge", &self.challenge)
+            .field("response", &self.response.as_ref().map(|_| "<redacted>"))
+            .finish()
+    }
+}
+
+impl Command for Auth {
+    fn is_sensitive(&self) -> bool {
+        true
+    }
+}
+
 impl Auth {
     /// Creates an AUTH command (from a challenge if provided)
     pub fn new(
@@ -352,6 +492,23 @@ mod test {
         assert_eq!(format!("{}", Vrfy::new("test".to_owned())), "VRFY test\r\n");
         assert_eq!(format!("{}", Expn::new("test".to_owned())), "EXPN test\r\n");
         assert_eq!(format!("{Rset}"), "RSET\r\n");
+        assert_eq!(
+            format!(
+                "{}",
+                Burl::new(
+                    "imap://user@imap.example.com/Drafts;UIDVALIDITY=1/;UID=2".to_owned(),
+                    true
+                )
+            ),
+            "BURL imap://user@imap.example.com/Drafts;UIDVALIDITY=1/;UID=2 LAST\r\n"
+        );
+        assert_eq!(
+            format!(
+                "{}",
+                Burl::new("imap://imap.example.com/INBOX".to_owned(), false)
+            ),
+            "BURL imap://imap.example.com/INBOX\r\n"
+        );
         let credentials = Credentials::new("user".to_owned(), "password".to_owned());
         assert_eq!(
             format!(
@@ -368,4 +525,17 @@ mod test {
             "AUTH LOGIN\r\n"
         );
     }
+
+    #[test]
+    fn test_debug_redacted() {
+        let credentials = Credentials::new("user".to_owned(), "password".to_owned());
+        for mechanism in [Mechanism::Plain, Mechanism::Xoauth2] {
+            let auth = Auth::new(mechanism, credentials.clone(), None).unwrap();
+            let debug = format!("{auth:?}");
+            assert!(!debug.contains("password"), "{debug}");
+            assert!(debug.contains("<redacted>"), "{debug}");
+            assert!(auth.is_sensitive());
+        }
+        assert!(!Noop.is_sensitive());
+    }
 }
@@ -0,0 +1,150 @@
+//! Step by step diagnosis of the connection to an SMTP server
+
+use std::{
+    net::SocketAddr,
+    time::{Duration, Instant},
+};
+
+use super::{extension::ServerInfo, transport::SmtpClient, Error};
+
+/// Encryption achieved on the connection
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Encryption {
+    /// The connection is in plain text
+    None,
+    /// The connection was encrypted from the start
+    Wrapper,
+    /// The connection was upgraded using `STARTTLS`
+    StartTls,
+}
+
+/// Report on each step of connecting to an SMTP server
+///
+/// Built by [`SmtpTransport::diagnose`][super::SmtpTransport::diagnose], it holds
+/// whatever could be learned before the first failing step, which is given by
+/// [`Diagnosis::error`]. The steps are the ones taken to open a connection for
+/// sending, with the same settings.
+#[derive(Debug)]
+pub struct Diagnosis {
+    resolved_addrs: Vec<SocketAddr>,
+    peer_addr: Option<SocketAddr>,
+    connect_attempts: usize,
+    connect_duration: Option<Duration>,
+    encryption: Encryption,
+    peer_certificate: Option<Vec<u8>>,
+    server_info: Option<ServerInfo>,
+    authenticated: Option<bool>,
+    error: Option<Error>,
+}
+
+impl Diagnosis {
+    pub(super) fn run(client: &SmtpClient) -> Self {
+        let mut diagnosis = Self {
+            resolved_addrs: Vec::new(),
+            peer_addr: None,
+            connect_attempts: 0,
+            connect_duration: None,
+            encryption: Encryption::None,
+            peer_certificate: None,
+            server_info: None,
+            authenticated: None,
+            error: None,
+        };
+        if let Err(err) = diagnosis.diagnose(client) {
+            diagnosis.error = Some(err);
+        }
+        diagnosis
+    }
+
+    fn diagnose(&mut self, client: &SmtpClient) -> Result<(), Error> {
+        self.resolved_addrs = client.resolve()?;
+
+        let start = Instant::now();
+        let result = client.open(&self.resolved_addrs, &mut self.connect_attempts);
+        let mut conn = result?;
+        self.connect_duration = Some(start.elapsed());
+        self.peer_addr = conn.peer_addr().ok();
+        if conn.is_encrypted() {
+            self.encryption = Encryption::Wrapper;
+        }
+
+        if client.starttls(&mut conn)? {
+            self.encryption = Encryption::StartTls;
+        }
+
+        #[cfg(any(feature = "native-tls", feature = "rustls-tls", feature = "boring-tls"))]
+        if conn.is_encrypted() {
+            self.peer_certificate = conn.peer_certificate().ok();
+        }
+        self.server_info = Some(conn.server_info().clone());
+
+        match client.authenticate(&mut conn) {
+            Ok(authenticated) => self.authenticated = authenticated.then_some(true),
+            Err(err) => {
+                self.authenticated = Some(false);
+                return Err(err);
+            }
+        }
+
+        conn.quit()?;
+        Ok(())
+    }
+
+    /// Whether all steps succeeded
+    pub fn is_ok(&self) -> bool {
+        self.error.is_none()
+    }
+
+    /// The error which interrupted the diagnosis, if any
+    pub fn error(&self) -> Option<&Error> {
+        self.error.as_ref()
+    }
+
+    /// The addresses the server name resolved to
+    pub fn resolved_addrs(&self) -> &[SocketAddr] {
+        &self.resolved_addrs
+    }
+
+    /// The address the connection was established to
+    ///
+    /// Whether IPv4 or IPv6 was used is given by [`SocketAddr::is_ipv6`].
+    pub fn peer_addr(&self) -> Option<SocketAddr> {
+        self.peer_addr
+    }
+
+    /// Number of resolved addresses a connection was attempted to,
+    /// the last one being the connected one unless all failed
+    pub fn connect_attempts(&self) -> usize {
+        self.connect_attempts
+    }
+
+    /// Time taken to connect and get the server greeting and `EHLO` response,
+    /// including the TLS handshake when using [`Encryption::Wrapper`]
+    pub fn connect_duration(&self) -> Option<Duration> {
+        self.connect_duration
+    }
+
+    /// The encryption achieved on the connection
+    pub fn encryption(&self) -> Encryption {
+        self.encryption
+    }
+
+    /// The DER encoded certificate presented by the server, if the connection is encrypted
+    ///
+    /// Subject and expiry can be read from it with any X.509 parser.
+    pub fn peer_certificate(&self) -> Option<&[u8]> {
+        self.peer_certificate.as_deref()
+    }
+
+    /// The capabilities announced by the server, once encryption is set up
+    pub fn server_info(&self) -> Option<&ServerInfo> {
+        self.server_info.as_ref()
+    }
+
+    /// Whether authentication succeeded, `None` if no credentials are configured
+    /// or the diagnosis failed earlier
+    pub fn authenticated(&self) -> Option<bool> {
+        self.authenticated
+    }
+}
@@ -0,0 +1,119 @@
+//! Cache of the resolved addresses of the relay
+
+use std::{
+    net::{SocketAddr, ToSocketAddrs},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use super::{error, Error};
+
+/// Keeps the addresses the relay hostname resolved to for a fixed time,
+/// so that reconnecting doesn't query the resolver each time
+///
+/// Clones share the cached addresses.
+#[derive(Debug, Clone)]
+pub(crate) struct DnsCache {
+    ttl: Duration,
+    entry: Arc<Mutex<Option<Entry>>>,
+}
+
+#[derive(Debug)]
+struct Entry {
+    server: String,
+    port: u16,
+    addrs: Vec<SocketAddr>,
+    expires: Instant,
+}
+
+impl DnsCache {
+    pub(crate) fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entry: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// The cached addresses of `server`, if they haven't expired
+    pub(crate) fn get(&self, server: &str, port: u16) -> Option<Vec<SocketAddr>> {
+        let entry = self.entry.lock().unwrap();
+        entry
+            .as_ref()
+            .filter(|entry| {
+                entry.server == server && entry.port == port && entry.expires > Instant::now()
+            })
+            .map(|entry| entry.addrs.clone())
+    }
+
+    /// Caches the addresses `server` resolved to, unless there are none
+    pub(crate) fn insert(&self, server: &str, port: u16, addrs: &[SocketAddr]) {
+        if addrs.is_empty() {
+            return;
+        }
+        *self.entry.lock().unwrap() = Some(Entry {
+            server: server.to_owned(),
+            port,
+            addrs: addrs.to_vec(),
+            expires: Instant::now() + self.ttl,
+        });
+    }
+
+    /// The addresses of `server`, resolving them if they aren't cached
+    pub(crate) fn resolve(&self, server: &str, port: u16) -> Result<Vec<SocketAddr>, Error> {
+        if let Some(addrs) = self.get(server, port) {
+            return Ok(addrs);
+        }
+
+        let addrs = resolve(server, port)?;
+        self.insert(server, port, &addrs);
+        Ok(addrs)
+    }
+
+    /// Forgets the cached addresses
+    pub(crate) fn flush(&self) {
+        *self.entry.lock().unwrap() = None;
+    }
+}
+
+/// Resolves `server` with the resolver of the system
+pub(crate) fn resolve(server: &str, port: u16) -> Result<Vec<SocketAddr>, Error> {
+    let addrs = (server, port)
+        .to_socket_addrs()
+        .map_err(error::connection)?
+        .collect::<Vec<_>>();
+    if addrs.is_empty() {
+        return Err(error::connection("could not resolve to any address"));
+    }
+    Ok(addrs)
+}
+
+#[cfg(test)]
+mod test {
+    use std::{net::SocketAddr, thread, time::Duration};
+
+    use super::DnsCache;
+
+    #[test]
+    fn dns_cache() {
+        let cache = DnsCache::new(Duration::from_millis(50));
+        let addrs = cache.resolve("127.0.0.1", 25).unwrap();
+        assert_eq!(addrs, ["127.0.0.1:25".parse::<SocketAddr>().unwrap()]);
+        assert_eq!(cache.get("127.0.0.1", 25), Some(addrs));
+        assert_eq!(cache.get("127.0.0.1", 587), None);
+        assert_eq!(cache.get("localhost", 25), None);
+
+        cache.flush();
+        assert_eq!(cache.get("127.0.0.1", 25), None);
+
+        cache.insert("127.0.0.1", 25, &[]);
+        assert_eq!(cache.get("127.0.0.1", 25), None, "failures aren't cached");
+
+        cache.clone().resolve("127.0.0.1", 25).unwrap();
+        assert!(
+            cache.get("127.0.0.1", 25).is_some(),
+            "clones share the cache"
+        );
+        thread::sleep(Duration::from_millis(60));
+        assert_eq!(cache.get("127.0.0.1", 25), None, "the addresses expired");
+    }
+}
@@ -0,0 +1,52 @@
+use super::{response::Response, Error};
+use crate::address::Address;
+
+/// Recipients the server would accept, found by a dry run
+///
+/// A dry run starts a transaction with `MAIL` and a `RCPT` for each
+/// recipient, then cancels it with `RSET` instead of sending the message.
+/// A recipient accepted by a dry run can still be rejected later, for
+/// example after the server checks the content of the message.
+///
+/// See [`SmtpConnection::dry_run`](super::client::SmtpConnection::dry_run).
+#[derive(Debug)]
+pub struct DryRun {
+    pub(super) accepted: Vec<Address>,
+    pub(super) rejected: Vec<(Address, Error)>,
+    pub(super) response: Option<Response>,
+}
+
+impl DryRun {
+    pub(super) fn new() -> Self {
+        Self {
+            accepted: Vec::new(),
+            rejected: Vec::new(),
+            response: None,
+        }
+    }
+
+    /// Recipients accepted by the server, in the order of the envelope
+    pub fn accepted(&self) -> &[Address] {
+        &self.accepted
+    }
+
+    /// Recipients rejected by the server, with the error replied to their
+    /// `RCPT` command
+    pub fn rejected(&self) -> &[(Address, Error)] {
+        &self.rejected
+    }
+
+    /// Whether all the recipients were accepted
+    pub fn is_accepted(&self) -> bool {
+        self.rejected.is_empty()
+    }
+
+    /// The reply to the last `RSET` when all the recipients were accepted,
+    /// or else the error of the first rejected one
+    pub(super) fn into_result(self) -> Result<Response, Error> {
+        match self.rejected.into_iter().next() {
+            Some((_, err)) => Err(err),
+            None => Ok(self.response.expect("envelopes have recipients")),
+        }
+    }
+}
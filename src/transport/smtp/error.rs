@@ -1,9 +1,12 @@
 //! Error and result type for SMTP clients
 
-use std::{error::Error as StdError, fmt};
+use std::{error::Error as StdError, fmt, time::Duration};
 
 use crate::{
-    transport::smtp::response::{Code, Severity},
+    transport::smtp::{
+        authentication::AuthHint,
+        response::{Category, Code, Detail, Response, Severity},
+    },
     BoxError,
 };
 
@@ -17,6 +20,8 @@ pub struct Error {
 struct Inner {
     kind: Kind,
     source: Option<BoxError>,
+    command: Option<FailedCommand>,
+    after_data: bool,
 }
 
 impl Error {
@@ -28,6 +33,8 @@ impl Error {
             inner: Box::new(Inner {
                 kind,
                 source: source.map(Into::into),
+                command: None,
+                after_data: false,
             }),
         }
     }
@@ -42,6 +49,11 @@ impl Error {
         matches!(self.inner.kind, Kind::Client)
     }
 
+    /// Returns true if the error is from connecting to the server
+    pub(crate) fn is_connection(&self) -> bool {
+        matches!(self.inner.kind, Kind::Connection)
+    }
+
     /// Returns true if the error is a transient SMTP error
     pub fn is_transient(&self) -> bool {
         matches!(self.inner.kind, Kind::Transient(_))
@@ -53,7 +65,14 @@ impl Error {
     }
 
     /// Returns true if the error is caused by a timeout
+    ///
+    /// This includes the server taking longer than the
+    /// [command deadline](super::SmtpTransportBuilder::command_deadline) to reply.
     pub fn is_timeout(&self) -> bool {
+        if matches!(self.inner.kind, Kind::Timeout) {
+            return true;
+        }
+
         let mut source = self.source();
 
         while let Some(err) = source {
@@ -67,6 +86,11 @@ impl Error {
         false
     }
 
+    /// Returns true if the server reply exceeded the maximum size
+    pub fn is_reply_too_large(&self) -> bool {
+        matches!(self.inner.kind, Kind::ReplyTooLarge)
+    }
+
     /// Returns true if the error is from TLS
     #[cfg(any(feature = "native-tls", feature = "rustls-tls", feature = "boring-tls"))]
     #[cfg_attr(
@@ -77,6 +101,26 @@ impl Error {
         matches!(self.inner.kind, Kind::Tls)
     }
 
+    /// Returns true if the connection pool refused to connect because
+    /// its circuit breaker is open
+    ///
+    /// See [`PoolConfig::circuit_breaker`](super::PoolConfig::circuit_breaker)
+    #[cfg(feature = "pool")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "pool")))]
+    pub fn is_circuit_open(&self) -> bool {
+        matches!(self.inner.kind, Kind::CircuitOpen)
+    }
+
+    /// Returns true if no pooled connection became available
+    /// before the checkout timeout
+    ///
+    /// See [`PoolConfig::checkout_timeout`](super::PoolConfig::checkout_timeout)
+    #[cfg(feature = "pool")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "pool")))]
+    pub fn is_pool_timeout(&self) -> bool {
+        matches!(self.inner.kind, Kind::PoolTimeout)
+    }
+
     /// Returns the status code, if the error was generated from a response.
     pub fn status(&self) -> Option<Code> {
         match self.inner.kind {
@@ -84,6 +128,168 @@ impl Error {
             _ => None,
         }
     }
+
+    /// Returns the command which failed, if the error happened while
+    /// sending a command or reading its reply
+    ///
+    /// It is also the [source](StdError::source) of the error.
+    pub fn failed_command(&self) -> Option<&FailedCommand> {
+        self.inner.command.as_ref()
+    }
+
+    /// Returns true if the server deferred the message because of greylisting
+    ///
+    /// Greylisting servers reject the first delivery attempt from an unknown
+    /// sender with a `450` or `451` reply, like Postgrey's `Greylisted, see ...`
+    /// or `4.7.1 Try again later`, and accept it when it is retried a few minutes
+    /// later, after [`Error::retry_after`] if the server said how long to wait.
+    /// The async transport can retry automatically, with
+    /// `AsyncSmtpTransportBuilder::greylist_retry`.
+    pub fn is_greylisted(&self) -> bool {
+        match (&self.inner.kind, self.reply_message()) {
+            (Kind::Transient(code), Some(message)) => {
+                code.category == Category::MailSystem
+                    && matches!(code.detail, Detail::Zero | Detail::One)
+                    && is_greylisting(&message)
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns the delay after which the server asked to try again, if it
+    /// gave one in its reply, like `Greylisted for 300 seconds`
+    pub fn retry_after(&self) -> Option<Duration> {
+        if !self.is_transient() {
+            return None;
+        }
+        parse_retry_after(&self.reply_message()?)
+    }
+
+    /// Returns the likely cause of an authentication failure, recognized
+    /// from the reply of a major provider like Gmail or Microsoft 365
+    pub fn auth_hint(&self) -> Option<AuthHint> {
+        self.status()?;
+        AuthHint::from_message(&self.reply_message()?)
+    }
+
+    /// The cause of the error, kept by the failed command if any
+    fn cause(&self) -> Option<&BoxError> {
+        match &self.inner.command {
+            Some(command) => command.source.as_ref(),
+            None => self.inner.source.as_ref(),
+        }
+    }
+
+    /// The text of the server reply which caused the error
+    fn reply_message(&self) -> Option<String> {
+        Some(self.cause()?.to_string())
+    }
+
+    /// Records `line` as the command which caused the error
+    ///
+    /// The arguments of `sensitive` commands are redacted, and bare
+    /// `sensitive` lines, like the response to an AUTH challenge, aren't
+    /// recorded.
+    pub(crate) fn with_command(mut self, line: &str, sensitive: bool) -> Self {
+        let line = line.trim_end_matches("\r\n");
+        let (name, argument) = match line.split_once(' ') {
+            Some((name, _)) if sensitive => (name, Some("<redacted>")),
+            Some((name, argument)) => (name, Some(argument)),
+            None if sensitive => return self,
+            None => (line, None),
+        };
+        let source = self.inner.source.take();
+        self.inner.command = Some(FailedCommand {
+            name: name.to_owned(),
+            argument: argument.map(ToOwned::to_owned),
+            recipient: None,
+            source,
+        });
+        self
+    }
+
+    /// Records that the error happened once the message content started being sent
+    pub(crate) fn after_data(mut self) -> Self {
+        self.inner.after_data = true;
+        self
+    }
+
+    /// Returns true if the server may have accepted the message anyway
+    ///
+    /// The error happened once the message content started being sent,
+    /// without a reply rejecting it, like a timeout waiting for the reply
+    /// to the final dot.
+    pub(crate) fn is_ambiguous(&self) -> bool {
+        self.inner.after_data && self.status().is_none()
+    }
+
+    /// Records the position of the recipient the failed command was sent for
+    pub(crate) fn with_recipient(mut self, index: usize) -> Self {
+        if let Some(command) = &mut self.inner.command {
+            command.recipient = Some(index);
+        }
+        self
+    }
+}
+
+/// An SMTP command which failed, returned by [`Error::failed_command`]
+///
+/// It is the source of the [`Error`], and its own source is the cause of
+/// the failure, like the reply of the server. Its [`Display`](fmt::Display)
+/// implementation only names the command, leaving out its argument which
+/// may contain addresses.
+pub struct FailedCommand {
+    name: String,
+    argument: Option<String>,
+    recipient: Option<usize>,
+    source: Option<BoxError>,
+}
+
+impl FailedCommand {
+    /// The name of the command, like `MAIL` or `RCPT`
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The argument of the command, like `TO:<user@example.com>`,
+    /// which is `<redacted>` for commands carrying secrets
+    pub fn argument(&self) -> Option<&str> {
+        self.argument.as_deref()
+    }
+
+    /// The position of the recipient in the envelope, for `RCPT` commands
+    pub fn recipient(&self) -> Option<usize> {
+        self.recipient
+    }
+}
+
+impl fmt::Debug for FailedCommand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FailedCommand")
+            .field("name", &self.name)
+            .field("argument", &self.argument)
+            .field("recipient", &self.recipient)
+            .finish()
+    }
+}
+
+impl fmt::Display for FailedCommand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} command failed", self.name)?;
+        if let Some(recipient) = self.recipient {
+            write!(f, " for recipient {recipient}")?;
+        }
+        Ok(())
+    }
+}
+
+impl StdError for FailedCommand {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        self.source.as_ref().map(|e| {
+            let r: &(dyn std::error::Error + 'static) = &**e;
+            r
+        })
+    }
 }
 
 #[derive(Debug)]
@@ -104,6 +310,10 @@ pub(crate) enum Kind {
     Connection,
     /// Underlying network i/o error
     Network,
+    /// The server didn't complete its reply before the command deadline
+    Timeout,
+    /// The server reply exceeded the maximum size
+    ReplyTooLarge,
     /// TLS error
     #[cfg_attr(
         docsrs,
@@ -111,6 +321,12 @@ pub(crate) enum Kind {
     )]
     #[cfg(any(feature = "native-tls", feature = "rustls-tls", feature = "boring-tls"))]
     Tls,
+    /// Connection attempts suspended after repeated failures
+    #[cfg(feature = "pool")]
+    CircuitOpen,
+    /// No pooled connection became available before the checkout timeout
+    #[cfg(feature = "pool")]
+    PoolTimeout,
 }
 
 impl fmt::Debug for Error {
@@ -119,10 +335,14 @@ impl fmt::Debug for Error {
 
         builder.field("kind", &self.inner.kind);
 
-        if let Some(source) = &self.inner.source {
+        if let Some(source) = self.cause() {
             builder.field("source", source);
         }
 
+        if let Some(command) = &self.inner.command {
+            builder.field("command", command);
+        }
+
         builder.finish()
     }
 }
@@ -134,8 +354,16 @@ impl fmt::Display for Error {
             Kind::Client => f.write_str("internal client error")?,
             Kind::Network => f.write_str("network error")?,
             Kind::Connection => f.write_str("Connection error")?,
+            Kind::Timeout => f.write_str("timed out waiting for the server reply")?,
+            Kind::ReplyTooLarge => f.write_str("server reply too large")?,
             #[cfg(any(feature = "native-tls", feature = "rustls-tls", feature = "boring-tls"))]
             Kind::Tls => f.write_str("tls error")?,
+            #[cfg(feature = "pool")]
+            Kind::CircuitOpen => {
+                f.write_str("circuit breaker open, not connecting after repeated failures")?;
+            }
+            #[cfg(feature = "pool")]
+            Kind::PoolTimeout => f.write_str("timed out waiting for a pooled connection")?,
             Kind::Transient(code) => {
                 write!(f, "transient error ({code})")?;
             }
@@ -144,7 +372,7 @@ impl fmt::Display for Error {
             }
         };
 
-        if let Some(e) = &self.inner.source {
+        if let Some(e) = self.cause() {
             write!(f, ": {e}")?;
         }
 
@@ -154,6 +382,10 @@ impl fmt::Display for Error {
 
 impl StdError for Error {
     fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        if let Some(command) = &self.inner.command {
+            return Some(command);
+        }
+
         self.inner.source.as_ref().map(|e| {
             let r: &(dyn std::error::Error + 'static) = &**e;
             r
@@ -161,6 +393,42 @@ impl StdError for Error {
     }
 }
 
+/// Whether a transient reply `message` looks like greylisting
+fn is_greylisting(message: &str) -> bool {
+    let message = message.to_ascii_lowercase();
+    [
+        "greylist",
+        "graylist",
+        "postgrey",
+        "try again later",
+        "try again in",
+        "please try later",
+    ]
+    .iter()
+    .any(|pattern| message.contains(pattern))
+}
+
+/// Finds a delay like `300 seconds` or `5 minutes` in a reply `message`
+fn parse_retry_after(message: &str) -> Option<Duration> {
+    let message = message.to_ascii_lowercase();
+    let mut words = message
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .peekable();
+    while let Some(word) = words.next() {
+        let Ok(value) = word.parse::<u64>() else {
+            continue;
+        };
+        let secs = match words.peek().copied() {
+            Some("s" | "sec" | "secs" | "second" | "seconds") => value,
+            Some("min" | "mins" | "minute" | "minutes") => value.saturating_mul(60),
+            _ => continue,
+        };
+        return Some(Duration::from_secs(secs));
+    }
+    None
+}
+
 pub(crate) fn code(c: Code, s: Option<String>) -> Error {
     match c.severity {
         Severity::TransientNegativeCompletion => Error::new(Kind::Transient(c), s),
@@ -169,6 +437,19 @@ pub(crate) fn code(c: Code, s: Option<String>) -> Error {
     }
 }
 
+/// Error for a `response` which wasn't expected by the command
+pub(crate) fn unexpected_response(response: &Response) -> Error {
+    if response.is_positive() {
+        self::response(format!(
+            "unexpected response ({}): {}",
+            response.code(),
+            response.message().collect::<Vec<_>>().join(" ")
+        ))
+    } else {
+        code(response.code(), Some(response.message().collect()))
+    }
+}
+
 pub(crate) fn response<E: Into<BoxError>>(e: E) -> Error {
     Error::new(Kind::Response, Some(e))
 }
@@ -181,6 +462,14 @@ pub(crate) fn network<E: Into<BoxError>>(e: E) -> Error {
     Error::new(Kind::Network, Some(e))
 }
 
+pub(crate) fn timeout() -> Error {
+    Error::new::<BoxError>(Kind::Timeout, None)
+}
+
+pub(crate) fn reply_too_large() -> Error {
+    Error::new::<BoxError>(Kind::ReplyTooLarge, None)
+}
+
 pub(crate) fn connection<E: Into<BoxError>>(e: E) -> Error {
     Error::new(Kind::Connection, Some(e))
 }
@@ -189,3 +478,112 @@ pub(crate) fn connection<E: Into<BoxError>>(e: E) -> Error {
 pub(crate) fn tls<E: Into<BoxError>>(e: E) -> Error {
     Error::new(Kind::Tls, Some(e))
 }
+
+#[cfg(feature = "pool")]
+pub(crate) fn circuit_open() -> Error {
+    Error::new::<BoxError>(Kind::CircuitOpen, None)
+}
+
+#[cfg(feature = "pool")]
+pub(crate) fn pool_timeout() -> Error {
+    Error::new::<BoxError>(Kind::PoolTimeout, None)
+}
+
+#[cfg(test)]
+mod test {
+    use std::{error::Error as _, time::Duration};
+
+    use super::{client, code, timeout};
+    use crate::transport::smtp::response::{Category, Code, Detail, Severity};
+
+    fn reply(severity: Severity, detail: Detail, message: &str) -> super::Error {
+        code(
+            Code::new(severity, Category::MailSystem, detail),
+            Some(message.to_owned()),
+        )
+    }
+
+    #[test]
+    fn greylisting() {
+        let err = reply(
+            Severity::TransientNegativeCompletion,
+            Detail::Zero,
+            "4.2.0 <root@example.com>: Recipient address rejected: Greylisted, see http://postgrey.schweikert.ch/help/example.com.html",
+        );
+        assert!(err.is_greylisted());
+        assert_eq!(err.retry_after(), None);
+
+        let err = reply(
+            Severity::TransientNegativeCompletion,
+            Detail::One,
+            "4.7.1 Greylisted for 300 seconds",
+        );
+        assert!(err.is_greylisted());
+        assert_eq!(err.retry_after(), Some(Duration::from_secs(300)));
+
+        let err = reply(
+            Severity::TransientNegativeCompletion,
+            Detail::One,
+            "4.7.1 Please try again in 5 minutes",
+        );
+        assert!(err.is_greylisted());
+        assert_eq!(err.retry_after(), Some(Duration::from_secs(300)));
+
+        // Not greylisting
+        let err = reply(
+            Severity::TransientNegativeCompletion,
+            Detail::Two,
+            "4.2.2 Mailbox full",
+        );
+        assert!(!err.is_greylisted());
+        let err = reply(
+            Severity::PermanentNegativeCompletion,
+            Detail::Zero,
+            "5.7.1 Greylisted, try again later",
+        );
+        assert!(!err.is_greylisted());
+        assert_eq!(err.retry_after(), None);
+    }
+
+    #[test]
+    fn failed_command() {
+        let err = client("rejected").with_command("AUTH PLAIN AHVzZXIAcGFzcw==\r\n", true);
+        let command = err.failed_command().unwrap();
+        assert_eq!(command.name(), "AUTH");
+        assert_eq!(command.argument(), Some("<redacted>"));
+        assert_eq!(err.to_string(), "internal client error: rejected");
+        assert!(!format!("{err:?}").contains("AHVzZXIAcGFzcw=="));
+
+        let err = client("rejected").with_command("cGFzcw==\r\n", true);
+        assert!(err.failed_command().is_none());
+
+        let err = client("rejected")
+            .with_command("RCPT TO:<user@domain.tld>\r\n", false)
+            .with_recipient(2);
+        let command = err.failed_command().unwrap();
+        assert_eq!(
+            (command.name(), command.argument(), command.recipient()),
+            ("RCPT", Some("TO:<user@domain.tld>"), Some(2))
+        );
+        assert_eq!(err.to_string(), "internal client error: rejected");
+
+        // The command is in the source chain, followed by the cause
+        let source = err.source().unwrap();
+        assert_eq!(source.to_string(), "RCPT command failed for recipient 2");
+        assert_eq!(source.source().unwrap().to_string(), "rejected");
+    }
+
+    #[test]
+    fn ambiguous_after_data() {
+        assert!(!timeout().is_ambiguous());
+        assert!(timeout().after_data().is_ambiguous());
+
+        // The server rejected the message
+        let err = reply(
+            Severity::PermanentNegativeCompletion,
+            Detail::Four,
+            "5.4.0 Message rejected",
+        );
+        assert!(!err.after_data().is_ambiguous());
+    }
+}
@@ -1,16 +1,20 @@
 //! ESMTP features
 
 use std::{
-    collections::HashSet,
-    fmt::{self, Display, Formatter},
+    collections::{HashMap, HashSet},
+    fmt::{self, Debug, Display, Formatter},
     net::{Ipv4Addr, Ipv6Addr},
+    str::FromStr,
 };
 
-use crate::transport::smtp::{
-    authentication::Mechanism,
-    error::{self, Error},
-    response::Response,
-    util::XText,
+use crate::{
+    address::{Address, Envelope},
+    transport::smtp::{
+        authentication::Mechanism,
+        error::{self, Error},
+        response::Response,
+        util::XText,
+    },
 };
 
 /// Client identifier, the parameter to `EHLO`
@@ -60,6 +64,33 @@ impl Display for ClientId {
     }
 }
 
+impl FromStr for ClientId {
+    type Err = Error;
+
+    /// Parses an address literal (`[192.0.2.1]`, `[IPv6:2001:db8::1]`),
+    /// a bare IP address or a domain name
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(literal) = s.strip_prefix('[').and_then(|s| s.strip_suffix(']')) {
+            return match literal.get(..5) {
+                Some(tag) if tag.eq_ignore_ascii_case("IPv6:") => {
+                    literal[5..].parse().map(Self::Ipv6).map_err(error::client)
+                }
+                _ => literal.parse().map(Self::Ipv4).map_err(error::client),
+            };
+        }
+
+        if let Ok(ip) = s.parse() {
+            Ok(Self::Ipv4(ip))
+        } else if let Ok(ip) = s.parse() {
+            Ok(Self::Ipv6(ip))
+        } else if s.is_empty() || s.contains(|c: char| c.is_whitespace() || c.is_control()) {
+            Err(error::client("invalid client id"))
+        } else {
+            Ok(Self::Domain(s.to_owned()))
+        }
+    }
+}
+
 impl ClientId {
     #[doc(hidden)]
     #[deprecated(since = "0.10.0", note = "Please use ClientId::Domain(domain) instead")]
@@ -86,6 +117,10 @@ pub enum Extension {
     ///
     /// Defined in [RFC 2487](https://tools.ietf.org/html/rfc2487)
     StartTls,
+    /// BURL keyword
+    ///
+    /// Defined in [RFC 4468](https://tools.ietf.org/html/rfc4468)
+    Burl,
     /// AUTH mechanism
     Authentication(Mechanism),
 }
@@ -96,6 +131,7 @@ impl Display for Extension {
             Extension::EightBitMime => f.write_str("8BITMIME"),
             Extension::SmtpUtfEight => f.write_str("SMTPUTF8"),
             Extension::StartTls => f.write_str("STARTTLS"),
+            Extension::Burl => f.write_str("BURL"),
             Extension::Authentication(mechanism) => write!(f, "AUTH {mechanism}"),
         }
     }
@@ -113,6 +149,19 @@ pub struct ServerInfo {
     ///
     /// It contains the features supported by the server and known by the `Extension` module.
     features: HashSet<Extension>,
+    /// Maximum message size accepted by the server, in bytes
+    ///
+    /// Announced with the `SIZE` keyword, defined in [RFC 1870](https://tools.ietf.org/html/rfc1870)
+    #[cfg_attr(feature = "serde", serde(default))]
+    max_size: Option<usize>,
+    /// Limits announced with the `LIMITS` keyword
+    #[cfg_attr(feature = "serde", serde(default))]
+    limits: Limits,
+    /// All EHLO keywords announced by the server, with their parameters
+    ///
+    /// Includes the keywords not known by the `Extension` module.
+    #[cfg_attr(feature = "serde", serde(default))]
+    keywords: HashMap<String, Vec<String>>,
 }
 
 impl Display for ServerInfo {
@@ -135,14 +184,26 @@ impl ServerInfo {
         };
 
         let mut features: HashSet<Extension> = HashSet::new();
+        let mut max_size = None;
+        let mut limits = Limits::default();
+        let mut keywords = HashMap::new();
 
-        for line in response.message() {
-            if line.is_empty() {
+        for (i, line) in response.message().enumerate() {
+            let mut split = line.split_whitespace();
+            let mut params = split.clone();
+            // Skip blank lines
+            let Some(first_word) = split.next() else {
                 continue;
+            };
+            // The first line holds the server name, not a keyword
+            if let Some(keyword) = params.next().filter(|_| i > 0) {
+                keywords.insert(
+                    keyword.to_ascii_uppercase(),
+                    params.map(str::to_owned).collect(),
+                );
             }
 
-            let mut split = line.split_whitespace();
-            match split.next().unwrap() {
+            match first_word {
                 "8BITMIME" => {
                     features.insert(Extension::EightBitMime);
                 }
@@ -152,6 +213,19 @@ impl ServerInfo {
                 "STARTTLS" => {
                     features.insert(Extension::StartTls);
                 }
+                "BURL" => {
+                    features.insert(Extension::Burl);
+                }
+                "SIZE" => {
+                    // `SIZE 0` or a missing value means no fixed limit
+                    max_size = split
+                        .next()
+                        .and_then(|size| size.parse().ok())
+                        .filter(|&size| size > 0);
+                }
+                "LIMITS" => {
+                    limits = Limits::parse(split);
+                }
                 "AUTH" => {
                     for mechanism in split {
                         match mechanism {
@@ -175,9 +249,38 @@ impl ServerInfo {
         Ok(ServerInfo {
             name: name.to_owned(),
             features,
+            max_size,
+            limits,
+            keywords,
         })
     }
 
+    /// Gets the parameters of an EHLO keyword, if announced by the server
+    ///
+    /// Unlike [`Self::supports_feature`], this works for any keyword,
+    /// including vendor specific ones or those not supported by lettre.
+    /// The keyword is matched case insensitively.
+    pub fn keyword(&self, keyword: &str) -> Option<&[String]> {
+        self.keywords
+            .get(&keyword.to_ascii_uppercase())
+            .map(Vec::as_slice)
+    }
+
+    /// The supported ESMTP features known by the `Extension` module
+    pub fn features(&self) -> impl Iterator<Item = Extension> + '_ {
+        self.features.iter().copied()
+    }
+
+    /// The maximum message size accepted by the server, in bytes, if it announced one
+    pub fn max_size(&self) -> Option<usize> {
+        self.max_size
+    }
+
+    /// The limits announced by the server with the `LIMITS` keyword
+    pub fn limits(&self) -> Limits {
+        self.limits
+    }
+
     /// Checks if the server supports an ESMTP feature
     pub fn supports_feature(&self, keyword: Extension) -> bool {
         self.features.contains(&keyword)
@@ -205,6 +308,156 @@ impl ServerInfo {
     }
 }
 
+/// Changes in the EHLO keywords announced by a server, between two connections
+///
+/// Reported to [`PoolObserver::server_info_changed`](super::PoolObserver::server_info_changed)
+/// when a pooled connection finds different capabilities than the previous
+/// one, like a relay dropping `STARTTLS` or lowering its `SIZE` after
+/// maintenance. The server names aren't compared, as they can differ
+/// between the hosts behind a relay.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ServerInfoChange {
+    previous: ServerInfo,
+    current: ServerInfo,
+}
+
+impl ServerInfoChange {
+    /// Compares `current` with the `previous` server information, returning
+    /// `None` when they announce the same keywords
+    pub(crate) fn new(previous: &ServerInfo, current: &ServerInfo) -> Option<Self> {
+        (previous.keywords != current.keywords).then(|| Self {
+            previous: previous.clone(),
+            current: current.clone(),
+        })
+    }
+
+    /// The server information of the previous connection
+    pub fn previous(&self) -> &ServerInfo {
+        &self.previous
+    }
+
+    /// The server information of the new connection
+    pub fn current(&self) -> &ServerInfo {
+        &self.current
+    }
+
+    /// The keywords which weren't announced by the previous connection, sorted
+    pub fn added_keywords(&self) -> Vec<&str> {
+        sorted_keys(&self.current.keywords, |keyword, _| {
+            !self.previous.keywords.contains_key(keyword)
+        })
+    }
+
+    /// The keywords which aren't announced anymore, sorted
+    pub fn removed_keywords(&self) -> Vec<&str> {
+        sorted_keys(&self.previous.keywords, |keyword, _| {
+            !self.current.keywords.contains_key(keyword)
+        })
+    }
+
+    /// The keywords announced with different parameters, like `SIZE`, sorted
+    pub fn changed_keywords(&self) -> Vec<&str> {
+        sorted_keys(&self.current.keywords, |keyword, params| {
+            self.previous
+                .keywords
+                .get(keyword)
+                .is_some_and(|previous| previous != params)
+        })
+    }
+}
+
+fn sorted_keys(
+    keywords: &HashMap<String, Vec<String>>,
+    filter: impl Fn(&str, &[String]) -> bool,
+) -> Vec<&str> {
+    let mut keys = keywords
+        .iter()
+        .filter(|(keyword, params)| filter(keyword, params))
+        .map(|(keyword, _)| keyword.as_str())
+        .collect::<Vec<_>>();
+    keys.sort_unstable();
+    keys
+}
+
+impl Display for ServerInfoChange {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let changes = [
+            ("added", self.added_keywords()),
+            ("removed", self.removed_keywords()),
+            ("changed", self.changed_keywords()),
+        ];
+        let mut first = true;
+        for (kind, keywords) in changes {
+            if keywords.is_empty() {
+                continue;
+            }
+            if !first {
+                f.write_str("; ")?;
+            }
+            first = false;
+            write!(f, "{kind} {}", keywords.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Limits announced by an SMTP server
+///
+/// Announced with the `LIMITS` keyword, defined in [RFC 9422](https://tools.ietf.org/html/rfc9422),
+/// so that clients can avoid the rejections caused by exceeding them.
+/// Unknown and invalid limits are ignored.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Limits {
+    rcpt_max: Option<usize>,
+    mail_max: Option<usize>,
+    rcpt_domain_max: Option<usize>,
+}
+
+impl Limits {
+    /// Parses the parameters of the `LIMITS` keyword
+    fn parse<'a>(params: impl Iterator<Item = &'a str>) -> Self {
+        let mut limits = Self::default();
+        for param in params {
+            let Some((name, value)) = param.split_once('=') else {
+                continue;
+            };
+            // Limits are at least 1
+            let Some(value) = value.parse().ok().filter(|&value| value > 0) else {
+                continue;
+            };
+            match name.to_ascii_uppercase().as_str() {
+                "RCPTMAX" => limits.rcpt_max = Some(value),
+                "MAILMAX" => limits.mail_max = Some(value),
+                "RCPTDOMAINMAX" => limits.rcpt_domain_max = Some(value),
+                _ => (),
+            }
+        }
+        limits
+    }
+
+    /// The maximum number of recipients accepted in a transaction, from `RCPTMAX`
+    ///
+    /// Sending a message to more recipients fails with a client error
+    /// before `MAIL FROM`, instead of having the extra recipients rejected.
+    pub fn rcpt_max(&self) -> Option<usize> {
+        self.rcpt_max
+    }
+
+    /// The maximum number of transactions accepted on a connection, from `MAILMAX`
+    ///
+    /// Pooled connections reaching it are closed instead of being reused.
+    pub fn mail_max(&self) -> Option<usize> {
+        self.mail_max
+    }
+
+    /// The maximum number of distinct recipient domains accepted in a
+    /// transaction, from `RCPTDOMAINMAX`
+    pub fn rcpt_domain_max(&self) -> Option<usize> {
+        self.rcpt_domain_max
+    }
+}
+
 /// A `MAIL FROM` extension parameter
 #[derive(PartialEq, Eq, Clone, Debug)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
@@ -289,6 +542,50 @@ impl Display for RcptParameter {
     }
 }
 
+/// Hook for ESMTP extensions not supported by lettre
+///
+/// Registered on the transport builders with `custom_extension`, it gets
+/// called when sending an email to add parameters to the `MAIL FROM` and
+/// `RCPT TO` commands, for example for vendor specific extensions.
+/// The keywords announced by the server are available through
+/// [`ServerInfo::keyword`].
+///
+/// ```rust
+/// use lettre::{
+///     address::{Address, Envelope},
+///     transport::smtp::extension::{CustomExtension, MailParameter, ServerInfo},
+/// };
+///
+/// #[derive(Debug)]
+/// struct MessageTag(String);
+///
+/// impl CustomExtension for MessageTag {
+///     fn mail_parameters(&self, server_info: &ServerInfo, _: &Envelope) -> Vec<MailParameter> {
+///         if server_info.keyword("X-TAG").is_none() {
+///             return Vec::new();
+///         }
+///
+///         vec![MailParameter::Other {
+///             keyword: "X-TAG".to_owned(),
+///             value: Some(self.0.clone()),
+///         }]
+///     }
+/// }
+/// ```
+pub trait CustomExtension: Debug + Send + Sync {
+    /// Parameters to add to the `MAIL FROM` command
+    fn mail_parameters(&self, server_info: &ServerInfo, envelope: &Envelope) -> Vec<MailParameter> {
+        let _ = (server_info, envelope);
+        Vec::new()
+    }
+
+    /// Parameters to add to the `RCPT TO` command for the `to` recipient
+    fn rcpt_parameters(&self, server_info: &ServerInfo, to: &Address) -> Vec<RcptParameter> {
+        let _ = (server_info, to);
+        Vec::new()
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -303,6 +600,46 @@ mod test {
         assert_eq!(format!("{LOCALHOST_CLIENT}"), "[127.0.0.1]".to_owned());
     }
 
+    #[test]
+    #[cfg(feature = "serde")]
+    fn test_serverinfo_deserialize_without_new_fields() {
+        // As serialized by previous versions
+        let json = r#"{"name":"me","features":["EightBitMime"]}"#;
+        let server_info: ServerInfo = serde_json::from_str(json).unwrap();
+        assert_eq!(server_info.name(), "me");
+        assert!(server_info.supports_feature(Extension::EightBitMime));
+        assert_eq!(server_info.max_size(), None);
+        assert_eq!(server_info.keyword("8BITMIME"), None);
+    }
+
+    #[test]
+    fn test_clientid_parse() {
+        assert_eq!(
+            "mail.example.com".parse::<ClientId>().unwrap(),
+            ClientId::Domain("mail.example.com".to_owned())
+        );
+        assert_eq!(
+            "[192.0.2.1]".parse::<ClientId>().unwrap(),
+            ClientId::Ipv4(Ipv4Addr::new(192, 0, 2, 1))
+        );
+        assert_eq!(
+            "192.0.2.1".parse::<ClientId>().unwrap(),
+            ClientId::Ipv4(Ipv4Addr::new(192, 0, 2, 1))
+        );
+        let ipv6 = "2001:db8::1".parse::<Ipv6Addr>().unwrap();
+        assert_eq!(
+            "[IPv6:2001:db8::1]".parse::<ClientId>().unwrap(),
+            ClientId::Ipv6(ipv6)
+        );
+        assert_eq!(
+            "2001:db8::1".parse::<ClientId>().unwrap(),
+            ClientId::Ipv6(ipv6)
+        );
+        assert!("".parse::<ClientId>().is_err());
+        assert!("[example.com]".parse::<ClientId>().is_err());
+        assert!("a b".parse::<ClientId>().is_err());
+    }
+
     #[test]
     fn test_extension_fmt() {
         assert_eq!(
@@ -326,6 +663,9 @@ mod test {
                 ServerInfo {
                     name: "name".to_owned(),
                     features: eightbitmime,
+                    max_size: None,
+                    limits: Limits::default(),
+                    keywords: HashMap::new(),
                 }
             ),
             "name with {EightBitMime}".to_owned()
@@ -339,6 +679,9 @@ mod test {
                 ServerInfo {
                     name: "name".to_owned(),
                     features: empty,
+                    max_size: None,
+                    limits: Limits::default(),
+                    keywords: HashMap::new(),
                 }
             ),
             "name with no supported features".to_owned()
@@ -353,6 +696,9 @@ mod test {
                 ServerInfo {
                     name: "name".to_owned(),
                     features: plain,
+                    max_size: None,
+                    limits: Limits::default(),
+                    keywords: HashMap::new(),
                 }
             ),
             "name with {Authentication(Plain)}".to_owned()
@@ -376,12 +722,24 @@ mod test {
         let server_info = ServerInfo {
             name: "me".to_owned(),
             features,
+            max_size: Some(42),
+            limits: Limits::default(),
+            keywords: HashMap::from([
+                ("8BITMIME".to_owned(), vec![]),
+                ("SIZE".to_owned(), vec!["42".to_owned()]),
+            ]),
         };
 
         assert_eq!(ServerInfo::from_response(&response).unwrap(), server_info);
 
         assert!(server_info.supports_feature(Extension::EightBitMime));
         assert!(!server_info.supports_feature(Extension::StartTls));
+        assert_eq!(server_info.name(), "me");
+        assert_eq!(server_info.max_size(), Some(42));
+        assert_eq!(
+            server_info.features().collect::<Vec<_>>(),
+            vec![Extension::EightBitMime]
+        );
 
         let response2 = Response::new(
             Code::new(
@@ -405,6 +763,21 @@ mod test {
         let server_info2 = ServerInfo {
             name: "me".to_owned(),
             features: features2,
+            max_size: Some(42),
+            limits: Limits::default(),
+            keywords: HashMap::from([
+                (
+                    "AUTH".to_owned(),
+                    vec![
+                        "PLAIN".to_owned(),
+                        "CRAM-MD5".to_owned(),
+                        "XOAUTH2".to_owned(),
+                        "OTHER".to_owned(),
+                    ],
+                ),
+                ("8BITMIME".to_owned(), vec![]),
+                ("SIZE".to_owned(), vec!["42".to_owned()]),
+            ]),
         };
 
         assert_eq!(ServerInfo::from_response(&response2).unwrap(), server_info2);
@@ -412,5 +785,81 @@ mod test {
         assert!(server_info2.supports_feature(Extension::EightBitMime));
         assert!(server_info2.supports_auth_mechanism(Mechanism::Plain));
         assert!(!server_info2.supports_feature(Extension::StartTls));
+        assert_eq!(
+            server_info2.keyword("auth").unwrap()[1],
+            "CRAM-MD5".to_owned()
+        );
+        assert_eq!(server_info2.keyword("8BITMIME"), Some(&[][..]));
+        assert_eq!(server_info2.keyword("CHUNKING"), None);
+
+        let response3 = Response::new(
+            Code::new(
+                Severity::PositiveCompletion,
+                Category::Unspecified4,
+                Detail::One,
+            ),
+            vec![
+                "me".to_owned(),
+                "SIZE 0".to_owned(),
+                " ".to_owned(),
+                "BURL imap".to_owned(),
+            ],
+        );
+        let server_info3 = ServerInfo::from_response(&response3).unwrap();
+        assert_eq!(server_info3.max_size(), None);
+        assert!(server_info3.supports_feature(Extension::Burl));
+        assert_eq!(server_info3.keyword("BURL"), Some(&["imap".to_owned()][..]));
+        assert_eq!(server_info3.limits(), Limits::default());
+    }
+
+    #[test]
+    fn test_serverinfo_limits() {
+        let response = Response::new(
+            Code::new(
+                Severity::PositiveCompletion,
+                Category::Unspecified4,
+                Detail::One,
+            ),
+            vec![
+                "me".to_owned(),
+                "LIMITS rcptmax=100 MAILMAX=0 RCPTDOMAINMAX=1 OTHER".to_owned(),
+            ],
+        );
+        let limits = ServerInfo::from_response(&response).unwrap().limits();
+        assert_eq!(limits.rcpt_max(), Some(100));
+        assert_eq!(limits.mail_max(), None);
+        assert_eq!(limits.rcpt_domain_max(), Some(1));
+    }
+
+    #[test]
+    fn test_serverinfo_change() {
+        let server_info = |name: &str, keywords: &[&str]| {
+            let mut message = vec![name.to_owned()];
+            message.extend(keywords.iter().map(|keyword| (*keyword).to_owned()));
+            let response = Response::new(
+                Code::new(
+                    Severity::PositiveCompletion,
+                    Category::MailSystem,
+                    Detail::Zero,
+                ),
+                message,
+            );
+            ServerInfo::from_response(&response).unwrap()
+        };
+
+        let previous = server_info("mx1", &["STARTTLS", "SIZE 100", "8BITMIME", "AUTH PLAIN"]);
+        let same = server_info("mx2", &["8BITMIME", "SIZE 100", "STARTTLS", "AUTH PLAIN"]);
+        assert_eq!(ServerInfoChange::new(&previous, &same), None);
+
+        let current = server_info("mx1", &["SIZE 50", "8BITMIME", "AUTH PLAIN", "PIPELINING"]);
+        let change = ServerInfoChange::new(&previous, &current).unwrap();
+        assert_eq!(change.added_keywords(), ["PIPELINING"]);
+        assert_eq!(change.removed_keywords(), ["STARTTLS"]);
+        assert_eq!(change.changed_keywords(), ["SIZE"]);
+        assert_eq!(change.current().max_size(), Some(50));
+        assert_eq!(
+            change.to_string(),
+            "added PIPELINING; removed STARTTLS; changed SIZE"
+        );
     }
 }
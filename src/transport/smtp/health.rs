@@ -0,0 +1,176 @@
+//! Health checks of the SMTP server, for the health endpoints of web services
+//!
+//! The transports are cheap to clone and can be shared between threads and
+//! tasks, so they can be kept in the state of a web framework as is.
+//! [`HealthCheck`] then reports whether the server can be reached, and can be
+//! serialized, with the `serde` feature, or returned from an `axum` handler,
+//! with the `axum` feature:
+//!
+//! ```rust,no_run
+//! # #[cfg(feature = "tokio1")]
+//! # async fn run() {
+//! use lettre::{AsyncSmtpTransport, Tokio1Executor};
+//!
+//! // Like an axum handler taking the transport from the state, which could
+//! // return the `HealthCheck` itself
+//! async fn health(mailer: AsyncSmtpTransport<Tokio1Executor>) -> (u16, String) {
+//!     let check = mailer.health_check().await;
+//!     (check.status_code(), check.to_string())
+//! }
+//!
+//! let mailer = AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous("localhost").build();
+//! let (status, body) = health(mailer.clone()).await;
+//! # }
+//! ```
+
+use std::{
+    fmt::{self, Display},
+    time::Duration,
+};
+
+#[cfg(feature = "pool")]
+use super::CircuitState;
+use super::Error;
+
+/// Outcome of a health check of the SMTP server
+///
+/// Built by [`SmtpTransport::health_check`](super::SmtpTransport::health_check)
+/// or [`AsyncSmtpTransport::health_check`](super::AsyncSmtpTransport::health_check),
+/// which check that a connection can be set up and that the server replies
+/// to `NOOP`.
+#[derive(Debug)]
+pub struct HealthCheck {
+    latency: Duration,
+    error: Option<Error>,
+    #[cfg(feature = "pool")]
+    circuit_state: CircuitState,
+}
+
+impl HealthCheck {
+    pub(super) fn new(
+        latency: Duration,
+        result: Result<(), Error>,
+        #[cfg(feature = "pool")] circuit_state: CircuitState,
+    ) -> Self {
+        Self {
+            latency,
+            error: result.err(),
+            #[cfg(feature = "pool")]
+            circuit_state,
+        }
+    }
+
+    /// Whether the server could be reached
+    pub fn is_healthy(&self) -> bool {
+        self.error.is_none()
+    }
+
+    /// Time taken by the check, including setting up a connection
+    /// if none was pooled
+    pub fn latency(&self) -> Duration {
+        self.latency
+    }
+
+    /// The error which made the check fail
+    pub fn error(&self) -> Option<&Error> {
+        self.error.as_ref()
+    }
+
+    /// The state of the circuit breaker of the connection pool after the check
+    #[cfg(feature = "pool")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "pool")))]
+    pub fn circuit_state(&self) -> CircuitState {
+        self.circuit_state
+    }
+
+    /// The HTTP status code for a health endpoint, `200` if the server
+    /// could be reached and `503` otherwise
+    pub fn status_code(&self) -> u16 {
+        if self.is_healthy() {
+            200
+        } else {
+            503
+        }
+    }
+}
+
+impl Display for HealthCheck {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.error {
+            None => write!(f, "ok ({:?})", self.latency),
+            Some(err) => write!(f, "unhealthy ({:?}): {err}", self.latency),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for HealthCheck {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let mut state = serializer.serialize_struct("HealthCheck", 4)?;
+        state.serialize_field("healthy", &self.is_healthy())?;
+        state.serialize_field("latency_ms", &self.latency.as_millis())?;
+        state.serialize_field("error", &self.error.as_ref().map(ToString::to_string))?;
+        #[cfg(feature = "pool")]
+        state.serialize_field("circuit_state", &self.circuit_state)?;
+        state.end()
+    }
+}
+
+#[cfg(feature = "axum")]
+#[cfg_attr(docsrs, doc(cfg(feature = "axum")))]
+impl axum_core::response::IntoResponse for HealthCheck {
+    fn into_response(self) -> axum_core::response::Response {
+        let status = http::StatusCode::from_u16(self.status_code())
+            .expect("health check status codes are valid");
+        (status, self.to_string()).into_response()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use super::HealthCheck;
+    #[cfg(feature = "pool")]
+    use crate::transport::smtp::CircuitState;
+    use crate::transport::smtp::{error, SmtpTransport};
+    #[cfg(feature = "tokio1")]
+    use crate::{AsyncSmtpTransport, Tokio1Executor};
+
+    fn is_shareable<T: Clone + Send + Sync + 'static>() {}
+
+    #[test]
+    fn transports_are_shareable() {
+        is_shareable::<SmtpTransport>();
+        #[cfg(feature = "tokio1")]
+        is_shareable::<AsyncSmtpTransport<Tokio1Executor>>();
+    }
+
+    #[test]
+    fn status_code() {
+        let check = HealthCheck::new(
+            Duration::from_millis(12),
+            Ok(()),
+            #[cfg(feature = "pool")]
+            CircuitState::Closed,
+        );
+        assert!(check.is_healthy());
+        assert_eq!(check.status_code(), 200);
+        assert_eq!(check.to_string(), "ok (12ms)");
+
+        let check = HealthCheck::new(
+            Duration::from_millis(12),
+            Err(error::connection("refused")),
+            #[cfg(feature = "pool")]
+            CircuitState::Closed,
+        );
+        assert!(!check.is_healthy());
+        assert_eq!(check.status_code(), 503);
+        assert_eq!(
+            check.to_string(),
+            "unhealthy (12ms): Connection error: refused"
+        );
+    }
+}
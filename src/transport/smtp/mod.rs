@@ -133,16 +133,26 @@
 //! # }
 //! ```
 
-use std::time::Duration;
+use std::{
+    borrow::Cow,
+    fmt::{self, Debug},
+    sync::Arc,
+    time::Duration,
+};
 
 use client::Tls;
 
-#[cfg(any(feature = "tokio1", feature = "async-std1"))]
-pub use self::async_transport::{AsyncSmtpTransport, AsyncSmtpTransportBuilder};
 #[cfg(feature = "pool")]
-pub use self::pool::PoolConfig;
+pub use self::pool::{CircuitState, PoolConfig, PoolEvent, PoolObserver};
+#[cfg(any(feature = "tokio1", feature = "async-std1"))]
+pub use self::{
+    async_transport::{AsyncSmtpTransport, AsyncSmtpTransportBuilder},
+    session::AsyncSession,
+};
 pub use self::{
-    error::Error,
+    dry_run::DryRun,
+    error::{Error, FailedCommand},
+    session::Session,
     transport::{SmtpTransport, SmtpTransportBuilder},
 };
 #[cfg(any(feature = "native-tls", feature = "rustls-tls", feature = "boring-tls"))]
@@ -150,8 +160,10 @@ use crate::transport::smtp::client::TlsParameters;
 use crate::transport::smtp::{
     authentication::{Credentials, Mechanism, DEFAULT_MECHANISMS},
     client::SmtpConnection,
-    extension::ClientId,
-    response::Response,
+    dns_cache::DnsCache,
+    extension::{ClientId, CustomExtension},
+    response::{ReplyLimits, Response},
+    stats::SendObserver,
 };
 
 #[cfg(any(feature = "tokio1", feature = "async-std1"))]
@@ -160,11 +172,22 @@ pub mod authentication;
 pub mod client;
 pub mod commands;
 mod connection_url;
+pub mod diagnosis;
+mod dns_cache;
+mod dry_run;
 mod error;
 pub mod extension;
+pub mod health;
+#[cfg(feature = "oauth2-helpers")]
+#[cfg_attr(docsrs, doc(cfg(feature = "oauth2-helpers")))]
+pub mod oauth2;
 #[cfg(feature = "pool")]
 mod pool;
 pub mod response;
+#[cfg(any(feature = "tokio1", feature = "async-std1"))]
+mod semaphore;
+mod session;
+pub mod stats;
 mod transport;
 pub(super) mod util;
 
@@ -181,13 +204,39 @@ pub const SUBMISSION_PORT: u16 = 587;
 /// Defined in [RFC8314](https://tools.ietf.org/html/rfc8314)
 pub const SUBMISSIONS_PORT: u16 = 465;
 
+/// `User-Agent` identifying lettre and its version, like `lettre/0.11`
+///
+/// See [`SmtpTransportBuilder::user_agent`]
+pub const DEFAULT_USER_AGENT: &str = concat!(
+    "lettre/",
+    env!("CARGO_PKG_VERSION_MAJOR"),
+    ".",
+    env!("CARGO_PKG_VERSION_MINOR")
+);
+
 /// Default timeout
 const DEFAULT_TIMEOUT: Duration = Duration::from_secs(60);
 
+/// Function producing the name sent during EHLO for each new connection
+#[derive(Clone)]
+struct HelloNameFn(Arc<dyn Fn() -> ClientId + Send + Sync>);
+
+impl Debug for HelloNameFn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("HelloNameFn")
+    }
+}
+
 #[derive(Debug, Clone)]
 struct SmtpInfo {
     /// Name sent during EHLO
     hello_name: ClientId,
+    /// Overrides `hello_name` with a per-connection name
+    hello_name_fn: Option<HelloNameFn>,
+    /// Hooks adding parameters to `MAIL FROM` and `RCPT TO`
+    custom_extensions: Vec<Arc<dyn CustomExtension>>,
+    /// Hooks called for each message accepted by the server
+    send_observers: Vec<Arc<dyn SendObserver>>,
     /// Server we are connecting to
     server: String,
     /// Port to connect to
@@ -198,9 +247,31 @@ struct SmtpInfo {
     authentication: Vec<Mechanism>,
     /// Credentials
     credentials: Option<Credentials>,
+    /// Source of OAuth 2.0 tokens, overriding `credentials`
+    #[cfg(feature = "oauth2-helpers")]
+    oauth2: Option<oauth2::OAuth2Credentials>,
+    /// Allow authenticating over unencrypted connections
+    plaintext_auth: bool,
     /// Define network timeout
     /// It can be changed later for specific needs (like a different timeout for each SMTP command)
     timeout: Option<Duration>,
+    /// Maximum time to wait for the whole reply to a command
+    command_deadline: Option<Duration>,
+    /// Limits on the size of replies
+    reply_limits: ReplyLimits,
+    /// Maximum time to set up a connection
+    connect_deadline: Option<Duration>,
+    /// Cache of the addresses `server` resolved to
+    dns_cache: Option<DnsCache>,
+    /// Send `HELO` when the server rejects `EHLO`
+    helo_fallback: bool,
+    /// Check the recipients instead of sending messages
+    dry_run: bool,
+    /// Delay before sending a greylisted message again, used by the async transport
+    #[cfg(any(feature = "tokio1", feature = "async-std1"))]
+    greylist_retry: Option<Duration>,
+    /// `User-Agent` added to messages without one
+    user_agent: Option<String>,
 }
 
 impl Default for SmtpInfo {
@@ -209,10 +280,129 @@ impl Default for SmtpInfo {
             server: "localhost".to_owned(),
             port: SMTP_PORT,
             hello_name: ClientId::default(),
+            hello_name_fn: None,
+            custom_extensions: Vec::new(),
+            send_observers: Vec::new(),
             credentials: None,
+            #[cfg(feature = "oauth2-helpers")]
+            oauth2: None,
+            plaintext_auth: false,
             authentication: DEFAULT_MECHANISMS.into(),
             timeout: Some(DEFAULT_TIMEOUT),
+            command_deadline: None,
+            reply_limits: ReplyLimits::default(),
+            connect_deadline: None,
+            dns_cache: None,
+            helo_fallback: false,
+            dry_run: false,
+            #[cfg(any(feature = "tokio1", feature = "async-std1"))]
+            greylist_retry: None,
+            user_agent: None,
             tls: Tls::None,
         }
     }
 }
+
+impl SmtpInfo {
+    /// Credentials used to authenticate a new connection
+    ///
+    /// Fails if credentials are set but the connection isn't `encrypted`,
+    /// unless plaintext authentication was allowed.
+    fn auth_credentials(&self, encrypted: bool) -> Result<Option<Credentials>, Error> {
+        self.check_plaintext_auth(encrypted)?;
+
+        #[cfg(feature = "oauth2-helpers")]
+        if let Some(oauth2) = &self.oauth2 {
+            return oauth2.credentials().map(Some);
+        }
+
+        Ok(self.credentials.clone())
+    }
+
+    /// Fails if credentials are set but the connection isn't `encrypted`,
+    /// unless plaintext authentication was allowed
+    fn check_plaintext_auth(&self, encrypted: bool) -> Result<(), Error> {
+        #[cfg(feature = "oauth2-helpers")]
+        let has_credentials = self.credentials.is_some() || self.oauth2.is_some();
+        #[cfg(not(feature = "oauth2-helpers"))]
+        let has_credentials = self.credentials.is_some();
+        if has_credentials && !encrypted && !self.plaintext_auth {
+            return Err(error::client(
+                "refusing to authenticate over an unencrypted connection",
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl SmtpInfo {
+    /// Name to send during EHLO on a new connection
+    fn hello_name(&self) -> ClientId {
+        match &self.hello_name_fn {
+            Some(HelloNameFn(f)) => f(),
+            None => self.hello_name.clone(),
+        }
+    }
+
+    /// Writes the configuration for logging, leaving out the secrets and
+    /// most of the username
+    fn fmt_summary(&self, f: &mut fmt::Formatter<'_>, pool_max_size: Option<u32>) -> fmt::Result {
+        let scheme = match self.tls {
+            #[cfg(any(feature = "native-tls", feature = "rustls-tls", feature = "boring-tls"))]
+            Tls::Wrapper(_) => "smtps",
+            _ => "smtp",
+        };
+        write!(f, "{scheme}://{}:{}", self.server, self.port)?;
+        if let Some(max_size) = pool_max_size {
+            write!(f, ", pool max={max_size}")?;
+        }
+
+        #[cfg(feature = "oauth2-helpers")]
+        let user = match &self.oauth2 {
+            Some(oauth2) => Some(oauth2.user()),
+            None => self.credentials.as_ref().map(Credentials::username),
+        };
+        #[cfg(not(feature = "oauth2-helpers"))]
+        let user = self.credentials.as_ref().map(Credentials::username);
+        match user {
+            Some(user) => {
+                let mechanisms = self
+                    .authentication
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>();
+                // Keep the username up to the `@`, or its first character
+                let kept = user.find('@').map_or_else(
+                    || user.chars().next().map_or(0, char::len_utf8),
+                    |at| at + 1,
+                );
+                write!(f, ", auth={} as {}…", mechanisms.join("/"), &user[..kept])?;
+            }
+            None => f.write_str(", auth=none")?,
+        }
+
+        match &self.tls {
+            Tls::None => f.write_str(", tls=none"),
+            #[cfg(any(feature = "native-tls", feature = "rustls-tls", feature = "boring-tls"))]
+            Tls::Opportunistic(tls_parameters) => {
+                write!(f, ", tls=opportunistic({})", tls_parameters.backend())
+            }
+            #[cfg(any(feature = "native-tls", feature = "rustls-tls", feature = "boring-tls"))]
+            Tls::Required(tls_parameters) => {
+                write!(f, ", tls=required({})", tls_parameters.backend())
+            }
+            #[cfg(any(feature = "native-tls", feature = "rustls-tls", feature = "boring-tls"))]
+            Tls::Wrapper(tls_parameters) => {
+                write!(f, ", tls=wrapper({})", tls_parameters.backend())
+            }
+        }
+    }
+
+    /// The formatted `email` with the configured `User-Agent` added, if any
+    fn stamp_user_agent<'a>(&self, email: &'a [u8]) -> Cow<'a, [u8]> {
+        match &self.user_agent {
+            Some(user_agent) => util::stamp_user_agent(email, user_agent),
+            None => Cow::Borrowed(email),
+        }
+    }
+}
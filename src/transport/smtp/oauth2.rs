@@ -0,0 +1,204 @@
+//! Authentication with OAuth 2.0 access tokens
+//!
+//! Providers like Gmail and Microsoft 365 expect OAuth 2.0 access tokens,
+//! which expire after about an hour. Instead of static [`Credentials`],
+//! a [`TokenProvider`] registered with
+//! [`SmtpTransportBuilder::oauth2_token_provider`](super::SmtpTransportBuilder::oauth2_token_provider)
+//! is asked for a token each time a connection authenticates using `XOAUTH2`.
+//!
+//! Getting the tokens, with a device code or a refresh token, is left to an
+//! OAuth 2.0 client like the `oauth2` crate. [`TokenCache`] avoids calling it
+//! for each connection.
+//!
+//! ```rust,no_run
+//! use std::time::{Duration, SystemTime};
+//!
+//! use lettre::{
+//!     transport::smtp::oauth2::{AccessToken, TokenCache},
+//!     SmtpTransport,
+//! };
+//!
+//! # use std::error::Error;
+//! # fn main() -> Result<(), Box<dyn Error>> {
+//! let tokens = TokenCache::new(|| {
+//!     // Exchange the refresh token with the OAuth 2.0 client of your choice
+//!     let token = String::from("ya29.a0AfH6SMB...");
+//!     let expires_in = Duration::from_secs(3599);
+//!     Ok(AccessToken::new(
+//!         token,
+//!         Some(SystemTime::now() + expires_in),
+//!     ))
+//! });
+//!
+//! let mailer = SmtpTransport::gmail_relay()?
+//!     .oauth2_token_provider("user@gmail.com".to_owned(), tokens)
+//!     .build();
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`Credentials`]: super::authentication::Credentials
+
+use std::{
+    fmt::{self, Debug},
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime},
+};
+
+use super::{authentication::Credentials, error, Error};
+use crate::BoxError;
+
+/// Refreshing tokens this long before they expire, so they don't expire during authentication
+const EXPIRY_MARGIN: Duration = Duration::from_secs(60);
+
+/// An OAuth 2.0 access token
+#[derive(Clone)]
+pub struct AccessToken {
+    secret: String,
+    expires_at: Option<SystemTime>,
+}
+
+impl AccessToken {
+    /// Creates an access token, valid until `expires_at` if known
+    pub fn new(secret: String, expires_at: Option<SystemTime>) -> Self {
+        Self { secret, expires_at }
+    }
+
+    /// When the token expires, if known
+    pub fn expires_at(&self) -> Option<SystemTime> {
+        self.expires_at
+    }
+
+    /// Whether the token is expired or about to expire
+    fn is_expired(&self) -> bool {
+        self.expires_at
+            .is_some_and(|expires_at| expires_at <= SystemTime::now() + EXPIRY_MARGIN)
+    }
+}
+
+impl Debug for AccessToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AccessToken")
+            .field("expires_at", &self.expires_at)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Source of the access tokens used to authenticate new connections
+///
+/// Called for each new connection, so it should return quickly, for example
+/// by caching tokens with [`TokenCache`]. The async transports call it on a
+/// blocking thread of the executor, as refreshing a token usually blocks.
+pub trait TokenProvider: Send + Sync {
+    /// Returns a valid access token
+    fn access_token(&self) -> Result<AccessToken, BoxError>;
+}
+
+/// Caches the token returned by `refresh` until it's about to expire
+pub struct TokenCache<F> {
+    refresh: F,
+    token: Mutex<Option<AccessToken>>,
+}
+
+impl<F> TokenCache<F>
+where
+    F: Fn() -> Result<AccessToken, BoxError> + Send + Sync,
+{
+    /// Creates a cache getting new tokens from `refresh`
+    pub fn new(refresh: F) -> Self {
+        Self {
+            refresh,
+            token: Mutex::new(None),
+        }
+    }
+}
+
+impl<F> TokenProvider for TokenCache<F>
+where
+    F: Fn() -> Result<AccessToken, BoxError> + Send + Sync,
+{
+    fn access_token(&self) -> Result<AccessToken, BoxError> {
+        let mut token = self.token.lock().unwrap();
+        match &*token {
+            Some(token) if !token.is_expired() => Ok(token.clone()),
+            _ => {
+                let new_token = (self.refresh)()?;
+                *token = Some(new_token.clone());
+                Ok(new_token)
+            }
+        }
+    }
+}
+
+impl<F> Debug for TokenCache<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TokenCache")
+            .field("token", &self.token)
+            .finish_non_exhaustive()
+    }
+}
+
+/// User authenticating with tokens from a provider
+#[derive(Clone)]
+pub(super) struct OAuth2Credentials {
+    user: String,
+    provider: Arc<dyn TokenProvider>,
+}
+
+impl OAuth2Credentials {
+    pub(super) fn new(user: String, provider: Arc<dyn TokenProvider>) -> Self {
+        Self { user, provider }
+    }
+
+    /// The user the tokens are for
+    pub(super) fn user(&self) -> &str {
+        &self.user
+    }
+
+    /// Credentials with a valid access token
+    pub(super) fn credentials(&self) -> Result<Credentials, Error> {
+        let token = self.provider.access_token().map_err(error::client)?;
+        Ok(Credentials::new(self.user.clone(), token.secret))
+    }
+}
+
+impl Debug for OAuth2Credentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OAuth2Credentials")
+            .field("user", &self.user)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        sync::atomic::{AtomicUsize, Ordering},
+        time::{Duration, SystemTime},
+    };
+
+    use super::{AccessToken, TokenCache, TokenProvider};
+
+    #[test]
+    fn token_cache() {
+        let refreshes = AtomicUsize::new(0);
+        let cache = TokenCache::new(|| {
+            let n = refreshes.fetch_add(1, Ordering::Relaxed);
+            Ok(AccessToken::new(
+                format!("token{n}"),
+                Some(SystemTime::now() + Duration::from_secs(3600)),
+            ))
+        });
+
+        assert_eq!(cache.access_token().unwrap().secret, "token0");
+        assert_eq!(cache.access_token().unwrap().secret, "token0");
+        assert_eq!(refreshes.load(Ordering::Relaxed), 1);
+
+        // Tokens about to expire are refreshed
+        *cache.token.lock().unwrap() = Some(AccessToken::new(
+            "expiring".to_owned(),
+            Some(SystemTime::now() + Duration::from_secs(10)),
+        ));
+        assert_eq!(cache.access_token().unwrap().secret, "token1");
+    }
+}
@@ -12,8 +12,8 @@ use futures_util::{
 };
 
 use super::{
-    super::{client::AsyncSmtpConnection, Error},
-    PoolConfig,
+    super::{client::AsyncSmtpConnection, extension::ServerInfo, Error},
+    CircuitBreaker, CircuitState, PoolConfig, PoolEvent,
 };
 use crate::{executor::SpawnHandle, transport::smtp::async_transport::AsyncSmtpClient, Executor};
 
@@ -21,12 +21,16 @@ pub struct Pool<E: Executor> {
     config: PoolConfig,
     connections: Mutex<Vec<ParkedConnection>>,
     client: AsyncSmtpClient<E>,
+    breaker: std::sync::Mutex<CircuitBreaker>,
+    /// Server information of the last connection opened
+    server_info: std::sync::Mutex<Option<ServerInfo>>,
     handle: OnceLock<E::Handle>,
 }
 
 struct ParkedConnection {
     conn: AsyncSmtpConnection,
     since: Instant,
+    last_keep_alive: Instant,
 }
 
 pub struct PooledConnection<E: Executor> {
@@ -40,6 +44,8 @@ impl<E: Executor> Pool<E> {
             config,
             connections: Mutex::new(Vec::new()),
             client,
+            breaker: std::sync::Mutex::new(CircuitBreaker::default()),
+            server_info: std::sync::Mutex::new(None),
             handle: OnceLock::new(),
         });
 
@@ -48,6 +54,8 @@ impl<E: Executor> Pool<E> {
 
             let min_idle = pool_.config.min_idle;
             let idle_timeout = pool_.config.idle_timeout;
+            let keep_alive = pool_.config.keep_alive;
+            let maintenance_interval = pool_.config.maintenance_interval();
             let pool = Arc::downgrade(&pool_);
 
             let handle = E::spawn(async move {
@@ -57,6 +65,10 @@ impl<E: Executor> Pool<E> {
 
                     match pool.upgrade() {
                         Some(pool) => {
+                            if let Some(interval) = keep_alive {
+                                pool.keep_alive(interval).await;
+                            }
+
                             #[allow(clippy::needless_collect)]
                             let (count, dropped) = {
                                 let mut connections = pool.connections.lock().await;
@@ -79,7 +91,7 @@ impl<E: Executor> Pool<E> {
                             #[cfg(feature = "tracing")]
                             let mut created = 0;
                             for _ in count..(min_idle as usize) {
-                                let conn = match pool.client.connection().await {
+                                let conn = match pool.connect().await {
                                     Ok(conn) => conn,
                                     Err(err) => {
                                         #[cfg(feature = "tracing")]
@@ -109,6 +121,9 @@ impl<E: Executor> Pool<E> {
                                 #[cfg(feature = "tracing")]
                                 tracing::debug!("dropped {} idle connections", dropped.len());
 
+                                for _ in &dropped {
+                                    pool.config.notify(PoolEvent::IdleTimeout);
+                                }
                                 abort_concurrent(dropped.into_iter().map(|conn| conn.unpark()))
                                     .await;
                             }
@@ -122,7 +137,7 @@ impl<E: Executor> Pool<E> {
                         }
                     }
 
-                    E::sleep(idle_timeout).await;
+                    E::sleep(maintenance_interval).await;
                 }
             });
             pool_
@@ -134,7 +149,67 @@ impl<E: Executor> Pool<E> {
         pool
     }
 
+    /// Send a `NOOP` on connections idle for longer than `interval`,
+    /// dropping the ones which fail it
+    async fn keep_alive(&self, interval: Duration) {
+        let idle = {
+            let mut connections = self.connections.lock().await;
+            let (idle, active): (Vec<_>, Vec<_>) = mem::take(&mut *connections)
+                .into_iter()
+                .partition(|conn| conn.needs_keep_alive(interval));
+            *connections = active;
+            idle
+        };
+
+        for mut conn in idle {
+            if conn.keep_alive().await {
+                let mut connections = self.connections.lock().await;
+                if connections.len() < self.config.max_size as usize {
+                    connections.push(conn);
+                    continue;
+                }
+                self.config.notify(PoolEvent::PoolFull);
+            } else {
+                #[cfg(feature = "tracing")]
+                tracing::debug!("dropping a connection which failed the keep-alive");
+
+                self.config.notify(PoolEvent::Broken);
+            }
+
+            conn.unpark().abort().await;
+        }
+    }
+
+    /// Open a new connection, going through the circuit breaker
+    async fn connect(&self) -> Result<AsyncSmtpConnection, Error> {
+        self.breaker.lock().unwrap().before_connect(&self.config)?;
+        let result = self.client.connection().await;
+        self.breaker
+            .lock()
+            .unwrap()
+            .record(result.is_ok(), &self.config);
+        if let Ok(conn) = &result {
+            self.config.notify(PoolEvent::Created);
+            self.config
+                .check_server_info(&self.server_info, conn.server_info());
+        }
+        result
+    }
+
+    pub fn circuit_state(&self) -> CircuitState {
+        self.breaker.lock().unwrap().state()
+    }
+
+    pub fn max_size(&self) -> u32 {
+        self.config.max_size
+    }
+
+    pub fn client(&self) -> &AsyncSmtpClient<E> {
+        &self.client
+    }
+
     pub async fn connection(self: &Arc<Self>) -> Result<PooledConnection<E>, Error> {
+        let start = Instant::now();
         loop {
             let conn = {
                 let mut connections = self.connections.lock().await;
@@ -150,6 +225,7 @@ impl<E: Executor> Pool<E> {
                         #[cfg(feature = "tracing")]
                         tracing::debug!("dropping a broken connection");
 
+                        self.config.notify(PoolEvent::Broken);
                         conn.abort().await;
                         continue;
                     }
@@ -157,26 +233,48 @@ impl<E: Executor> Pool<E> {
                     #[cfg(feature = "tracing")]
                     tracing::debug!("reusing a pooled connection");
 
-                    return Ok(PooledConnection::wrap(conn, Arc::clone(self)));
+                    self.config.notify(PoolEvent::Reused);
+                    return Ok(self.checked_out(conn, start));
                 }
                 None => {
                     #[cfg(feature = "tracing")]
                     tracing::debug!("creating a new connection");
 
-                    let conn = self.client.connection().await?;
-                    return Ok(PooledConnection::wrap(conn, Arc::clone(self)));
+                    let conn = self.connect().await?;
+                    return Ok(self.checked_out(conn, start));
                 }
             }
         }
     }
 
+    /// Hand `conn` to the transport, `start` being when it was requested
+    fn checked_out(
+        self: &Arc<Self>,
+        conn: AsyncSmtpConnection,
+        start: Instant,
+    ) -> PooledConnection<E> {
+        let wait = start.elapsed();
+        #[cfg(feature = "tracing")]
+        tracing::debug!("checked out a connection after {:?}", wait);
+
+        self.config.notify(PoolEvent::CheckedOut { wait });
+        PooledConnection::wrap(conn, Arc::clone(self))
+    }
+
     async fn recycle(&self, mut conn: AsyncSmtpConnection) {
         if conn.has_broken() {
             #[cfg(feature = "tracing")]
             tracing::debug!("dropping a broken connection instead of recycling it");
 
+            self.config.notify(PoolEvent::Broken);
             conn.abort().await;
             drop(conn);
+        } else if conn.is_exhausted() {
+            #[cfg(feature = "tracing")]
+            tracing::debug!("closing a connection which reached the MAILMAX limit of the server");
+
+            self.config.notify(PoolEvent::Exhausted);
+            conn.abort().await;
         } else {
             #[cfg(feature = "tracing")]
             tracing::debug!("recycling connection");
@@ -184,10 +282,12 @@ impl<E: Executor> Pool<E> {
             let mut connections = self.connections.lock().await;
             if connections.len() >= self.config.max_size as usize {
                 drop(connections);
+                self.config.notify(PoolEvent::PoolFull);
                 conn.abort().await;
             } else {
                 let conn = ParkedConnection::park(conn);
                 connections.push(conn);
+                self.config.notify(PoolEvent::Recycled);
             }
         }
     }
@@ -236,9 +336,11 @@ impl<E: Executor> Drop for Pool<E> {
 
 impl ParkedConnection {
     fn park(conn: AsyncSmtpConnection) -> Self {
+        let now = Instant::now();
         Self {
             conn,
-            since: Instant::now(),
+            since: now,
+            last_keep_alive: now,
         }
     }
 
@@ -246,6 +348,16 @@ impl ParkedConnection {
         self.since.elapsed()
     }
 
+    fn needs_keep_alive(&self, interval: Duration) -> bool {
+        self.last_keep_alive.elapsed() >= interval
+    }
+
+    /// Send a `NOOP`, returning whether the connection is still usable
+    async fn keep_alive(&mut self) -> bool {
+        self.last_keep_alive = Instant::now();
+        self.conn.test_connected().await
+    }
+
     fn unpark(self) -> AsyncSmtpConnection {
         self.conn
     }
@@ -1,4 +1,14 @@
-use std::time::Duration;
+use std::{
+    fmt::Debug,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use super::{
+    error,
+    extension::{ServerInfo, ServerInfoChange},
+    Error,
+};
 
 #[cfg(any(feature = "tokio1", feature = "async-std1"))]
 pub mod async_impl;
@@ -12,6 +22,10 @@ pub struct PoolConfig {
     min_idle: u32,
     max_size: u32,
     idle_timeout: Duration,
+    keep_alive: Option<Duration>,
+    circuit_breaker: Option<(u32, Duration)>,
+    checkout_timeout: Option<Duration>,
+    observers: Vec<Arc<dyn PoolObserver>>,
 }
 
 impl PoolConfig {
@@ -53,6 +67,113 @@ impl PoolConfig {
         self.idle_timeout = idle_timeout;
         self
     }
+
+    /// Send a `NOOP` on connections which have been idle for `interval`
+    ///
+    /// Keeps NATs and firewalls from silently dropping idle connections,
+    /// which would otherwise make the next send fail. Idle connections are
+    /// checked every `interval`, so it should be at most half of the shortest
+    /// timeout on the path to the server. Connections failing the `NOOP` are dropped.
+    ///
+    /// Disabled by default
+    pub fn keep_alive(mut self, interval: Duration) -> Self {
+        self.keep_alive = Some(interval);
+        self
+    }
+
+    /// Stop connecting to the server for `cool_down` after `failures`
+    /// consecutive connection failures
+    ///
+    /// While the circuit is open, getting a new connection fails immediately
+    /// with an error for which [`Error::is_circuit_open`] returns `true`, instead of
+    /// waiting for the server to time out. Once `cool_down` has elapsed, a single
+    /// connection attempt probes the server, closing the circuit if it succeeds
+    /// and opening it again otherwise.
+    ///
+    /// The current state is returned by `circuit_state` on the transport.
+    ///
+    /// Disabled by default
+    ///
+    /// # Panics
+    ///
+    /// If `failures` is zero.
+    pub fn circuit_breaker(mut self, failures: u32, cool_down: Duration) -> Self {
+        assert!(failures > 0, "the failures threshold must not be zero");
+        self.circuit_breaker = Some((failures, cool_down));
+        self
+    }
+
+    /// Limit the connections in use at the same time to
+    /// [`max_size`](PoolConfig::max_size), waiting up to `timeout`
+    /// for one to be returned to the pool
+    ///
+    /// Senders get the connections in the order they asked for them.
+    /// When `timeout` elapses, sending fails with an error for which
+    /// [`Error::is_pool_timeout`] returns `true`.
+    ///
+    /// Only applies to the blocking [`SmtpTransport`](crate::SmtpTransport).
+    ///
+    /// Disabled by default, not limiting the connections in use.
+    pub fn checkout_timeout(mut self, timeout: Duration) -> Self {
+        self.checkout_timeout = Some(timeout);
+        self
+    }
+
+    /// Get the minimum number of idle connections
+    pub fn get_min_idle(&self) -> u32 {
+        self.min_idle
+    }
+
+    /// Get the maximum number of pooled connections
+    pub fn get_max_size(&self) -> u32 {
+        self.max_size
+    }
+
+    /// Get the connection idle timeout
+    pub fn get_idle_timeout(&self) -> Duration {
+        self.idle_timeout
+    }
+
+    /// Register an observer notified of the pool lifecycle events
+    ///
+    /// Can be called multiple times to register more observers.
+    pub fn observer<X: PoolObserver + 'static>(mut self, observer: Arc<X>) -> Self {
+        self.observers.push(observer);
+        self
+    }
+
+    /// Notify the observers of `event`
+    fn notify(&self, event: PoolEvent) {
+        for observer in &self.observers {
+            observer.event(event);
+        }
+    }
+
+    /// Notify the observers if the new connection announced other
+    /// capabilities than the previous one, stored in `last`
+    fn check_server_info(&self, last: &Mutex<Option<ServerInfo>>, server_info: &ServerInfo) {
+        let mut last = last.lock().unwrap();
+        if let Some(previous) = &*last {
+            let Some(change) = ServerInfoChange::new(previous, server_info) else {
+                return;
+            };
+
+            #[cfg(feature = "tracing")]
+            tracing::info!("server capabilities changed: {}", change);
+
+            for observer in &self.observers {
+                observer.server_info_changed(&change);
+            }
+        }
+        *last = Some(server_info.clone());
+    }
+
+    /// Interval at which the pool maintenance task runs
+    fn maintenance_interval(&self) -> Duration {
+        self.keep_alive.map_or(self.idle_timeout, |interval| {
+            interval.min(self.idle_timeout)
+        })
+    }
 }
 
 impl Default for PoolConfig {
@@ -61,6 +182,206 @@ impl Default for PoolConfig {
             min_idle: 0,
             max_size: 10,
             idle_timeout: Duration::from_secs(60),
+            keep_alive: None,
+            circuit_breaker: None,
+            checkout_timeout: None,
+            observers: Vec::new(),
+        }
+    }
+}
+
+/// Lifecycle event of a pooled connection
+///
+/// See [`PoolObserver`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+#[cfg_attr(docsrs, doc(cfg(feature = "pool")))]
+pub enum PoolEvent {
+    /// A new connection was opened
+    Created,
+    /// An idle connection was taken from the pool
+    Reused,
+    /// A connection was returned to the pool after being used
+    Recycled,
+    /// An idle connection was closed after the idle timeout
+    IdleTimeout,
+    /// A connection was closed because it was broken, or failed the keep-alive
+    Broken,
+    /// A connection was closed instead of being returned to the full pool
+    PoolFull,
+    /// A connection was closed after reaching the number of transactions
+    /// the server accepts on a connection, see [`Limits::mail_max`](super::extension::Limits::mail_max)
+    Exhausted,
+    /// A connection was handed to the transport after waiting for `wait`,
+    /// which includes connecting to the server for new connections
+    CheckedOut {
+        /// Time spent getting the connection
+        wait: Duration,
+    },
+}
+
+/// Hook called on the lifecycle events of pooled connections
+///
+/// Registered with [`PoolConfig::observer`], for example to export metrics
+/// explaining the number of connections opened by the pool.
+///
+/// ```rust
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+///
+/// use lettre::transport::smtp::{PoolEvent, PoolObserver};
+///
+/// #[derive(Debug, Default)]
+/// struct Connections {
+///     created: AtomicUsize,
+/// }
+///
+/// impl PoolObserver for Connections {
+///     fn event(&self, event: PoolEvent) {
+///         if event == PoolEvent::Created {
+///             self.created.fetch_add(1, Ordering::Relaxed);
+///         }
+///     }
+/// }
+/// ```
+#[cfg_attr(docsrs, doc(cfg(feature = "pool")))]
+pub trait PoolObserver: Debug + Send + Sync {
+    /// Called on each event, it must not block
+    fn event(&self, event: PoolEvent);
+
+    /// Called when a new connection finds that the server announces other
+    /// capabilities than on the previous one, it must not block
+    ///
+    /// Relays can silently change behavior after maintenance, for example
+    /// stopping to offer `STARTTLS` or lowering the maximum message size.
+    fn server_info_changed(&self, change: &ServerInfoChange) {
+        let _ = change;
+    }
+}
+
+/// State of the circuit breaker of a connection pool
+///
+/// See [`PoolConfig::circuit_breaker`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(docsrs, doc(cfg(feature = "pool")))]
+pub enum CircuitState {
+    /// New connections are attempted normally
+    Closed,
+    /// New connections fail immediately until the cool-down ends
+    Open,
+    /// The cool-down ended, the next connection attempt probes the server
+    HalfOpen,
+}
+
+/// Tracks consecutive connection failures
+#[derive(Debug, Default)]
+struct CircuitBreaker {
+    failures: u32,
+    open_until: Option<Instant>,
+    /// Start of the connection attempt probing the server while half-open
+    probe: Option<Instant>,
+}
+
+impl CircuitBreaker {
+    fn state(&self) -> CircuitState {
+        match self.open_until {
+            None => CircuitState::Closed,
+            Some(until) if Instant::now() < until => CircuitState::Open,
+            Some(_) => CircuitState::HalfOpen,
+        }
+    }
+
+    /// Check whether a new connection may be attempted
+    fn before_connect(&mut self, config: &PoolConfig) -> Result<(), Error> {
+        match self.state() {
+            CircuitState::Closed => Ok(()),
+            CircuitState::Open => Err(error::circuit_open()),
+            CircuitState::HalfOpen => {
+                // Allow a new probe if the previous one got lost, for example
+                // because the future trying to connect was dropped
+                let cool_down = config.circuit_breaker.map_or(Duration::ZERO, |(_, d)| d);
+                match self.probe {
+                    Some(probe) if probe.elapsed() < cool_down => Err(error::circuit_open()),
+                    _ => {
+                        self.probe = Some(Instant::now());
+                        Ok(())
+                    }
+                }
+            }
+        }
+    }
+
+    /// Record the outcome of a connection attempt
+    fn record(&mut self, success: bool, config: &PoolConfig) {
+        let Some((threshold, cool_down)) = config.circuit_breaker else {
+            return;
+        };
+
+        self.probe = None;
+        if success {
+            self.failures = 0;
+            self.open_until = None;
+        } else {
+            self.failures = self.failures.saturating_add(1);
+            if self.failures >= threshold {
+                #[cfg(feature = "tracing")]
+                tracing::warn!(
+                    "opening the circuit breaker after {} connection failures",
+                    self.failures
+                );
+
+                self.open_until = Some(Instant::now() + cool_down);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use super::{CircuitBreaker, CircuitState, PoolConfig};
+
+    #[test]
+    fn circuit_breaker() {
+        let config = PoolConfig::new().circuit_breaker(2, Duration::from_millis(50));
+        let mut breaker = CircuitBreaker::default();
+
+        assert!(breaker.before_connect(&config).is_ok());
+        breaker.record(false, &config);
+        assert_eq!(breaker.state(), CircuitState::Closed);
+        assert!(breaker.before_connect(&config).is_ok());
+        breaker.record(false, &config);
+        assert_eq!(breaker.state(), CircuitState::Open);
+        assert!(breaker
+            .before_connect(&config)
+            .unwrap_err()
+            .is_circuit_open());
+
+        std::thread::sleep(Duration::from_millis(60));
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+        assert!(breaker.before_connect(&config).is_ok());
+        // only a single probe at a time
+        assert!(breaker.before_connect(&config).is_err());
+        breaker.record(false, &config);
+        assert_eq!(breaker.state(), CircuitState::Open);
+
+        std::thread::sleep(Duration::from_millis(60));
+        assert!(breaker.before_connect(&config).is_ok());
+        breaker.record(true, &config);
+        assert_eq!(breaker.state(), CircuitState::Closed);
+        assert!(breaker.before_connect(&config).is_ok());
+    }
+
+    #[test]
+    fn circuit_breaker_disabled() {
+        let config = PoolConfig::new();
+        let mut breaker = CircuitBreaker::default();
+
+        for _ in 0..10 {
+            assert!(breaker.before_connect(&config).is_ok());
+            breaker.record(false, &config);
         }
+        assert_eq!(breaker.state(), CircuitState::Closed);
     }
 }
@@ -1,15 +1,16 @@
 use std::{
+    collections::VecDeque,
     fmt::{self, Debug},
     mem,
     ops::{Deref, DerefMut},
-    sync::{Arc, Mutex, TryLockError},
+    sync::{Arc, Condvar, Mutex, TryLockError},
     thread,
     time::{Duration, Instant},
 };
 
 use super::{
-    super::{client::SmtpConnection, Error},
-    PoolConfig,
+    super::{client::SmtpConnection, error, extension::ServerInfo, Error},
+    CircuitBreaker, CircuitState, PoolConfig, PoolEvent,
 };
 use crate::transport::smtp::transport::SmtpClient;
 
@@ -17,24 +18,57 @@ pub struct Pool {
     config: PoolConfig,
     connections: Mutex<Vec<ParkedConnection>>,
     client: SmtpClient,
+    breaker: Mutex<CircuitBreaker>,
+    /// Server information of the last connection opened
+    server_info: Mutex<Option<ServerInfo>>,
+    checkout: Option<Checkout>,
+}
+
+/// Fair semaphore limiting the connections in use, see [`PoolConfig::checkout_timeout`]
+struct Checkout {
+    timeout: Duration,
+    state: Mutex<CheckoutState>,
+    available: Condvar,
+}
+
+struct CheckoutState {
+    /// Number of connections which can still be checked out
+    permits: usize,
+    /// Tickets of the waiting senders, in arrival order
+    queue: VecDeque<u64>,
+    next_ticket: u64,
+}
+
+/// Permission to use a connection, given back to the [`Checkout`] when dropped
+struct Permit {
+    pool: Arc<Pool>,
 }
 
 struct ParkedConnection {
     conn: SmtpConnection,
     since: Instant,
+    last_keep_alive: Instant,
 }
 
 pub struct PooledConnection {
     conn: Option<SmtpConnection>,
     pool: Arc<Pool>,
+    /// Dropped after recycling `conn`, so the next sender can reuse it
+    _permit: Option<Permit>,
 }
 
 impl Pool {
     pub fn new(config: PoolConfig, client: SmtpClient) -> Arc<Self> {
+        let checkout = config
+            .checkout_timeout
+            .map(|timeout| Checkout::new(config.max_size as usize, timeout));
         let pool = Arc::new(Self {
             config,
             connections: Mutex::new(Vec::new()),
             client,
+            breaker: Mutex::new(CircuitBreaker::default()),
+            server_info: Mutex::new(None),
+            checkout,
         });
 
         {
@@ -42,6 +76,8 @@ impl Pool {
 
             let min_idle = pool_.config.min_idle;
             let idle_timeout = pool_.config.idle_timeout;
+            let keep_alive = pool_.config.keep_alive;
+            let maintenance_interval = pool_.config.maintenance_interval();
             let pool = Arc::downgrade(&pool_);
 
             thread::Builder::new()
@@ -51,6 +87,10 @@ impl Pool {
                         #[cfg(feature = "tracing")]
                         tracing::trace!("running cleanup tasks");
 
+                        if let Some(interval) = keep_alive {
+                            pool.keep_alive(interval);
+                        }
+
                         #[allow(clippy::needless_collect)]
                         let (count, dropped) = {
                             let mut connections = pool.connections.lock().unwrap();
@@ -73,7 +113,7 @@ impl Pool {
                         #[cfg(feature = "tracing")]
                         let mut created = 0;
                         for _ in count..(min_idle as usize) {
-                            let conn = match pool.client.connection() {
+                            let conn = match pool.connect() {
                                 Ok(conn) => conn,
                                 Err(err) => {
                                     #[cfg(feature = "tracing")]
@@ -104,12 +144,13 @@ impl Pool {
                             tracing::debug!("dropped {} idle connections", dropped.len());
 
                             for conn in dropped {
+                                pool.config.notify(PoolEvent::IdleTimeout);
                                 let mut conn = conn.unpark();
                                 conn.abort();
                             }
                         }
 
-                        thread::sleep(idle_timeout);
+                        thread::sleep(maintenance_interval);
                     }
                 })
                 .expect("couldn't spawn the Pool thread");
@@ -118,7 +159,82 @@ impl Pool {
         pool
     }
 
+    /// Send a `NOOP` on connections idle for longer than `interval`,
+    /// dropping the ones which fail it
+    fn keep_alive(&self, interval: Duration) {
+        let idle = {
+            let mut connections = self.connections.lock().unwrap();
+            let (idle, active): (Vec<_>, Vec<_>) = mem::take(&mut *connections)
+                .into_iter()
+                .partition(|conn| conn.needs_keep_alive(interval));
+            *connections = active;
+            idle
+        };
+
+        for mut conn in idle {
+            if conn.keep_alive() {
+                let mut connections = self.connections.lock().unwrap();
+                if connections.len() < self.config.max_size as usize {
+                    connections.push(conn);
+                    continue;
+                }
+                self.config.notify(PoolEvent::PoolFull);
+            } else {
+                #[cfg(feature = "tracing")]
+                tracing::debug!("dropping a connection which failed the keep-alive");
+
+                self.config.notify(PoolEvent::Broken);
+            }
+
+            conn.unpark().abort();
+        }
+    }
+
+    /// Open a new connection, going through the circuit breaker
+    fn connect(&self) -> Result<SmtpConnection, Error> {
+        self.breaker.lock().unwrap().before_connect(&self.config)?;
+        let result = self.client.connection();
+        self.breaker
+            .lock()
+            .unwrap()
+            .record(result.is_ok(), &self.config);
+        if let Ok(conn) = &result {
+            self.config.notify(PoolEvent::Created);
+            self.config
+                .check_server_info(&self.server_info, conn.server_info());
+        }
+        result
+    }
+
+    pub fn circuit_state(&self) -> CircuitState {
+        self.breaker.lock().unwrap().state()
+    }
+
+    pub fn max_size(&self) -> u32 {
+        self.config.max_size
+    }
+
+    pub fn client(&self) -> &SmtpClient {
+        &self.client
+    }
+
     pub fn connection(self: &Arc<Self>) -> Result<PooledConnection, Error> {
+        let start = Instant::now();
+        let permit = match &self.checkout {
+            Some(checkout) => {
+                if !checkout.acquire() {
+                    #[cfg(feature = "tracing")]
+                    tracing::debug!("timed out waiting for a pooled connection");
+
+                    return Err(error::pool_timeout());
+                }
+                Some(Permit {
+                    pool: Arc::clone(self),
+                })
+            }
+            None => None,
+        };
+
         loop {
             let conn = {
                 let mut connections = self.connections.lock().unwrap();
@@ -134,6 +250,7 @@ impl Pool {
                         #[cfg(feature = "tracing")]
                         tracing::debug!("dropping a broken connection");
 
+                        self.config.notify(PoolEvent::Broken);
                         conn.abort();
                         continue;
                     }
@@ -141,26 +258,53 @@ impl Pool {
                     #[cfg(feature = "tracing")]
                     tracing::debug!("reusing a pooled connection");
 
-                    return Ok(PooledConnection::wrap(conn, Arc::clone(self)));
+                    self.config.notify(PoolEvent::Reused);
+                    return Ok(self.checked_out(conn, start, permit));
                 }
                 None => {
                     #[cfg(feature = "tracing")]
                     tracing::debug!("creating a new connection");
 
-                    let conn = self.client.connection()?;
-                    return Ok(PooledConnection::wrap(conn, Arc::clone(self)));
+                    let conn = self.connect()?;
+                    return Ok(self.checked_out(conn, start, permit));
                 }
             }
         }
     }
 
+    /// Hand `conn` to the transport, `start` being when it was requested
+    fn checked_out(
+        self: &Arc<Self>,
+        conn: SmtpConnection,
+        start: Instant,
+        permit: Option<Permit>,
+    ) -> PooledConnection {
+        let wait = start.elapsed();
+        #[cfg(feature = "tracing")]
+        tracing::debug!("checked out a connection after {:?}", wait);
+
+        self.config.notify(PoolEvent::CheckedOut { wait });
+        PooledConnection {
+            conn: Some(conn),
+            pool: Arc::clone(self),
+            _permit: permit,
+        }
+    }
+
     fn recycle(&self, mut conn: SmtpConnection) {
         if conn.has_broken() {
             #[cfg(feature = "tracing")]
             tracing::debug!("dropping a broken connection instead of recycling it");
 
+            self.config.notify(PoolEvent::Broken);
             conn.abort();
             drop(conn);
+        } else if conn.is_exhausted() {
+            #[cfg(feature = "tracing")]
+            tracing::debug!("closing a connection which reached the MAILMAX limit of the server");
+
+            self.config.notify(PoolEvent::Exhausted);
+            conn.abort();
         } else {
             #[cfg(feature = "tracing")]
             tracing::debug!("recycling connection");
@@ -168,10 +312,12 @@ impl Pool {
             let mut connections = self.connections.lock().unwrap();
             if connections.len() >= self.config.max_size as usize {
                 drop(connections);
+                self.config.notify(PoolEvent::PoolFull);
                 conn.abort();
             } else {
                 let conn = ParkedConnection::park(conn);
                 connections.push(conn);
+                self.config.notify(PoolEvent::Recycled);
             }
         }
     }
@@ -208,11 +354,73 @@ impl Drop for Pool {
     }
 }
 
+impl Checkout {
+    fn new(permits: usize, timeout: Duration) -> Self {
+        Self {
+            timeout,
+            state: Mutex::new(CheckoutState {
+                permits,
+                queue: VecDeque::new(),
+                next_ticket: 0,
+            }),
+            available: Condvar::new(),
+        }
+    }
+
+    /// Wait for a connection to be available, in arrival order,
+    /// returning `false` on timeout
+    fn acquire(&self) -> bool {
+        let deadline = Instant::now() + self.timeout;
+
+        let mut state = self.state.lock().unwrap();
+        let ticket = state.next_ticket;
+        state.next_ticket += 1;
+        state.queue.push_back(ticket);
+
+        loop {
+            if state.permits > 0 && state.queue.front() == Some(&ticket) {
+                state.permits -= 1;
+                state.queue.pop_front();
+                // The next sender may get a connection too
+                self.available.notify_all();
+                return true;
+            }
+
+            let now = Instant::now();
+            if now >= deadline {
+                state.queue.retain(|&t| t != ticket);
+                self.available.notify_all();
+                return false;
+            }
+            state = self
+                .available
+                .wait_timeout(state, deadline - now)
+                .unwrap()
+                .0;
+        }
+    }
+
+    fn release(&self) {
+        self.state.lock().unwrap().permits += 1;
+        self.available.notify_all();
+    }
+}
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        if let Some(checkout) = &self.pool.checkout {
+            checkout.release();
+        }
+    }
+}
+
 impl ParkedConnection {
     fn park(conn: SmtpConnection) -> Self {
+        let now = Instant::now();
         Self {
             conn,
-            since: Instant::now(),
+            since: now,
+            last_keep_alive: now,
         }
     }
 
@@ -220,17 +428,18 @@ impl ParkedConnection {
         self.since.elapsed()
     }
 
-    fn unpark(self) -> SmtpConnection {
-        self.conn
+    fn needs_keep_alive(&self, interval: Duration) -> bool {
+        self.last_keep_alive.elapsed() >= interval
     }
-}
 
-impl PooledConnection {
-    fn wrap(conn: SmtpConnection, pool: Arc<Pool>) -> Self {
-        Self {
-            conn: Some(conn),
-            pool,
-        }
+    /// Send a `NOOP`, returning whether the connection is still usable
+    fn keep_alive(&mut self) -> bool {
+        self.last_keep_alive = Instant::now();
+        self.conn.test_connected()
+    }
+
+    fn unpark(self) -> SmtpConnection {
+        self.conn
     }
 }
 
@@ -257,3 +466,52 @@ impl Drop for PooledConnection {
         self.pool.recycle(conn);
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::{
+        sync::{Arc, Mutex},
+        thread,
+        time::Duration,
+    };
+
+    use super::Checkout;
+
+    #[test]
+    fn checkout_timeout() {
+        let checkout = Checkout::new(1, Duration::from_millis(50));
+
+        assert!(checkout.acquire());
+        assert!(!checkout.acquire());
+        checkout.release();
+        assert!(checkout.acquire());
+    }
+
+    #[test]
+    fn checkout_fifo() {
+        let checkout = Arc::new(Checkout::new(1, Duration::from_secs(5)));
+        let order = Arc::new(Mutex::new(Vec::new()));
+        assert!(checkout.acquire());
+
+        let waiters = (0..4)
+            .map(|i| {
+                let checkout = Arc::clone(&checkout);
+                let order = Arc::clone(&order);
+                let waiter = thread::spawn(move || {
+                    assert!(checkout.acquire());
+                    order.lock().unwrap().push(i);
+                    checkout.release();
+                });
+                // Make sure the waiters queue in order
+                thread::sleep(Duration::from_millis(20));
+                waiter
+            })
+            .collect::<Vec<_>>();
+
+        checkout.release();
+        for waiter in waiters {
+            waiter.join().unwrap();
+        }
+        assert_eq!(*order.lock().unwrap(), [0, 1, 2, 3]);
+    }
+}
@@ -3,20 +3,82 @@
 
 use std::{
     fmt::{Display, Formatter, Result},
-    result,
-    str::FromStr,
+    iter, result,
+    str::{self, FromStr},
 };
 
 use nom::{
     branch::alt,
     bytes::streaming::{tag, take_until},
-    combinator::{complete, map},
+    combinator::{complete, map, peek},
     multi::many0,
     sequence::{preceded, tuple},
     IResult,
 };
 
-use crate::transport::smtp::{error, Error};
+use crate::transport::{
+    smtp::{error, Error},
+    Receipt,
+};
+
+/// Limits on the size of server replies
+///
+/// They protect the client from servers sending endless replies, which
+/// would otherwise be buffered until memory runs out. Exceeding them fails
+/// with an error for which [`Error::is_reply_too_large`] is true.
+///
+/// The defaults, 4 KiB per line and 64 KiB per reply, are far more than
+/// any legitimate server sends.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub struct ReplyLimits {
+    max_line_length: usize,
+    max_size: usize,
+}
+
+impl Default for ReplyLimits {
+    fn default() -> Self {
+        Self {
+            max_line_length: 4 * 1024,
+            max_size: 64 * 1024,
+        }
+    }
+}
+
+impl ReplyLimits {
+    /// Creates the default limits
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the maximum length of a reply line, in bytes, including the code
+    /// and the line ending
+    pub fn max_line_length(mut self, max_line_length: usize) -> Self {
+        self.max_line_length = max_line_length;
+        self
+    }
+
+    /// Set the maximum size of a whole reply, in bytes, like a multiline `EHLO` reply
+    pub fn max_size(mut self, max_size: usize) -> Self {
+        self.max_size = max_size;
+        self
+    }
+
+    /// Checks the size of a reply being read, `line` being the length of its last line
+    pub(crate) fn check(&self, line: usize, size: usize) -> result::Result<(), Error> {
+        if line > self.max_line_length || size > self.max_size {
+            Err(error::reply_too_large())
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Maximum number of bytes to read for the next line of a reply of `size` bytes,
+    /// one more than allowed to detect longer lines
+    #[cfg(any(feature = "tokio1", feature = "async-std1"))]
+    pub(crate) fn next_read(&self, size: usize) -> usize {
+        self.max_line_length.min(self.max_size.saturating_sub(size)) + 1
+    }
+}
 
 /// The first digit indicates severity
 #[derive(PartialEq, Eq, Copy, Clone, Debug)]
@@ -139,8 +201,13 @@ impl From<Code> for u16 {
 
 /// Contains an SMTP reply, with separated code and message
 ///
-/// The text message is optional, only the code is mandatory
-#[derive(PartialEq, Eq, Clone, Debug)]
+/// The text message is optional, only the code is mandatory.
+/// Lines of text are decoded as UTF-8, falling back to latin1 when the server
+/// sends invalid UTF-8, the bytes as received being kept in [`Response::raw_message`].
+///
+/// Responses are compared by their code and message, not by their
+/// [receipt](Response::receipt).
+#[derive(Clone, Debug)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Response {
     /// Response code
@@ -148,22 +215,66 @@ pub struct Response {
     /// Server response string (optional)
     /// Handle multiline responses
     message: Vec<String>,
+    /// Server response lines, before decoding
+    #[cfg_attr(feature = "serde", serde(default))]
+    raw_message: Vec<Vec<u8>>,
+    /// Information about the message accepted with this reply
+    #[cfg_attr(feature = "serde", serde(default))]
+    receipt: Option<Receipt>,
+}
+
+// The receipt is left out, its timestamp makes identical replies differ
+impl PartialEq for Response {
+    fn eq(&self, other: &Self) -> bool {
+        self.code == other.code
+            && self.message == other.message
+            && self.raw_message == other.raw_message
+    }
 }
 
+impl Eq for Response {}
+
 impl FromStr for Response {
     type Err = Error;
 
     fn from_str(s: &str) -> result::Result<Response, Error> {
-        parse_response(s)
-            .map(|(_, r)| r)
-            .map_err(|e| error::response(e.to_owned()))
+        Response::from_bytes(s.as_bytes())
     }
 }
 
 impl Response {
     /// Creates a new `Response`
     pub fn new(code: Code, message: Vec<String>) -> Response {
-        Response { code, message }
+        let raw_message = message
+            .iter()
+            .map(|line| line.as_bytes().to_vec())
+            .collect();
+        Response {
+            code,
+            message,
+            raw_message,
+            receipt: None,
+        }
+    }
+
+    /// Parses a whole reply, as received from the server
+    ///
+    /// Lines of text which aren't valid UTF-8 are decoded as latin1.
+    pub fn from_bytes(bytes: &[u8]) -> result::Result<Response, Error> {
+        parse_response(bytes)
+            .map(|(_, r)| r)
+            .map_err(|e| error::response(e.map(decode_error)))
+    }
+
+    /// Creates a `Response` from lines of text as received, decoding them
+    fn from_raw(code: Code, raw_message: Vec<Vec<u8>>) -> Response {
+        let message = raw_message.iter().map(|line| decode(line)).collect();
+        Response {
+            code,
+            message,
+            raw_message,
+            receipt: None,
+        }
     }
 
     /// Tells if the response is positive
@@ -197,11 +308,75 @@ impl Response {
     pub fn message(&self) -> impl Iterator<Item = &str> {
         self.message.iter().map(String::as_str)
     }
+
+    /// Returns the line of the message at `index`, starting from zero
+    pub fn line(&self, index: usize) -> Option<&str> {
+        self.message.get(index).map(String::as_str)
+    }
+
+    /// Returns the last line of the message, if any
+    pub fn last_line(&self) -> Option<&str> {
+        self.message.last().map(String::as_str)
+    }
+
+    /// Information about the sent message, when this is the final reply
+    /// to a message sent by the transport or [`SmtpConnection::send`](super::client::SmtpConnection::send)
+    pub fn receipt(&self) -> Option<&Receipt> {
+        self.receipt.as_ref()
+    }
+
+    /// Attaches the receipt of the raw `email` accepted with this reply
+    pub(crate) fn with_receipt(mut self, email: &[u8]) -> Self {
+        let id = self.last_line().map(ToOwned::to_owned);
+        self.receipt = Some(Receipt::new(email, id));
+        self
+    }
+
+    /// Attaches the receipt of a streamed message of `size` bytes,
+    /// from its `headers`
+    pub(crate) fn with_streamed_receipt(mut self, headers: &[u8], size: usize) -> Self {
+        let id = self.last_line().map(ToOwned::to_owned);
+        self.receipt = Some(Receipt::new(headers, id).with_size(size));
+        self
+    }
+
+    /// Server response lines as received, without the code and the line ending
+    ///
+    /// Useful when the server doesn't use UTF-8, as [`Response::message`]
+    /// decodes invalid UTF-8 lines as latin1.
+    pub fn raw_message(&self) -> impl Iterator<Item = &[u8]> {
+        self.raw_message.iter().map(Vec::as_slice)
+    }
+}
+
+impl From<Response> for Receipt {
+    fn from(response: Response) -> Self {
+        response
+            .receipt
+            .unwrap_or_else(|| Receipt::new(&[], response.message.last().cloned()))
+    }
+}
+
+/// Decodes a line of reply text as UTF-8, falling back to latin1
+fn decode(raw: &[u8]) -> String {
+    match str::from_utf8(raw) {
+        Ok(text) => text.to_owned(),
+        Err(_) => raw.iter().map(|&b| char::from(b)).collect(),
+    }
+}
+
+fn decode_error(e: nom::error::Error<&[u8]>) -> nom::error::Error<String> {
+    nom::error::Error::new(decode(e.input), e.code)
+}
+
+/// Turns an error from [`parse_response`] into an [`Error`]
+pub(crate) fn parse_error(e: nom::error::Error<&[u8]>) -> Error {
+    error::response(decode_error(e).to_string())
 }
 
 // Parsers (originally from tokio-smtp)
 
-fn parse_code(i: &str) -> IResult<&str, Code> {
+fn parse_code(i: &[u8]) -> IResult<&[u8], Code> {
     let (i, severity) = parse_severity(i)?;
     let (i, category) = parse_category(i)?;
     let (i, detail) = parse_detail(i)?;
@@ -215,7 +390,7 @@ fn parse_code(i: &str) -> IResult<&str, Code> {
     ))
 }
 
-fn parse_severity(i: &str) -> IResult<&str, Severity> {
+fn parse_severity(i: &[u8]) -> IResult<&[u8], Severity> {
     alt((
         map(tag("2"), |_| Severity::PositiveCompletion),
         map(tag("3"), |_| Severity::PositiveIntermediate),
@@ -224,7 +399,7 @@ fn parse_severity(i: &str) -> IResult<&str, Severity> {
     ))(i)
 }
 
-fn parse_category(i: &str) -> IResult<&str, Category> {
+fn parse_category(i: &[u8]) -> IResult<&[u8], Category> {
     alt((
         map(tag("0"), |_| Category::Syntax),
         map(tag("1"), |_| Category::Information),
@@ -235,7 +410,7 @@ fn parse_category(i: &str) -> IResult<&str, Category> {
     ))(i)
 }
 
-fn parse_detail(i: &str) -> IResult<&str, Detail> {
+fn parse_detail(i: &[u8]) -> IResult<&[u8], Detail> {
     alt((
         map(tag("0"), |_| Detail::Zero),
         map(tag("1"), |_| Detail::One),
@@ -250,35 +425,39 @@ fn parse_detail(i: &str) -> IResult<&str, Detail> {
     ))(i)
 }
 
-pub(crate) fn parse_response(i: &str) -> IResult<&str, Response> {
+pub(crate) fn parse_response(i: &[u8]) -> IResult<&[u8], Response> {
     let (i, lines) = many0(tuple((
         parse_code,
         preceded(tag("-"), take_until("\r\n")),
         tag("\r\n"),
     )))(i)?;
-    let (i, (last_code, last_line)) =
-        tuple((parse_code, preceded(tag(" "), take_until("\r\n"))))(i)?;
+    // The text of the last line is optional, `250\r\n` being a valid reply
+    let (i, (last_code, last_line)) = tuple((
+        parse_code,
+        alt((
+            preceded(tag(" "), take_until("\r\n")),
+            map(peek(tag("\r\n")), |_| &b""[..]),
+        )),
+    ))(i)?;
     let (i, _) = complete(tag("\r\n"))(i)?;
 
     // Check that all codes are equal.
     if !lines.iter().all(|&(code, _, _)| code == last_code) {
         return Err(nom::Err::Failure(nom::error::Error::new(
-            "",
+            &b""[..],
             nom::error::ErrorKind::Not,
         )));
     }
 
     // Extract text from lines, and append last line.
-    let mut lines: Vec<String> = lines.into_iter().map(|(_, text, _)| text.into()).collect();
-    lines.push(last_line.into());
-
-    Ok((
-        i,
-        Response {
-            code: last_code,
-            message: lines,
-        },
-    ))
+    let lines = lines
+        .into_iter()
+        .map(|(_, text, _)| text)
+        .chain(iter::once(last_line))
+        .map(<[u8]>::to_vec)
+        .collect();
+
+    Ok((i, Response::from_raw(last_code, lines)))
 }
 
 #[cfg(test)]
@@ -350,6 +529,13 @@ mod test {
                     "SIZE 42".to_owned(),
                     "AUTH PLAIN CRAM-MD5".to_owned(),
                 ],
+                raw_message: vec![
+                    b"me".to_vec(),
+                    b"8BITMIME".to_vec(),
+                    b"SIZE 42".to_vec(),
+                    b"AUTH PLAIN CRAM-MD5".to_vec(),
+                ],
+                receipt: None,
             }
         );
 
@@ -360,6 +546,46 @@ mod test {
         assert!(wrong_end.parse::<Response>().is_err());
     }
 
+    #[test]
+    fn test_response_from_bytes() {
+        let response = Response::from_bytes(b"250\r\n").unwrap();
+        assert!(response.has_code(250));
+        assert_eq!(response.message().collect::<Vec<_>>(), vec![""]);
+
+        let response = Response::from_bytes(b"250-\r\n250 OK\r\n").unwrap();
+        assert_eq!(response.message().collect::<Vec<_>>(), vec!["", "OK"]);
+
+        // Mismatched codes
+        assert!(Response::from_bytes(b"250-me\r\n251 OK\r\n").is_err());
+        // Missing separator
+        assert!(Response::from_bytes(b"250OK\r\n").is_err());
+        assert!(Response::from_bytes(b"250-me\r\n250OK\r\n").is_err());
+        // Incomplete
+        assert!(Response::from_bytes(b"250 OK").is_err());
+        assert!(Response::from_bytes(b"25").is_err());
+        assert!(Response::from_bytes(b"").is_err());
+        // Invalid codes
+        assert!(Response::from_bytes(b"650 OK\r\n").is_err());
+        assert!(Response::from_bytes(b"2\xff0 OK\r\n").is_err());
+    }
+
+    #[test]
+    fn test_response_decoding() {
+        let raw_response = b"550-Bo\xeete pleine\r\n550 Bo\xc3\xaete pleine\r\n";
+        let (_, response) = parse_response(raw_response).unwrap();
+        assert_eq!(
+            response.message().collect::<Vec<_>>(),
+            vec!["Boîte pleine", "Boîte pleine"]
+        );
+        assert_eq!(
+            response.raw_message().collect::<Vec<_>>(),
+            vec![&b"Bo\xeete pleine"[..], &b"Bo\xc3\xaete pleine"[..]]
+        );
+        assert_eq!(response.line(1), Some("Boîte pleine"));
+        assert_eq!(response.line(2), None);
+        assert_eq!(response.last_line(), Some("Boîte pleine"));
+    }
+
     #[test]
     fn test_response_is_positive() {
         assert!(Response::new(
@@ -487,7 +713,7 @@ mod test {
     #[test]
     fn test_response_incomplete() {
         let raw_response = "250-smtp.example.org\r\n";
-        let res = parse_response(raw_response);
+        let res = parse_response(raw_response.as_bytes());
         match res {
             Err(nom::Err::Incomplete(_)) => {}
             _ => panic!("Expected incomplete response, got {res:?}"),
@@ -573,4 +799,13 @@ mod test {
             Some("")
         );
     }
+
+    #[test]
+    fn test_response_eq_ignores_receipt() {
+        let response = Response::from_str("250 2.0.0 Ok: queued as 1234\r\n").unwrap();
+        let sent = response.clone().with_receipt(b"Subject: Hi\r\n\r\nHello");
+        assert!(sent.receipt().is_some());
+        assert_eq!(sent, response);
+        assert_ne!(sent, Response::from_str("250 Ok\r\n").unwrap());
+    }
 }
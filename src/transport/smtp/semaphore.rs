@@ -0,0 +1,234 @@
+//! Executor agnostic async semaphore
+
+use std::{
+    collections::VecDeque,
+    future::Future,
+    pin::Pin,
+    sync::Mutex,
+    task::{Context, Poll, Waker},
+};
+
+/// Limits the number of concurrently held [`Permit`]s
+///
+/// Waiters are served in arrival order, so that a large request can't
+/// be starved by smaller ones.
+#[derive(Debug)]
+pub(crate) struct Semaphore {
+    capacity: usize,
+    state: Mutex<State>,
+}
+
+#[derive(Debug)]
+struct State {
+    available: usize,
+    /// Waiters, in arrival order
+    queue: VecDeque<Waiter>,
+    next_ticket: u64,
+}
+
+#[derive(Debug)]
+struct Waiter {
+    ticket: u64,
+    permits: usize,
+    waker: Waker,
+}
+
+impl State {
+    /// Wake the waiters at the front of the queue which the available
+    /// permits can satisfy, in order
+    fn wake_ready(&self) {
+        let mut available = self.available;
+        for waiter in &self.queue {
+            if waiter.permits > available {
+                break;
+            }
+            available -= waiter.permits;
+            waiter.waker.wake_by_ref();
+        }
+    }
+}
+
+impl Semaphore {
+    pub(crate) fn new(permits: usize) -> Self {
+        Self {
+            capacity: permits,
+            state: Mutex::new(State {
+                available: permits,
+                queue: VecDeque::new(),
+                next_ticket: 0,
+            }),
+        }
+    }
+
+    /// Wait for a permit to become available
+    pub(crate) fn acquire(&self) -> Acquire<'_> {
+        self.acquire_many(1)
+    }
+
+    /// Wait for `permits` permits to become available at once
+    ///
+    /// Asking for more permits than the semaphore has waits for all of them
+    /// instead, so that it doesn't wait forever.
+    pub(crate) fn acquire_many(&self, permits: usize) -> Acquire<'_> {
+        Acquire {
+            semaphore: self,
+            permits: permits.min(self.capacity),
+            ticket: None,
+        }
+    }
+}
+
+/// Future returned by [`Semaphore::acquire_many`]
+#[derive(Debug)]
+pub(crate) struct Acquire<'a> {
+    semaphore: &'a Semaphore,
+    permits: usize,
+    /// Position in the queue, once waiting
+    ticket: Option<u64>,
+}
+
+impl<'a> Future for Acquire<'a> {
+    type Output = Permit<'a>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let semaphore = self.semaphore;
+        let permits = self.permits;
+        let mut state = semaphore.state.lock().unwrap();
+
+        match self.ticket {
+            None if state.queue.is_empty() && state.available >= permits => {}
+            None => {
+                let ticket = state.next_ticket;
+                state.next_ticket += 1;
+                state.queue.push_back(Waiter {
+                    ticket,
+                    permits,
+                    waker: cx.waker().clone(),
+                });
+                self.ticket = Some(ticket);
+                return Poll::Pending;
+            }
+            Some(ticket) => {
+                let first = state.queue.front().map(|waiter| waiter.ticket);
+                if first != Some(ticket) || state.available < permits {
+                    if let Some(waiter) = state.queue.iter_mut().find(|w| w.ticket == ticket) {
+                        if !waiter.waker.will_wake(cx.waker()) {
+                            waiter.waker = cx.waker().clone();
+                        }
+                    }
+                    return Poll::Pending;
+                }
+
+                state.queue.pop_front();
+                self.ticket = None;
+            }
+        }
+
+        state.available -= permits;
+        // The next waiters may fit in the remaining permits
+        state.wake_ready();
+        Poll::Ready(Permit { semaphore, permits })
+    }
+}
+
+impl Drop for Acquire<'_> {
+    fn drop(&mut self) {
+        if let Some(ticket) = self.ticket {
+            // Cancelled while waiting, let the next waiters through
+            let mut state = self.semaphore.state.lock().unwrap();
+            state.queue.retain(|waiter| waiter.ticket != ticket);
+            state.wake_ready();
+        }
+    }
+}
+
+/// Gives back its permits to the [`Semaphore`] when dropped
+#[derive(Debug)]
+pub(crate) struct Permit<'a> {
+    semaphore: &'a Semaphore,
+    permits: usize,
+}
+
+impl Drop for Permit<'_> {
+    fn drop(&mut self) {
+        let mut state = self.semaphore.state.lock().unwrap();
+        state.available += self.permits;
+        state.wake_ready();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::task::{Context, Poll};
+
+    use futures_util::{task::noop_waker_ref, FutureExt};
+
+    use super::Semaphore;
+
+    #[test]
+    fn limits_permits() {
+        let semaphore = Semaphore::new(2);
+
+        let first = semaphore.acquire().now_or_never().unwrap();
+        let second = semaphore.acquire().now_or_never().unwrap();
+        assert!(semaphore.acquire().now_or_never().is_none());
+
+        drop(first);
+        let third = semaphore.acquire().now_or_never();
+        assert!(third.is_some());
+        assert!(semaphore.acquire().now_or_never().is_none());
+
+        drop(second);
+        drop(third);
+        assert!(semaphore.acquire().now_or_never().is_some());
+    }
+
+    #[test]
+    fn limits_weighted_permits() {
+        let semaphore = Semaphore::new(10);
+
+        let first = semaphore.acquire_many(6).now_or_never().unwrap();
+        assert!(semaphore.acquire_many(5).now_or_never().is_none());
+        let second = semaphore.acquire_many(4).now_or_never().unwrap();
+
+        drop(first);
+        // More than the capacity waits for all the permits
+        assert!(semaphore.acquire_many(20).now_or_never().is_none());
+        drop(second);
+        assert!(semaphore.acquire_many(20).now_or_never().is_some());
+    }
+
+    #[test]
+    fn serves_waiters_in_order() {
+        let semaphore = Semaphore::new(10);
+        let mut cx = Context::from_waker(noop_waker_ref());
+
+        let mut small = (0..10)
+            .map(|_| semaphore.acquire().now_or_never().unwrap())
+            .collect::<Vec<_>>();
+        let mut large = semaphore.acquire_many(5);
+        assert!(large.poll_unpin(&mut cx).is_pending());
+
+        // Small requests keep arriving as permits are released, but wait
+        // behind the large one
+        let mut waiting = Vec::new();
+        for _ in 0..4 {
+            drop(small.pop());
+            let mut next = semaphore.acquire();
+            assert!(next.poll_unpin(&mut cx).is_pending());
+            waiting.push(next);
+            assert!(large.poll_unpin(&mut cx).is_pending());
+        }
+
+        drop(small.pop());
+        let Poll::Ready(large) = large.poll_unpin(&mut cx) else {
+            panic!("the large request should get the released permits");
+        };
+        assert!(waiting[0].poll_unpin(&mut cx).is_pending());
+
+        drop(large);
+        for next in &mut waiting {
+            assert!(next.poll_unpin(&mut cx).is_ready());
+        }
+    }
+}
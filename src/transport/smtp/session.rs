@@ -0,0 +1,275 @@
+//! Sessions giving manual control over SMTP transactions
+
+#[cfg(all(not(feature = "pool"), any(feature = "tokio1", feature = "async-std1")))]
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
+
+#[cfg(any(feature = "tokio1", feature = "async-std1"))]
+use super::client::AsyncSmtpConnection;
+#[cfg(all(feature = "pool", any(feature = "tokio1", feature = "async-std1")))]
+use super::pool::async_impl::PooledConnection as AsyncPooledConnection;
+#[cfg(feature = "pool")]
+use super::pool::sync_impl::PooledConnection;
+use super::{
+    client::SmtpConnection,
+    commands::{Data, Mail, Rcpt, Rset},
+    extension::{MailParameter, RcptParameter},
+    Error, Response,
+};
+use crate::Address;
+#[cfg(any(feature = "tokio1", feature = "async-std1"))]
+use crate::Executor;
+
+#[cfg(feature = "pool")]
+type Connection = PooledConnection;
+#[cfg(not(feature = "pool"))]
+type Connection = SmtpConnection;
+
+/// An SMTP session checked out from a [`SmtpTransport`](super::SmtpTransport)
+///
+/// Allows running several transactions on the same connection, resetting them
+/// with `RSET` or sending commands which aren't modeled by
+/// [`Transport::send`](crate::Transport::send), through the underlying
+/// [`SmtpConnection`] it dereferences to.
+///
+/// When the session is released or dropped, any pending transaction is reset
+/// and the connection goes back to the pool. Without the `pool` feature the
+/// connection is closed instead.
+///
+/// ```rust,no_run
+/// # use std::error::Error;
+/// use lettre::SmtpTransport;
+///
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// let mailer = SmtpTransport::relay("smtp.example.com")?.build();
+///
+/// let mut session = mailer.session()?;
+/// for (to, email) in [("alice@example.com", "..."), ("bob@example.com", "...")] {
+///     session.mail(Some("newsletter@example.com".parse()?), vec![])?;
+///     session.rcpt(to.parse()?, vec![])?;
+///     session.data(email.as_bytes())?;
+/// }
+/// session.release()?;
+/// # Ok(())
+/// # }
+/// ```
+#[cfg_attr(docsrs, doc(cfg(feature = "smtp-transport")))]
+pub struct Session {
+    conn: Connection,
+    released: bool,
+}
+
+impl Session {
+    pub(super) fn new(conn: Connection) -> Self {
+        Self {
+            conn,
+            released: false,
+        }
+    }
+
+    /// Start a transaction with `MAIL FROM`
+    pub fn mail(
+        &mut self,
+        from: Option<Address>,
+        parameters: Vec<MailParameter>,
+    ) -> Result<Response, Error> {
+        self.conn.command(Mail::new(from, parameters))
+    }
+
+    /// Add a recipient to the current transaction with `RCPT TO`
+    pub fn rcpt(&mut self, to: Address, parameters: Vec<RcptParameter>) -> Result<Response, Error> {
+        self.conn.command(Rcpt::new(to, parameters))
+    }
+
+    /// Send the message content with `DATA`, completing the current transaction
+    pub fn data(&mut self, email: &[u8]) -> Result<Response, Error> {
+        self.conn.command(Data)?;
+        self.conn.message(email)
+    }
+
+    /// Abort the current transaction with `RSET`
+    pub fn rset(&mut self) -> Result<Response, Error> {
+        self.conn.command(Rset)
+    }
+
+    /// Release the connection, reporting errors which would be ignored on drop
+    pub fn release(mut self) -> Result<(), Error> {
+        self.released = true;
+        self.close()
+    }
+
+    fn close(&mut self) -> Result<(), Error> {
+        #[cfg(feature = "pool")]
+        let result = self.conn.command(Rset);
+        #[cfg(not(feature = "pool"))]
+        let result = self.conn.quit();
+
+        match result {
+            Ok(_) => Ok(()),
+            Err(err) => {
+                // Make sure the pool doesn't reuse the connection
+                self.conn.abort();
+                Err(err)
+            }
+        }
+    }
+}
+
+impl Deref for Session {
+    type Target = SmtpConnection;
+
+    fn deref(&self) -> &Self::Target {
+        &self.conn
+    }
+}
+
+impl DerefMut for Session {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.conn
+    }
+}
+
+impl Drop for Session {
+    fn drop(&mut self) {
+        if !self.released {
+            let _ = self.close();
+        }
+    }
+}
+
+#[cfg(all(feature = "pool", any(feature = "tokio1", feature = "async-std1")))]
+type AsyncConnection<E> = AsyncPooledConnection<E>;
+#[cfg(all(not(feature = "pool"), any(feature = "tokio1", feature = "async-std1")))]
+type AsyncConnection<E> = Unpooled<E>;
+
+/// A connection owned by a session when connections aren't pooled
+#[cfg(all(not(feature = "pool"), any(feature = "tokio1", feature = "async-std1")))]
+struct Unpooled<E> {
+    conn: AsyncSmtpConnection,
+    marker_: PhantomData<E>,
+}
+
+#[cfg(all(not(feature = "pool"), any(feature = "tokio1", feature = "async-std1")))]
+impl<E> Deref for Unpooled<E> {
+    type Target = AsyncSmtpConnection;
+
+    fn deref(&self) -> &Self::Target {
+        &self.conn
+    }
+}
+
+#[cfg(all(not(feature = "pool"), any(feature = "tokio1", feature = "async-std1")))]
+impl<E> DerefMut for Unpooled<E> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.conn
+    }
+}
+
+/// An SMTP session checked out from an [`AsyncSmtpTransport`](super::AsyncSmtpTransport)
+///
+/// The async counterpart of [`Session`]. When dropped without being released,
+/// resetting the transaction happens in a background task.
+#[cfg(any(feature = "tokio1", feature = "async-std1"))]
+#[cfg_attr(docsrs, doc(cfg(any(feature = "tokio1", feature = "async-std1"))))]
+pub struct AsyncSession<E: Executor> {
+    conn: Option<AsyncConnection<E>>,
+}
+
+#[cfg(any(feature = "tokio1", feature = "async-std1"))]
+impl<E: Executor> AsyncSession<E> {
+    #[cfg(feature = "pool")]
+    pub(super) fn new(conn: AsyncPooledConnection<E>) -> Self {
+        Self { conn: Some(conn) }
+    }
+
+    #[cfg(not(feature = "pool"))]
+    pub(super) fn new(conn: AsyncSmtpConnection) -> Self {
+        Self {
+            conn: Some(Unpooled {
+                conn,
+                marker_: PhantomData,
+            }),
+        }
+    }
+
+    /// Start a transaction with `MAIL FROM`
+    pub async fn mail(
+        &mut self,
+        from: Option<Address>,
+        parameters: Vec<MailParameter>,
+    ) -> Result<Response, Error> {
+        self.command(Mail::new(from, parameters)).await
+    }
+
+    /// Add a recipient to the current transaction with `RCPT TO`
+    pub async fn rcpt(
+        &mut self,
+        to: Address,
+        parameters: Vec<RcptParameter>,
+    ) -> Result<Response, Error> {
+        self.command(Rcpt::new(to, parameters)).await
+    }
+
+    /// Send the message content with `DATA`, completing the current transaction
+    pub async fn data(&mut self, email: &[u8]) -> Result<Response, Error> {
+        self.command(Data).await?;
+        self.message(email).await
+    }
+
+    /// Abort the current transaction with `RSET`
+    pub async fn rset(&mut self) -> Result<Response, Error> {
+        self.command(Rset).await
+    }
+
+    /// Release the connection, reporting errors which would be ignored on drop
+    pub async fn release(mut self) -> Result<(), Error> {
+        let conn = self.conn.take().expect("conn hasn't been released yet");
+        close(conn).await
+    }
+}
+
+#[cfg(any(feature = "tokio1", feature = "async-std1"))]
+async fn close<E: Executor>(mut conn: AsyncConnection<E>) -> Result<(), Error> {
+    #[cfg(feature = "pool")]
+    let result = conn.command(Rset).await;
+    #[cfg(not(feature = "pool"))]
+    let result = conn.quit().await;
+
+    match result {
+        Ok(_) => Ok(()),
+        Err(err) => {
+            // Make sure the pool doesn't reuse the connection
+            conn.abort().await;
+            Err(err)
+        }
+    }
+}
+
+#[cfg(any(feature = "tokio1", feature = "async-std1"))]
+impl<E: Executor> Deref for AsyncSession<E> {
+    type Target = AsyncSmtpConnection;
+
+    fn deref(&self) -> &Self::Target {
+        self.conn.as_deref().expect("conn hasn't been released yet")
+    }
+}
+
+#[cfg(any(feature = "tokio1", feature = "async-std1"))]
+impl<E: Executor> DerefMut for AsyncSession<E> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.conn
+            .as_deref_mut()
+            .expect("conn hasn't been released yet")
+    }
+}
+
+#[cfg(any(feature = "tokio1", feature = "async-std1"))]
+impl<E: Executor> Drop for AsyncSession<E> {
+    fn drop(&mut self) {
+        if let Some(conn) = self.conn.take() {
+            E::spawn(async move {
+                let _ = close(conn).await;
+            });
+        }
+    }
+}
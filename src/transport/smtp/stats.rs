@@ -0,0 +1,98 @@
+//! Statistics about sent messages
+
+use std::{fmt::Debug, time::Duration};
+
+use super::response::Response;
+use crate::address::Envelope;
+
+/// Size and timing of a message sent over an SMTP connection
+///
+/// Getting a connection, which may involve connecting to the server
+/// or checking one out of the pool, isn't included.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SendStats {
+    pub(super) message_size: usize,
+    pub(super) bytes_written: usize,
+    pub(super) envelope: Duration,
+    pub(super) data: Duration,
+}
+
+impl SendStats {
+    /// Size of the message given to the transport, `0` when sent with `BURL`
+    pub fn message_size(&self) -> usize {
+        self.message_size
+    }
+
+    /// Number of bytes written to the server, including commands
+    /// and the dot-stuffing of the message content
+    pub fn bytes_written(&self) -> usize {
+        self.bytes_written
+    }
+
+    /// Time taken by the `MAIL FROM` and `RCPT TO` commands
+    pub fn envelope_duration(&self) -> Duration {
+        self.envelope
+    }
+
+    /// Time taken to transfer the message content and get the server
+    /// acceptance, with `DATA` or `BURL`
+    pub fn data_duration(&self) -> Duration {
+        self.data
+    }
+
+    /// Time taken by the whole transaction
+    pub fn duration(&self) -> Duration {
+        self.envelope + self.data
+    }
+}
+
+/// Hook called for each message accepted by the server
+///
+/// Registered on the transport builders with `send_observer`, for example
+/// for throughput accounting.
+///
+/// ```rust
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+///
+/// use lettre::{
+///     address::Envelope,
+///     transport::smtp::{
+///         response::Response,
+///         stats::{SendObserver, SendStats},
+///     },
+/// };
+///
+/// #[derive(Debug, Default)]
+/// struct Throughput {
+///     bytes: AtomicUsize,
+/// }
+///
+/// impl SendObserver for Throughput {
+///     fn sent(&self, _: &Envelope, _: &Response, stats: &SendStats) {
+///         self.bytes
+///             .fetch_add(stats.bytes_written(), Ordering::Relaxed);
+///     }
+/// }
+/// ```
+pub trait SendObserver: Debug + Send + Sync {
+    /// Called once the server accepted the message
+    fn sent(&self, envelope: &Envelope, response: &Response, stats: &SendStats);
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use super::SendStats;
+
+    #[test]
+    fn send_stats_duration() {
+        let stats = SendStats {
+            message_size: 10,
+            bytes_written: 64,
+            envelope: Duration::from_millis(20),
+            data: Duration::from_millis(30),
+        };
+        assert_eq!(stats.duration(), Duration::from_millis(50));
+    }
+}
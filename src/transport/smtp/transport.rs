@@ -1,12 +1,30 @@
-#[cfg(feature = "pool")]
-use std::sync::Arc;
-use std::{fmt::Debug, time::Duration};
+use std::{
+    fmt::{self, Debug, Display},
+    io::{BufReader, Read},
+    net::SocketAddr,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
+#[cfg(feature = "oauth2-helpers")]
+use super::oauth2::{OAuth2Credentials, TokenProvider};
 #[cfg(feature = "pool")]
 use super::pool::sync_impl::Pool;
+use super::{
+    commands::Noop,
+    diagnosis::Diagnosis,
+    dns_cache::{self, DnsCache},
+    error,
+    extension::{CustomExtension, ServerInfo},
+    health::HealthCheck,
+    response::ReplyLimits,
+    stats::SendObserver,
+    util::read_header_section,
+    ClientId, Credentials, DryRun, Error, HelloNameFn, Mechanism, Response, Session,
+    SmtpConnection, SmtpInfo,
+};
 #[cfg(feature = "pool")]
-use super::PoolConfig;
-use super::{ClientId, Credentials, Error, Mechanism, Response, SmtpConnection, SmtpInfo};
+use super::{CircuitState, PoolConfig};
 #[cfg(any(feature = "native-tls", feature = "rustls-tls", feature = "boring-tls"))]
 use super::{Tls, TlsParameters, SUBMISSIONS_PORT, SUBMISSION_PORT};
 use crate::{address::Envelope, Transport};
@@ -26,10 +44,18 @@ impl Transport for SmtpTransport {
     type Error = Error;
 
     /// Sends an email
+    ///
+    /// If the server greylisted the message, see [`Error::is_greylisted`], the
+    /// error is returned without waiting, and [`Error::retry_after`] gives the
+    /// delay the server asked for, if any.
     fn send_raw(&self, envelope: &Envelope, email: &[u8]) -> Result<Self::Ok, Self::Error> {
+        if self.client().info.dry_run {
+            return self.dry_run(envelope)?.into_result();
+        }
+
         let mut conn = self.inner.connection()?;
 
-        let result = conn.send(envelope, email)?;
+        let result = conn.send(envelope, &self.client().info.stamp_user_agent(email))?;
 
         #[cfg(not(feature = "pool"))]
         conn.abort();
@@ -46,6 +72,42 @@ impl Debug for SmtpTransport {
     }
 }
 
+/// Formats the configuration of the transport for logging
+///
+/// The secrets are left out, and only the start of the username is kept.
+///
+/// ```rust
+/// # #[cfg(feature = "native-tls")]
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// use lettre::{transport::smtp::authentication::Credentials, SmtpTransport};
+///
+/// let mailer = SmtpTransport::relay("smtp.example.com")?
+///     .credentials(Credentials::new(
+///         "user@example.com".to_owned(),
+///         "secret".to_owned(),
+///     ))
+///     .build();
+/// // smtps://smtp.example.com:465, pool max=10, auth=PLAIN/LOGIN as user@…, tls=wrapper(native-tls)
+/// // with native-tls, or tls=wrapper(rustls) if rustls-tls is enabled too
+/// let summary = mailer.to_string();
+/// assert!(summary.contains("auth=PLAIN/LOGIN as user@…"));
+/// assert!(!summary.contains("secret"));
+/// # Ok(())
+/// # }
+/// # #[cfg(not(feature = "native-tls"))]
+/// # fn main() {}
+/// ```
+impl Display for SmtpTransport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        #[cfg(feature = "pool")]
+        let pool_max_size = Some(self.inner.max_size());
+        #[cfg(not(feature = "pool"))]
+        let pool_max_size = None;
+
+        self.client().info.fmt_summary(f, pool_max_size)
+    }
+}
+
 impl SmtpTransport {
     /// Simple and secure transport, using TLS connections to communicate with the SMTP server
     ///
@@ -90,6 +152,58 @@ impl SmtpTransport {
             .tls(Tls::Required(tls_parameters)))
     }
 
+    /// Transport for Gmail, using SMTP over TLS to `smtp.gmail.com`
+    ///
+    /// Authenticates with `PLAIN`, which requires an [app password] when
+    /// two-step verification is enabled. To authenticate with an OAuth 2.0
+    /// access token instead, set the mechanism to [`Mechanism::Xoauth2`].
+    ///
+    /// [app password]: https://support.google.com/accounts/answer/185833
+    #[cfg(any(feature = "native-tls", feature = "rustls-tls", feature = "boring-tls"))]
+    #[cfg_attr(
+        docsrs,
+        doc(cfg(any(feature = "native-tls", feature = "rustls-tls", feature = "boring-tls")))
+    )]
+    pub fn gmail_relay() -> Result<SmtpTransportBuilder, Error> {
+        Ok(Self::relay("smtp.gmail.com")?.authentication(vec![Mechanism::Plain]))
+    }
+
+    /// Transport for Microsoft 365, using STARTTLS to `smtp.office365.com`
+    ///
+    /// The server only accepts `STARTTLS` on the submission port, and doesn't
+    /// support `PLAIN`, so `LOGIN` is used. SMTP AUTH must be enabled for the
+    /// mailbox. To authenticate with an OAuth 2.0 access token instead,
+    /// set the mechanism to [`Mechanism::Xoauth2`].
+    #[cfg(any(feature = "native-tls", feature = "rustls-tls", feature = "boring-tls"))]
+    #[cfg_attr(
+        docsrs,
+        doc(cfg(any(feature = "native-tls", feature = "rustls-tls", feature = "boring-tls")))
+    )]
+    pub fn office365_relay() -> Result<SmtpTransportBuilder, Error> {
+        Ok(Self::starttls_relay("smtp.office365.com")?.authentication(vec![Mechanism::Login]))
+    }
+
+    /// Transport for Amazon SES in `region`, like `us-east-1`, using SMTP over TLS
+    ///
+    /// The credentials are the SES SMTP credentials, which are different
+    /// from the AWS access keys.
+    #[cfg(any(feature = "native-tls", feature = "rustls-tls", feature = "boring-tls"))]
+    #[cfg_attr(
+        docsrs,
+        doc(cfg(any(feature = "native-tls", feature = "rustls-tls", feature = "boring-tls")))
+    )]
+    pub fn ses_relay(region: &str) -> Result<SmtpTransportBuilder, Error> {
+        if region.is_empty()
+            || !region
+                .bytes()
+                .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-')
+        {
+            return Err(error::connection(format!("invalid SES region '{region}'")));
+        }
+
+        Self::relay(&format!("email-smtp.{region}.amazonaws.com"))
+    }
+
     /// Creates a new local SMTP client to port 25
     ///
     /// Shortcut for local unencrypted relay (typical local email daemon that will handle relaying)
@@ -199,6 +313,24 @@ impl SmtpTransport {
         super::connection_url::from_connection_url(connection_url)
     }
 
+    /// Creates a new transport builder from the environment
+    ///
+    /// Makes configuring containerized deployments easy. The connection URL
+    /// is read from the `LETTRE_SMTP_URL` environment variable, in the format
+    /// described in [`from_url`](Self::from_url). If `LETTRE_CA_BUNDLE` is set,
+    /// the PEM encoded certificates in the file it points to are trusted
+    /// in addition to the root certificates of the TLS backend.
+    ///
+    /// Fails if `LETTRE_SMTP_URL` isn't set or if the CA bundle can't be read.
+    #[cfg(any(feature = "native-tls", feature = "rustls-tls", feature = "boring-tls"))]
+    #[cfg_attr(
+        docsrs,
+        doc(cfg(any(feature = "native-tls", feature = "rustls-tls", feature = "boring-tls")))
+    )]
+    pub fn from_env() -> Result<SmtpTransportBuilder, Error> {
+        super::connection_url::from_env()
+    }
+
     /// Tests the SMTP connection
     ///
     /// `test_connection()` tests the connection by using the SMTP NOOP command.
@@ -213,10 +345,172 @@ impl SmtpTransport {
 
         Ok(is_connected)
     }
+
+    /// Checks that the server can be reached, for a health endpoint
+    ///
+    /// Unlike [`Self::test_connection`], the check doesn't fail, and the
+    /// [`HealthCheck`] gives the latency and the error, if any.
+    pub fn health_check(&self) -> HealthCheck {
+        let start = Instant::now();
+        let result = self.noop();
+        HealthCheck::new(
+            start.elapsed(),
+            result,
+            #[cfg(feature = "pool")]
+            self.circuit_state(),
+        )
+    }
+
+    fn noop(&self) -> Result<(), Error> {
+        let mut conn = self.inner.connection()?;
+
+        conn.command(Noop)?;
+
+        #[cfg(not(feature = "pool"))]
+        conn.quit()?;
+
+        Ok(())
+    }
+
+    /// Gets the capabilities announced by the server in its `EHLO` response
+    ///
+    /// Useful for adapting to the relay or for diagnostics, for example to check
+    /// that it supports `STARTTLS` or `SMTPUTF8`. The connection is closed afterward
+    /// if a connection pool is not used.
+    pub fn capabilities(&self) -> Result<ServerInfo, Error> {
+        #[cfg_attr(feature = "pool", allow(unused_mut))]
+        let mut conn = self.inner.connection()?;
+
+        let server_info = conn.server_info().clone();
+
+        #[cfg(not(feature = "pool"))]
+        conn.quit()?;
+
+        Ok(server_info)
+    }
+
+    /// Gets the state of the circuit breaker of the connection pool
+    ///
+    /// See [`PoolConfig::circuit_breaker`]
+    #[cfg(feature = "pool")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "pool")))]
+    pub fn circuit_state(&self) -> CircuitState {
+        self.inner.circuit_state()
+    }
+
+    /// Forgets the cached addresses of the server, resolving its hostname
+    /// again for the next connection
+    ///
+    /// See [`SmtpTransportBuilder::dns_cache_ttl`].
+    pub fn flush_dns_cache(&self) {
+        if let Some(cache) = &self.client().info.dns_cache {
+            cache.flush();
+        }
+    }
+
+    /// Sends a message stored on an IMAP server, referenced by an URLAUTH `url`
+    ///
+    /// The message content isn't uploaded again, the server fetches it using
+    /// the `BURL` extension. See [`SmtpConnection::send_burl`](super::client::SmtpConnection::send_burl).
+    pub fn send_burl(&self, envelope: &Envelope, url: &str) -> Result<Response, Error> {
+        let mut conn = self.inner.connection()?;
+
+        let result = conn.send_burl(envelope, url)?;
+
+        #[cfg(not(feature = "pool"))]
+        conn.abort();
+
+        Ok(result)
+    }
+
+    /// Sends the message read from `reader`, streaming it to the server
+    ///
+    /// Unlike [`Transport::send_raw`], the message isn't loaded in memory,
+    /// only its header section, to add the configured `User-Agent`.
+    /// See [`SmtpConnection::send_from_reader`].
+    pub fn send_raw_from_reader<R: Read>(
+        &self,
+        envelope: &Envelope,
+        reader: R,
+    ) -> Result<Response, Error> {
+        let mut reader = BufReader::new(reader);
+        let headers = read_header_section(&mut reader).map_err(error::client)?;
+        let headers = self.client().info.stamp_user_agent(&headers);
+
+        let mut conn = self.inner.connection()?;
+
+        let result = conn.send_from_reader(envelope, headers.as_ref().chain(reader))?;
+
+        #[cfg(not(feature = "pool"))]
+        conn.abort();
+
+        Ok(result)
+    }
+
+    /// Checks which recipients of `envelope` the server accepts, without
+    /// sending a message
+    ///
+    /// See [`SmtpConnection::dry_run`].
+    pub fn dry_run(&self, envelope: &Envelope) -> Result<DryRun, Error> {
+        let mut conn = self.inner.connection()?;
+
+        let dry_run = conn.dry_run(envelope)?;
+
+        #[cfg(not(feature = "pool"))]
+        conn.abort();
+
+        Ok(dry_run)
+    }
+
+    /// Checks out a connection for running transactions manually
+    ///
+    /// See [`Session`]
+    pub fn session(&self) -> Result<Session, Error> {
+        Ok(Session::new(self.inner.connection()?))
+    }
+
+    /// Connects to the server and reports on each step
+    ///
+    /// Unlike [`Self::test_connection`], a new connection is always opened and
+    /// the result gives the resolved addresses, connection time, encryption,
+    /// server certificate and capabilities, and the authentication outcome,
+    /// for display in setup wizards or health endpoints.
+    pub fn diagnose(&self) -> Diagnosis {
+        self.client().diagnose()
+    }
+
+    fn client(&self) -> &SmtpClient {
+        #[cfg(feature = "pool")]
+        let client = self.inner.client();
+        #[cfg(not(feature = "pool"))]
+        let client = &self.inner;
+
+        client
+    }
 }
 
 /// Contains client configuration.
 /// Instances of this struct can be created using functions of [`SmtpTransport`].
+///
+/// Clone the builder to build variations of the same base configuration,
+/// like transports authenticating with the credentials of each tenant.
+///
+/// ```rust,no_run
+/// # #[cfg(feature = "native-tls")]
+/// # fn run() -> Result<(), Box<dyn std::error::Error>> {
+/// use lettre::{transport::smtp::authentication::Credentials, SmtpTransport};
+///
+/// let base = SmtpTransport::relay("smtp.example.com")?;
+/// let tenant_a = base
+///     .clone()
+///     .credentials(Credentials::new("a".to_owned(), "secret-a".to_owned()))
+///     .build();
+/// let tenant_b = base
+///     .credentials(Credentials::new("b".to_owned(), "secret-b".to_owned()))
+///     .build();
+/// # Ok(())
+/// # }
+/// ```
 #[derive(Debug, Clone)]
 pub struct SmtpTransportBuilder {
     info: SmtpInfo,
@@ -243,6 +537,43 @@ impl SmtpTransportBuilder {
     /// Set the name used during EHLO
     pub fn hello_name(mut self, name: ClientId) -> Self {
         self.info.hello_name = name;
+        self.info.hello_name_fn = None;
+        self
+    }
+
+    /// Register a hook adding parameters to the `MAIL FROM` and `RCPT TO` commands
+    ///
+    /// See [`CustomExtension`]
+    pub fn custom_extension<X>(mut self, extension: X) -> Self
+    where
+        X: CustomExtension + 'static,
+    {
+        self.info.custom_extensions.push(Arc::new(extension));
+        self
+    }
+
+    /// Register a hook called for each message accepted by the server,
+    /// with its size and the time taken to send it
+    ///
+    /// See [`SendObserver`]
+    pub fn send_observer<X>(mut self, observer: Arc<X>) -> Self
+    where
+        X: SendObserver + 'static,
+    {
+        self.info.send_observers.push(observer);
+        self
+    }
+
+    /// Set a function producing the name used during EHLO, called for each new connection
+    ///
+    /// Useful when connections may go out from different source addresses,
+    /// like behind a NAT with several public IPs. Replaces the name set via
+    /// [`Self::hello_name`].
+    pub fn hello_name_fn<F>(mut self, hello_name_fn: F) -> Self
+    where
+        F: Fn() -> ClientId + Send + Sync + 'static,
+    {
+        self.info.hello_name_fn = Some(HelloNameFn(Arc::new(hello_name_fn)));
         self
     }
 
@@ -252,6 +583,32 @@ impl SmtpTransportBuilder {
         self
     }
 
+    /// Authenticate with OAuth 2.0 tokens from `provider` using `XOAUTH2`
+    ///
+    /// A token is requested for each new connection, replacing the
+    /// [`Self::credentials`] and [`Self::authentication`] settings.
+    #[cfg(feature = "oauth2-helpers")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "oauth2-helpers")))]
+    pub fn oauth2_token_provider<P>(mut self, user: String, provider: P) -> Self
+    where
+        P: TokenProvider + 'static,
+    {
+        self.info.oauth2 = Some(OAuth2Credentials::new(user, Arc::new(provider)));
+        self.info.authentication = vec![Mechanism::Xoauth2];
+        self
+    }
+
+    /// Allow authenticating over unencrypted connections
+    ///
+    /// By default, sending fails instead of sending the credentials in
+    /// cleartext when the connection isn't encrypted, for example with
+    /// [`Tls::None`](super::client::Tls::None) or a TLS misconfiguration.
+    /// Only use it for test servers in a trusted network.
+    pub fn allow_plaintext_auth(mut self) -> Self {
+        self.info.plaintext_auth = true;
+        self
+    }
+
     /// Set the authentication mechanism to use
     pub fn authentication(mut self, mechanisms: Vec<Mechanism>) -> Self {
         self.info.authentication = mechanisms;
@@ -264,6 +621,86 @@ impl SmtpTransportBuilder {
         self
     }
 
+    /// Set the maximum time to wait for the whole reply to each command
+    ///
+    /// The [timeout](Self::timeout) applies to each network read, so a server
+    /// sending its reply a few bytes at a time can make a command last forever.
+    /// The deadline bounds the total time, failing with an error for which
+    /// [`Error::is_timeout`] is true. Disabled by default.
+    pub fn command_deadline(mut self, deadline: Option<Duration>) -> Self {
+        self.info.command_deadline = deadline;
+        self
+    }
+
+    /// Set the maximum time to set up a connection
+    ///
+    /// It covers connecting, the TLS handshake and authentication, which
+    /// otherwise can't be interrupted, failing with an error for which
+    /// [`Error::is_timeout`] is true. Disabled by default.
+    pub fn connect_deadline(mut self, deadline: Option<Duration>) -> Self {
+        self.info.connect_deadline = deadline;
+        self
+    }
+
+    /// Keep the addresses the server hostname resolved to for `ttl`
+    ///
+    /// By default the hostname is resolved again for each new connection,
+    /// including the ones opened by the pool to replace closed connections.
+    /// The cached addresses are forgotten when connecting to all of them
+    /// fails, and can be forgotten at any time with
+    /// [`SmtpTransport::flush_dns_cache`]. Disabled by default.
+    pub fn dns_cache_ttl(mut self, ttl: Option<Duration>) -> Self {
+        self.info.dns_cache = ttl.map(DnsCache::new);
+        self
+    }
+
+    /// Send `HELO` if the server rejects `EHLO` with a permanent error
+    ///
+    /// Allows talking to legacy appliances which don't support ESMTP. No
+    /// extension is available after `HELO`, including `STARTTLS` and `AUTH`.
+    /// Disabled by default.
+    pub fn helo_fallback(mut self, enabled: bool) -> Self {
+        self.info.helo_fallback = enabled;
+        self
+    }
+
+    /// Check the recipients of the messages instead of sending them
+    ///
+    /// Sending a message starts a transaction with `MAIL` and a `RCPT` for
+    /// each recipient, then cancels it with `RSET` instead of sending `DATA`.
+    /// It fails like a real send when a recipient is rejected, otherwise
+    /// the reply to `RSET` is returned. Use [`SmtpTransport::dry_run`] to get all the
+    /// rejected recipients. Disabled by default.
+    pub fn dry_run(mut self, enabled: bool) -> Self {
+        self.info.dry_run = enabled;
+        self
+    }
+
+    /// Add a `User-Agent` header to the messages which have neither a
+    /// `User-Agent` nor an `X-Mailer` header
+    ///
+    /// Helps identifying which application sent a message across a fleet of
+    /// services. Use [`DEFAULT_USER_AGENT`](super::DEFAULT_USER_AGENT) to
+    /// identify lettre and its version. Line breaks in `user_agent` are
+    /// replaced by spaces. Disabled by default.
+    ///
+    /// The header is added to the formatted message, after any DKIM signature
+    /// was computed, so it shouldn't be part of the signed headers.
+    pub fn user_agent(mut self, user_agent: Option<String>) -> Self {
+        self.info.user_agent = user_agent
+            .map(|user_agent| user_agent.split_whitespace().collect::<Vec<_>>().join(" "));
+        self
+    }
+
+    /// Set the limits on the size of server replies
+    ///
+    /// Replies read while setting up the connection, up to the `EHLO` reply,
+    /// are bound by the default limits.
+    pub fn reply_limits(mut self, limits: ReplyLimits) -> Self {
+        self.info.reply_limits = limits;
+        self
+    }
+
     /// Set the port to use
     ///
     /// # ⚠️⚠️⚠️ You probably don't need to call this method ⚠️⚠️⚠️
@@ -313,6 +750,33 @@ impl SmtpTransportBuilder {
         self
     }
 
+    /// Get the hostname or address of the server
+    pub fn get_host(&self) -> &str {
+        &self.info.server
+    }
+
+    /// Get the port to connect to
+    pub fn get_port(&self) -> u16 {
+        self.info.port
+    }
+
+    /// Get the TLS settings
+    #[cfg(any(feature = "native-tls", feature = "rustls-tls", feature = "boring-tls"))]
+    #[cfg_attr(
+        docsrs,
+        doc(cfg(any(feature = "native-tls", feature = "rustls-tls", feature = "boring-tls")))
+    )]
+    pub fn get_tls(&self) -> &Tls {
+        &self.info.tls
+    }
+
+    /// Get the configuration of the connection pool
+    #[cfg(feature = "pool")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "pool")))]
+    pub fn get_pool_config(&self) -> &PoolConfig {
+        &self.pool_config
+    }
+
     /// Build the transport
     ///
     /// If the `pool` feature is enabled, an `Arc` wrapped pool is created.
@@ -338,6 +802,33 @@ impl SmtpClient {
     ///
     /// Handles encryption and authentication
     pub fn connection(&self) -> Result<SmtpConnection, Error> {
+        let addrs = self.resolve()?;
+        let mut conn = self.open(&addrs, &mut 0)?;
+        self.starttls(&mut conn)?;
+        self.authenticate(&mut conn)?;
+        conn.set_custom_extensions(self.info.custom_extensions.clone());
+        conn.set_send_observers(self.info.send_observers.clone());
+        conn.set_deadline(None);
+        Ok(conn)
+    }
+
+    /// The addresses of the server, from the DNS cache if enabled
+    pub(super) fn resolve(&self) -> Result<Vec<SocketAddr>, Error> {
+        match &self.info.dns_cache {
+            Some(cache) => cache.resolve(&self.info.server, self.info.port),
+            None => dns_cache::resolve(&self.info.server, self.info.port),
+        }
+    }
+
+    /// Connects to the first of `addrs` accepting the connection, up to the
+    /// `EHLO` response, setting `attempts` to the number of addresses tried
+    ///
+    /// The connect deadline, if any, keeps applying to the returned connection.
+    pub(super) fn open(
+        &self,
+        addrs: &[SocketAddr],
+        attempts: &mut usize,
+    ) -> Result<SmtpConnection, Error> {
         #[allow(clippy::match_single_binding)]
         let tls_parameters = match &self.info.tls {
             #[cfg(any(feature = "native-tls", feature = "rustls-tls", feature = "boring-tls"))]
@@ -345,42 +836,142 @@ impl SmtpClient {
             _ => None,
         };
 
-        #[allow(unused_mut)]
-        let mut conn = SmtpConnection::connect::<(&str, u16)>(
-            (self.info.server.as_ref(), self.info.port),
+        let deadline = self
+            .info
+            .connect_deadline
+            .map(|deadline| Instant::now() + deadline);
+        let mut conn = SmtpConnection::connect_with_deadline(
+            addrs,
             self.info.timeout,
-            &self.info.hello_name,
+            &self.info.hello_name(),
             tls_parameters,
             None,
-        )?;
+            deadline,
+            self.info.helo_fallback,
+            attempts,
+        )
+        .map_err(|err| {
+            if let (Some(cache), true) = (&self.info.dns_cache, err.is_connection()) {
+                // None of the cached addresses may be valid anymore
+                cache.flush();
+            }
+            err
+        })?;
+        conn.set_command_deadline(self.info.command_deadline);
+        conn.set_reply_limits(self.info.reply_limits);
+        Ok(conn)
+    }
 
+    /// Upgrades `conn` with `STARTTLS` as configured, telling whether it was
+    pub(super) fn starttls(&self, conn: &mut SmtpConnection) -> Result<bool, Error> {
         #[cfg(any(feature = "native-tls", feature = "rustls-tls", feature = "boring-tls"))]
         match &self.info.tls {
-            Tls::Opportunistic(tls_parameters) => {
-                if conn.can_starttls() {
-                    conn.starttls(tls_parameters, &self.info.hello_name)?;
-                }
+            Tls::Opportunistic(tls_parameters) if conn.can_starttls() => {
+                conn.starttls(tls_parameters, &self.info.hello_name())?;
+                return Ok(true);
             }
             Tls::Required(tls_parameters) => {
-                conn.starttls(tls_parameters, &self.info.hello_name)?;
+                conn.starttls(tls_parameters, &self.info.hello_name())?;
+                return Ok(true);
             }
             _ => (),
         }
 
-        if let Some(credentials) = &self.info.credentials {
-            conn.auth(&self.info.authentication, credentials)?;
+        let _ = conn;
+        Ok(false)
+    }
+
+    /// Authenticates `conn` if credentials are configured, telling whether they are
+    pub(super) fn authenticate(&self, conn: &mut SmtpConnection) -> Result<bool, Error> {
+        match self.info.auth_credentials(conn.is_encrypted())? {
+            Some(credentials) => {
+                conn.auth(&self.info.authentication, &credentials)?;
+                Ok(true)
+            }
+            None => Ok(false),
         }
-        Ok(conn)
+    }
+
+    fn diagnose(&self) -> Diagnosis {
+        Diagnosis::run(self)
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::{
+        io::{BufRead, BufReader, Write},
+        net::{Ipv4Addr, TcpListener, TcpStream},
+        sync::{mpsc, Arc, Mutex},
+        thread,
+        time::Duration,
+    };
+
+    #[cfg(feature = "pool")]
+    use crate::transport::smtp::{
+        extension::ServerInfoChange, PoolConfig, PoolEvent, PoolObserver,
+    };
     use crate::{
-        transport::smtp::{authentication::Credentials, client::Tls},
-        SmtpTransport,
+        address::Envelope,
+        transport::smtp::{
+            authentication::{Credentials, Mechanism},
+            client::Tls,
+            extension::ClientId,
+            response::{ReplyLimits, Response},
+            stats::{SendObserver, SendStats},
+            DEFAULT_USER_AGENT,
+        },
+        SmtpTransport, Transport,
     };
 
+    /// Runs a server answering the greeting and `EHLO`, then calls `then` with the connection
+    fn fake_server(then: impl FnOnce(TcpStream) + Send + 'static) -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut line = String::new();
+            stream.write_all(b"220 fake\r\n").unwrap();
+            reader.read_line(&mut line).unwrap();
+            stream.write_all(b"250 fake\r\n").unwrap();
+            reader.read_line(&mut line).unwrap();
+            then(stream);
+        });
+        port
+    }
+
+    fn envelope() -> Envelope {
+        Envelope::new(
+            Some("user@localhost".parse().unwrap()),
+            vec!["root@localhost".parse().unwrap()],
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn provider_presets() {
+        let builder = SmtpTransport::gmail_relay().unwrap();
+        assert_eq!(builder.info.server, "smtp.gmail.com");
+        assert_eq!(builder.info.port, 465);
+        assert!(matches!(builder.info.tls, Tls::Wrapper(_)));
+        assert_eq!(builder.info.authentication, [Mechanism::Plain]);
+
+        let builder = SmtpTransport::office365_relay().unwrap();
+        assert_eq!(builder.info.server, "smtp.office365.com");
+        assert_eq!(builder.info.port, 587);
+        assert!(matches!(builder.info.tls, Tls::Required(_)));
+        assert_eq!(builder.info.authentication, [Mechanism::Login]);
+
+        let builder = SmtpTransport::ses_relay("eu-west-1").unwrap();
+        assert_eq!(builder.info.server, "email-smtp.eu-west-1.amazonaws.com");
+        assert_eq!(builder.info.port, 465);
+        assert!(matches!(builder.info.tls, Tls::Wrapper(_)));
+
+        assert!(SmtpTransport::ses_relay("eu-west-1.evil.com/").is_err());
+        assert!(SmtpTransport::ses_relay("").is_err());
+    }
+
     #[test]
     fn transport_from_url() {
         let builder = SmtpTransport::from_url("smtp://127.0.0.1:2525").unwrap();
@@ -447,4 +1038,758 @@ mod tests {
         assert_eq!(builder.info.credentials, None);
         assert!(matches!(builder.info.tls, Tls::Wrapper(_)));
     }
+
+    #[test]
+    fn transport_hello_name() {
+        let builder = SmtpTransport::from_url("smtp://127.0.0.1:2525/mail.example.com").unwrap();
+        assert_eq!(
+            builder.info.hello_name(),
+            ClientId::Domain("mail.example.com".to_owned())
+        );
+
+        let builder = SmtpTransport::from_url("smtp://127.0.0.1:2525/[192.0.2.1]").unwrap();
+        assert_eq!(
+            builder.info.hello_name(),
+            ClientId::Ipv4(Ipv4Addr::new(192, 0, 2, 1))
+        );
+
+        let builder = builder.hello_name_fn(|| ClientId::Ipv4(Ipv4Addr::new(192, 0, 2, 2)));
+        assert_eq!(
+            builder.info.hello_name(),
+            ClientId::Ipv4(Ipv4Addr::new(192, 0, 2, 2))
+        );
+
+        let builder = builder.hello_name(ClientId::Domain("example.com".to_owned()));
+        assert_eq!(
+            builder.info.hello_name(),
+            ClientId::Domain("example.com".to_owned())
+        );
+    }
+
+    #[test]
+    fn transport_command_deadline() {
+        // Reply to `MAIL FROM` one byte at a time, each within the timeout
+        let port = fake_server(|mut stream| {
+            for byte in b"250 OK\r\n" {
+                thread::sleep(Duration::from_millis(100));
+                if stream.write_all(&[*byte]).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let mailer = SmtpTransport::builder_dangerous("127.0.0.1")
+            .port(port)
+            .timeout(Some(Duration::from_secs(1)))
+            .command_deadline(Some(Duration::from_millis(300)))
+            .build();
+        let err = mailer.send_raw(&envelope(), b"test").unwrap_err();
+        assert!(err.is_timeout());
+    }
+
+    #[test]
+    fn transport_reply_too_large() {
+        let port = fake_server(|mut stream| {
+            for _ in 0..2000 {
+                if stream.write_all(&[b'x'; 100]).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let mailer = SmtpTransport::builder_dangerous("127.0.0.1")
+            .port(port)
+            .build();
+        let err = mailer.send_raw(&envelope(), b"test").unwrap_err();
+        assert!(err.is_reply_too_large());
+    }
+
+    #[test]
+    fn transport_reply_line_too_long() {
+        let port = fake_server(|mut stream| {
+            let _ = stream.write_all(format!("250-{}\r\n250 OK\r\n", "x".repeat(200)).as_bytes());
+        });
+
+        let mailer = SmtpTransport::builder_dangerous("127.0.0.1")
+            .port(port)
+            .reply_limits(ReplyLimits::new().max_line_length(100))
+            .build();
+        let err = mailer.send_raw(&envelope(), b"test").unwrap_err();
+        assert!(err.is_reply_too_large());
+    }
+
+    /// Runs a server sending its greeting one byte at a time, each within the timeout
+    fn slow_greeting_server() -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            for byte in b"220 slow\r\n" {
+                thread::sleep(Duration::from_millis(100));
+                if stream.write_all(&[*byte]).is_err() {
+                    break;
+                }
+            }
+        });
+        port
+    }
+
+    #[test]
+    fn transport_connect_deadline() {
+        let mailer = SmtpTransport::builder_dangerous("127.0.0.1")
+            .port(slow_greeting_server())
+            .timeout(Some(Duration::from_secs(1)))
+            .connect_deadline(Some(Duration::from_millis(300)))
+            .build();
+        let err = mailer.send_raw(&envelope(), b"test").unwrap_err();
+        assert!(err.is_timeout());
+    }
+
+    #[test]
+    #[cfg(feature = "tokio1")]
+    fn async_transport_connect_deadline() {
+        use crate::{AsyncSmtpTransport, AsyncTransport, Tokio1Executor};
+
+        let port = slow_greeting_server();
+        let runtime = tokio1_crate::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        runtime.block_on(async {
+            let mailer: AsyncSmtpTransport<Tokio1Executor> =
+                AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous("127.0.0.1")
+                    .port(port)
+                    .timeout(Some(Duration::from_secs(1)))
+                    .connect_deadline(Some(Duration::from_millis(300)))
+                    .build();
+            let err = mailer.send_raw(&envelope(), b"test").await.unwrap_err();
+            assert!(err.is_timeout());
+        });
+    }
+
+    #[derive(Debug, Default)]
+    struct Recorder(Mutex<Vec<SendStats>>);
+
+    impl SendObserver for Recorder {
+        fn sent(&self, _: &Envelope, _: &Response, stats: &SendStats) {
+            self.0.lock().unwrap().push(*stats);
+        }
+    }
+
+    #[test]
+    fn transport_send_observer() {
+        let port = fake_server(|mut stream| {
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut line = String::new();
+            // `MAIL FROM` was already read
+            for reply in ["250 OK\r\n", "250 OK\r\n"] {
+                stream.write_all(reply.as_bytes()).unwrap();
+                reader.read_line(&mut line).unwrap();
+            }
+            stream.write_all(b"354 Go ahead\r\n").unwrap();
+            while line != ".\r\n" {
+                line.clear();
+                reader.read_line(&mut line).unwrap();
+            }
+            stream.write_all(b"250 OK\r\n").unwrap();
+        });
+
+        let recorder = Arc::new(Recorder::default());
+        let mailer = SmtpTransport::builder_dangerous("127.0.0.1")
+            .port(port)
+            .send_observer(Arc::clone(&recorder))
+            .build();
+        let response = mailer.send_raw(&envelope(), b"test").unwrap();
+        let receipt = response.receipt().unwrap();
+        assert_eq!(receipt.id(), Some("OK"));
+        assert_eq!(receipt.size(), 4);
+
+        let stats = recorder.0.lock().unwrap();
+        assert_eq!(stats.len(), 1);
+        assert_eq!(stats[0].message_size(), 4);
+        // `MAIL FROM`, `RCPT TO`, `DATA` and the message with its terminating dot
+        assert_eq!(stats[0].bytes_written(), 28 + 26 + 6 + 4 + 5);
+    }
+
+    #[test]
+    fn transport_user_agent() {
+        let (tx, rx) = mpsc::channel();
+        let port = fake_server(move |mut stream| {
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut line = String::new();
+            for reply in ["250 OK\r\n", "250 OK\r\n"] {
+                stream.write_all(reply.as_bytes()).unwrap();
+                reader.read_line(&mut line).unwrap();
+            }
+            stream.write_all(b"354 Go ahead\r\n").unwrap();
+            let mut data = String::new();
+            while line != ".\r\n" {
+                line.clear();
+                reader.read_line(&mut line).unwrap();
+                data.push_str(&line);
+            }
+            stream.write_all(b"250 OK\r\n").unwrap();
+            tx.send(data).unwrap();
+        });
+
+        let mailer = SmtpTransport::builder_dangerous("127.0.0.1")
+            .port(port)
+            .user_agent(Some(DEFAULT_USER_AGENT.to_owned()))
+            .build();
+        mailer
+            .send_raw(&envelope(), b"Subject: test\r\n\r\nBody")
+            .unwrap();
+        assert_eq!(
+            rx.recv().unwrap(),
+            format!("Subject: test\r\nUser-Agent: {DEFAULT_USER_AGENT}\r\n\r\nBody\r\n.\r\n")
+        );
+        assert!(DEFAULT_USER_AGENT.starts_with("lettre/0."));
+    }
+
+    #[test]
+    fn transport_send_raw_from_reader() {
+        let (tx, rx) = mpsc::channel();
+        let port = fake_server(move |mut stream| {
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut line = String::new();
+            for reply in ["250 OK\r\n", "250 OK\r\n"] {
+                stream.write_all(reply.as_bytes()).unwrap();
+                reader.read_line(&mut line).unwrap();
+            }
+            stream.write_all(b"354 Go ahead\r\n").unwrap();
+            let mut data = String::new();
+            while line != ".\r\n" {
+                line.clear();
+                reader.read_line(&mut line).unwrap();
+                data.push_str(&line);
+            }
+            stream.write_all(b"250 OK queued as 1234\r\n").unwrap();
+            tx.send(data).unwrap();
+        });
+
+        let mailer = SmtpTransport::builder_dangerous("127.0.0.1")
+            .port(port)
+            .user_agent(Some(DEFAULT_USER_AGENT.to_owned()))
+            .build();
+        let long_line = "a".repeat(10_000);
+        let email = format!("Message-ID: <1@domain.tld>\r\n\r\n{long_line}\r\n.hidden\r\n");
+        let response = mailer
+            .send_raw_from_reader(&envelope(), email.as_bytes())
+            .unwrap();
+
+        assert_eq!(
+            rx.recv().unwrap(),
+            format!(
+                "Message-ID: <1@domain.tld>\r\nUser-Agent: {DEFAULT_USER_AGENT}\r\n\r\n{long_line}\r\n..hidden\r\n\r\n.\r\n"
+            )
+        );
+        let receipt = response.receipt().unwrap();
+        assert_eq!(receipt.message_id(), Some("<1@domain.tld>"));
+        assert_eq!(receipt.id(), Some("OK queued as 1234"));
+        assert_eq!(
+            receipt.size(),
+            email.len() + DEFAULT_USER_AGENT.len() + "User-Agent: \r\n".len()
+        );
+    }
+
+    /// Runs a server answering `EHLO` with each of `ehlo` on successive connections,
+    /// then accepting all commands, and sends the commands received on each connection
+    fn ehlo_server(ehlo: Vec<&'static str>) -> (u16, mpsc::Receiver<String>) {
+        let (tx, rx) = mpsc::channel();
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        thread::spawn(move || {
+            for ehlo in ehlo {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut commands = String::new();
+                let mut line = String::new();
+                stream.write_all(b"220 fake\r\n").unwrap();
+                reader.read_line(&mut line).unwrap();
+                stream.write_all(ehlo.as_bytes()).unwrap();
+                loop {
+                    line.clear();
+                    if reader.read_line(&mut line).unwrap() == 0 {
+                        break;
+                    }
+                    if line.starts_with("QUIT") {
+                        stream.write_all(b"221 Bye\r\n").unwrap();
+                        break;
+                    }
+                    commands.push_str(&line);
+                    if line.starts_with("DATA") {
+                        stream.write_all(b"354 Go ahead\r\n").unwrap();
+                        while line != ".\r\n" {
+                            line.clear();
+                            reader.read_line(&mut line).unwrap();
+                        }
+                    }
+                    if line.starts_with("RCPT TO:<unknown@") {
+                        stream.write_all(b"550 No such user\r\n").unwrap();
+                        continue;
+                    }
+                    stream.write_all(b"250 OK\r\n").unwrap();
+                }
+                tx.send(commands).unwrap();
+            }
+        });
+        (port, rx)
+    }
+
+    #[test]
+    fn transport_rcptmax() {
+        let (port, rx) = ehlo_server(vec!["250-fake\r\n250 LIMITS RCPTMAX=1\r\n"]);
+        let builder = SmtpTransport::builder_dangerous("127.0.0.1").port(port);
+        #[cfg(feature = "pool")]
+        let builder =
+            builder.pool_config(PoolConfig::new().idle_timeout(Duration::from_millis(10)));
+        let mailer = builder.build();
+        let envelope = Envelope::new(
+            Some("user@localhost".parse().unwrap()),
+            vec![
+                "root@localhost".parse().unwrap(),
+                "admin@localhost".parse().unwrap(),
+            ],
+        )
+        .unwrap();
+        let err = mailer
+            .send_raw(&envelope, b"Subject: test\r\n\r\nBody")
+            .unwrap_err();
+        assert!(err.is_client());
+        assert!(err
+            .to_string()
+            .contains("Envelope has 2 recipients but the server accepts at most 1"));
+        // No transaction was started
+        assert_eq!(rx.recv().unwrap(), "");
+    }
+
+    #[cfg(feature = "pool")]
+    #[derive(Debug, Default)]
+    struct PoolEvents {
+        events: Mutex<Vec<PoolEvent>>,
+        changes: Mutex<Vec<String>>,
+    }
+
+    #[cfg(feature = "pool")]
+    impl PoolObserver for PoolEvents {
+        fn event(&self, event: PoolEvent) {
+            if !matches!(event, PoolEvent::CheckedOut { .. }) {
+                self.events.lock().unwrap().push(event);
+            }
+        }
+
+        fn server_info_changed(&self, change: &ServerInfoChange) {
+            self.changes.lock().unwrap().push(change.to_string());
+        }
+    }
+
+    #[cfg(feature = "pool")]
+    #[test]
+    fn transport_pool_mailmax() {
+        let (port, _rx) = ehlo_server(vec![
+            "250-fake\r\n250 LIMITS MAILMAX=1\r\n",
+            "250-fake\r\n250 LIMITS MAILMAX=1\r\n",
+        ]);
+        let events = Arc::new(PoolEvents::default());
+        let mailer = SmtpTransport::builder_dangerous("127.0.0.1")
+            .port(port)
+            .pool_config(PoolConfig::new().observer(Arc::clone(&events)))
+            .build();
+        mailer
+            .send_raw(&envelope(), b"Subject: test\r\n\r\nBody")
+            .unwrap();
+        assert_eq!(
+            *events.events.lock().unwrap(),
+            [PoolEvent::Created, PoolEvent::Exhausted]
+        );
+
+        // The next message goes on a new connection
+        mailer
+            .send_raw(&envelope(), b"Subject: test\r\n\r\nBody")
+            .unwrap();
+        assert_eq!(
+            *events.events.lock().unwrap(),
+            [
+                PoolEvent::Created,
+                PoolEvent::Exhausted,
+                PoolEvent::Created,
+                PoolEvent::Exhausted
+            ]
+        );
+    }
+
+    #[cfg(feature = "pool")]
+    #[test]
+    fn transport_pool_server_info_changed() {
+        let (port, _rx) = ehlo_server(vec![
+            "250-fake\r\n250-STARTTLS\r\n250-SIZE 100\r\n250 LIMITS MAILMAX=1\r\n",
+            "250-fake\r\n250-SIZE 100\r\n250 LIMITS MAILMAX=1\r\n",
+            "250-fake\r\n250-SIZE 100\r\n250 LIMITS MAILMAX=1\r\n",
+        ]);
+        let events = Arc::new(PoolEvents::default());
+        let mailer = SmtpTransport::builder_dangerous("127.0.0.1")
+            .port(port)
+            .pool_config(PoolConfig::new().observer(Arc::clone(&events)))
+            .build();
+        for _ in 0..3 {
+            mailer
+                .send_raw(&envelope(), b"Subject: test\r\n\r\nBody")
+                .unwrap();
+        }
+        assert_eq!(*events.changes.lock().unwrap(), ["removed STARTTLS"]);
+    }
+
+    #[test]
+    fn transport_helo_fallback() {
+        let (port, rx) = ehlo_server(vec![
+            "500 Command unrecognized\r\n",
+            "500 Command unrecognized\r\n",
+        ]);
+
+        let err = SmtpTransport::builder_dangerous("127.0.0.1")
+            .port(port)
+            .build()
+            .send_raw(&envelope(), b"Subject: test\r\n\r\nBody")
+            .unwrap_err();
+        assert!(err.is_permanent());
+        assert_eq!(rx.recv().unwrap(), "");
+
+        let builder = SmtpTransport::builder_dangerous("127.0.0.1")
+            .port(port)
+            .hello_name(ClientId::Domain("legacy.tld".to_owned()))
+            .helo_fallback(true);
+        #[cfg(feature = "pool")]
+        let builder =
+            builder.pool_config(PoolConfig::new().idle_timeout(Duration::from_millis(10)));
+        builder
+            .build()
+            .send_raw(&envelope(), b"Subject: test\r\n\r\nBody")
+            .unwrap();
+        assert!(rx
+            .recv()
+            .unwrap()
+            .starts_with("HELO legacy.tld\r\nMAIL FROM:<user@localhost>\r\n"));
+    }
+
+    #[test]
+    fn transport_diagnose() {
+        let (port, rx) = ehlo_server(vec!["500 Command unrecognized\r\n"]);
+        let diagnosis = SmtpTransport::builder_dangerous("127.0.0.1")
+            .port(port)
+            .hello_name(ClientId::Domain("legacy.tld".to_owned()))
+            .helo_fallback(true)
+            .dns_cache_ttl(Some(Duration::from_secs(60)))
+            .build()
+            .diagnose();
+        assert!(diagnosis.is_ok(), "{:?}", diagnosis.error());
+        assert_eq!(diagnosis.connect_attempts(), 1);
+        assert_eq!(
+            diagnosis.peer_addr(),
+            Some((Ipv4Addr::LOCALHOST, port).into())
+        );
+        assert_eq!(rx.recv().unwrap(), "HELO legacy.tld\r\n");
+
+        let diagnosis = SmtpTransport::builder_dangerous("127.0.0.1")
+            .port(slow_greeting_server())
+            .timeout(Some(Duration::from_secs(1)))
+            .connect_deadline(Some(Duration::from_millis(300)))
+            .build()
+            .diagnose();
+        assert!(diagnosis.error().unwrap().is_timeout());
+        assert_eq!(diagnosis.connect_attempts(), 1);
+        assert_eq!(diagnosis.connect_duration(), None);
+    }
+
+    #[test]
+    #[cfg(feature = "tokio1")]
+    fn async_transport_helo_fallback() {
+        use crate::{AsyncSmtpTransport, AsyncTransport, Tokio1Executor};
+
+        let (port, rx) = ehlo_server(vec!["500 Command unrecognized\r\n"]);
+        let runtime = tokio1_crate::runtime::Runtime::new().unwrap();
+        runtime.block_on(async {
+            let builder = AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous("127.0.0.1")
+                .port(port)
+                .hello_name(ClientId::Domain("legacy.tld".to_owned()))
+                .helo_fallback(true);
+            #[cfg(feature = "pool")]
+            let builder =
+                builder.pool_config(PoolConfig::new().idle_timeout(Duration::from_millis(10)));
+            let mailer: AsyncSmtpTransport<Tokio1Executor> = builder.build();
+            mailer
+                .send_raw(&envelope(), b"Subject: test\r\n\r\nBody")
+                .await
+                .unwrap();
+        });
+        assert!(rx
+            .recv()
+            .unwrap()
+            .starts_with("HELO legacy.tld\r\nMAIL FROM:<user@localhost>\r\n"));
+    }
+
+    #[test]
+    fn transport_dry_run() {
+        let (port, rx) = ehlo_server(vec!["250 fake\r\n", "250 fake\r\n"]);
+        let envelope = Envelope::new(
+            Some("user@localhost".parse().unwrap()),
+            vec![
+                "root@localhost".parse().unwrap(),
+                "unknown@localhost".parse().unwrap(),
+                "admin@localhost".parse().unwrap(),
+            ],
+        )
+        .unwrap();
+        let commands = concat!(
+            "MAIL FROM:<user@localhost>\r\n",
+            "RCPT TO:<root@localhost>\r\n",
+            "RCPT TO:<unknown@localhost>\r\n",
+            "RCPT TO:<admin@localhost>\r\n",
+            "RSET\r\n",
+        );
+
+        let builder = SmtpTransport::builder_dangerous("127.0.0.1").port(port);
+        #[cfg(feature = "pool")]
+        let builder =
+            builder.pool_config(PoolConfig::new().idle_timeout(Duration::from_millis(10)));
+
+        let dry_run = builder.clone().build().dry_run(&envelope).unwrap();
+        assert!(!dry_run.is_accepted());
+        assert_eq!(
+            dry_run.accepted(),
+            [
+                "root@localhost".parse().unwrap(),
+                "admin@localhost".parse().unwrap()
+            ]
+        );
+        let (rejected, err) = &dry_run.rejected()[0];
+        assert_eq!(rejected.to_string(), "unknown@localhost");
+        assert!(err.is_permanent());
+        assert_eq!(rx.recv().unwrap(), commands);
+
+        let err = builder
+            .dry_run(true)
+            .build()
+            .send_raw(&envelope, b"Subject: test\r\n\r\nBody")
+            .unwrap_err();
+        assert!(err.is_permanent());
+        assert_eq!(err.failed_command().unwrap().recipient(), Some(1));
+        assert_eq!(rx.recv().unwrap(), commands);
+    }
+
+    #[test]
+    #[cfg(any(feature = "native-tls", feature = "rustls-tls", feature = "boring-tls"))]
+    fn connection_starttls_unsupported() {
+        use crate::transport::smtp::client::{SmtpConnection, TlsParameters};
+
+        let (port, rx) = ehlo_server(vec![
+            "250-fake\r\n250 STARTTLS\r\n",
+            "250-fake\r\n250 8BITMIME\r\n",
+        ]);
+        let hello_name = ClientId::default();
+        let connect =
+            || SmtpConnection::connect(("127.0.0.1", port), None, &hello_name, None, None).unwrap();
+
+        let mut conn = connect();
+        assert!(!conn.is_encrypted());
+        assert!(conn.can_starttls());
+        conn.quit().unwrap();
+        assert_eq!(rx.recv().unwrap(), "");
+
+        let mut conn = connect();
+        assert!(!conn.can_starttls());
+        let tls_parameters = TlsParameters::new("localhost".to_owned()).unwrap();
+        let err = conn.starttls(&tls_parameters, &hello_name).unwrap_err();
+        assert!(err.is_client());
+        assert!(!conn.has_broken());
+        conn.quit().unwrap();
+        assert_eq!(rx.recv().unwrap(), "", "STARTTLS isn't sent");
+    }
+
+    #[test]
+    fn transport_dns_cache() {
+        let ttl = Some(Duration::from_secs(60));
+        let (port, _rx) = ehlo_server(vec!["250 fake\r\n"]);
+        let mailer = SmtpTransport::builder_dangerous("127.0.0.1")
+            .port(port)
+            .dns_cache_ttl(ttl)
+            .build();
+        let cache = mailer.client().info.dns_cache.clone().unwrap();
+
+        assert!(mailer.test_connection().unwrap());
+        assert_eq!(
+            cache.get("127.0.0.1", port),
+            Some(vec![(Ipv4Addr::LOCALHOST, port).into()])
+        );
+        mailer.flush_dns_cache();
+        assert_eq!(cache.get("127.0.0.1", port), None);
+
+        let port = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let mailer = SmtpTransport::builder_dangerous("127.0.0.1")
+            .port(port)
+            .dns_cache_ttl(ttl)
+            .build();
+        let cache = mailer.client().info.dns_cache.clone().unwrap();
+        cache.insert("127.0.0.1", port, &[(Ipv4Addr::LOCALHOST, port).into()]);
+        assert!(mailer.test_connection().is_err());
+        assert_eq!(
+            cache.get("127.0.0.1", port),
+            None,
+            "the addresses are forgotten when connecting fails"
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "tokio1")]
+    fn async_transport_dns_cache() {
+        use crate::{AsyncSmtpTransport, Tokio1Executor};
+
+        let ttl = Some(Duration::from_secs(60));
+        let (port, _rx) = ehlo_server(vec!["250 fake\r\n"]);
+        let runtime = tokio1_crate::runtime::Runtime::new().unwrap();
+        runtime.block_on(async {
+            let mailer: AsyncSmtpTransport<Tokio1Executor> =
+                AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous("127.0.0.1")
+                    .port(port)
+                    .dns_cache_ttl(ttl)
+                    .build();
+            let cache = mailer.client().info.dns_cache.clone().unwrap();
+
+            assert!(mailer.test_connection().await.unwrap());
+            assert_eq!(
+                cache.get("127.0.0.1", port),
+                Some(vec![(Ipv4Addr::LOCALHOST, port).into()])
+            );
+            mailer.flush_dns_cache();
+            assert_eq!(cache.get("127.0.0.1", port), None);
+
+            let port = TcpListener::bind("127.0.0.1:0")
+                .unwrap()
+                .local_addr()
+                .unwrap()
+                .port();
+            let mailer: AsyncSmtpTransport<Tokio1Executor> =
+                AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous("127.0.0.1")
+                    .port(port)
+                    .dns_cache_ttl(ttl)
+                    .build();
+            let cache = mailer.client().info.dns_cache.clone().unwrap();
+            cache.insert("127.0.0.1", port, &[(Ipv4Addr::LOCALHOST, port).into()]);
+            assert!(mailer.test_connection().await.is_err());
+            assert_eq!(
+                cache.get("127.0.0.1", port),
+                None,
+                "the addresses are forgotten when connecting fails"
+            );
+        });
+    }
+
+    #[test]
+    #[cfg(any(feature = "native-tls", feature = "rustls-tls", feature = "boring-tls"))]
+    fn builder_getters() {
+        let base = SmtpTransport::starttls_relay("smtp.example.com").unwrap();
+        assert_eq!(base.get_host(), "smtp.example.com");
+        assert_eq!(base.get_port(), 587);
+        assert!(matches!(base.get_tls(), Tls::Required(_)));
+
+        let variation = base.clone().port(2525).tls(Tls::None);
+        assert_eq!(variation.get_host(), "smtp.example.com");
+        assert_eq!(variation.get_port(), 2525);
+        assert!(matches!(variation.get_tls(), Tls::None));
+        assert_eq!(base.get_port(), 587);
+
+        #[cfg(feature = "pool")]
+        {
+            let base = base.pool_config(
+                PoolConfig::new()
+                    .max_size(3)
+                    .min_idle(1)
+                    .idle_timeout(Duration::from_secs(30)),
+            );
+            let pool_config = base.get_pool_config();
+            assert_eq!(pool_config.get_max_size(), 3);
+            assert_eq!(pool_config.get_min_idle(), 1);
+            assert_eq!(pool_config.get_idle_timeout(), Duration::from_secs(30));
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "tokio1")]
+    fn async_builder_getters() {
+        use crate::{AsyncSmtpTransport, Tokio1Executor};
+
+        let base = AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous("smtp.example.com");
+        assert_eq!(base.get_host(), "smtp.example.com");
+        assert_eq!(base.get_port(), 25);
+
+        let variation = base.clone().port(2525);
+        assert_eq!(variation.get_host(), "smtp.example.com");
+        assert_eq!(variation.get_port(), 2525);
+        assert_eq!(base.get_port(), 25);
+
+        #[cfg(any(feature = "tokio1-native-tls", feature = "tokio1-rustls-tls"))]
+        assert!(matches!(base.get_tls(), Tls::None));
+
+        #[cfg(feature = "pool")]
+        {
+            let base = base.pool_config(
+                PoolConfig::new()
+                    .max_size(3)
+                    .min_idle(1)
+                    .idle_timeout(Duration::from_secs(30)),
+            );
+            let pool_config = base.get_pool_config();
+            assert_eq!(pool_config.get_max_size(), 3);
+            assert_eq!(pool_config.get_min_idle(), 1);
+            assert_eq!(pool_config.get_idle_timeout(), Duration::from_secs(30));
+        }
+    }
+
+    #[test]
+    #[cfg(all(
+        any(feature = "native-tls", feature = "rustls-tls", feature = "boring-tls"),
+        feature = "pool"
+    ))]
+    fn transport_display() {
+        // The label of whichever TLS library is active
+        let backend =
+            crate::transport::smtp::client::TlsParameters::new("smtp.example.com".to_owned())
+                .unwrap()
+                .backend();
+
+        let mailer = SmtpTransport::relay("smtp.example.com")
+            .unwrap()
+            .credentials(Credentials::new(
+                "user@example.com".to_owned(),
+                "secret".to_owned(),
+            ))
+            .build();
+        assert_eq!(
+            mailer.to_string(),
+            format!(
+                "smtps://smtp.example.com:465, pool max=10, auth=PLAIN/LOGIN as user@…, tls=wrapper({backend})"
+            )
+        );
+
+        let mailer = SmtpTransport::builder_dangerous("localhost")
+            .credentials(Credentials::new("apikey".to_owned(), "secret".to_owned()))
+            .authentication(vec![Mechanism::Login])
+            .pool_config(PoolConfig::new().max_size(2))
+            .build();
+        assert_eq!(
+            mailer.to_string(),
+            "smtp://localhost:25, pool max=2, auth=LOGIN as a…, tls=none"
+        );
+
+        let mailer = SmtpTransport::starttls_relay("smtp.example.com")
+            .unwrap()
+            .build();
+        assert_eq!(
+            mailer.to_string(),
+            format!("smtp://smtp.example.com:587, pool max=10, auth=none, tls=required({backend})")
+        );
+    }
 }
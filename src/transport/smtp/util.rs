@@ -1,6 +1,17 @@
 //! Utils for string manipulation
 
-use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::{
+    borrow::Cow,
+    fmt::{Display, Formatter, Result as FmtResult},
+    io::{self, BufRead, Read},
+};
+
+#[cfg(any(feature = "tokio1", feature = "async-std1"))]
+use futures_util::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt};
+
+/// Maximum size of the header section read by [`read_header_section`],
+/// the rest of a message without a blank line is streamed as its body
+const MAX_HEADER_SECTION: u64 = 1024 * 1024;
 
 /// Encode a string as xtext
 #[derive(Debug)]
@@ -27,9 +38,77 @@ impl Display for XText<'_> {
     }
 }
 
+/// Add a `User-Agent` header to the end of the header section of `email`
+///
+/// Messages which already have a `User-Agent` or `X-Mailer` header, or
+/// without a blank line ending the header section, are returned as is.
+pub(super) fn stamp_user_agent<'a>(email: &'a [u8], user_agent: &str) -> Cow<'a, [u8]> {
+    let mut pos = 0;
+    while let Some(len) = email[pos..].iter().position(|&b| b == b'\n') {
+        let line = &email[pos..pos + len];
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+        if line.is_empty() {
+            // Reuse the line ending of the message
+            let (headers, rest) = email.split_at(pos);
+            let mut stamped = Vec::with_capacity(email.len() + user_agent.len() + 14);
+            stamped.extend_from_slice(headers);
+            stamped.extend_from_slice(b"User-Agent: ");
+            stamped.extend_from_slice(user_agent.as_bytes());
+            stamped.extend_from_slice(&rest[..=len]);
+            stamped.extend_from_slice(rest);
+            return Cow::Owned(stamped);
+        }
+        if is_header(line, "User-Agent") || is_header(line, "X-Mailer") {
+            break;
+        }
+        pos += len + 1;
+    }
+    Cow::Borrowed(email)
+}
+
+/// Reads the header section of a message from `reader`, up to and
+/// including the blank line ending it
+pub(super) fn read_header_section<R: BufRead>(reader: &mut R) -> io::Result<Vec<u8>> {
+    let mut headers = Vec::new();
+    let mut reader = reader.take(MAX_HEADER_SECTION);
+    loop {
+        let start = headers.len();
+        if reader.read_until(b'\n', &mut headers)? == 0 || is_blank_line(&headers[start..]) {
+            return Ok(headers);
+        }
+    }
+}
+
+/// Reads the header section of a message from `reader`, see [`read_header_section`]
+#[cfg(any(feature = "tokio1", feature = "async-std1"))]
+pub(super) async fn read_header_section_async<R: AsyncBufRead + Unpin>(
+    reader: &mut R,
+) -> io::Result<Vec<u8>> {
+    let mut headers = Vec::new();
+    let mut reader = reader.take(MAX_HEADER_SECTION);
+    loop {
+        let start = headers.len();
+        if reader.read_until(b'\n', &mut headers).await? == 0 || is_blank_line(&headers[start..]) {
+            return Ok(headers);
+        }
+    }
+}
+
+fn is_blank_line(line: &[u8]) -> bool {
+    line == b"\r\n" || line == b"\n"
+}
+
+fn is_header(line: &[u8], name: &str) -> bool {
+    line.len() > name.len()
+        && line[name.len()] == b':'
+        && line[..name.len()].eq_ignore_ascii_case(name.as_bytes())
+}
+
 #[cfg(test)]
 mod tests {
-    use super::XText;
+    use std::borrow::Cow;
+
+    use super::{read_header_section, stamp_user_agent, XText};
 
     #[test]
     fn test() {
@@ -44,4 +123,51 @@ mod tests {
             assert_eq!(format!("{}", XText(input)), (*expect).to_owned());
         }
     }
+
+    #[test]
+    fn user_agent() {
+        let stamped = stamp_user_agent(b"Subject: test\r\n\r\nBody\r\n", "lettre/0.11");
+        assert_eq!(
+            &*stamped,
+            b"Subject: test\r\nUser-Agent: lettre/0.11\r\n\r\nBody\r\n"
+        );
+
+        let stamped = stamp_user_agent(b"Subject: test\n\nBody", "lettre/0.11");
+        assert_eq!(&*stamped, b"Subject: test\nUser-Agent: lettre/0.11\n\nBody");
+
+        let stamped = stamp_user_agent(b"Subject: test\r\n\r\nUser-Agent: app\r\n", "lettre/0.11");
+        assert_eq!(
+            &*stamped,
+            b"Subject: test\r\nUser-Agent: lettre/0.11\r\n\r\nUser-Agent: app\r\n"
+        );
+
+        for email in [
+            &b"Subject: test\r\nuser-agent: app\r\n\r\nBody"[..],
+            b"X-Mailer: app\r\n\r\nBody",
+            b"Subject: test",
+        ] {
+            assert!(matches!(
+                stamp_user_agent(email, "lettre/0.11"),
+                Cow::Borrowed(_)
+            ));
+        }
+    }
+
+    #[test]
+    fn header_section() {
+        let mut reader = &b"Subject: Hi\r\nFrom: a@b.c\r\n\r\nBody\r\n"[..];
+        let headers = read_header_section(&mut reader).unwrap();
+        assert_eq!(headers, b"Subject: Hi\r\nFrom: a@b.c\r\n\r\n");
+        assert_eq!(reader, b"Body\r\n");
+
+        let mut reader = &b"Subject: Hi\n\nBody"[..];
+        let headers = read_header_section(&mut reader).unwrap();
+        assert_eq!(headers, b"Subject: Hi\n\n");
+        assert_eq!(reader, b"Body");
+
+        let mut reader = &b"Subject: Hi"[..];
+        let headers = read_header_section(&mut reader).unwrap();
+        assert_eq!(headers, b"Subject: Hi");
+        assert!(reader.is_empty());
+    }
 }
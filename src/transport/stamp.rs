@@ -0,0 +1,232 @@
+//! Adding headers to messages when they are sent
+//!
+//! [`Stamped`] wraps a transport and calls functions right before each
+//! [`Message`] is formatted, to add or replace headers such as a unique
+//! tracking identifier, a campaign identifier or a fresh `Date`. The
+//! message is copied only if headers were set.
+//!
+//! Raw messages, sent with [`Transport::send_raw`], are sent unchanged.
+//! Headers set by the functions aren't covered by a DKIM signature computed
+//! when building the message, so don't replace signed headers.
+//!
+//! ```rust
+//! use lettre::{
+//!     message::header::{HeaderName, HeaderValue},
+//!     transport::{stamp::Stamped, stub::StubTransport},
+//!     Message, Transport,
+//! };
+//!
+//! # use std::error::Error;
+//! # fn main() -> Result<(), Box<dyn Error>> {
+//! let email = Message::builder()
+//!     .from("NoBody <nobody@domain.tld>".parse()?)
+//!     .to("Hei <hei@domain.tld>".parse()?)
+//!     .subject("Happy new year")
+//!     .body(String::from("Be happy!"))?;
+//!
+//! let mailer = Stamped::new(StubTransport::new_ok()).stamp(|_message, headers| {
+//!     headers.insert_raw(HeaderValue::new(
+//!         HeaderName::new_from_ascii_str("X-Campaign-Id"),
+//!         "new-year".to_owned(),
+//!     ));
+//! });
+//! mailer.send(&email)?;
+//!
+//! let (_, sent) = &mailer.inner().messages()[0];
+//! assert!(sent.contains("X-Campaign-Id: new-year\r\n"));
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{
+    fmt::{self, Debug},
+    sync::Arc,
+};
+
+#[cfg(any(feature = "tokio1", feature = "async-std1"))]
+use async_trait::async_trait;
+
+#[cfg(any(feature = "tokio1", feature = "async-std1"))]
+use super::AsyncTransport;
+use super::Transport;
+use crate::{message::header::Headers, Envelope, Message};
+
+/// Function setting headers on a message about to be sent
+type Stamp = dyn Fn(&Message, &mut Headers) + Send + Sync;
+
+#[derive(Clone)]
+struct StampFn(Arc<Stamp>);
+
+impl Debug for StampFn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("StampFn")
+    }
+}
+
+/// Transport setting headers on the messages right before sending them
+///
+/// See the [module documentation](self).
+#[derive(Debug, Clone)]
+pub struct Stamped<T> {
+    inner: T,
+    stamps: Vec<StampFn>,
+}
+
+impl<T> Stamped<T> {
+    /// Wraps `inner`, sending the messages unchanged until functions are added
+    pub fn new(inner: T) -> Self {
+        Self {
+            inner,
+            stamps: Vec::new(),
+        }
+    }
+
+    /// Adds a function called with each message and the headers to set on it
+    ///
+    /// The headers set by the function replace the ones with the same name
+    /// in the message. The functions are called in the order they were
+    /// added, with the message as it was built.
+    pub fn stamp<F>(mut self, stamp: F) -> Self
+    where
+        F: Fn(&Message, &mut Headers) + Send + Sync + 'static,
+    {
+        self.stamps.push(StampFn(Arc::new(stamp)));
+        self
+    }
+
+    /// The wrapped transport
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    /// The headers to set on `message`, `None` if there are none
+    fn headers(&self, message: &Message) -> Option<Headers> {
+        let mut headers = Headers::new();
+        for stamp in &self.stamps {
+            (stamp.0)(message, &mut headers);
+        }
+        (!headers.is_empty()).then_some(headers)
+    }
+
+    /// A copy of `message` with the headers set, `None` if there are none
+    fn stamped(&self, message: &Message) -> Option<Message> {
+        let headers = self.headers(message)?;
+        let mut message = message.clone();
+        message.headers_mut().set_all(headers);
+        Some(message)
+    }
+}
+
+impl<T: Transport> Transport for Stamped<T> {
+    type Ok = T::Ok;
+    type Error = T::Error;
+
+    fn send(&self, message: &Message) -> Result<Self::Ok, Self::Error> {
+        match self.stamped(message) {
+            Some(message) => self.inner.send(&message),
+            None => self.inner.send(message),
+        }
+    }
+
+    fn send_with_envelope(
+        &self,
+        message: &Message,
+        envelope: &Envelope,
+    ) -> Result<Self::Ok, Self::Error> {
+        match self.stamped(message) {
+            Some(message) => self.inner.send_with_envelope(&message, envelope),
+            None => self.inner.send_with_envelope(message, envelope),
+        }
+    }
+
+    fn send_raw(&self, envelope: &Envelope, email: &[u8]) -> Result<Self::Ok, Self::Error> {
+        self.inner.send_raw(envelope, email)
+    }
+}
+
+#[cfg(any(feature = "tokio1", feature = "async-std1"))]
+#[async_trait]
+impl<T: AsyncTransport + Sync> AsyncTransport for Stamped<T> {
+    type Ok = T::Ok;
+    type Error = T::Error;
+
+    async fn send(&self, mut message: Message) -> Result<Self::Ok, Self::Error> {
+        if let Some(headers) = self.headers(&message) {
+            message.headers_mut().set_all(headers);
+        }
+        self.inner.send(message).await
+    }
+
+    async fn send_with_envelope(
+        &self,
+        message: &Message,
+        envelope: &Envelope,
+    ) -> Result<Self::Ok, Self::Error> {
+        match self.stamped(message) {
+            Some(message) => self.inner.send_with_envelope(&message, envelope).await,
+            None => self.inner.send_with_envelope(message, envelope).await,
+        }
+    }
+
+    async fn send_raw(&self, envelope: &Envelope, email: &[u8]) -> Result<Self::Ok, Self::Error> {
+        self.inner.send_raw(envelope, email).await
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use pretty_assertions::assert_eq;
+
+    use super::Stamped;
+    use crate::{
+        message::header::{self, HeaderName, HeaderValue},
+        transport::stub::StubTransport,
+        Message, Transport,
+    };
+
+    #[test]
+    fn stamped_headers() {
+        let email = Message::builder()
+            .from("nobody@domain.tld".parse().unwrap())
+            .to("hei@domain.tld".parse().unwrap())
+            .subject("Happy new year")
+            .body(String::from("Be happy!"))
+            .unwrap();
+
+        let count = AtomicUsize::new(0);
+        let mailer = Stamped::new(StubTransport::new_ok())
+            .stamp(move |_, headers| {
+                let id = count.fetch_add(1, Ordering::Relaxed);
+                headers.insert_raw(HeaderValue::new(
+                    HeaderName::new_from_ascii_str("X-Tracking-Id"),
+                    id.to_string(),
+                ));
+            })
+            .stamp(|message, headers| {
+                let subject = message.headers().get::<header::Subject>().unwrap();
+                headers.set(header::Subject::from(format!(
+                    "[news] {}",
+                    subject.as_ref()
+                )));
+            });
+        mailer.send(&email).unwrap();
+        mailer.send(&email).unwrap();
+
+        let messages = mailer.inner().messages();
+        assert!(messages[0].1.contains("Subject: [news] Happy new year\r\n"));
+        assert!(messages[0].1.contains("X-Tracking-Id: 0\r\n"));
+        assert!(messages[1].1.contains("X-Tracking-Id: 1\r\n"));
+        assert_eq!(messages[1].1.matches("Subject:").count(), 1);
+        assert_eq!(
+            email.headers().get_raw("Subject"),
+            Some("Happy new year"),
+            "the message is copied"
+        );
+
+        let raw = b"Subject: raw\r\n\r\nBody";
+        mailer.send_raw(email.envelope(), raw).unwrap();
+        assert_eq!(mailer.inner().messages()[2].1, "Subject: raw\r\n\r\nBody");
+    }
+}
@@ -40,6 +40,9 @@
 //! # try_main().unwrap();
 //! # }
 //! ```
+//!
+//! To check the exact bytes sent over SMTP against golden files, wrap it in a
+//! [`RecordingTransport`].
 
 use std::{
     error::Error as StdError,
@@ -52,9 +55,17 @@ use async_trait::async_trait;
 #[cfg(any(feature = "tokio1", feature = "async-std1"))]
 use futures_util::lock::Mutex as FuturesMutex;
 
+#[cfg(feature = "smtp-transport")]
+#[cfg_attr(docsrs, doc(cfg(feature = "smtp-transport")))]
+pub use self::recording::{RecordingTransport, UPDATE_GOLDEN_VAR};
 #[cfg(any(feature = "tokio1", feature = "async-std1"))]
 use crate::AsyncTransport;
-use crate::{address::Envelope, Transport};
+#[cfg(feature = "builder")]
+use crate::Message;
+use crate::{address::Envelope, transport::Receipt, Transport};
+
+#[cfg(feature = "smtp-transport")]
+mod recording;
 
 /// An error returned by the stub transport
 #[non_exhaustive]
@@ -152,6 +163,46 @@ impl AsyncStubTransport {
     }
 }
 
+impl StubTransport {
+    /// Sends the email like [`Transport::send`], returning a [`Receipt`]
+    #[cfg(feature = "builder")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "builder")))]
+    pub fn send_with_receipt(&self, message: &Message) -> Result<Receipt, Error> {
+        self.send_raw_with_receipt(message.envelope(), &message.formatted())
+    }
+
+    /// Sends the email like [`Transport::send_raw`], returning a [`Receipt`]
+    pub fn send_raw_with_receipt(
+        &self,
+        envelope: &Envelope,
+        email: &[u8],
+    ) -> Result<Receipt, Error> {
+        self.send_raw(envelope, email)?;
+        Ok(Receipt::new(email, None))
+    }
+}
+
+#[cfg(any(feature = "tokio1", feature = "async-std1"))]
+impl AsyncStubTransport {
+    /// Sends the email like [`AsyncTransport::send`], returning a [`Receipt`]
+    #[cfg(feature = "builder")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "builder")))]
+    pub async fn send_with_receipt(&self, message: &Message) -> Result<Receipt, Error> {
+        self.send_raw_with_receipt(message.envelope(), &message.formatted())
+            .await
+    }
+
+    /// Sends the email like [`AsyncTransport::send_raw`], returning a [`Receipt`]
+    pub async fn send_raw_with_receipt(
+        &self,
+        envelope: &Envelope,
+        email: &[u8],
+    ) -> Result<Receipt, Error> {
+        self.send_raw(envelope, email).await?;
+        Ok(Receipt::new(email, None))
+    }
+}
+
 impl Transport for StubTransport {
     type Ok = ();
     type Error = Error;
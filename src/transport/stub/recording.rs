@@ -0,0 +1,219 @@
+use std::{
+    env, fs,
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+#[cfg(any(feature = "tokio1", feature = "async-std1"))]
+use async_trait::async_trait;
+
+#[cfg(any(feature = "tokio1", feature = "async-std1"))]
+use crate::AsyncTransport;
+use crate::{address::Envelope, transport::smtp::client::transaction, Transport};
+
+/// Environment variable making [`RecordingTransport::assert_golden`] write
+/// the golden files instead of comparing them
+pub const UPDATE_GOLDEN_VAR: &str = "LETTRE_UPDATE_GOLDEN";
+
+/// Wraps a transport, recording the bytes an SMTP client would write to
+/// send each message
+///
+/// Unlike [`StubTransport`](super::StubTransport), which stores the messages
+/// as lossy strings, the recordings hold the exact SMTP transaction, from
+/// the `MAIL` command to the final dot, with the message dot-stuffed.
+/// Comparing them with golden files kept in version control catches
+/// unintended changes in the formatting of messages, like after upgrading
+/// lettre. The messages are then sent with the wrapped transport.
+///
+/// The transaction is recorded as sent to a server supporting the
+/// `8BITMIME` and `SMTPUTF8` extensions, without extensions added by
+/// the transport. Build the messages with a
+/// [`FixedClock`](crate::message::FixedClock) and a fixed `Message-ID`
+/// so that they don't change between runs.
+///
+/// ```rust
+/// # #[cfg(feature = "builder")]
+/// # {
+/// use lettre::{
+///     transport::stub::{RecordingTransport, StubTransport},
+///     Message, Transport,
+/// };
+///
+/// # use std::error::Error;
+/// # fn try_main() -> Result<(), Box<dyn Error>> {
+/// let email = Message::builder()
+///     .from("NoBody <nobody@domain.tld>".parse()?)
+///     .to("Hei <hei@domain.tld>".parse()?)
+///     .message_id(Some("<welcome@domain.tld>".to_owned()))
+///     .body(String::from("Be happy!"))?;
+///
+/// let sender = RecordingTransport::new(StubTransport::new_ok());
+/// sender.send(&email)?;
+/// assert!(sender
+///     .transcript()
+///     .starts_with(b"MAIL FROM:<nobody@domain.tld>\r\n"));
+///
+/// // In a test, compared with the content of the file
+/// // sender.assert_golden("tests/golden/welcome.txt");
+/// # Ok(())
+/// # }
+/// # try_main().unwrap();
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct RecordingTransport<T> {
+    inner: T,
+    recordings: Arc<Mutex<Vec<Vec<u8>>>>,
+}
+
+impl<T> RecordingTransport<T> {
+    /// Creates a transport recording the messages sent with `inner`
+    pub fn new(inner: T) -> Self {
+        Self {
+            inner,
+            recordings: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    /// Returns the wrapped transport
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    /// Returns the transactions recorded for each message, in sending order
+    pub fn recordings(&self) -> Vec<Vec<u8>> {
+        self.recordings
+            .lock()
+            .expect("Couldn't acquire lock to read recordings")
+            .clone()
+    }
+
+    /// Returns all the recorded transactions, one after another
+    pub fn transcript(&self) -> Vec<u8> {
+        self.recordings().concat()
+    }
+
+    /// Checks that the transcript matches the content of the `golden` file
+    ///
+    /// When the [`UPDATE_GOLDEN_VAR`] environment variable is set, the file
+    /// is written with the transcript instead, so that the changes can be
+    /// reviewed with version control.
+    ///
+    /// # Panics
+    ///
+    /// If the transcript is different, with the first different line,
+    /// or if the file can't be read or written.
+    pub fn assert_golden<P: AsRef<Path>>(&self, golden: P) {
+        let golden = golden.as_ref();
+        let transcript = self.transcript();
+
+        if env::var_os(UPDATE_GOLDEN_VAR).is_some() {
+            if let Some(parent) = golden.parent() {
+                fs::create_dir_all(parent).expect("Couldn't create golden file directory");
+            }
+            fs::write(golden, &transcript).expect("Couldn't write golden file");
+            return;
+        }
+
+        let expected = fs::read(golden).unwrap_or_else(|err| {
+            panic!(
+                "Couldn't read golden file {}: {err}, set {UPDATE_GOLDEN_VAR} to create it",
+                golden.display()
+            )
+        });
+        if expected == transcript {
+            return;
+        }
+
+        let mut expected_lines = expected.split_inclusive(|&b| b == b'\n');
+        let mut lines = transcript.split_inclusive(|&b| b == b'\n');
+        for line_number in 1.. {
+            let (expected_line, line) = (expected_lines.next(), lines.next());
+            if expected_line != line {
+                let display = |line: Option<&[u8]>| {
+                    line.map_or_else(
+                        || "end of transcript".to_owned(),
+                        |line| format!("{:?}", String::from_utf8_lossy(line)),
+                    )
+                };
+                panic!(
+                    "Transcript differs from golden file {} at line {line_number}\n  expected: {}\n     found: {}\nset {UPDATE_GOLDEN_VAR} to update it",
+                    golden.display(),
+                    display(expected_line),
+                    display(line)
+                );
+            }
+        }
+    }
+
+    fn record(&self, envelope: &Envelope, email: &[u8]) {
+        self.recordings
+            .lock()
+            .expect("Couldn't acquire lock to write recordings")
+            .push(transaction(envelope, email));
+    }
+}
+
+impl<T: Transport> Transport for RecordingTransport<T> {
+    type Ok = T::Ok;
+    type Error = T::Error;
+
+    fn send_raw(&self, envelope: &Envelope, email: &[u8]) -> Result<Self::Ok, Self::Error> {
+        self.record(envelope, email);
+        self.inner.send_raw(envelope, email)
+    }
+}
+
+#[cfg(any(feature = "tokio1", feature = "async-std1"))]
+#[async_trait]
+impl<T: AsyncTransport + Sync> AsyncTransport for RecordingTransport<T> {
+    type Ok = T::Ok;
+    type Error = T::Error;
+
+    async fn send_raw(&self, envelope: &Envelope, email: &[u8]) -> Result<Self::Ok, Self::Error> {
+        self.record(envelope, email);
+        self.inner.send_raw(envelope, email).await
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use super::RecordingTransport;
+    use crate::{address::Envelope, transport::stub::StubTransport, Transport};
+
+    #[test]
+    fn transaction() {
+        let sender = RecordingTransport::new(StubTransport::new_ok());
+        let envelope = Envelope::new(
+            Some("nobody@domain.tld".parse().unwrap()),
+            vec![
+                "hei@domain.tld".parse().unwrap(),
+                "yuin@domain.tld".parse().unwrap(),
+            ],
+        )
+        .unwrap();
+        sender
+            .send_raw(&envelope, b"Subject: test\r\n\r\n.hidden\r\n")
+            .unwrap();
+        sender
+            .send_raw(&envelope, "Subject: tést\r\n\r\nBe happy!".as_bytes())
+            .unwrap();
+
+        let recordings = sender.recordings();
+        assert_eq!(
+            String::from_utf8_lossy(&recordings[0]),
+            concat!(
+                "MAIL FROM:<nobody@domain.tld>\r\n",
+                "RCPT TO:<hei@domain.tld>\r\n",
+                "RCPT TO:<yuin@domain.tld>\r\n",
+                "DATA\r\n",
+                "Subject: test\r\n\r\n..hidden\r\n\r\n.\r\n",
+            )
+        );
+        assert!(recordings[1].starts_with(b"MAIL FROM:<nobody@domain.tld> BODY=8BITMIME\r\n"));
+        assert_eq!(sender.transcript(), recordings.concat());
+        assert_eq!(sender.inner().messages().len(), 2);
+    }
+}
@@ -11,7 +11,8 @@ fn default_date() -> std::time::SystemTime {
 mod sync {
     use std::{
         env::temp_dir,
-        fs::{read_to_string, remove_file},
+        fs::{create_dir_all, read_to_string, remove_dir_all, remove_file, write},
+        time::Duration,
     };
 
     use lettre::{FileTransport, Message, Transport};
@@ -52,6 +53,30 @@ mod sync {
         remove_file(eml_file).unwrap();
     }
 
+    #[test]
+    fn file_transport_with_receipt() {
+        let sender = FileTransport::new(temp_dir());
+        let email = Message::builder()
+            .message_id(Some("<123@domain.tld>".to_owned()))
+            .from("NoBody <nobody@domain.tld>".parse().unwrap())
+            .to("Hei <hei@domain.tld>".parse().unwrap())
+            .subject("Happy new year")
+            .date(default_date())
+            .body(String::from("Be happy!"))
+            .unwrap();
+
+        let receipt = sender.send_with_receipt(&email).unwrap();
+        assert_eq!(receipt.message_id(), Some("<123@domain.tld>"));
+        assert_eq!(receipt.size(), email.formatted().len());
+
+        let eml_file = temp_dir().join(format!("{}.eml", receipt.id().unwrap()));
+        assert_eq!(
+            read_to_string(&eml_file).unwrap().as_bytes(),
+            email.formatted()
+        );
+        remove_file(eml_file).unwrap();
+    }
+
     #[test]
     #[cfg(feature = "file-transport-envelope")]
     fn file_transport_with_envelope() {
@@ -101,6 +126,107 @@ mod sync {
         remove_file(eml_file).unwrap();
         remove_file(json_file).unwrap();
     }
+
+    #[test]
+    #[cfg(feature = "file-transport-envelope")]
+    fn file_transport_with_bundle() {
+        let sender = FileTransport::with_bundle(temp_dir());
+        let email = Message::builder()
+            .from("NoBody <nobody@domain.tld>".parse().unwrap())
+            .to("Hei <hei@domain.tld>".parse().unwrap())
+            .subject("Happy new year")
+            .date(default_date())
+            .body(String::from("Be happy!"))
+            .unwrap();
+
+        let id = sender.send(&email).unwrap();
+
+        let bundle_file = temp_dir().join(format!("{id}.emlb"));
+        let bundle = read_to_string(&bundle_file).unwrap();
+        assert_eq!(
+            bundle,
+            concat!(
+                "168\n",
+                "From: NoBody <nobody@domain.tld>\r\n",
+                "To: Hei <hei@domain.tld>\r\n",
+                "Subject: Happy new year\r\n",
+                "Date: Tue, 15 Nov 1994 08:12:31 +0000\r\n",
+                "Content-Transfer-Encoding: 7bit\r\n",
+                "\r\n",
+                "Be happy!",
+                "{\"forward_path\":[\"hei@domain.tld\"],\"reverse_path\":\"nobody@domain.tld\"}"
+            )
+        );
+        assert!(!temp_dir().join(format!("{id}.eml")).exists());
+
+        let (envelope, message) = sender.read(&id).unwrap();
+        assert_eq!(&envelope, email.envelope());
+        assert_eq!(message, email.formatted());
+
+        remove_file(bundle_file).unwrap();
+    }
+
+    #[test]
+    fn file_transport_sharded() {
+        let dir = temp_dir().join("lettre-sharded-sync");
+        let sender = FileTransport::new(&dir).shard_by_date(true);
+        let email = Message::builder()
+            .from("NoBody <nobody@domain.tld>".parse().unwrap())
+            .to("Hei <hei@domain.tld>".parse().unwrap())
+            .subject("Happy new year")
+            .date(default_date())
+            .body(String::from("Be happy!"))
+            .unwrap();
+
+        let id = sender.send(&email).unwrap();
+
+        let parts = id.split('/').collect::<Vec<_>>();
+        assert_eq!(parts.len(), 4, "{id}");
+        assert_eq!(parts[0].len(), 4);
+        assert_eq!(parts[1].len(), 2);
+        assert_eq!(parts[2].len(), 2);
+
+        let eml = read_to_string(dir.join(format!("{id}.eml"))).unwrap();
+        assert_eq!(eml.as_bytes(), email.formatted());
+
+        remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn file_transport_prune() {
+        let dir = temp_dir().join("lettre-prune-sync");
+        create_dir_all(&dir).unwrap();
+        write(dir.join("notes.txt"), "keep me").unwrap();
+        let sender = FileTransport::new(&dir);
+        let sharded = FileTransport::new(&dir).shard_by_date(true);
+        let email = Message::builder()
+            .from("NoBody <nobody@domain.tld>".parse().unwrap())
+            .to("Hei <hei@domain.tld>".parse().unwrap())
+            .subject("Happy new year")
+            .date(default_date())
+            .body(String::from("Be happy!"))
+            .unwrap();
+
+        let id = sender.send(&email).unwrap();
+        let sharded_id = sharded.send(&email).unwrap();
+        let eml_file = dir.join(format!("{id}.eml"));
+        let sharded_file = dir.join(format!("{sharded_id}.eml"));
+
+        assert!(sender.prune(Duration::from_secs(3600)).unwrap().is_empty());
+
+        let mut expected = vec![eml_file.clone(), sharded_file.clone()];
+        expected.sort();
+        assert_eq!(sender.prune_dry_run(Duration::ZERO).unwrap(), expected);
+        assert!(eml_file.exists());
+        assert!(sharded_file.exists());
+
+        assert_eq!(sender.prune(Duration::ZERO).unwrap(), expected);
+        assert!(!eml_file.exists());
+        assert!(!dir.join(&sharded_id[..4]).exists());
+        assert!(dir.join("notes.txt").exists());
+
+        remove_dir_all(dir).unwrap();
+    }
 }
 
 #[cfg(test)]
@@ -108,7 +234,8 @@ mod sync {
 mod tokio_1 {
     use std::{
         env::temp_dir,
-        fs::{read_to_string, remove_file},
+        fs::{read_dir, read_to_string, remove_dir_all, remove_file},
+        time::Duration,
     };
 
     use lettre::{AsyncFileTransport, AsyncTransport, Message, Tokio1Executor};
@@ -149,6 +276,74 @@ mod tokio_1 {
         );
         remove_file(eml_file).unwrap();
     }
+
+    #[tokio::test]
+    async fn file_transport_sharded_tokio1() {
+        let dir = temp_dir().join("lettre-sharded-tokio1");
+        let sender = AsyncFileTransport::<Tokio1Executor>::new(&dir).shard_by_date(true);
+        let email = Message::builder()
+            .from("NoBody <nobody@domain.tld>".parse().unwrap())
+            .to("Hei <hei@domain.tld>".parse().unwrap())
+            .subject("Happy new year")
+            .date(default_date())
+            .body(String::from("Be happy!"))
+            .unwrap();
+
+        let id = sender.send(email.clone()).await.unwrap();
+        assert_eq!(id.split('/').count(), 4, "{id}");
+
+        let eml = read_to_string(dir.join(format!("{id}.eml"))).unwrap();
+        assert_eq!(eml.as_bytes(), email.formatted());
+
+        remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn file_transport_prune_tokio1() {
+        let dir = temp_dir().join("lettre-prune-tokio1");
+        let sender = AsyncFileTransport::<Tokio1Executor>::new(&dir).shard_by_date(true);
+        let email = Message::builder()
+            .from("NoBody <nobody@domain.tld>".parse().unwrap())
+            .to("Hei <hei@domain.tld>".parse().unwrap())
+            .subject("Happy new year")
+            .date(default_date())
+            .body(String::from("Be happy!"))
+            .unwrap();
+
+        let id = sender.send(email).await.unwrap();
+        let eml_file = dir.join(format!("{id}.eml"));
+
+        let expected = vec![eml_file];
+        assert_eq!(
+            sender.prune_dry_run(Duration::ZERO).await.unwrap(),
+            expected
+        );
+        assert_eq!(sender.prune(Duration::ZERO).await.unwrap(), expected);
+        assert_eq!(read_dir(&dir).unwrap().count(), 0);
+
+        remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    #[cfg(feature = "file-transport-envelope")]
+    async fn file_transport_with_bundle_tokio1() {
+        let sender = AsyncFileTransport::<Tokio1Executor>::with_bundle(temp_dir());
+        let email = Message::builder()
+            .from("NoBody <nobody@domain.tld>".parse().unwrap())
+            .to("Hei <hei@domain.tld>".parse().unwrap())
+            .subject("Happy new year")
+            .date(default_date())
+            .body(String::from("Be happy!"))
+            .unwrap();
+
+        let id = sender.send(email.clone()).await.unwrap();
+
+        let (envelope, message) = sender.read(&id).await.unwrap();
+        assert_eq!(&envelope, email.envelope());
+        assert_eq!(message, email.formatted());
+
+        remove_file(temp_dir().join(format!("{id}.emlb"))).unwrap();
+    }
 }
 
 #[cfg(test)]
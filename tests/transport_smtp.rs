@@ -1,7 +1,19 @@
 #[cfg(test)]
 #[cfg(all(feature = "smtp-transport", feature = "builder"))]
 mod sync {
-    use lettre::{Message, SmtpTransport, Transport};
+    use std::fmt;
+
+    use lettre::{
+        address::Envelope,
+        transport::smtp::{
+            authentication::Credentials,
+            commands::Command,
+            diagnosis::Encryption,
+            extension::{CustomExtension, MailParameter, RcptParameter, ServerInfo},
+            response::Response,
+        },
+        Address, Message, SmtpTransport, Transport,
+    };
 
     #[test]
     fn smtp_transport_simple() {
@@ -18,12 +30,207 @@ mod sync {
             .build();
         sender.send(&email).unwrap();
     }
+
+    #[test]
+    fn smtp_transport_plaintext_auth() {
+        let email = Message::builder()
+            .from("NoBody <nobody@domain.tld>".parse().unwrap())
+            .to("Hei <hei@domain.tld>".parse().unwrap())
+            .subject("Happy new year")
+            .body(String::from("Be happy!"))
+            .unwrap();
+
+        let builder = SmtpTransport::builder_dangerous("127.0.0.1")
+            .port(2525)
+            .credentials(Credentials::new("user".to_owned(), "password".to_owned()));
+        let err = builder.clone().build().send(&email).unwrap_err();
+        assert!(err.to_string().contains("unencrypted connection"), "{err}");
+
+        // The server doesn't support AUTH, so it fails later
+        let err = builder
+            .allow_plaintext_auth()
+            .build()
+            .send(&email)
+            .unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("No compatible authentication mechanism"),
+            "{err}"
+        );
+    }
+
+    #[derive(Debug)]
+    struct Tag {
+        mail: bool,
+        rcpt: bool,
+    }
+
+    impl CustomExtension for Tag {
+        fn mail_parameters(&self, server_info: &ServerInfo, _: &Envelope) -> Vec<MailParameter> {
+            assert!(server_info.keyword("8BITMIME").is_some());
+            self.mail
+                .then(|| MailParameter::Other {
+                    keyword: "X-TAG".to_owned(),
+                    value: Some("1".to_owned()),
+                })
+                .into_iter()
+                .collect()
+        }
+
+        fn rcpt_parameters(&self, _: &ServerInfo, to: &Address) -> Vec<RcptParameter> {
+            self.rcpt
+                .then(|| RcptParameter::Other {
+                    keyword: "X-TAG".to_owned(),
+                    value: Some(to.to_string()),
+                })
+                .into_iter()
+                .collect()
+        }
+    }
+
+    #[test]
+    fn smtp_transport_custom_extension() {
+        let email = Message::builder()
+            .from("NoBody <nobody@domain.tld>".parse().unwrap())
+            .to("Hei <hei@domain.tld>".parse().unwrap())
+            .subject("Happy new year")
+            .body(String::from("Be happy!"))
+            .unwrap();
+
+        let sender = |mail, rcpt| {
+            SmtpTransport::builder_dangerous("127.0.0.1")
+                .port(2525)
+                .custom_extension(Tag { mail, rcpt })
+                .build()
+        };
+
+        sender(false, false).send(&email).unwrap();
+        // the test server doesn't know about the parameters
+        let err = sender(true, false).send(&email).unwrap_err();
+        assert!(err.is_permanent());
+        let err = sender(false, true).send(&email).unwrap_err();
+        assert!(err.is_permanent());
+    }
+
+    #[test]
+    fn smtp_transport_session() {
+        let sender = SmtpTransport::builder_dangerous("127.0.0.1")
+            .port(2525)
+            .build();
+        let mut session = sender.session().unwrap();
+
+        for to in ["hei@domain.tld", "yuin@domain.tld"] {
+            session
+                .mail(Some("nobody@domain.tld".parse().unwrap()), vec![])
+                .unwrap();
+            session.rcpt(to.parse().unwrap(), vec![]).unwrap();
+            let response = session.data(b"Subject: Hello\r\n\r\nBe happy!").unwrap();
+            assert!(response.is_positive());
+        }
+
+        session
+            .mail(Some("nobody@domain.tld".parse().unwrap()), vec![])
+            .unwrap();
+        assert!(session.rset().unwrap().is_positive());
+        assert!(session.test_connected());
+        session.release().unwrap();
+    }
+
+    struct StrictNoop(u16);
+
+    impl fmt::Display for StrictNoop {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str("NOOP\r\n")
+        }
+    }
+
+    impl Command for StrictNoop {
+        fn is_expected(&self, response: &Response) -> bool {
+            response.has_code(self.0)
+        }
+    }
+
+    #[test]
+    fn smtp_transport_custom_command() {
+        let sender = SmtpTransport::builder_dangerous("127.0.0.1")
+            .port(2525)
+            .build();
+        let mut session = sender.session().unwrap();
+
+        session.send_command(StrictNoop(250)).unwrap();
+        let err = session.send_command(StrictNoop(354)).unwrap_err();
+        assert!(err.is_response());
+        session.command("NOOP\r\n").unwrap();
+    }
+
+    #[test]
+    fn smtp_transport_diagnose() {
+        let sender = SmtpTransport::builder_dangerous("127.0.0.1")
+            .port(2525)
+            .build();
+        let diagnosis = sender.diagnose();
+
+        assert!(diagnosis.is_ok(), "{:?}", diagnosis.error());
+        assert_eq!(
+            diagnosis.resolved_addrs(),
+            &["127.0.0.1:2525".parse().unwrap()]
+        );
+        assert_eq!(
+            diagnosis.peer_addr(),
+            Some("127.0.0.1:2525".parse().unwrap())
+        );
+        assert_eq!(diagnosis.connect_attempts(), 1);
+        assert!(diagnosis.connect_duration().is_some());
+        assert_eq!(diagnosis.encryption(), Encryption::None);
+        assert_eq!(diagnosis.peer_certificate(), None);
+        assert!(diagnosis.server_info().is_some());
+        assert_eq!(diagnosis.authenticated(), None);
+
+        let sender = SmtpTransport::builder_dangerous("127.0.0.1")
+            .port(1)
+            .build();
+        let diagnosis = sender.diagnose();
+
+        assert!(!diagnosis.is_ok());
+        assert!(diagnosis.error().is_some());
+        assert_eq!(diagnosis.resolved_addrs().len(), 1);
+        assert_eq!(diagnosis.peer_addr(), None);
+        assert_eq!(diagnosis.connect_attempts(), 1);
+        assert!(diagnosis.connect_duration().is_none());
+        assert!(diagnosis.server_info().is_none());
+    }
+
+    #[test]
+    fn smtp_transport_health_check() {
+        let sender = SmtpTransport::builder_dangerous("127.0.0.1")
+            .port(2525)
+            .build();
+        let check = sender.health_check();
+        assert!(check.is_healthy(), "{check}");
+        assert_eq!(check.status_code(), 200);
+
+        let sender = SmtpTransport::builder_dangerous("127.0.0.1")
+            .port(1)
+            .build();
+        let check = sender.health_check();
+        assert!(!check.is_healthy());
+        assert_eq!(check.status_code(), 503);
+        assert!(check.error().is_some());
+    }
 }
 
 #[cfg(test)]
 #[cfg(all(feature = "smtp-transport", feature = "builder", feature = "tokio1"))]
 mod tokio_1 {
-    use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+    use std::{
+        io::{BufRead, BufReader, Write},
+        net::TcpListener,
+        thread,
+        time::Duration,
+    };
+
+    use futures_util::{stream, StreamExt};
+    use lettre::{address::Envelope, AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
     use tokio1_crate as tokio;
 
     #[tokio::test]
@@ -42,6 +249,141 @@ mod tokio_1 {
                 .build();
         sender.send(email).await.unwrap();
     }
+
+    #[tokio::test]
+    async fn smtp_transport_session_tokio1() {
+        let sender: AsyncSmtpTransport<Tokio1Executor> =
+            AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous("127.0.0.1")
+                .port(2525)
+                .build();
+        let mut session = sender.session().await.unwrap();
+
+        session
+            .mail(Some("nobody@domain.tld".parse().unwrap()), vec![])
+            .await
+            .unwrap();
+        session
+            .rcpt("hei@domain.tld".parse().unwrap(), vec![])
+            .await
+            .unwrap();
+        let response = session
+            .data(b"Subject: Hello\r\n\r\nBe happy!")
+            .await
+            .unwrap();
+        assert!(response.is_positive());
+        session.release().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn smtp_transport_max_concurrent_sends_tokio1() {
+        let email = Message::builder()
+            .from("NoBody <nobody@domain.tld>".parse().unwrap())
+            .to("Hei <hei@domain.tld>".parse().unwrap())
+            .subject("Happy new year")
+            .body(String::from("Be happy!"))
+            .unwrap();
+
+        let sender: AsyncSmtpTransport<Tokio1Executor> =
+            AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous("127.0.0.1")
+                .port(2525)
+                .max_concurrent_sends(2)
+                .build();
+
+        let sends = (0..5).map(|_| {
+            let sender = sender.clone();
+            let email = email.clone();
+            tokio::spawn(async move { sender.send(email).await })
+        });
+        for send in sends.collect::<Vec<_>>() {
+            send.await.unwrap().unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn smtp_transport_memory_budget_tokio1() {
+        let email = Message::builder()
+            .from("NoBody <nobody@domain.tld>".parse().unwrap())
+            .to("Hei <hei@domain.tld>".parse().unwrap())
+            .subject("Happy new year")
+            .body("Be happy!\r\n".repeat(1000))
+            .unwrap();
+
+        // Smaller than a single email, which is then sent alone
+        let sender: AsyncSmtpTransport<Tokio1Executor> =
+            AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous("127.0.0.1")
+                .port(2525)
+                .memory_budget(4096)
+                .build();
+
+        let sends = (0..5).map(|_| {
+            let sender = sender.clone();
+            let email = email.clone();
+            tokio::spawn(async move { sender.send(email).await })
+        });
+        for send in sends.collect::<Vec<_>>() {
+            send.await.unwrap().unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn smtp_transport_send_all_tokio1() {
+        let sender: AsyncSmtpTransport<Tokio1Executor> =
+            AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous("127.0.0.1")
+                .port(2525)
+                .build();
+
+        let messages = (0..5).map(|i| {
+            Message::builder()
+                .from("NoBody <nobody@domain.tld>".parse().unwrap())
+                .to("Hei <hei@domain.tld>".parse().unwrap())
+                .subject(format!("Happy new year {i}"))
+                .body(String::from("Be happy!"))
+                .unwrap()
+        });
+        let results = sender
+            .send_all(stream::iter(messages), 2)
+            .collect::<Vec<_>>()
+            .await;
+        assert_eq!(results.len(), 5);
+        for result in results {
+            assert!(result.unwrap().is_positive());
+        }
+    }
+
+    #[tokio::test]
+    async fn smtp_transport_command_deadline_tokio1() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut line = String::new();
+            stream.write_all(b"220 slow\r\n").unwrap();
+            reader.read_line(&mut line).unwrap();
+            stream.write_all(b"250 slow\r\n").unwrap();
+            reader.read_line(&mut line).unwrap();
+            // Reply to `MAIL FROM` one byte at a time
+            for byte in b"250 OK\r\n" {
+                thread::sleep(Duration::from_millis(100));
+                if stream.write_all(&[*byte]).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let sender: AsyncSmtpTransport<Tokio1Executor> =
+            AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous("127.0.0.1")
+                .port(port)
+                .command_deadline(Some(Duration::from_millis(300)))
+                .build();
+        let envelope = Envelope::new(
+            Some("nobody@domain.tld".parse().unwrap()),
+            vec!["hei@domain.tld".parse().unwrap()],
+        )
+        .unwrap();
+        let err = sender.send_raw(&envelope, b"test").await.unwrap_err();
+        assert!(err.is_timeout());
+    }
 }
 
 #[cfg(test)]
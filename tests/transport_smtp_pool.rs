@@ -1,8 +1,16 @@
 #[cfg(all(test, feature = "smtp-transport", feature = "pool"))]
 mod sync {
-    use std::{sync::mpsc, thread};
+    use std::{
+        sync::{mpsc, Arc, Mutex},
+        thread,
+        time::Duration,
+    };
 
-    use lettre::{address::Envelope, SmtpTransport, Transport};
+    use lettre::{
+        address::Envelope,
+        transport::smtp::{CircuitState, PoolConfig, PoolEvent, PoolObserver},
+        SmtpTransport, Transport,
+    };
 
     fn envelope() -> Envelope {
         Envelope::new(
@@ -22,6 +30,91 @@ mod sync {
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn send_with_keep_alive() {
+        let mailer = SmtpTransport::builder_dangerous("127.0.0.1")
+            .port(2525)
+            .pool_config(
+                PoolConfig::new()
+                    .min_idle(1)
+                    .keep_alive(Duration::from_millis(50)),
+            )
+            .build();
+
+        mailer
+            .send_raw(&envelope(), b"test1")
+            .expect("Send failed before keep-alive");
+        thread::sleep(Duration::from_millis(300));
+        mailer
+            .send_raw(&envelope(), b"test2")
+            .expect("Send failed after keep-alive");
+    }
+
+    #[test]
+    fn circuit_breaker() {
+        let mailer = SmtpTransport::builder_dangerous("127.0.0.1")
+            .port(1)
+            .pool_config(PoolConfig::new().circuit_breaker(2, Duration::from_secs(60)))
+            .build();
+
+        for _ in 0..2 {
+            let err = mailer.send_raw(&envelope(), b"test").unwrap_err();
+            assert!(!err.is_circuit_open());
+        }
+        assert_eq!(mailer.circuit_state(), CircuitState::Open);
+
+        let err = mailer.send_raw(&envelope(), b"test").unwrap_err();
+        assert!(err.is_circuit_open());
+    }
+
+    #[derive(Debug, Default)]
+    struct Events(Mutex<Vec<PoolEvent>>);
+
+    impl PoolObserver for Events {
+        fn event(&self, event: PoolEvent) {
+            let event = match event {
+                PoolEvent::CheckedOut { .. } => PoolEvent::CheckedOut {
+                    wait: Duration::ZERO,
+                },
+                event => event,
+            };
+            self.0.lock().unwrap().push(event);
+        }
+    }
+
+    #[test]
+    fn pool_events() {
+        let events = Arc::new(Events::default());
+        let mailer = SmtpTransport::builder_dangerous("127.0.0.1")
+            .port(2525)
+            .pool_config(
+                PoolConfig::new()
+                    .idle_timeout(Duration::from_millis(100))
+                    .observer(Arc::clone(&events)),
+            )
+            .build();
+
+        mailer.send_raw(&envelope(), b"test1").unwrap();
+        mailer.send_raw(&envelope(), b"test2").unwrap();
+        thread::sleep(Duration::from_millis(350));
+
+        let checked_out = PoolEvent::CheckedOut {
+            wait: Duration::ZERO,
+        };
+        assert_eq!(
+            *events.0.lock().unwrap(),
+            [
+                PoolEvent::Created,
+                checked_out,
+                PoolEvent::Recycled,
+                PoolEvent::Reused,
+                checked_out,
+                PoolEvent::Recycled,
+                PoolEvent::IdleTimeout,
+            ]
+        );
+    }
+
     #[test]
     fn send_from_thread() {
         let mailer = SmtpTransport::builder_dangerous("127.0.0.1")
@@ -57,3 +150,140 @@ mod sync {
             .expect("Send failed from main thread");
     }
 }
+
+#[cfg(all(test, feature = "smtp-transport", feature = "pool", feature = "tokio1"))]
+mod tokio_1 {
+    use std::{
+        io::{BufRead, BufReader, Write},
+        net::TcpListener,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        thread,
+        time::Duration,
+    };
+
+    use lettre::{
+        address::Envelope,
+        transport::smtp::{CircuitState, PoolConfig},
+        AsyncSmtpTransport, AsyncTransport, Tokio1Executor,
+    };
+    use tokio1_crate as tokio;
+
+    fn envelope() -> Envelope {
+        Envelope::new(
+            Some("user@localhost".parse().unwrap()),
+            vec!["root@localhost".parse().unwrap()],
+        )
+        .unwrap()
+    }
+
+    /// Runs a server counting connections, replying slowly on the first one
+    /// to the line starting with `slow`
+    fn slow_server(connections: Arc<AtomicUsize>, slow: &'static str) -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let first = connections.fetch_add(1, Ordering::SeqCst) == 0;
+                thread::spawn(move || {
+                    let mut reader = BufReader::new(stream.try_clone().unwrap());
+                    let mut line = String::new();
+                    let mut in_data = false;
+                    stream.write_all(b"220 fake\r\n").unwrap();
+                    while reader.read_line(&mut line).unwrap_or(0) > 0 {
+                        if first && line.starts_with(slow) {
+                            thread::sleep(Duration::from_millis(300));
+                        }
+                        let reply: &[u8] = if in_data {
+                            in_data = line != ".\r\n";
+                            if in_data {
+                                line.clear();
+                                continue;
+                            }
+                            b"250 OK\r\n"
+                        } else if line.starts_with("DATA") {
+                            in_data = true;
+                            b"354 Go ahead\r\n"
+                        } else {
+                            b"250 OK\r\n"
+                        };
+                        line.clear();
+                        if stream.write_all(reply).is_err() {
+                            break;
+                        }
+                    }
+                });
+            }
+        });
+        port
+    }
+
+    /// Drops a send while waiting for the reply to the line starting with `slow`,
+    /// and checks the connection isn't reused by the next one
+    async fn dropped_send(slow: &'static str) {
+        let connections = Arc::new(AtomicUsize::new(0));
+        let port = slow_server(Arc::clone(&connections), slow);
+        let mailer: AsyncSmtpTransport<Tokio1Executor> =
+            AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous("127.0.0.1")
+                .port(port)
+                .build();
+
+        let envelope = envelope();
+        let send = mailer.send_raw(&envelope, b"test");
+        assert!(tokio::time::timeout(Duration::from_millis(100), send)
+            .await
+            .is_err());
+        // Let the pool handle the dropped connection in the background
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        mailer.send_raw(&envelope, b"test").await.unwrap();
+        assert_eq!(connections.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn dropped_send_discards_connection() {
+        dropped_send("MAIL").await;
+    }
+
+    #[tokio::test]
+    async fn dropped_send_during_data_discards_connection() {
+        dropped_send(".\r\n").await;
+    }
+
+    #[tokio::test]
+    async fn partitions_are_isolated() {
+        let mailer: AsyncSmtpTransport<Tokio1Executor> =
+            AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous("127.0.0.1")
+                .port(1)
+                .pool_config(PoolConfig::new().circuit_breaker(2, Duration::from_secs(60)))
+                .build();
+
+        let tenant = mailer.partition("tenant-a");
+        for _ in 0..2 {
+            assert!(tenant.send_raw(&envelope(), b"test").await.is_err());
+        }
+        assert_eq!(tenant.circuit_state(), CircuitState::Open);
+        assert_eq!(
+            mailer.clone().partition("tenant-a").circuit_state(),
+            CircuitState::Open
+        );
+
+        assert_eq!(mailer.circuit_state(), CircuitState::Closed);
+        assert_eq!(
+            mailer.partition("tenant-b").circuit_state(),
+            CircuitState::Closed
+        );
+        let err = mailer.send_raw(&envelope(), b"test").await.unwrap_err();
+        assert!(!err.is_circuit_open());
+
+        assert!(mailer.remove_partition("tenant-a"));
+        assert!(!mailer.remove_partition("tenant-a"));
+        assert_eq!(
+            mailer.partition("tenant-a").circuit_state(),
+            CircuitState::Closed
+        );
+    }
+}
@@ -0,0 +1,257 @@
+#[cfg(all(test, feature = "smtp-transport", feature = "tokio1"))]
+mod turmoil_sim {
+    use std::{
+        io,
+        net::SocketAddr,
+        pin::Pin,
+        sync::atomic::{AtomicBool, Ordering},
+        task::{Context, Poll},
+        time::Duration,
+    };
+
+    use lettre::{
+        address::Envelope, transport::smtp::client::AsyncTokioStream, AsyncSmtpTransport,
+        AsyncTransport, Tokio1Executor,
+    };
+    use tokio1_crate::io::{
+        AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader, ReadBuf,
+    };
+    use turmoil::net::{TcpListener, TcpStream};
+
+    /// A simulated TCP stream
+    #[derive(Debug)]
+    struct SimStream(TcpStream);
+
+    impl AsyncRead for SimStream {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &mut ReadBuf<'_>,
+        ) -> Poll<io::Result<()>> {
+            Pin::new(&mut self.0).poll_read(cx, buf)
+        }
+    }
+
+    impl AsyncWrite for SimStream {
+        fn poll_write(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &[u8],
+        ) -> Poll<io::Result<usize>> {
+            Pin::new(&mut self.0).poll_write(cx, buf)
+        }
+
+        fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Pin::new(&mut self.0).poll_flush(cx)
+        }
+
+        fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Pin::new(&mut self.0).poll_shutdown(cx)
+        }
+    }
+
+    impl AsyncTokioStream for SimStream {
+        fn peer_addr(&self) -> io::Result<SocketAddr> {
+            self.0.peer_addr()
+        }
+    }
+
+    /// Whether the first attempt to send to `greylisted@localhost` was deferred
+    static GREYLISTED: AtomicBool = AtomicBool::new(false);
+
+    /// A minimal SMTP server accepting every email
+    async fn smtp_server() -> Result<(), Box<dyn std::error::Error>> {
+        let listener = TcpListener::bind(("0.0.0.0", 25)).await?;
+        loop {
+            let (stream, _) = listener.accept().await?;
+            tokio1_crate::spawn(async move {
+                let (read, mut write) = tokio1_crate::io::split(stream);
+                let mut lines = BufReader::new(read).lines();
+                write.write_all(b"220 server ESMTP\r\n").await?;
+                let mut in_data = false;
+                while let Some(line) = lines.next_line().await? {
+                    let reply: &[u8] = match line.get(..4).map(str::to_ascii_uppercase) {
+                        _ if in_data && line == "." => {
+                            in_data = false;
+                            b"250 queued\r\n"
+                        }
+                        _ if in_data => continue,
+                        Some(command)
+                            if command == "RCPT"
+                                && line.contains("greylisted")
+                                && !GREYLISTED.swap(true, Ordering::SeqCst) =>
+                        {
+                            b"451 4.7.1 Greylisted, please try again later\r\n"
+                        }
+                        Some(command) if command == "RCPT" && line.contains("blocked") => {
+                            b"550 mailbox unavailable\r\n"
+                        }
+                        Some(command) if command == "EHLO" => {
+                            b"250-server\r\n250-AUTH XOAUTH2\r\n250 8BITMIME\r\n"
+                        }
+                        Some(command) if command == "DATA" => {
+                            in_data = true;
+                            b"354 go ahead\r\n"
+                        }
+                        Some(command) if command == "QUIT" => {
+                            write.write_all(b"221 bye\r\n").await?;
+                            break;
+                        }
+                        _ => b"250 ok\r\n",
+                    };
+                    write.write_all(reply).await?;
+                }
+                Ok::<_, io::Error>(())
+            });
+        }
+    }
+
+    fn mailer() -> AsyncSmtpTransport<Tokio1Executor> {
+        AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous("server")
+            .port(25)
+            .timeout(Some(Duration::from_secs(5)))
+            .command_deadline(Some(Duration::from_secs(5)))
+            .connector(|server, port| async move {
+                let stream = TcpStream::connect((server.as_str(), port)).await?;
+                Ok(Box::new(SimStream(stream)) as Box<dyn AsyncTokioStream>)
+            })
+            .build::<Tokio1Executor>()
+    }
+
+    #[test]
+    fn send_through_partition() {
+        let mut sim = turmoil::Builder::new().build();
+        sim.host("server", smtp_server);
+        sim.client("client", async {
+            let mailer = mailer();
+            let envelope = Envelope::new(
+                Some("user@localhost".parse()?),
+                vec!["root@localhost".parse()?],
+            )?;
+
+            mailer.send_raw(&envelope, b"test").await?;
+
+            turmoil::partition("client", "server");
+            assert!(mailer.send_raw(&envelope, b"test").await.is_err());
+
+            turmoil::repair("client", "server");
+            mailer.send_raw(&envelope, b"test").await?;
+            Ok(())
+        });
+
+        sim.run().unwrap();
+    }
+
+    #[test]
+    fn error_names_rejected_recipient() {
+        let mut sim = turmoil::Builder::new().build();
+        sim.host("server", smtp_server);
+        sim.client("client", async {
+            let envelope = Envelope::new(
+                Some("user@localhost".parse()?),
+                vec!["root@localhost".parse()?, "blocked@localhost".parse()?],
+            )?;
+
+            let err = mailer().send_raw(&envelope, b"test").await.unwrap_err();
+            assert!(err.is_permanent());
+            let command = err.failed_command().unwrap();
+            assert_eq!(command.name(), "RCPT");
+            assert_eq!(command.argument(), Some("TO:<blocked@localhost>"));
+            assert_eq!(command.recipient(), Some(1));
+            assert_eq!(
+                err.to_string(),
+                "permanent error (550): mailbox unavailable"
+            );
+            Ok(())
+        });
+
+        sim.run().unwrap();
+    }
+
+    #[test]
+    fn retry_greylisted() {
+        let mut sim = turmoil::Builder::new()
+            .simulation_duration(Duration::from_secs(120))
+            .build();
+        sim.host("server", smtp_server);
+        sim.client("client", async {
+            let mailer = AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous("server")
+                .port(25)
+                .greylist_retry(Some(Duration::from_secs(60)))
+                .memory_budget(8)
+                .connector(|server, port| async move {
+                    let stream = TcpStream::connect((server.as_str(), port)).await?;
+                    Ok(Box::new(SimStream(stream)) as Box<dyn AsyncTokioStream>)
+                })
+                .build::<Tokio1Executor>();
+            let envelope = Envelope::new(
+                Some("user@localhost".parse()?),
+                vec!["greylisted@localhost".parse()?],
+            )?;
+
+            let other = Envelope::new(
+                Some("user@localhost".parse()?),
+                vec!["root@localhost".parse()?],
+            )?;
+
+            let start = tokio1_crate::time::Instant::now();
+            let (greylisted, other) =
+                tokio1_crate::join!(mailer.send_raw(&envelope, b"test"), async {
+                    // Needs the whole memory budget, released while waiting to retry
+                    tokio1_crate::time::sleep(Duration::from_secs(1)).await;
+                    mailer.send_raw(&other, b"test").await?;
+                    Ok::<_, lettre::transport::smtp::Error>(start.elapsed())
+                });
+            greylisted?;
+            assert!(start.elapsed() >= Duration::from_secs(60));
+            assert!(other? < Duration::from_secs(60));
+            Ok(())
+        });
+
+        sim.run().unwrap();
+    }
+
+    #[cfg(feature = "oauth2-helpers")]
+    #[test]
+    fn oauth2_token_refresh_off_executor() {
+        use std::{
+            sync::{Arc, Mutex},
+            thread::{self, ThreadId},
+        };
+
+        use lettre::transport::smtp::oauth2::{AccessToken, TokenCache};
+
+        let mut sim = turmoil::Builder::new().build();
+        sim.host("server", smtp_server);
+        sim.client("client", async {
+            let refreshed_on = Arc::new(Mutex::new(None::<ThreadId>));
+            let provider = TokenCache::new({
+                let refreshed_on = Arc::clone(&refreshed_on);
+                move || {
+                    *refreshed_on.lock().unwrap() = Some(thread::current().id());
+                    Ok(AccessToken::new("token".to_owned(), None))
+                }
+            });
+            let mailer = AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous("server")
+                .port(25)
+                .oauth2_token_provider("user@localhost".to_owned(), provider)
+                .allow_plaintext_auth()
+                .connector(|server, port| async move {
+                    let stream = TcpStream::connect((server.as_str(), port)).await?;
+                    Ok(Box::new(SimStream(stream)) as Box<dyn AsyncTokioStream>)
+                })
+                .build::<Tokio1Executor>();
+            let envelope = Envelope::new(
+                Some("user@localhost".parse()?),
+                vec!["root@localhost".parse()?],
+            )?;
+
+            mailer.send_raw(&envelope, b"test").await?;
+            let refreshed_on = refreshed_on.lock().unwrap().expect("token refreshed");
+            assert_ne!(refreshed_on, thread::current().id());
+            Ok(())
+        });
+
+        sim.run().unwrap();
+    }
+}
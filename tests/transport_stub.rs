@@ -1,7 +1,7 @@
 #[cfg(test)]
 #[cfg(feature = "builder")]
 mod sync {
-    use lettre::{transport::stub::StubTransport, Message, Transport};
+    use lettre::{address::Envelope, transport::stub::StubTransport, Message, Transport};
 
     #[test]
     fn stub_transport() {
@@ -24,12 +24,96 @@ mod sync {
         )];
         assert_eq!(sender_ok.messages(), expected_messages);
     }
+
+    #[test]
+    fn stub_transport_with_envelope() {
+        let sender = StubTransport::new_ok();
+        let email = Message::builder()
+            .from("NoBody <nobody@domain.tld>".parse().unwrap())
+            .to("Hei <hei@domain.tld>".parse().unwrap())
+            .subject("Happy new year")
+            .body(String::from("Be happy!"))
+            .unwrap();
+        let envelope = Envelope::new(
+            Some("bounces+hei=domain.tld@domain.tld".parse().unwrap()),
+            vec!["hei@domain.tld".parse().unwrap()],
+        )
+        .unwrap();
+
+        sender.send_with_envelope(&email, &envelope).unwrap();
+
+        let expected_messages = [(envelope, String::from_utf8(email.formatted()).unwrap())];
+        assert_eq!(sender.messages(), expected_messages);
+    }
+
+    #[cfg(feature = "smtp-transport")]
+    #[test]
+    fn recording_transport_golden() {
+        use std::{
+            env::temp_dir,
+            fs,
+            panic::{catch_unwind, AssertUnwindSafe},
+            time::{Duration, SystemTime},
+        };
+
+        use lettre::{message::FixedClock, transport::stub::RecordingTransport};
+
+        let build = |subject: &str| {
+            Message::builder()
+                .clock(FixedClock::new(
+                    SystemTime::UNIX_EPOCH + Duration::from_secs(784887151),
+                ))
+                .from("NoBody <nobody@domain.tld>".parse().unwrap())
+                .to("Hei <hei@domain.tld>".parse().unwrap())
+                .message_id(Some("<golden@domain.tld>".to_owned()))
+                .subject(subject)
+                .body(String::from("Be happy!\r\n.\r\n"))
+                .unwrap()
+        };
+        let sender = RecordingTransport::new(StubTransport::new_ok());
+        sender.send(&build("Happy new year")).unwrap();
+
+        let golden = temp_dir().join("lettre-recording-golden.txt");
+        fs::write(
+            &golden,
+            concat!(
+                "MAIL FROM:<nobody@domain.tld>\r\n",
+                "RCPT TO:<hei@domain.tld>\r\n",
+                "DATA\r\n",
+                "From: NoBody <nobody@domain.tld>\r\n",
+                "To: Hei <hei@domain.tld>\r\n",
+                "Message-ID: <golden@domain.tld>\r\n",
+                "Subject: Happy new year\r\n",
+                "Content-Transfer-Encoding: 7bit\r\n",
+                "Date: Tue, 15 Nov 1994 08:12:31 +0000\r\n",
+                "\r\n",
+                "Be happy!\r\n",
+                "..\r\n",
+                "\r\n",
+                ".\r\n",
+            ),
+        )
+        .unwrap();
+        sender.assert_golden(&golden);
+
+        let changed = RecordingTransport::new(StubTransport::new_ok());
+        changed.send(&build("Happy new year!")).unwrap();
+        assert!(catch_unwind(AssertUnwindSafe(|| changed.assert_golden(&golden))).is_err());
+
+        fs::remove_file(golden).unwrap();
+    }
 }
 
 #[cfg(test)]
 #[cfg(all(feature = "builder", feature = "tokio1"))]
 mod tokio_1 {
-    use lettre::{transport::stub::AsyncStubTransport, AsyncTransport, Message};
+    use lettre::{
+        transport::{
+            blocking::BlockingBridge,
+            stub::{AsyncStubTransport, StubTransport},
+        },
+        AsyncTransport, Message, Tokio1Executor,
+    };
     use tokio1_crate as tokio;
 
     #[tokio::test]
@@ -53,6 +137,27 @@ mod tokio_1 {
         )];
         assert_eq!(sender_ok.messages().await, expected_messages);
     }
+
+    #[tokio::test]
+    async fn blocking_bridge_tokio1() {
+        let sender_ok = BlockingBridge::<_, Tokio1Executor>::new(StubTransport::new_ok());
+        let sender_ko = BlockingBridge::<_, Tokio1Executor>::new(StubTransport::new_error());
+        let email = Message::builder()
+            .from("NoBody <nobody@domain.tld>".parse().unwrap())
+            .to("Hei <hei@domain.tld>".parse().unwrap())
+            .subject("Happy new year")
+            .body(String::from("Be happy!"))
+            .unwrap();
+
+        sender_ok.clone().send(email.clone()).await.unwrap();
+        sender_ko.send(email.clone()).await.unwrap_err();
+
+        let expected_messages = [(
+            email.envelope().clone(),
+            String::from_utf8(email.formatted()).unwrap(),
+        )];
+        assert_eq!(sender_ok.transport().messages(), expected_messages);
+    }
 }
 
 #[cfg(test)]